@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use xtce_rs::parser::MdbLoader;
+
+const FILES: [&str; 4] = [
+    "test-xtce-files/multi-dt.xml",
+    "test-xtce-files/multi-pkt.xml",
+    "test-xtce-files/BogusSAT-2.xml",
+    "test-xtce-files/simulator.xml",
+];
+
+// loads the files one by one or all at once (read and parsed in parallel with the `parallel` feature)
+fn multi_file(c: &mut Criterion) {
+    let paths = FILES.map(Path::new);
+
+    let mut group = c.benchmark_group("parse multi file");
+    group.bench_function("add_file", |b| {
+        b.iter(|| {
+            let mut loader = MdbLoader::new();
+            for path in paths {
+                loader.add_file(path).unwrap();
+            }
+            loader.finish().unwrap()
+        })
+    });
+    group.bench_function("add_files", |b| {
+        b.iter(|| {
+            let mut loader = MdbLoader::new();
+            loader.add_files(&paths).unwrap();
+            loader.finish().unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, multi_file);
+criterion_main!(benches);
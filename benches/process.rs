@@ -0,0 +1,61 @@
+use std::path::Path;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use xtce_rs::{
+    mdb::MissionDatabase,
+    parser,
+    proc::{
+        containers::{process, process_with},
+        ProcessorData,
+    },
+};
+
+fn hex_to_bytes(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+fn flightdata(c: &mut Criterion) {
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/simulator.xml")).unwrap();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+
+    let packet = hex_to_bytes("0801fb7e0047517e74b4b36500000021435dc000c27265604254e148458ccd9a41ddb43940314c983e00c49c42ec8a3d42ec8a3d3ebbbecb3f7ec02f4238333340af2a30c1ad70a441ddb4390520");
+    let root_container = mdb.search_container("/YSS/SIMULATOR/FlightData").unwrap();
+
+    c.bench_function("process flightdata", |b| {
+        b.iter(|| process_with(&mdb, &pdata, black_box(&packet), root_container).unwrap())
+    });
+}
+
+// decodes a stream of 100k DHS packets creating the processor data for each packet or only once
+fn dhs_stream(c: &mut Criterion) {
+    const NUM_PACKETS: usize = 100_000;
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/simulator.xml")).unwrap();
+
+    let packet = hex_to_bytes("0801fff50015517e58c1b065000000020401050105010402000074b6");
+    let root_container = mdb.search_container("/YSS/SIMULATOR/DHS").unwrap();
+
+    let mut group = c.benchmark_group("dhs stream");
+    group.sample_size(10);
+    group.bench_function("process", |b| {
+        b.iter(|| {
+            for _ in 0..NUM_PACKETS {
+                process(&mdb, black_box(&packet), root_container).unwrap();
+            }
+        })
+    });
+    group.bench_function("process_with", |b| {
+        let pdata = ProcessorData::new(&mdb).unwrap();
+        b.iter(|| {
+            for _ in 0..NUM_PACKETS {
+                process_with(&mdb, &pdata, black_box(&packet), root_container).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, flightdata, dhs_stream);
+criterion_main!(benches);
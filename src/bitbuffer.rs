@@ -189,6 +189,11 @@ impl BitBuffer<'_> {
         &self.b[pos..pos + len]
     }
 
+    /// number of bits from the current position until the end of the buffer
+    pub fn remaining_bits(&self) -> usize {
+        self.bitsize().saturating_sub(self.position)
+    }
+
     pub fn remaining_bytes(&self) -> usize {
         self.ensure_byte_boundary();
 
@@ -202,7 +207,15 @@ impl BitBuffer<'_> {
     }
 }
 
+impl<'a> BitBuffer<'a> {
+    /// the underlying data, independent of the current position
+    pub fn data(&self) -> &'a [u8] {
+        self.b
+    }
+}
+
 #[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ByteOrder {
     BigEndian,
     LittleEndian,
@@ -249,7 +262,9 @@ mod tests {
         let mut bitbuf = BitBuffer::wrap(&b);
 
         assert_eq!(0x18, bitbuf.get_byte());
+        assert_eq!(24, bitbuf.remaining_bits());
         assert_eq!([0x7A, 0x23, 0xFF], bitbuf.get_bytes_ref(3));
+        assert_eq!(0, bitbuf.remaining_bits());
 
     }
 
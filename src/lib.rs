@@ -8,6 +8,37 @@ pub mod mdb;
 pub mod parser;
 pub mod proc;
 
+use thiserror::Error;
+
+/// Wraps the errors of the parser, of the mdb and of the processing such that an application
+/// using all of them can propagate the errors with `?`
+#[derive(Error, Debug)]
+pub enum XtceRsError {
+    #[error("{0}")]
+    Xtce(parser::XtceError),
+    #[error("{0}")]
+    Mdb(mdb::MdbError),
+    #[error("processing error: {0}")]
+    Proc(proc::ProcError),
+}
+
+impl From<parser::XtceError> for XtceRsError {
+    fn from(e: parser::XtceError) -> XtceRsError {
+        XtceRsError::Xtce(e)
+    }
+}
+
+impl From<mdb::MdbError> for XtceRsError {
+    fn from(e: mdb::MdbError) -> XtceRsError {
+        XtceRsError::Mdb(e)
+    }
+}
+
+impl From<proc::ProcError> for XtceRsError {
+    fn from(e: proc::ProcError) -> XtceRsError {
+        XtceRsError::Proc(e)
+    }
+}
 
 
 #[cfg(test)]
@@ -20,4 +51,29 @@ mod tests {
     fn test_bogus2() {
     
     }
+
+    // parses, checks and processes a packet propagating all the errors with ?
+    fn pipeline(path: &str, container: &str, packet: &[u8]) -> Result<usize, XtceRsError> {
+        let mut mdb = MissionDatabase::new();
+        parser::parse(&mut mdb, Path::new(path))?;
+        mdb.check_type_references()?;
+        let cidx = mdb.search_container(container).ok_or_else(|| {
+            mdb::MdbError::InvalidName(format!("unknown container {}", container))
+        })?;
+        Ok(process(&mdb, packet, cidx)?.len())
+    }
+
+    #[test]
+    fn test_error_conversions() {
+        let bogus = "test-xtce-files/BogusSAT-2.xml";
+        let packet = [0x08, 0x23, 0xC0, 0x56, 0, 5, 0x35, 0x10, 0x20, 0x03, 0x05, 0, 0];
+        assert_eq!(4, pipeline(bogus, "/BogusSAT/CCSDSPacket", &packet).unwrap());
+
+        let r = pipeline("test-xtce-files/missing.xml", "/BogusSAT/CCSDSPacket", &packet);
+        assert!(matches!(r, Err(XtceRsError::Xtce(parser::XtceError::Io(_)))));
+        let r = pipeline(bogus, "/BogusSAT/Unknown", &packet);
+        assert!(matches!(r, Err(XtceRsError::Mdb(mdb::MdbError::InvalidName(_)))));
+        let r = pipeline(bogus, "/BogusSAT/CCSDSPacket", &packet[..3]);
+        assert!(matches!(r, Err(XtceRsError::Proc(_))), "{:?}", r);
+    }
 }
@@ -30,13 +30,21 @@ impl std::fmt::Debug for MdbItemDebug<'_, SpaceSystem> {
             let pt = mdb.get_parameter(v.to_owned());
             writeln!(f, "\t\t {:?} ", MdbItemDebug { item: pt, mdb })?;
         }
+        writeln!(f, "\t{} Algorithms: ", ss.algorithms.len())?;
+        for v in ss.algorithms.values() {
+            writeln!(f, "\t\t {} ", mdb.name2str(mdb.get_algorithm(*v).name()))?;
+        }
         Ok(())
     }
 }
 
-fn write_debug_units(f: &mut Formatter<'_>, units: &Vec<UnitType>) -> fmt::Result {
-    if units.len() > 0 {
-        write!(f, ", units: {:?})", units)?;
+fn write_debug_units(f: &mut Formatter<'_>, units: &[UnitType]) -> fmt::Result {
+    write!(f, ", units: ")?;
+    for (i, u) in units.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", u)?;
     }
     Ok(())
 }
@@ -96,7 +104,18 @@ impl std::fmt::Debug for MdbItemDebug<'_, SequenceContainer> {
                     let para = mdb.get_parameter(pidx);
                     writeln!(f, "{}", mdb.name2str(para.name()))?;
                 },
-                ContainerEntryData::ContainerRef(_) => todo!(),
+                ContainerEntryData::ContainerRef(cidx) => {
+                    let c = mdb.get_container(cidx);
+                    writeln!(f, "container {}", mdb.name2str(c.name()))?;
+                },
+                ContainerEntryData::ParameterSegmentRef(ref seg) => {
+                    let para = mdb.get_parameter(seg.pidx);
+                    writeln!(f, "{} segment order: {:?} size_in_bits: {}", mdb.name2str(para.name()), seg.order, seg.size_in_bits)?;
+                },
+                ContainerEntryData::ContainerSegmentRef(ref seg) => {
+                    let c = mdb.get_container(seg.cidx);
+                    writeln!(f, "container {} segment order: {:?} size_in_bits: {}", mdb.name2str(c.name()), seg.order, seg.size_in_bits)?;
+                },
                 ContainerEntryData::IndirectParameterRef(_) => todo!(),
                 ContainerEntryData::ArrayParameterRef(_) => todo!(),
             }
@@ -0,0 +1,942 @@
+//! Writes the MissionDatabase back as XTCE
+//!
+//! The items are referenced by their fully qualified names such that the document can be parsed
+//! back without depending on the relative name resolution.
+
+use std::fmt::{self, Write};
+
+use crate::bitbuffer::ByteOrder;
+
+use super::types::{
+    AlgorithmText, BinaryDataEncoding, BinarySize, Calibrator, ContextCalibrator, Crc, DataEncoding,
+    DataType, EnumerationAlarm, FloatDataEncoding, FloatEncodingType, IntegerDataEncoding, IntegerEncodingType,
+    LeadingSize, StringBoxSize, StringDataEncoding, StringSize, TransformAlgorithm, TypeData,
+    ValidRange, ValueEnumeration,
+};
+use super::*;
+
+const XTCE_NAMESPACE: &str = "http://www.omg.org/spec/XTCE/20180204";
+
+type Attributes = Vec<(&'static str, String)>;
+
+impl MissionDatabase {
+    /// Serializes the space systems with their parameter types, parameters and containers as an XTCE document.
+    ///
+    /// The algorithms and the commands are not exported.
+    ///
+    /// An XTCE document has one root space system; if the database contains several top level space systems
+    /// (e.g. parsed from different files), only the first one is exported.
+    pub fn to_xtce_string(&self) -> String {
+        let mut s = String::new();
+        self.write_xtce(&mut s).expect("writing to a String cannot fail");
+        s
+    }
+
+    /// writes the XTCE document described in [to_xtce_string](Self::to_xtce_string)
+    pub fn write_xtce<W: Write>(&self, w: &mut W) -> fmt::Result {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        let top = self.child_space_systems(&self.space_systems[0]);
+        if top.len() > 1 {
+            log::warn!(
+                "The XTCE document can contain only one root space system, exporting only {}",
+                self.qn_to_string(&top[0].fqn)
+            );
+        }
+        let mut xw = XtceWriter { mdb: self, w, depth: 0 };
+        match top.first() {
+            Some(ss) => xw.write_space_system(ss, true),
+            None => xw.empty("SpaceSystem", vec![("xmlns:xtce", XTCE_NAMESPACE.to_owned())]),
+        }
+    }
+
+    // sorted by name, as the other items of the space system
+    fn child_space_systems(&self, parent: &SpaceSystem) -> Vec<&SpaceSystem> {
+        let mut v: Vec<_> = self
+            .space_systems
+            .iter()
+            .filter(|ss| !ss.fqn.is_root() && ss.fqn.clone().parent() == parent.fqn)
+            .collect();
+        v.sort_by_key(|ss| self.name2str(ss.name()));
+        v
+    }
+}
+
+struct XtceWriter<'a, W: Write> {
+    mdb: &'a MissionDatabase,
+    w: &'a mut W,
+    depth: usize,
+}
+
+impl<W: Write> XtceWriter<'_, W> {
+    fn write_space_system(&mut self, ss: &SpaceSystem, root: bool) -> fmt::Result {
+        let mut attrs = Vec::new();
+        if root {
+            attrs.push(("xmlns:xtce", XTCE_NAMESPACE.to_owned()));
+        }
+        attrs.extend(name_attributes(self.mdb, &ss.name));
+        if let Some(status) = &ss.operational_status {
+            attrs.push(("operationalStatus", status.clone()));
+        }
+        self.open("SpaceSystem", attrs)?;
+        self.write_description(&ss.name)?;
+        if ss.version.is_some() || ss.date.is_some() {
+            let mut attrs = Vec::new();
+            if let Some(version) = &ss.version {
+                attrs.push(("version", version.clone()));
+            }
+            if let Some(date) = &ss.date {
+                attrs.push(("date", date.clone()));
+            }
+            self.empty("Header", attrs)?;
+        }
+
+        let ptypes = self.sorted(&ss.parameter_types);
+        let params = self.sorted(&ss.parameters);
+        let containers = self.sorted(&ss.containers);
+
+        if !ptypes.is_empty() || !params.is_empty() || !containers.is_empty() {
+            self.open("TelemetryMetaData", vec![])?;
+            if !ptypes.is_empty() {
+                self.open("ParameterTypeSet", vec![])?;
+                for idx in ptypes {
+                    self.write_data_type(self.mdb.get_data_type(idx))?;
+                }
+                self.close("ParameterTypeSet")?;
+            }
+            if !params.is_empty() {
+                self.open("ParameterSet", vec![])?;
+                for idx in params {
+                    self.write_parameter(self.mdb.get_parameter(idx))?;
+                }
+                self.close("ParameterSet")?;
+            }
+            if !containers.is_empty() {
+                self.open("ContainerSet", vec![])?;
+                for idx in containers {
+                    self.write_container(self.mdb.get_container(idx))?;
+                }
+                self.close("ContainerSet")?;
+            }
+            self.close("TelemetryMetaData")?;
+        }
+
+        for css in self.mdb.child_space_systems(ss) {
+            self.write_space_system(css, false)?;
+        }
+        self.close("SpaceSystem")
+    }
+
+    // the items are written in the order of their names such that the output does not depend on the parsing order
+    fn sorted(&self, items: &HashMap<NameIdx, Index>) -> Vec<Index> {
+        let mut v: Vec<_> = items.iter().map(|(name, idx)| (self.mdb.name2str(*name), *idx)).collect();
+        v.sort();
+        v.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    fn write_description(&mut self, ndescr: &NameDescription) -> fmt::Result {
+        if let Some(ld) = &ndescr.long_description {
+            self.text("LongDescription", vec![], ld)?;
+        }
+        if !ndescr.aliases.is_empty() {
+            let mut aliases: Vec<_> = ndescr.aliases.iter().collect();
+            aliases.sort();
+            self.open("AliasSet", vec![])?;
+            for (name_space, alias) in aliases {
+                self.empty("Alias", vec![("nameSpace", name_space.clone()), ("alias", alias.clone())])?;
+            }
+            self.close("AliasSet")?;
+        }
+        Ok(())
+    }
+
+    fn write_data_type(&mut self, dtype: &DataType) -> fmt::Result {
+        let mut attrs = name_attributes(self.mdb, &dtype.ndescr);
+        let tag = match &dtype.type_data {
+            TypeData::Integer(idt) => {
+                attrs.push(("signed", idt.signed.to_string()));
+                attrs.push(("sizeInBits", idt.size_in_bits.to_string()));
+                "IntegerParameterType"
+            }
+            TypeData::Float(fdt) => {
+                attrs.push(("sizeInBits", fdt.size_in_bits.to_string()));
+                "FloatParameterType"
+            }
+            TypeData::String(_) => "StringParameterType",
+            TypeData::Binary(_) => "BinaryParameterType",
+            TypeData::Boolean(bdt) => {
+                attrs.push(("oneStringValue", bdt.one_string_value.clone()));
+                attrs.push(("zeroStringValue", bdt.zero_string_value.clone()));
+                "BooleanParameterType"
+            }
+            TypeData::Enumerated(_) => "EnumeratedParameterType",
+            TypeData::Aggregate(_) => "AggregateParameterType",
+            TypeData::Array(adt) => {
+                attrs.push(("arrayTypeRef", self.mdb.data_type_qn(adt.dtype)));
+                // the dimensions are given by the ArrayParameterRefEntry (XTCE 1.1)
+                if adt.dim.is_empty() {
+                    attrs.push(("numberOfDimensions", "1".to_owned()));
+                }
+                "ArrayParameterType"
+            }
+            TypeData::AbsoluteTime(_) => "AbsoluteTimeParameterType",
+        };
+        if let Some(v) = &dtype.initial_value {
+            attrs.push(("initialValue", v.to_string()));
+        }
+
+        self.open(tag, attrs)?;
+        self.write_description(&dtype.ndescr)?;
+        if let DataEncoding::Float(FloatDataEncoding {
+            encoding: FloatEncodingType::Custom { exponent_bits, mantissa_bits },
+            ..
+        }) = &dtype.encoding
+        {
+            self.open("AncillaryDataSet", vec![])?;
+            self.text("AncillaryData", vec![("name", "FloatExponentBits".to_owned())], &exponent_bits.to_string())?;
+            self.text("AncillaryData", vec![("name", "FloatMantissaBits".to_owned())], &mantissa_bits.to_string())?;
+            self.close("AncillaryDataSet")?;
+        }
+        if !dtype.units.is_empty() {
+            self.open("UnitSet", vec![])?;
+            for u in &dtype.units {
+                let mut attrs = Vec::new();
+                if u.power != 1.0 {
+                    attrs.push(("power", u.power.to_string()));
+                }
+                if u.factor != "1" {
+                    attrs.push(("factor", u.factor.clone()));
+                }
+                if let Some(d) = &u.description {
+                    attrs.push(("description", d.clone()));
+                }
+                self.text("Unit", attrs, &u.unit)?;
+            }
+            self.close("UnitSet")?;
+        }
+        self.write_encoding(&dtype.encoding, &dtype.calibrator, &dtype.context_calibrators)?;
+
+        match &dtype.type_data {
+            TypeData::Integer(idt) => self.write_valid_ranges(&idt.valid_ranges)?,
+            TypeData::Float(fdt) => self.write_valid_ranges(&fdt.valid_ranges)?,
+            TypeData::Enumerated(edt) => {
+                self.open("EnumerationList", vec![])?;
+                for ve in &edt.enumeration {
+                    let mut attrs = vec![("value", ve.value.to_string())];
+                    if ve.max_value != ve.value {
+                        attrs.push(("maxValue", ve.max_value.to_string()));
+                    }
+                    attrs.push(("label", ve.label.clone()));
+                    if let Some(d) = &ve.description {
+                        attrs.push(("shortDescription", d.clone()));
+                    }
+                    self.empty("Enumeration", attrs)?;
+                }
+                self.close("EnumerationList")?;
+                if let Some(alarm) = &edt.default_alarm {
+                    self.write_enumeration_alarm("DefaultAlarm", alarm, &edt.enumeration, None)?;
+                }
+                if !edt.context_alarm.is_empty() {
+                    self.open("ContextAlarmList", vec![])?;
+                    for ca in &edt.context_alarm {
+                        self.write_enumeration_alarm("ContextAlarm", &ca.alarm, &edt.enumeration, Some(ca.criteria))?;
+                    }
+                    self.close("ContextAlarmList")?;
+                }
+            }
+            TypeData::Aggregate(adt) => {
+                self.open("MemberList", vec![])?;
+                for m in &adt.members {
+                    let mut attrs = name_attributes(self.mdb, &m.ndescr);
+                    attrs.push(("typeRef", self.mdb.data_type_qn(m.dtype)));
+                    if m.ndescr.long_description.is_none() && m.ndescr.aliases.is_empty() {
+                        self.empty("Member", attrs)?;
+                    } else {
+                        self.open("Member", attrs)?;
+                        self.write_description(&m.ndescr)?;
+                        self.close("Member")?;
+                    }
+                }
+                self.close("MemberList")?;
+            }
+            TypeData::Array(adt) if !adt.dim.is_empty() => {
+                self.open("DimensionList", vec![])?;
+                for d in &adt.dim {
+                    self.open("Dimension", vec![])?;
+                    self.write_integer_value("StartingIndex", &d.starting_index)?;
+                    self.write_integer_value("EndingIndex", &d.ending_index)?;
+                    self.close("Dimension")?;
+                }
+                self.close("DimensionList")?;
+            }
+            _ => {}
+        }
+
+        self.close(tag)
+    }
+
+    // the alarms are written in the order of the enumeration
+    fn write_enumeration_alarm(
+        &mut self,
+        tag: &str,
+        alarm: &EnumerationAlarm,
+        enumeration: &[ValueEnumeration],
+        criteria: Option<MatchCriteriaIdx>,
+    ) -> fmt::Result {
+        self.open(tag, vec![("defaultAlarmLevel", alarm.default_level.to_string())])?;
+        self.open("EnumerationAlarmList", vec![])?;
+        for ve in enumeration {
+            if let Some(level) = alarm.levels.get(&ve.label) {
+                let attrs = vec![("alarmLevel", level.to_string()), ("enumerationLabel", ve.label.clone())];
+                self.empty("EnumerationAlarm", attrs)?;
+            }
+        }
+        self.close("EnumerationAlarmList")?;
+        if let Some(mcidx) = criteria {
+            self.open("ContextMatch", vec![])?;
+            self.write_match_criteria(mcidx)?;
+            self.close("ContextMatch")?;
+        }
+        self.close(tag)
+    }
+
+    // a single range is written as a ValidRange, several ones as a ValidRangeSet
+    fn write_valid_ranges(&mut self, ranges: &[ValidRange]) -> fmt::Result {
+        match ranges {
+            [] => Ok(()),
+            [vr] => self.write_valid_range(vr, true),
+            _ => {
+                let applies = ranges[0].applies_to_calibrated.to_string();
+                self.open("ValidRangeSet", vec![("validRangeAppliesToCalibrated", applies)])?;
+                for vr in ranges {
+                    self.write_valid_range(vr, false)?;
+                }
+                self.close("ValidRangeSet")
+            }
+        }
+    }
+
+    // inside a ValidRangeSet, the validRangeAppliesToCalibrated attribute is given by the set
+    fn write_valid_range(&mut self, vr: &ValidRange, with_applies: bool) -> fmt::Result {
+        let mut attrs = Vec::new();
+        if let Some(min) = vr.min {
+            attrs.push((if vr.min_inclusive { "minInclusive" } else { "minExclusive" }, min.to_string()));
+        }
+        if let Some(max) = vr.max {
+            attrs.push((if vr.max_inclusive { "maxInclusive" } else { "maxExclusive" }, max.to_string()));
+        }
+        if with_applies {
+            attrs.push(("validRangeAppliesToCalibrated", vr.applies_to_calibrated.to_string()));
+        }
+        self.empty("ValidRange", attrs)
+    }
+
+    fn write_encoding(
+        &mut self,
+        encoding: &DataEncoding,
+        calibrator: &Option<Calibrator>,
+        context_calibrators: &[ContextCalibrator],
+    ) -> fmt::Result {
+        let (tag, attrs) = match encoding {
+            DataEncoding::None => return Ok(()),
+            DataEncoding::Integer(ide) => ("IntegerDataEncoding", integer_encoding_attributes(ide)),
+            DataEncoding::Float(fde) => ("FloatDataEncoding", float_encoding_attributes(fde)),
+            DataEncoding::String(sde) => return self.write_string_encoding(sde),
+            DataEncoding::Binary(bde) => return self.write_binary_encoding(bde),
+            DataEncoding::Boolean(_) => {
+                log::warn!("The boolean data encoding has no XTCE equivalent, it is not exported");
+                return Ok(());
+            }
+        };
+
+        // the calibrators are part of the numeric encodings
+        if calibrator.is_none() && context_calibrators.is_empty() {
+            return self.empty(tag, attrs);
+        }
+        self.open(tag, attrs)?;
+        if let Some(cal) = calibrator {
+            self.open("DefaultCalibrator", vec![])?;
+            self.write_calibrator(cal)?;
+            self.close("DefaultCalibrator")?;
+        }
+        if !context_calibrators.is_empty() {
+            self.open("ContextCalibratorList", vec![])?;
+            for cc in context_calibrators {
+                self.open("ContextCalibrator", vec![])?;
+                self.open("ContextMatch", vec![])?;
+                self.write_match_criteria(cc.criteria)?;
+                self.close("ContextMatch")?;
+                self.open("Calibrator", vec![])?;
+                self.write_calibrator(&cc.calibrator)?;
+                self.close("Calibrator")?;
+                self.close("ContextCalibrator")?;
+            }
+            self.close("ContextCalibratorList")?;
+        }
+        self.close(tag)
+    }
+
+    fn write_string_encoding(&mut self, sde: &StringDataEncoding) -> fmt::Result {
+        let mut attrs = vec![("encoding", sde.encoding.clone())];
+        if sde.byte_order == ByteOrder::LittleEndian {
+            attrs.push(("byteOrder", byte_order_str(sde.byte_order).to_owned()));
+        }
+        self.open("StringDataEncoding", attrs)?;
+
+        if sde.max_box_size_in_bits.is_some() || matches!(sde.box_size_in_bits, StringBoxSize::Dynamic(_)) {
+            let max_size = sde.max_box_size_in_bits.unwrap_or(0);
+            self.open("Variable", vec![("maxSizeInBits", max_size.to_string())])?;
+            self.write_string_size(sde)?;
+            if let StringBoxSize::Dynamic(dv) = &sde.box_size_in_bits {
+                self.write_dynamic_value(dv)?;
+            }
+            self.close("Variable")?;
+        } else if !matches!(
+            (&sde.box_size_in_bits, &sde.size_in_bits),
+            (StringBoxSize::Undefined, StringSize::Custom)
+        ) {
+            self.open("SizeInBits", vec![])?;
+            match (&sde.box_size_in_bits, &sde.size_in_bits) {
+                (StringBoxSize::Fixed(size), _) | (_, StringSize::Fixed(size)) => {
+                    self.open("Fixed", vec![])?;
+                    self.text("FixedValue", vec![], &size.to_string())?;
+                    self.close("Fixed")?;
+                }
+                _ => {}
+            }
+            self.write_string_size(sde)?;
+            self.close("SizeInBits")?;
+        }
+
+        if let Some(algo) = &sde.from_binary_transform_algorithm {
+            self.write_transform_algorithm("FromBinaryTransformAlgorithm", algo)?;
+        }
+        self.close("StringDataEncoding")
+    }
+
+    // writes the terminator or the leading size, the fixed size is written with the box size
+    fn write_string_size(&mut self, sde: &StringDataEncoding) -> fmt::Result {
+        match &sde.size_in_bits {
+            StringSize::TerminationChar(tc) => self.text("TerminationChar", vec![], &hex::encode_upper(tc)),
+            StringSize::LeadingSize(ls) => self.write_leading_size(ls, sde.byte_order),
+            StringSize::Fixed(_) | StringSize::Custom => Ok(()),
+        }
+    }
+
+    fn write_leading_size(&mut self, ls: &LeadingSize, encoding_byte_order: ByteOrder) -> fmt::Result {
+        let mut attrs = vec![("sizeInBitsOfSizeTag", ls.tag_size_in_bits.to_string())];
+        if ls.byte_order != encoding_byte_order {
+            attrs.push(("byteOrder", byte_order_str(ls.byte_order).to_owned()));
+        }
+        self.empty("LeadingSize", attrs)
+    }
+
+    fn write_binary_encoding(&mut self, bde: &BinaryDataEncoding) -> fmt::Result {
+        self.open("BinaryDataEncoding", vec![])?;
+        if let Some(crc) = &bde.crc {
+            self.open("ErrorDetectCorrect", vec![])?;
+            self.write_crc(crc)?;
+            self.close("ErrorDetectCorrect")?;
+        }
+        match &bde.size_in_bits {
+            BinarySize::Fixed(size) => {
+                self.write_integer_value("SizeInBits", &IntegerValue::FixedValue(*size as i64))?
+            }
+            // by convention -1 means that the size is determined by the algorithm
+            BinarySize::Algorithm => self.write_integer_value("SizeInBits", &IntegerValue::FixedValue(-1))?,
+            BinarySize::Dynamic(dv) => {
+                self.open("SizeInBits", vec![])?;
+                self.write_dynamic_value(dv)?;
+                self.close("SizeInBits")?;
+            }
+            BinarySize::LeadingSize(_) => {
+                log::warn!("The leading size of the binary data encoding has no XTCE equivalent, it is not exported")
+            }
+        }
+        if let Some(algo) = &bde.from_binary_transform_algorithm {
+            self.write_transform_algorithm("FromBinaryTransformAlgorithm", algo)?;
+        }
+        if let Some(algo) = &bde.to_binary_transform_algorithm {
+            self.write_transform_algorithm("ToBinaryTransformAlgorithm", algo)?;
+        }
+        self.close("BinaryDataEncoding")
+    }
+
+    fn write_crc(&mut self, crc: &Crc) -> fmt::Result {
+        let attrs = vec![
+            ("width", crc.width.to_string()),
+            ("reflectData", crc.reflect_data.to_string()),
+            ("reflectRemainder", crc.reflect_remainder.to_string()),
+            ("reference", "start".to_owned()),
+            ("bitsFromReference", crc.bits_from_reference.to_string()),
+        ];
+        let digits = crc.width as usize / 4;
+        self.open("CRC", attrs)?;
+        self.text("Polynomial", vec![], &format!("{:01$X}", crc.polynomial, digits))?;
+        self.text("InitRemainder", vec![], &format!("{:01$X}", crc.init_remainder, digits))?;
+        self.text("FinalXOR", vec![], &format!("{:01$X}", crc.final_xor, digits))?;
+        self.close("CRC")
+    }
+
+    fn write_transform_algorithm(&mut self, tag: &str, algo: &TransformAlgorithm) -> fmt::Result {
+        let attrs = vec![("name", algo.name.clone())];
+        match &algo.algorithm_text {
+            Some(text) => {
+                self.open(tag, attrs)?;
+                self.write_algorithm_text(text)?;
+                self.close(tag)
+            }
+            None => self.empty(tag, attrs),
+        }
+    }
+
+    fn write_algorithm_text(&mut self, text: &AlgorithmText) -> fmt::Result {
+        self.text("AlgorithmText", vec![("language", text.language.clone())], &text.text)
+    }
+
+    fn write_calibrator(&mut self, cal: &Calibrator) -> fmt::Result {
+        match cal {
+            Calibrator::Polynomial(pc) => {
+                self.open("PolynomialCalibrator", vec![])?;
+                for (exponent, coefficient) in pc.coefficients.iter().enumerate() {
+                    // the missing terms have the coefficient 0, at least one term is required
+                    if *coefficient != 0.0 || (exponent == 0 && pc.coefficients.iter().all(|c| *c == 0.0)) {
+                        let attrs =
+                            vec![("coefficient", coefficient.to_string()), ("exponent", exponent.to_string())];
+                        self.empty("Term", attrs)?;
+                    }
+                }
+                self.close("PolynomialCalibrator")
+            }
+            Calibrator::Spline(sc) => {
+                let attrs = vec![("order", sc.order.to_string()), ("extrapolate", sc.extrapolate.to_string())];
+                self.open("SplineCalibrator", attrs)?;
+                for p in &sc.points {
+                    let attrs = vec![("raw", p.raw.to_string()), ("calibrated", p.calibrated.to_string())];
+                    self.empty("SplinePoint", attrs)?;
+                }
+                self.close("SplineCalibrator")
+            }
+            Calibrator::MathOperation(ops) => {
+                self.open("MathOperationCalibrator", vec![])?;
+                self.write_math_operation(ops)?;
+                self.close("MathOperationCalibrator")
+            }
+        }
+    }
+
+    fn write_math_operation(&mut self, ops: &[MathOp]) -> fmt::Result {
+        for op in ops {
+            match op {
+                MathOp::Value(v) => self.text("ValueOperand", vec![], &v.to_string())?,
+                MathOp::ThisParameter => self.empty("ThisParameterOperand", vec![])?,
+                MathOp::ParameterInstance(pref) => {
+                    self.empty("ParameterInstanceRefOperand", para_ref_attributes(self.mdb, pref))?
+                }
+                MathOp::Operator(o) => self.text("Operator", vec![], &o.to_string())?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_parameter(&mut self, param: &Parameter) -> fmt::Result {
+        let Some(ptype) = param.ptype else {
+            log::warn!("Parameter {} has no type, it is not exported", self.mdb.name2str(param.name()));
+            return Ok(());
+        };
+        let mut attrs = name_attributes(self.mdb, &param.ndescr);
+        attrs.push(("parameterTypeRef", self.mdb.data_type_qn(ptype)));
+        if let Some(v) = &param.initial_value {
+            attrs.push(("initialValue", v.to_string()));
+        }
+
+        let data_source = data_source_str(&param.data_source);
+        if param.ndescr.long_description.is_none()
+            && param.ndescr.aliases.is_empty()
+            && param.properties.is_none()
+            && data_source.is_none()
+        {
+            return self.empty("Parameter", attrs);
+        }
+
+        self.open("Parameter", attrs)?;
+        self.write_description(&param.ndescr)?;
+        if param.properties.is_some() || data_source.is_some() {
+            let mut attrs = Vec::new();
+            if let Some(ds) = data_source {
+                attrs.push(("dataSource", ds.to_owned()));
+            }
+            match &param.properties {
+                Some(props) => {
+                    attrs.push(("readOnly", props.read_only.to_string()));
+                    attrs.push(("persistence", props.persistence.to_string()));
+                    if props.system_name.is_none() && props.rate_in_stream.is_none() {
+                        self.empty("ParameterProperties", attrs)?;
+                    } else {
+                        self.open("ParameterProperties", attrs)?;
+                        if let Some(sn) = &props.system_name {
+                            self.text("SystemName", vec![], sn)?;
+                        }
+                        if let Some(rate) = &props.rate_in_stream {
+                            self.open("RateInStreamSet", vec![])?;
+                            self.write_rate_in_stream("RateInStream", rate)?;
+                            self.close("RateInStreamSet")?;
+                        }
+                        self.close("ParameterProperties")?;
+                    }
+                }
+                None => self.empty("ParameterProperties", attrs)?,
+            }
+        }
+        self.close("Parameter")
+    }
+
+    fn write_container(&mut self, container: &SequenceContainer) -> fmt::Result {
+        let mut attrs = name_attributes(self.mdb, &container.ndescr);
+        attrs.push(("abstract", container.abstract_.to_string()));
+        self.open("SequenceContainer", attrs)?;
+        self.write_description(&container.ndescr)?;
+
+        if let Some(rate) = &container.default_rate_in_stream {
+            self.write_rate_in_stream("DefaultRateInStream", rate)?;
+        }
+
+        if let Some(size) = container.size_in_bits {
+            self.open("BinaryEncoding", vec![])?;
+            self.write_integer_value("SizeInBits", &IntegerValue::FixedValue(size as i64))?;
+            self.close("BinaryEncoding")?;
+        }
+
+        self.open("EntryList", vec![])?;
+        for entry in &container.entries {
+            self.write_container_entry(entry)?;
+        }
+        self.close("EntryList")?;
+
+        if let Some((base, criteria)) = container.base_container {
+            let attrs = vec![("containerRef", self.mdb.container_qn(base))];
+            match criteria {
+                Some(mcidx) => {
+                    self.open("BaseContainer", attrs)?;
+                    self.open("RestrictionCriteria", vec![])?;
+                    self.write_match_criteria(mcidx)?;
+                    self.close("RestrictionCriteria")?;
+                    self.close("BaseContainer")?;
+                }
+                None => self.empty("BaseContainer", attrs)?,
+            }
+        }
+
+        self.close("SequenceContainer")
+    }
+
+    fn write_rate_in_stream(&mut self, tag: &str, rate: &RateInStream) -> fmt::Result {
+        let basis = match rate.basis {
+            RateBasis::PerSecond => "perSecond",
+            RateBasis::PerContainerUpdate => "perContainerUpdate",
+        };
+        let mut attrs = vec![("basis", basis.to_owned())];
+        if let Some(min) = rate.minimum_value {
+            attrs.push(("minimumValue", min.to_string()));
+        }
+        if let Some(max) = rate.maximum_value {
+            attrs.push(("maximumValue", max.to_string()));
+        }
+        self.empty(tag, attrs)
+    }
+
+    fn write_container_entry(&mut self, entry: &ContainerEntry) -> fmt::Result {
+        let mdb = self.mdb;
+        let (tag, attrs) = match &entry.data {
+            ContainerEntryData::ParameterRef(pidx) => {
+                ("ParameterRefEntry", vec![("parameterRef", mdb.parameter_qn(*pidx))])
+            }
+            ContainerEntryData::ContainerRef(cidx) => {
+                ("ContainerRefEntry", vec![("containerRef", mdb.container_qn(*cidx))])
+            }
+            ContainerEntryData::ParameterSegmentRef(psr) => {
+                let mut attrs = vec![("parameterRef", mdb.parameter_qn(psr.pidx))];
+                if let Some(order) = psr.order {
+                    attrs.push(("order", order.to_string()));
+                }
+                attrs.push(("sizeInBits", psr.size_in_bits.to_string()));
+                ("ParameterSegmentRefEntry", attrs)
+            }
+            ContainerEntryData::ContainerSegmentRef(csr) => {
+                let mut attrs = vec![("containerRef", mdb.container_qn(csr.cidx))];
+                if let Some(order) = csr.order {
+                    attrs.push(("order", order.to_string()));
+                }
+                attrs.push(("sizeInBits", csr.size_in_bits.to_string()));
+                ("ContainerSegmentRefEntry", attrs)
+            }
+            // not read by the parser
+            ContainerEntryData::IndirectParameterRef(_) | ContainerEntryData::ArrayParameterRef(_) => {
+                return Ok(())
+            }
+        };
+
+        if entry.location_in_container.is_none() && entry.repeat.is_none() && entry.include_condition.is_none() {
+            return self.empty(tag, attrs);
+        }
+
+        self.open(tag, attrs)?;
+        if let Some(loc) = &entry.location_in_container {
+            let reference_location = match loc.reference_location {
+                ReferenceLocationType::ContainerStart => "containerStart",
+                ReferenceLocationType::PreviousEntry => "previousEntry",
+            };
+            self.write_integer_value_with_attributes(
+                "LocationInContainerInBits",
+                vec![("referenceLocation", reference_location.to_owned())],
+                &IntegerValue::FixedValue(loc.location_in_bits as i64),
+            )?;
+        }
+        if let Some(repeat) = &entry.repeat {
+            self.open("RepeatEntry", vec![])?;
+            self.write_integer_value("Count", &repeat.count)?;
+            if repeat.offset_in_bits != 0 {
+                self.write_integer_value("Offset", &IntegerValue::FixedValue(repeat.offset_in_bits as i64))?;
+            }
+            self.close("RepeatEntry")?;
+        }
+        if let Some(mcidx) = entry.include_condition {
+            self.open("IncludeCondition", vec![])?;
+            self.write_match_criteria(mcidx)?;
+            self.close("IncludeCondition")?;
+        }
+        self.close(tag)
+    }
+
+    // writes the criteria element, the enclosing element is written by the caller
+    fn write_match_criteria(&mut self, mcidx: MatchCriteriaIdx) -> fmt::Result {
+        match self.mdb.get_match_criteria(mcidx) {
+            MatchCriteria::Comparison(c) => self.write_comparison(c),
+            MatchCriteria::ComparisonList(list) => {
+                self.open("ComparisonList", vec![])?;
+                for c in list {
+                    self.write_comparison(c)?;
+                }
+                self.close("ComparisonList")
+            }
+            MatchCriteria::BooleanExpression(expr) => {
+                self.open("BooleanExpression", vec![])?;
+                self.write_boolean_term(expr)?;
+                self.close("BooleanExpression")
+            }
+            MatchCriteria::CustomAlgorithm(algo) => {
+                self.open("CustomAlgorithm", vec![("name", algo.name.clone())])?;
+                if let Some(text) = &algo.algorithm_text {
+                    self.write_algorithm_text(text)?;
+                }
+                if !algo.inputs.is_empty() {
+                    self.open("InputSet", vec![])?;
+                    for input in &algo.inputs {
+                        self.empty("InputParameterInstanceRef", para_ref_attributes(self.mdb, input))?;
+                    }
+                    self.close("InputSet")?;
+                }
+                self.close("CustomAlgorithm")
+            }
+        }
+    }
+
+    fn write_comparison(&mut self, c: &Comparison) -> fmt::Result {
+        let mut attrs = para_ref_attributes(self.mdb, &c.param_instance);
+        attrs.push(("comparisonOperator", c.comparison_operator.to_string()));
+        attrs.push(("value", c.value.clone()));
+        self.empty("Comparison", attrs)
+    }
+
+    fn write_boolean_term(&mut self, expr: &BooleanExpression) -> fmt::Result {
+        match expr {
+            BooleanExpression::Condition(c) => {
+                self.open("Condition", vec![])?;
+                self.empty("ParameterInstanceRef", para_ref_attributes(self.mdb, &c.param_instance))?;
+                self.text("ComparisonOperator", vec![], &c.comparison_operator.to_string())?;
+                self.text("Value", vec![], &c.value)?;
+                self.close("Condition")
+            }
+            BooleanExpression::And(terms) => self.write_boolean_terms("ANDedConditions", terms),
+            BooleanExpression::Or(terms) => self.write_boolean_terms("ORedConditions", terms),
+        }
+    }
+
+    fn write_boolean_terms(&mut self, tag: &str, terms: &[BooleanExpression]) -> fmt::Result {
+        self.open(tag, vec![])?;
+        for t in terms {
+            self.write_boolean_term(t)?;
+        }
+        self.close(tag)
+    }
+
+    fn write_integer_value(&mut self, tag: &str, iv: &IntegerValue) -> fmt::Result {
+        self.write_integer_value_with_attributes(tag, vec![], iv)
+    }
+
+    fn write_integer_value_with_attributes(&mut self, tag: &str, attrs: Attributes, iv: &IntegerValue) -> fmt::Result {
+        self.open(tag, attrs)?;
+        match iv {
+            IntegerValue::FixedValue(v) => self.text("FixedValue", vec![], &v.to_string())?,
+            IntegerValue::DynamicValue(dv) => self.write_dynamic_value(dv)?,
+        }
+        self.close(tag)
+    }
+
+    fn write_dynamic_value(&mut self, dv: &DynamicValueType) -> fmt::Result {
+        match dv {
+            DynamicValueType::Parameter(pdv) => {
+                self.open("DynamicValue", vec![])?;
+                self.empty("ParameterInstanceRef", para_ref_attributes(self.mdb, &pdv.para_ref))?;
+                if let Some(adj) = &pdv.adjustment {
+                    let attrs = vec![("slope", adj.slope.to_string()), ("intercept", adj.intercept.to_string())];
+                    self.empty("LinearAdjustment", attrs)?;
+                }
+                self.close("DynamicValue")
+            }
+            DynamicValueType::DiscreteLookupList(lookups) => {
+                self.open("DiscreteLookupList", vec![])?;
+                for dl in lookups {
+                    self.open("DiscreteLookup", vec![("value", dl.value.to_string())])?;
+                    self.write_match_criteria(dl.criteria)?;
+                    self.close("DiscreteLookup")?;
+                }
+                self.close("DiscreteLookupList")
+            }
+        }
+    }
+
+    fn open(&mut self, tag: &str, attrs: Attributes) -> fmt::Result {
+        self.start_tag(tag, attrs)?;
+        writeln!(self.w, ">")?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn close(&mut self, tag: &str) -> fmt::Result {
+        self.depth -= 1;
+        self.indent()?;
+        writeln!(self.w, "</xtce:{}>", tag)
+    }
+
+    fn empty(&mut self, tag: &str, attrs: Attributes) -> fmt::Result {
+        self.start_tag(tag, attrs)?;
+        writeln!(self.w, "/>")
+    }
+
+    // writes an element containing only text
+    fn text(&mut self, tag: &str, attrs: Attributes, text: &str) -> fmt::Result {
+        self.start_tag(tag, attrs)?;
+        writeln!(self.w, ">{}</xtce:{}>", escape(text), tag)
+    }
+
+    fn start_tag(&mut self, tag: &str, attrs: Attributes) -> fmt::Result {
+        self.indent()?;
+        write!(self.w, "<xtce:{}", tag)?;
+        for (name, value) in attrs {
+            write!(self.w, " {}=\"{}\"", name, escape(&value))?;
+        }
+        Ok(())
+    }
+
+    fn indent(&mut self) -> fmt::Result {
+        for _ in 0..self.depth {
+            self.w.write_str("  ")?;
+        }
+        Ok(())
+    }
+}
+
+fn name_attributes(mdb: &MissionDatabase, ndescr: &NameDescription) -> Attributes {
+    let mut attrs = vec![("name", mdb.name2str(ndescr.name).to_owned())];
+    if let Some(sd) = &ndescr.short_description {
+        attrs.push(("shortDescription", sd.clone()));
+    }
+    attrs
+}
+
+fn para_ref_attributes(mdb: &MissionDatabase, pref: &ParameterInstanceRef) -> Attributes {
+    let mut name = mdb.parameter_qn(pref.pidx);
+    for pe in pref.member_path.iter().flatten() {
+        if pe.name.is_some() {
+            name.push('.');
+        }
+        name.push_str(&pe.to_string(mdb));
+    }
+    let mut attrs = vec![("parameterRef", name)];
+    if pref.instance != 0 {
+        attrs.push(("instance", pref.instance.to_string()));
+    }
+    if !pref.use_calibrated_value {
+        attrs.push(("useCalibratedValue", "false".to_owned()));
+    }
+    attrs
+}
+
+fn integer_encoding_attributes(ide: &IntegerDataEncoding) -> Attributes {
+    let encoding = match ide.encoding {
+        IntegerEncodingType::Unsigned => "unsigned",
+        IntegerEncodingType::TwosComplement => "twosComplement",
+        IntegerEncodingType::SignMagnitude => "signMagnitude",
+        IntegerEncodingType::OnesComplement => "onesComplement",
+        IntegerEncodingType::Bcd => "BCD",
+        IntegerEncodingType::PackedBcd => "packedBCD",
+    };
+    let mut attrs = vec![("sizeInBits", ide.size_in_bits.to_string()), ("encoding", encoding.to_owned())];
+    if ide.byte_order == ByteOrder::LittleEndian {
+        attrs.push(("byteOrder", byte_order_str(ide.byte_order).to_owned()));
+    }
+    if let Some(t) = ide.change_threshold {
+        attrs.push(("changeThreshold", t.to_string()));
+    }
+    attrs
+}
+
+fn float_encoding_attributes(fde: &FloatDataEncoding) -> Attributes {
+    let encoding = match fde.encoding {
+        // the custom layout is given by the ancillary data of the type
+        FloatEncodingType::IEEE754_1985 | FloatEncodingType::Custom { .. } => "IEEE754_1985",
+        FloatEncodingType::Milstd1750a => "MILSTD_1750A",
+    };
+    let mut attrs = vec![("sizeInBits", fde.size_in_bits.to_string()), ("encoding", encoding.to_owned())];
+    if fde.byte_order == ByteOrder::LittleEndian {
+        attrs.push(("byteOrder", byte_order_str(fde.byte_order).to_owned()));
+    }
+    if let Some(t) = fde.change_threshold {
+        attrs.push(("changeThreshold", t.to_string()));
+    }
+    attrs
+}
+
+fn byte_order_str(byte_order: ByteOrder) -> &'static str {
+    match byte_order {
+        ByteOrder::BigEndian => "mostSignificantByteFirst",
+        ByteOrder::LittleEndian => "leastSignificantByteFirst",
+    }
+}
+
+// the telemetered parameters are the default, the sources not supported by the parser are skipped
+fn data_source_str(ds: &DataSource) -> Option<&'static str> {
+    match ds {
+        DataSource::Telemetered => None,
+        DataSource::Derived => Some("derived"),
+        DataSource::Constant => Some("constant"),
+        DataSource::Local => Some("local"),
+        DataSource::System => Some("system"),
+        _ => None,
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut r = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => r.push_str("&amp;"),
+            '<' => r.push_str("&lt;"),
+            '>' => r.push_str("&gt;"),
+            '"' => r.push_str("&quot;"),
+            '\'' => r.push_str("&apos;"),
+            _ => r.push(c),
+        }
+    }
+    r
+}
@@ -1,16 +1,24 @@
 pub mod debug;
+mod export;
+#[cfg(feature = "serde")]
+mod serialization;
 pub mod types;
 pub mod utils;
 
 use std::sync::Arc;
-use std::{collections::HashMap, fmt::Debug};
+use std::time::Duration;
+use std::{collections::{HashMap, HashSet}, fmt::Debug};
 
 use std::fmt;
 use std::fmt::Formatter;
 
 use lasso::{Key, ThreadedRodeo};
 
-use self::types::{DataType, MemberPath};
+use self::types::{AlgorithmText, DataType, MemberPath, TypeData};
+use crate::{
+    pvlist::ParameterValueList,
+    value::{ParameterValue, Value},
+};
 
 pub(crate) type NameIdx = lasso::Spur;
 
@@ -21,19 +29,23 @@ pub type DataTypeIdx = Index;
 pub type ParameterIdx = Index;
 pub type ContainerIdx = Index;
 pub type MatchCriteriaIdx = Index;
+pub type AlgorithmIdx = Index;
+pub type MetaCommandIdx = Index;
 
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum MdbError {
-    #[error("out of range")]
+    #[error("out of range: {0}")]
     OutOfRange(String),
-    #[error("invalid value")]
+    #[error("invalid value: {0}")]
     InvalidValue(String),
-    #[error("duplicate name")]
+    #[error("duplicate name: {0}")]
     DuplicateName(String),
-    #[error("invalid name")]
+    #[error("invalid name: {0}")]
     InvalidName(String),
+    #[error("invalid mdb: {0}")]
+    InvalidMdb(String),
 }
 
 type Result<T> = std::result::Result<T, MdbError>;
@@ -45,12 +57,15 @@ type Result<T> = std::result::Result<T, MdbError>;
 /// (e.g. a parameter definition contains the index of its parameter type in the parameter_types vector).
 ///
 /// Similaryly for names - we use some numeric identifiers for each name and the String has to be retrieved from the NameDb
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MissionDatabase {
+    #[cfg_attr(feature = "serde", serde(with = "serialization::name_db"))]
     name_db: NameDb,
     pub space_systems: Vec<SpaceSystem>,
     /// qualified space system names
     /// to lookup an item (parameter, type, etc) by fully qualified name,
     /// the space system is taken from the map and then in the space system there is a map with all the items
+    #[cfg_attr(feature = "serde", serde(with = "serialization::map_as_vec"))]
     space_systems_qn: HashMap<QualifiedName, SpaceSystemIdx>,
 
     /// vectors with definitions
@@ -58,11 +73,16 @@ pub struct MissionDatabase {
     pub parameters: Vec<Parameter>,
     pub containers: Vec<SequenceContainer>,
     pub match_criteria: Vec<MatchCriteria>,
+    pub algorithms: Vec<Algorithm>,
+    pub meta_commands: Vec<MetaCommand>,
 
     //parent to child container mapping
     //(it is the reverse of the base containers relation)
     pub child_containers: HashMap<ContainerIdx, Vec<ContainerIdx>>,
     pub decoder_defs: Vec<DecoderDef>,
+
+    /// namespace -> alias -> item, to find the parameters and containers by alias
+    aliases: HashMap<String, HashMap<String, AliasTarget>>,
 }
 
 pub trait NamedItem {
@@ -73,6 +93,7 @@ pub trait NamedItem {
 }
 
 #[derive(Clone, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QualifiedName(Vec<NameIdx>);
 
 impl QualifiedName {
@@ -99,6 +120,11 @@ impl QualifiedName {
         QualifiedName(v)
     }
 
+    /// returns true if the qualified name is equal to the prefix or is a descendant of it
+    pub fn starts_with(&self, prefix: &QualifiedName) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
     pub fn push(&mut self, name: NameIdx) {
         self.0.push(name);
     }
@@ -143,6 +169,12 @@ impl QualifiedName {
         Some(qn)
     }
 
+    /// get a qualified name from a string, interning the parts not yet found in the NameDb.
+    /// Unlike [QualifiedName::from_str] it cannot fail, it is used to build the names of the new items.
+    pub fn from_str_interning(name_db: &NameDb, qnstr: &str) -> QualifiedName {
+        QualifiedName(qnstr.split("/").filter(|p| !p.is_empty()).map(|p| name_db.get_or_intern(p)).collect())
+    }
+
     /// parse string to (space_system_qn, name).
     /// The string is split by "/",
     /// If the path contains any name not found in the NameDb or if the path is emty, None is returned
@@ -168,6 +200,8 @@ pub enum NameReferenceType {
     Parameter,
     SequenceContainer,
     Algorithm,
+    ArgumentType,
+    MetaCommand,
 }
 
 impl std::fmt::Debug for QualifiedName {
@@ -182,6 +216,7 @@ impl std::fmt::Debug for QualifiedName {
 /// non zero U32 which has the advantage of not consuming extra space inside an Option<>
 /// it is used to index parameters, containers, matchcrietrias...
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Index(std::num::NonZeroU32);
 
 impl Index {
@@ -198,19 +233,41 @@ impl Index {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NameDescription {
     pub name: NameIdx,
     pub short_description: Option<String>,
     pub long_description: Option<String>,
+    /// namespace -> alias
+    pub aliases: HashMap<String, String>,
 }
 
 impl NameDescription {
     pub fn new(name: NameIdx) -> NameDescription {
-        NameDescription { name, short_description: None, long_description: None }
+        NameDescription {
+            name,
+            short_description: None,
+            long_description: None,
+            aliases: HashMap::new(),
+        }
     }
+
+    /// returns the alias of the item in the given namespace
+    pub fn alias(&self, name_space: &str) -> Option<&str> {
+        self.aliases.get(name_space).map(|a| a.as_str())
+    }
+}
+
+/// The item found by [MissionDatabase::find_by_alias]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AliasTarget {
+    Parameter(ParameterIdx),
+    Container(ContainerIdx),
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DataSource {
     /**
      * used for data acquired from outside, parameters of this type cannot be changed
@@ -252,6 +309,7 @@ pub enum DataSource {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnitType {
     pub description: Option<String>,
     pub power: f64,
@@ -259,10 +317,85 @@ pub struct UnitType {
     pub unit: String,
 }
 
+// formats the unit as factor*unit^power skipping the factor and power if they are 1
+impl std::fmt::Display for UnitType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.factor != "1" {
+            write!(f, "{}*", self.factor)?;
+        }
+        write!(f, "{}", self.unit)?;
+        if self.power != 1f64 {
+            write!(f, "^{}", self.power)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parameter {
     pub ndescr: NameDescription,
     pub ptype: Option<DataTypeIdx>,
     pub data_source: DataSource,
+    pub properties: Option<ParameterProperties>,
+    /// initial value (engineering) converted according to the parameter type
+    pub initial_value: Option<Value>,
+}
+
+impl Parameter {
+    /// returns true if the parameter cannot be set by the clients.
+    /// That is only the case if it is explicitly specified in the ParameterProperties
+    pub fn read_only(&self) -> bool {
+        self.properties.as_ref().is_some_and(|p| p.read_only)
+    }
+
+    /// returns true if the parameter values should be archived, which is the default if not specified
+    /// in the ParameterProperties
+    pub fn persistence(&self) -> bool {
+        self.properties.as_ref().is_none_or(|p| p.persistence)
+    }
+
+    /// returns the name of the system producing the parameter, if specified in the ParameterProperties
+    pub fn system_name(&self) -> Option<&str> {
+        self.properties.as_ref().and_then(|p| p.system_name.as_deref())
+    }
+
+    /// returns the expected rate of the parameter in the telemetry stream, if specified in the ParameterProperties
+    pub fn rate_in_stream(&self) -> Option<&RateInStream> {
+        self.properties.as_ref().and_then(|p| p.rate_in_stream.as_ref())
+    }
+}
+
+/// Holds the content of the XTCE <ParameterProperties> element
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParameterProperties {
+    pub system_name: Option<String>,
+    pub read_only: bool,
+    /// if false, the parameter values should not be archived
+    pub persistence: bool,
+    /// the first RateInStream of the RateInStreamSet; the stream reference is not used
+    pub rate_in_stream: Option<RateInStream>,
+}
+
+/// Expected rate of a parameter or container in the telemetry stream.
+///
+/// It is used to detect the stale parameters: if no value is received in the expected interval,
+/// the last value can be considered expired.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RateInStream {
+    pub basis: RateBasis,
+    /// minimum number of occurrences per basis
+    pub minimum_value: Option<f64>,
+    /// maximum number of occurrences per basis
+    pub maximum_value: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RateBasis {
+    PerSecond,
+    PerContainerUpdate,
 }
 
 impl NamedItem for Parameter {
@@ -271,35 +404,457 @@ impl NamedItem for Parameter {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SequenceContainer {
     pub ndescr: NameDescription,
     pub base_container: Option<(ContainerIdx, Option<MatchCriteriaIdx>)>,
     //abstract is a reserved word in Rust
     pub abstract_: bool,
     pub entries: Vec<ContainerEntry>,
+    /// fixed size of the container, if specified
+    pub size_in_bits: Option<u32>,
+    /// expected rate of the container in the telemetry stream, if specified
+    pub default_rate_in_stream: Option<RateInStream>,
     pub idx: ContainerIdx
 }
 
+impl SequenceContainer {
+    /// number of entries in the entry list of this container, the inherited entries are not counted
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+}
+
 impl NamedItem for SequenceContainer {
     fn name_descr(&self) -> &NameDescription {
         &self.ndescr
     }
 }
 
+/// An algorithm computing the values of its output parameters from the values of its inputs.
+///
+/// The algorithms are not executed by the crate.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Algorithm {
+    pub ndescr: NameDescription,
+    pub inputs: Vec<AlgorithmInput>,
+    pub outputs: Vec<AlgorithmOutput>,
+    pub algorithm_type: AlgorithmType,
+    /// when the algorithm has to run; if empty, it runs whenever one of its inputs is updated
+    pub triggers: Vec<AlgorithmTrigger>,
+}
+
+impl NamedItem for Algorithm {
+    fn name_descr(&self) -> &NameDescription {
+        &self.ndescr
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AlgorithmType {
+    /// the operands and operators of the MathOperation, in postfix (Reverse Polish) notation
+    Math(Vec<MathOp>),
+    /// the script of a CustomAlgorithm, to be run by the application
+    Custom(AlgorithmText),
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AlgorithmTrigger {
+    /// the algorithm runs each time the parameter is extracted or computed
+    OnParameterUpdate(ParameterIdx),
+    /// the algorithm runs periodically, the period is in milliseconds
+    OnPeriodicRate(u64),
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlgorithmInput {
+    /// the name under which the algorithm refers to the input, if given
+    pub name: Option<String>,
+    pub param_instance: ParameterInstanceRef,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlgorithmOutput {
+    /// the name under which the algorithm refers to the output, if given
+    pub name: Option<String>,
+    pub pidx: ParameterIdx,
+}
+
+/// An element of a MathOperation
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MathOp {
+    /// pushes the constant on the stack
+    Value(f64),
+    /// pushes the value of the parameter being calibrated (only in calibrators)
+    ThisParameter,
+    /// pushes the value of the parameter instance
+    ParameterInstance(ParameterInstanceRef),
+    /// pops its operands from the stack and pushes the result
+    Operator(MathOperator),
+}
+
+/// The operators of the MathOperation, named as in XTCE
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MathOperator {
+    /// +
+    Add,
+    /// -
+    Subtract,
+    /// *
+    Multiply,
+    /// /
+    Divide,
+    /// %
+    Modulo,
+    /// ^, the value below the top of the stack raised to the power of the top value
+    Power,
+    /// y^x, the top of the stack raised to the power of the value below it
+    ReversePower,
+    /// ln
+    Ln,
+    /// log, base 10
+    Log,
+    /// e^x
+    Exp,
+    /// 1/x
+    Inverse,
+    /// x!
+    Factorial,
+    /// tan
+    Tan,
+    /// cos
+    Cos,
+    /// sin
+    Sin,
+    /// atan
+    Atan,
+    /// atan2
+    Atan2,
+    /// acos
+    Acos,
+    /// asin
+    Asin,
+    /// tanh
+    Tanh,
+    /// cosh
+    Cosh,
+    /// sinh
+    Sinh,
+    /// atanh
+    Atanh,
+    /// acosh
+    Acosh,
+    /// asinh
+    Asinh,
+    /// swap, exchanges the two values on top of the stack
+    Swap,
+    /// drop, removes the value on top of the stack
+    Drop,
+    /// dup, duplicates the value on top of the stack
+    Dup,
+    /// over, pushes a copy of the second value of the stack
+    Over,
+    /// <<
+    LeftShift,
+    /// >>
+    RightShift,
+    /// &
+    BitwiseAnd,
+    /// |
+    BitwiseOr,
+    /// &&
+    LogicalAnd,
+    /// ||
+    LogicalOr,
+    /// !
+    LogicalNot,
+    /// abs
+    Abs,
+    /// div, integer division
+    IntegerDivide,
+    /// ==
+    Equal,
+    /// !=
+    NotEqual,
+    /// <
+    LessThan,
+    /// <=
+    LessOrEqual,
+    /// >
+    GreaterThan,
+    /// >=
+    GreaterOrEqual,
+}
+
+impl std::fmt::Display for MathOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use MathOperator::*;
+
+        let s = match self {
+            Add => "+",
+            Subtract => "-",
+            Multiply => "*",
+            Divide => "/",
+            Modulo => "%",
+            Power => "^",
+            ReversePower => "y^x",
+            Ln => "ln",
+            Log => "log",
+            Exp => "e^x",
+            Inverse => "1/x",
+            Factorial => "x!",
+            Tan => "tan",
+            Cos => "cos",
+            Sin => "sin",
+            Atan => "atan",
+            Atan2 => "atan2",
+            Acos => "acos",
+            Asin => "asin",
+            Tanh => "tanh",
+            Cosh => "cosh",
+            Sinh => "sinh",
+            Atanh => "atanh",
+            Acosh => "acosh",
+            Asinh => "asinh",
+            Swap => "swap",
+            Drop => "drop",
+            Dup => "dup",
+            Over => "over",
+            LeftShift => "<<",
+            RightShift => ">>",
+            BitwiseAnd => "&",
+            BitwiseOr => "|",
+            LogicalAnd => "&&",
+            LogicalOr => "||",
+            LogicalNot => "!",
+            Abs => "abs",
+            IntegerDivide => "div",
+            Equal => "==",
+            NotEqual => "!=",
+            LessThan => "<",
+            LessOrEqual => "<=",
+            GreaterThan => ">",
+            GreaterOrEqual => ">=",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A command definition, the arguments are encoded as specified by its command container.
+///
+/// The container of a command inheriting from a base command is encoded after the containers of
+/// its ancestors; the BaseContainer of the command container is not used.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetaCommand {
+    pub ndescr: NameDescription,
+    pub base_meta_command: Option<MetaCommandIdx>,
+    /// the values fixed by this command for arguments of its base commands, see
+    /// [MissionDatabase::effective_arguments]
+    pub argument_assignments: Vec<ArgumentAssignment>,
+    //abstract is a reserved word in Rust
+    pub abstract_: bool,
+    pub arguments: Vec<Argument>,
+    pub command_container: Option<CommandContainer>,
+    /// the verifiers from the VerifierSet, in document order; the verifiers of the base commands are not included
+    pub verifiers: Vec<CommandVerifier>,
+    /// the significance of this command, see [MissionDatabase::command_significance] for the inherited one
+    pub default_significance: Option<Significance>,
+    /// the constraints from the TransmissionConstraintList, in document order
+    pub transmission_constraints: Vec<TransmissionConstraint>,
+}
+
+/// The value given by a command to an argument of one of its base commands.
+///
+/// The value is the engineering value, converted according to the type of the argument.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArgumentAssignment {
+    pub name: NameIdx,
+    pub value: Value,
+}
+
+/// A condition which has to be met for the command to be sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransmissionConstraint {
+    pub criteria: MatchCriteriaIdx,
+    /// how long to wait for the criteria to become true before rejecting the command;
+    /// if not specified the criteria is checked only once
+    pub timeout: Option<Duration>,
+}
+
+/// The consequences of sending a command, to be shown to the operator before the command is sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Significance {
+    pub consequence_level: ConsequenceLevel,
+    pub reason_for_warning: Option<String>,
+}
+
+/// The XTCE 1.2 consequence levels, in increasing order of severity, followed by the user1 and user2 levels
+/// of XTCE 1.1.
+///
+/// The other XTCE 1.1 levels are mapped when parsing: normal to Normal, vital to Warning, critical to Critical
+/// and forbidden to Severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConsequenceLevel {
+    /// "none" in XTCE 1.2
+    Normal,
+    Watch,
+    Warning,
+    Distress,
+    Critical,
+    Severe,
+    /// mission specific level of XTCE 1.1; its severity relative to the other levels is not defined
+    User1,
+    /// mission specific level of XTCE 1.1; its severity relative to the other levels is not defined
+    User2,
+}
+
+impl NamedItem for MetaCommand {
+    fn name_descr(&self) -> &NameDescription {
+        &self.ndescr
+    }
+}
+
+/// Defines how the execution of a command at a given stage is verified.
+///
+/// The verifier passes when the check succeeds within the check window. The verification itself is not performed
+/// by this library.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommandVerifier {
+    pub stage: VerifierStage,
+    pub check: VerifierCheck,
+    pub check_window: CheckWindow,
+}
+
+/// The stage of the command execution confirmed by a verifier, corresponding to the XTCE <...Verifier> elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerifierStage {
+    TransferredToRange,
+    SentFromRange,
+    Received,
+    Accepted,
+    Queued,
+    Execution,
+    Complete,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerifierCheck {
+    /// the verifier passes when the container is received
+    Container(ContainerIdx),
+    /// the verifier passes when the criteria evaluates to true
+    Criteria(MatchCriteriaIdx),
+}
+
+/// The time window in which a verifier is checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckWindow {
+    pub time_to_start_checking: Option<Duration>,
+    pub time_to_stop_checking: Duration,
+    pub relative_to: CheckWindowStart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CheckWindowStart {
+    /// the window starts when the command is released
+    CommandRelease,
+    /// the window starts when the previous verifier has passed
+    TimeLastVerifierPassed,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Argument {
+    pub ndescr: NameDescription,
+    pub atype: DataTypeIdx,
+    /// the engineering value used if no value is given for the argument, converted according to the argument type
+    pub initial_value: Option<Value>,
+}
+
+impl NamedItem for Argument {
+    fn name_descr(&self) -> &NameDescription {
+        &self.ndescr
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommandContainer {
+    pub ndescr: NameDescription,
+    pub entries: Vec<CommandContainerEntry>,
+    /// the byte used to fill the bits not covered by any entry
+    pub idle_pattern: u8,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommandContainerEntry {
+    pub location_in_container: Option<LocationInContainerInBits>,
+    pub data: CommandEntryData,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CommandEntryData {
+    /// the name of an argument of the command or of one of its base commands
+    ArgumentRef(NameIdx),
+    FixedValue(FixedValueEntry),
+}
+
+/// Constant bits of a command
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FixedValueEntry {
+    /// the value is right aligned: only the last size_in_bits bits are encoded
+    pub binary_value: Vec<u8>,
+    pub size_in_bits: u32,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContainerEntry {
     pub location_in_container: Option<LocationInContainerInBits>,
     pub include_condition: Option<MatchCriteriaIdx>,
+    pub repeat: Option<RepeatEntry>,
     pub data: ContainerEntryData,
 }
 
+/// The entry is extracted count times, the offset (in bits) is skipped between two repetitions.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepeatEntry {
+    pub count: IntegerValue,
+    pub offset_in_bits: u32,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ContainerEntryData {
     ParameterRef(ParameterIdx),
     ContainerRef(ContainerIdx),
     IndirectParameterRef(IndirectParameterRefEntry),
     ArrayParameterRef(ArrayParameterRefEntry),
+    ParameterSegmentRef(ParameterSegmentRefEntry),
+    ContainerSegmentRef(ContainerSegmentRefEntry),
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocationInContainerInBits {
     pub reference_location: ReferenceLocationType,
     pub location_in_bits: i32,
@@ -308,17 +863,47 @@ pub struct LocationInContainerInBits {
 /// The location may be relative to the start of the container (containerStart),
 /// or relative to the end of the previous entry (previousEntry)
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ReferenceLocationType {
     ContainerStart,
     PreviousEntry,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MatchCriteria {
     Comparison(Comparison),
     ComparisonList(Vec<Comparison>),
+    BooleanExpression(BooleanExpression),
+    CustomAlgorithm(CustomAlgorithm),
+}
+
+/// A match criteria computed by an algorithm from the values of its input parameters.
+///
+/// The algorithm is not executed by the crate, it is passed to the
+/// [CustomAlgorithmExecutor](crate::proc::CustomAlgorithmExecutor) registered in the ProcessorData.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomAlgorithm {
+    pub name: String,
+    pub algorithm_text: Option<AlgorithmText>,
+    /// the algorithm is called with the values of these parameters, in this order
+    pub inputs: Vec<ParameterInstanceRef>,
+}
+
+/// A tree of conditions combined with AND and OR
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BooleanExpression {
+    Condition(Comparison),
+    And(Vec<BooleanExpression>),
+    Or(Vec<BooleanExpression>),
 }
 
+/// Compares the value of a parameter (or of one of its members) with a constant.
+///
+/// If the compared value is an array, the constant is its length.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Comparison {
     pub param_instance: ParameterInstanceRef,
     pub comparison_operator: ComparisonOperator,
@@ -326,6 +911,7 @@ pub struct Comparison {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ComparisonOperator {
     Equality,
     Inequality,
@@ -350,6 +936,7 @@ impl std::fmt::Display for ComparisonOperator {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParameterInstanceRef {
     pub pidx: ParameterIdx,
     pub member_path: Option<MemberPath>,
@@ -381,6 +968,7 @@ impl ParameterInstanceRef {
 }
 
 //definition for custom data decoder
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DecoderDef {
     //TODO: it should contain a factory function which can create data decoders
     //e.g. LeadingSizeBinaryDecoder::new()
@@ -390,29 +978,73 @@ pub struct DecoderDef {
     //before the processing starts, the factory function will be called to instantiate decoders which will then be used during the processing
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndirectParameterRefEntry {}
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArrayParameterRefEntry {}
 
+/// A part of a parameter split over several entries.
+/// The segments are concatenated in order and the result is decoded as the parameter raw value.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParameterSegmentRefEntry {
+    pub pidx: ParameterIdx,
+    /// if not specified, the segments are concatenated in the order in which they appear in the packet;
+    /// the first segment has order 1
+    pub order: Option<u32>,
+    pub size_in_bits: u32,
+}
+
+/// A part of a container split over several entries.
+/// The segments are concatenated in order and the entries of the container are extracted from the result.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContainerSegmentRefEntry {
+    pub cidx: ContainerIdx,
+    /// if not specified, the segments are concatenated in the order in which they appear in the packet;
+    /// the first segment has order 1
+    pub order: Option<u32>,
+    pub size_in_bits: u32,
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IntegerValue {
     FixedValue(i64),
     DynamicValue(DynamicValueType),
 }
 
+/// A value computed while processing
 #[derive(Debug)]
-pub struct DynamicValueType {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DynamicValueType {
+    Parameter(ParameterDynamicValue),
+    /// the value of the first lookup whose criteria matches
+    DiscreteLookupList(Vec<DiscreteLookup>),
+}
+
+/// The value of a parameter, with an optional linear adjustment
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParameterDynamicValue {
     pub para_ref: ParameterInstanceRef,
     pub adjustment: Option<LinearAdjustment>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiscreteLookup {
+    pub criteria: MatchCriteriaIdx,
+    pub value: i64,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LinearAdjustment {
     pub slope: f64,
     pub intercept: f64
 }
-static mut count:u64 = 0;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpaceSystem {
     pub id: SpaceSystemIdx,
     pub fqn: QualifiedName,
@@ -420,6 +1052,26 @@ pub struct SpaceSystem {
     pub parameters: HashMap<NameIdx, ParameterIdx>,
     pub parameter_types: HashMap<NameIdx, DataTypeIdx>,
     pub containers: HashMap<NameIdx, ContainerIdx>,
+    pub algorithms: HashMap<NameIdx, AlgorithmIdx>,
+    /// the argument types are stored in the same vector as the parameter types
+    pub argument_types: HashMap<NameIdx, DataTypeIdx>,
+    pub meta_commands: HashMap<NameIdx, MetaCommandIdx>,
+    /// the operationalStatus attribute of the SpaceSystem element
+    pub operational_status: Option<String>,
+    /// the version from the Header element
+    pub version: Option<String>,
+    /// the date from the Header element
+    pub date: Option<String>,
+}
+
+/// The version metadata of a space system, see [MissionDatabase::version_info]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionInfo<'a> {
+    /// fully qualified name of the space system
+    pub space_system: String,
+    pub operational_status: Option<&'a str>,
+    pub version: Option<&'a str>,
+    pub date: Option<&'a str>,
 }
 
 impl SpaceSystem {
@@ -431,6 +1083,12 @@ impl SpaceSystem {
             parameters: HashMap::new(),
             parameter_types: HashMap::new(),
             containers: HashMap::new(),
+            algorithms: HashMap::new(),
+            argument_types: HashMap::new(),
+            meta_commands: HashMap::new(),
+            operational_status: None,
+            version: None,
+            date: None,
         }
     }
 
@@ -449,8 +1107,11 @@ impl MissionDatabase {
             parameters: Vec::new(),
             containers: Vec::new(),
             match_criteria: Vec::new(),
+            algorithms: Vec::new(),
+            meta_commands: Vec::new(),
             child_containers: HashMap::new(),
-            decoder_defs: Vec::new()
+            decoder_defs: Vec::new(),
+            aliases: HashMap::new(),
         };
         //create the root space system - it has "" name and an empty qualified name
         let ss_idx = SpaceSystemIdx::new(0);
@@ -479,6 +1140,65 @@ impl MissionDatabase {
         }
     }
 
+    /// removes the space system and its sub-systems.
+    ///
+    /// The items of the removed systems are tombstoned: they stay in the item vectors such that the indices of
+    /// the other items do not change, but they cannot be found by name or alias anymore, they are no longer
+    /// child containers of their base containers and they do not have initial values.
+    /// The mdb does not check whether the remaining items refer to the removed ones; that is the responsibility
+    /// of the caller, typically the space system is removed to load again a new version of it.
+    ///
+    /// The root space system cannot be removed, see [MissionDatabase::clear].
+    pub fn remove_space_system(&mut self, fqn: &QualifiedName) -> Result<()> {
+        if fqn.is_root() {
+            return Err(MdbError::InvalidName("The root space system cannot be removed".to_owned()));
+        }
+        if !self.space_systems_qn.contains_key(fqn) {
+            return Err(MdbError::InvalidName(format!("Unknown space system {}", self.qn_to_string(fqn))));
+        }
+
+        let (removed, kept): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.space_systems).into_iter().partition(|ss| ss.fqn.starts_with(fqn));
+        self.space_systems = kept;
+        self.space_systems_qn.clear();
+        for (i, ss) in self.space_systems.iter_mut().enumerate() {
+            ss.id = SpaceSystemIdx::new(i);
+            self.space_systems_qn.insert(ss.fqn.clone(), ss.id);
+        }
+
+        let removed_containers: HashSet<_> = removed.iter().flat_map(|ss| ss.containers.values().copied()).collect();
+        self.child_containers.retain(|base, _| !removed_containers.contains(base));
+        for children in self.child_containers.values_mut() {
+            children.retain(|c| !removed_containers.contains(c));
+        }
+
+        // the aliases of the removed items may have hidden duplicates of the remaining ones
+        self.aliases.clear();
+        let targets: Vec<_> = self
+            .space_systems
+            .iter()
+            .flat_map(|ss| {
+                let params = ss.parameters.values().map(|&idx| AliasTarget::Parameter(idx));
+                params.chain(ss.containers.values().map(|&idx| AliasTarget::Container(idx)))
+            })
+            .collect();
+        for target in targets {
+            self.add_aliases(target);
+        }
+
+        Ok(())
+    }
+
+    /// removes all the space systems and items, keeping only the root space system.
+    ///
+    /// The names stay interned in the name db.
+    pub fn clear(&mut self) {
+        let name_db = Arc::clone(&self.name_db);
+        // the root name is the first one interned in both name dbs so it has the same index
+        *self = MissionDatabase::new();
+        self.name_db = name_db;
+    }
+
     pub fn add_parameter_type(
         &mut self,
         space_system: &QualifiedName,
@@ -494,6 +1214,17 @@ impl MissionDatabase {
         idx
     }
 
+    pub fn add_argument_type(&mut self, space_system: &QualifiedName, atype: DataType) -> DataTypeIdx {
+        let name = atype.name();
+
+        let idx = DataTypeIdx::new(self.parameter_types.len());
+        self.parameter_types.push(atype);
+
+        let ss = self.get_space_system_mut(space_system).unwrap();
+        ss.argument_types.insert(name, idx);
+        idx
+    }
+
     pub fn add_parameter(
         &mut self,
         space_system: &QualifiedName,
@@ -506,6 +1237,7 @@ impl MissionDatabase {
 
         let ss = self.get_space_system_mut(space_system).unwrap();
         ss.parameters.insert(param_name, idx);
+        self.add_aliases(AliasTarget::Parameter(idx));
         idx
     }
 
@@ -530,7 +1262,68 @@ impl MissionDatabase {
         if let Some(base_idx) = base_idx {
             self.child_containers.entry(base_idx).or_insert(Vec::new()).push(idx);
         }
+        self.add_aliases(AliasTarget::Container(idx));
+
+        idx
+    }
+
+    // indexes the aliases of the item; if the alias is already used in the namespace, the item with
+    // the smallest qualified name is kept such that the result does not depend on the order in which
+    // the items are added. The parser reports the duplicates as warnings.
+    fn add_aliases(&mut self, target: AliasTarget) {
+        let nd = match target {
+            AliasTarget::Parameter(idx) => self.parameters[idx.index()].name_descr(),
+            AliasTarget::Container(idx) => self.containers[idx.index()].name_descr(),
+        };
+        let mut duplicates = Vec::new();
+        for (name_space, alias) in &nd.aliases {
+            let ns_aliases = self.aliases.entry(name_space.clone()).or_default();
+            match ns_aliases.get(alias) {
+                Some(&existing) => duplicates.push((name_space.clone(), alias.clone(), existing)),
+                None => {
+                    ns_aliases.insert(alias.clone(), target);
+                }
+            }
+        }
+        for (name_space, alias, existing) in duplicates {
+            let existing_qn = self.alias_target_qn(existing);
+            let target_qn = self.alias_target_qn(target);
+            if target_qn < existing_qn {
+                self.aliases.get_mut(&name_space).unwrap().insert(alias, target);
+            }
+        }
+    }
+
+    pub(crate) fn alias_target_qn(&self, target: AliasTarget) -> String {
+        match target {
+            AliasTarget::Parameter(idx) => self.parameter_qn(idx),
+            AliasTarget::Container(idx) => self.container_qn(idx),
+        }
+    }
+
+    pub fn add_algorithm(&mut self, space_system: &QualifiedName, algo: Algorithm) -> AlgorithmIdx {
+        let name = algo.name();
+
+        let idx = AlgorithmIdx::new(self.algorithms.len());
+        self.algorithms.push(algo);
 
+        let ss = self.get_space_system_mut(space_system).unwrap();
+        ss.algorithms.insert(name, idx);
+        idx
+    }
+
+    pub fn add_meta_command(
+        &mut self,
+        space_system: &QualifiedName,
+        cmd: MetaCommand,
+    ) -> MetaCommandIdx {
+        let name = cmd.name();
+
+        let idx = MetaCommandIdx::new(self.meta_commands.len());
+        self.meta_commands.push(cmd);
+
+        let ss = self.get_space_system_mut(space_system).unwrap();
+        ss.meta_commands.insert(name, idx);
         idx
     }
 
@@ -555,6 +1348,26 @@ impl MissionDatabase {
         }
     }
 
+    /// returns the operational status, version and date of each space system sorted by qualified name,
+    /// for example to check which build of the MDB has been loaded.
+    ///
+    /// The root space system which does not correspond to any XTCE element is not included.
+    pub fn version_info(&self) -> Vec<VersionInfo<'_>> {
+        let mut v: Vec<_> = self
+            .space_systems
+            .iter()
+            .filter(|ss| !ss.fqn.is_root())
+            .map(|ss| VersionInfo {
+                space_system: self.qn_to_string(&ss.fqn),
+                operational_status: ss.operational_status.as_deref(),
+                version: ss.version.as_deref(),
+                date: ss.date.as_deref(),
+            })
+            .collect();
+        v.sort_by(|a, b| a.space_system.cmp(&b.space_system));
+        v
+    }
+
     pub fn get_container(&self, idx: ContainerIdx) -> &SequenceContainer {
         &self.containers[idx.index()]
     }
@@ -593,17 +1406,136 @@ impl MissionDatabase {
         self.get_space_system(space_system).and_then(|ss| ss.parameters.get(&name)).map(|idx| *idx)
     }
 
+    pub fn get_algorithm(&self, idx: AlgorithmIdx) -> &Algorithm {
+        &self.algorithms[idx.index()]
+    }
+
+    pub fn get_algorithm_idx(
+        &self,
+        space_system: &QualifiedName,
+        name: NameIdx,
+    ) -> Option<AlgorithmIdx> {
+        self.get_space_system(space_system).and_then(|ss| ss.algorithms.get(&name)).copied()
+    }
+
+    pub fn get_argument_type_idx(
+        &self,
+        space_system: &QualifiedName,
+        name: NameIdx,
+    ) -> Option<DataTypeIdx> {
+        self.get_space_system(space_system).and_then(|ss| ss.argument_types.get(&name)).copied()
+    }
+
+    pub fn get_meta_command(&self, idx: MetaCommandIdx) -> &MetaCommand {
+        &self.meta_commands[idx.index()]
+    }
+
+    pub fn get_meta_command_idx(
+        &self,
+        space_system: &QualifiedName,
+        name: NameIdx,
+    ) -> Option<MetaCommandIdx> {
+        self.get_space_system(space_system).and_then(|ss| ss.meta_commands.get(&name)).copied()
+    }
+
+    /// returns the argument with the given name of the command or of one of its base commands
+    pub fn get_argument(&self, cmdidx: MetaCommandIdx, name: NameIdx) -> Option<&Argument> {
+        let cmd = self.get_meta_command(cmdidx);
+        match cmd.arguments.iter().find(|a| a.name() == name) {
+            Some(arg) => Some(arg),
+            None => cmd.base_meta_command.and_then(|base| self.get_argument(base, name)),
+        }
+    }
+
+    /// checks the engineering value of the argument against its type: the value has to be inside the valid range
+    /// of the numeric types and has to be one of the labels of the enumerated types.
+    ///
+    /// Returns an OutOfRange error for a value outside the range and an InvalidValue error for an unknown label
+    /// or a value which is not a label. The valid ranges applying to the raw values are not checked.
+    pub fn validate_argument(&self, arg: &Argument, value: &Value) -> Result<()> {
+        let dtype = self.get_data_type(arg.atype);
+        match &dtype.type_data {
+            TypeData::Integer(_) | TypeData::Float(_) => dtype.check_valid_range(value, true).map_err(|e| match e {
+                MdbError::OutOfRange(msg) => MdbError::OutOfRange(format!(
+                    "Invalid value for the argument {}: {}",
+                    self.name2str(arg.name()),
+                    msg
+                )),
+                e => e,
+            }),
+            TypeData::Enumerated(edt) => {
+                let label = match value {
+                    Value::Enumerated(ev) => ev.value.as_str(),
+                    Value::StringValue(s) => s.as_str(),
+                    _ => {
+                        return Err(MdbError::InvalidValue(format!(
+                            "Invalid value {} for the enumerated argument {}, a label is expected",
+                            value,
+                            self.name2str(arg.name())
+                        )))
+                    }
+                };
+                if edt.enumeration.iter().any(|ve| ve.label == label) {
+                    Ok(())
+                } else {
+                    Err(MdbError::InvalidValue(format!(
+                        "Invalid label '{}' for the argument {}, should be one of {:?}",
+                        label,
+                        self.name2str(arg.name()),
+                        edt.enumeration.iter().map(|ve| ve.label.as_str()).collect::<Vec<_>>()
+                    )))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// returns the arguments for which a value has to be provided when sending the command: the arguments
+    /// of the base commands (starting with the root command) followed by the command's own arguments,
+    /// excluding those which have been assigned a value in the inheritance chain.
+    pub fn effective_arguments(&self, cmdidx: MetaCommandIdx) -> Vec<&Argument> {
+        let assigned: HashSet<NameIdx> = self.argument_assignments(cmdidx).iter().map(|aa| aa.name).collect();
+        let mut arguments = Vec::new();
+        let mut idx = Some(cmdidx);
+        while let Some(cmd) = idx.map(|idx| self.get_meta_command(idx)) {
+            arguments.splice(0..0, cmd.arguments.iter().filter(|a| !assigned.contains(&a.name())));
+            idx = cmd.base_meta_command;
+        }
+        arguments
+    }
+
+    /// returns the argument assignments of the command and of its base commands.
+    ///
+    /// If an argument is assigned more than once, the assignment of the command closest to the given one is returned.
+    pub fn argument_assignments(&self, cmdidx: MetaCommandIdx) -> Vec<&ArgumentAssignment> {
+        let mut assignments: Vec<&ArgumentAssignment> = Vec::new();
+        let mut idx = Some(cmdidx);
+        while let Some(cmd) = idx.map(|idx| self.get_meta_command(idx)) {
+            for aa in &cmd.argument_assignments {
+                if !assignments.iter().any(|a| a.name == aa.name) {
+                    assignments.push(aa);
+                }
+            }
+            idx = cmd.base_meta_command;
+        }
+        assignments
+    }
+
+    /// returns the significance of the command, inherited from the closest base command if the command
+    /// does not define one
+    pub fn command_significance(&self, cmdidx: MetaCommandIdx) -> Option<&Significance> {
+        let cmd = self.get_meta_command(cmdidx);
+        match &cmd.default_significance {
+            Some(significance) => Some(significance),
+            None => cmd.base_meta_command.and_then(|base| self.command_significance(base)),
+        }
+    }
+
     pub fn get_match_criteria(&self, idx: MatchCriteriaIdx) -> &MatchCriteria {
         &self.match_criteria[idx.index()]
     }
 
     pub fn name2str(&self, idx: NameIdx) -> &str {
-        unsafe {count+=1;
-        if count == 10000 {
-            panic!("bum")
-        }
-        };
-        //panic!("bau bau");
         self.name_db.try_resolve(&idx).unwrap_or("<none>")
     }
 
@@ -611,6 +1543,52 @@ impl MissionDatabase {
         qn.to_string(&self.name_db)
     }
 
+    /// returns the fully qualified name of the parameter (e.g. /YSS/SIMULATOR/BatteryVoltage1)
+    pub fn parameter_qn(&self, pidx: ParameterIdx) -> String {
+        let name = self.get_parameter(pidx).name();
+        self.item_qn(name, |ss| ss.parameters.get(&name) == Some(&pidx))
+    }
+
+    /// returns the fully qualified name of the container
+    pub fn container_qn(&self, cidx: ContainerIdx) -> String {
+        let name = self.get_container(cidx).name();
+        self.item_qn(name, |ss| ss.containers.get(&name) == Some(&cidx))
+    }
+
+    /// returns the fully qualified name of the data type
+    pub fn data_type_qn(&self, dtidx: DataTypeIdx) -> String {
+        let name = self.get_data_type(dtidx).name();
+        self.item_qn(name, |ss| {
+            ss.parameter_types.get(&name) == Some(&dtidx)
+                || ss.argument_types.get(&name) == Some(&dtidx)
+        })
+    }
+
+    /// returns the fully qualified name of the algorithm
+    pub fn algorithm_qn(&self, aidx: AlgorithmIdx) -> String {
+        let name = self.get_algorithm(aidx).name();
+        self.item_qn(name, |ss| ss.algorithms.get(&name) == Some(&aidx))
+    }
+
+    /// returns the fully qualified name of the command
+    pub fn meta_command_qn(&self, cmdidx: MetaCommandIdx) -> String {
+        let name = self.get_meta_command(cmdidx).name();
+        self.item_qn(name, |ss| ss.meta_commands.get(&name) == Some(&cmdidx))
+    }
+
+    // the items do not know their space system so we look for the space system containing them.
+    // if none is found (should not happen), the simple name is returned
+    fn item_qn<F: Fn(&SpaceSystem) -> bool>(&self, name: NameIdx, owns: F) -> String {
+        match self.space_systems.iter().find(|ss| owns(ss)) {
+            Some(ss) => {
+                let mut qn = ss.fqn.clone();
+                qn.push(name);
+                self.qn_to_string(&qn)
+            }
+            None => self.name2str(name).to_owned(),
+        }
+    }
+
     pub fn get_or_intern(&mut self, name_str: &str) -> NameIdx {
         self.name_db.get_or_intern(name_str)
     }
@@ -629,10 +1607,366 @@ impl MissionDatabase {
         let ss = self.get_space_system(&ssqn)?;
         ss.containers.get(&name).copied()
     }
+
+    /// finds the parameter or container having the alias in the given namespace
+    pub fn find_by_alias(&self, name_space: &str, alias: &str) -> Option<AliasTarget> {
+        self.aliases.get(name_space)?.get(alias).copied()
+    }
+
+    /// searches a parameter by fully qualified name
+    pub fn search_parameter(&self, qnstr: &str) -> Option<ParameterIdx> {
+        let (ssqn, name) = QualifiedName::parse_ss_name(&self.name_db, qnstr)?;
+
+        let ss = self.get_space_system(&ssqn)?;
+        ss.parameters.get(&name).copied()
+    }
+
+    /// searches an algorithm by fully qualified name
+    pub fn search_algorithm(&self, qnstr: &str) -> Option<AlgorithmIdx> {
+        let (ssqn, name) = QualifiedName::parse_ss_name(&self.name_db, qnstr)?;
+
+        let ss = self.get_space_system(&ssqn)?;
+        ss.algorithms.get(&name).copied()
+    }
+
+    /// searches a command by fully qualified name
+    pub fn search_meta_command(&self, qnstr: &str) -> Option<MetaCommandIdx> {
+        let (ssqn, name) = QualifiedName::parse_ss_name(&self.name_db, qnstr)?;
+
+        let ss = self.get_space_system(&ssqn)?;
+        ss.meta_commands.get(&name).copied()
+    }
+
+    /// checks the consistency of the mdb items which the processing relies upon, without it the extraction of
+    /// the cyclic containers or types would recurse without end.
+    ///
+    /// It is called by the parser once all the items are loaded; it has to be called again if the items are
+    /// modified afterwards.
+    pub fn validate(&self) -> Result<()> {
+        self.check_container_inheritance()?;
+        self.check_type_references()
+    }
+
+    /// checks that no container inherits (directly or transitively) from itself.
+    ///
+    /// Returns an InvalidMdb error listing the containers forming the cycle.
+    pub fn check_container_inheritance(&self) -> Result<()> {
+        // containers whose inheritance chain is known to end in a root container
+        let mut checked = HashSet::new();
+
+        for container in &self.containers {
+            let mut chain = Vec::new();
+            let mut cidx = container.idx;
+            loop {
+                if checked.contains(&cidx) {
+                    break;
+                }
+                if let Some(pos) = chain.iter().position(|c| *c == cidx) {
+                    let names: Vec<String> =
+                        chain[pos..].iter().chain([&cidx]).map(|c| self.container_qn(*c)).collect();
+                    return Err(MdbError::InvalidMdb(format!(
+                        "Cyclic container inheritance: {}",
+                        names.join(" -> ")
+                    )));
+                }
+                chain.push(cidx);
+                match self.get_container(cidx).base_container {
+                    Some((base_idx, _)) => cidx = base_idx,
+                    None => break,
+                }
+            }
+            checked.extend(chain);
+        }
+        Ok(())
+    }
+
+    /// checks that no aggregate or array type contains itself, directly or through the types of its
+    /// members or elements.
+    ///
+    /// Returns an InvalidMdb error listing the types forming the cycle.
+    pub fn check_type_references(&self) -> Result<()> {
+        // types known to not lead back to themselves
+        let mut checked = HashSet::new();
+        let mut chain = Vec::new();
+        for idx in 0..self.parameter_types.len() {
+            self.check_type_cycle(DataTypeIdx::new(idx), &mut chain, &mut checked)?;
+        }
+        Ok(())
+    }
+
+    // depth first search through the member and element types, chain holds the types being visited
+    fn check_type_cycle(
+        &self,
+        dtidx: DataTypeIdx,
+        chain: &mut Vec<DataTypeIdx>,
+        checked: &mut HashSet<DataTypeIdx>,
+    ) -> Result<()> {
+        if checked.contains(&dtidx) {
+            return Ok(());
+        }
+        if let Some(pos) = chain.iter().position(|t| *t == dtidx) {
+            let names: Vec<String> =
+                chain[pos..].iter().chain([&dtidx]).map(|t| self.data_type_qn(*t)).collect();
+            return Err(MdbError::InvalidMdb(format!(
+                "Cyclic type reference: {}",
+                names.join(" -> ")
+            )));
+        }
+
+        chain.push(dtidx);
+        match &self.get_data_type(dtidx).type_data {
+            TypeData::Aggregate(atype) => {
+                for member in &atype.members {
+                    self.check_type_cycle(member.dtype, chain, checked)?;
+                }
+            }
+            TypeData::Array(atype) => self.check_type_cycle(atype.dtype, chain, checked)?,
+            _ => {}
+        }
+        chain.pop();
+        checked.insert(dtidx);
+
+        Ok(())
+    }
+
+    /// returns all the parameters extracted from a packet matching the given container.
+    ///
+    /// The list starts with the parameters of the root container of the inheritance chain and includes
+    /// the parameters of the containers referenced in the entry lists. The include conditions are ignored,
+    /// all the entries are assumed present. Each parameter appears only once, in the order of its first occurrence.
+    pub fn container_parameters(&self, idx: ContainerIdx) -> Vec<ParameterIdx> {
+        let mut result = Vec::new();
+        let mut seen = HashSet::new();
+        let mut visited = HashSet::new();
+        for cidx in self.inheritance_chain(idx) {
+            self.collect_entry_parameters(cidx, &mut result, &mut seen, &mut visited);
+        }
+        result
+    }
+
+    /// returns the initial values of all the parameters having one, in the order of the parameter indices.
+    ///
+    /// The initial value of the parameter has precedence over the one of its type.
+    /// The initial values are engineering values, they are used also as raw values.
+    pub fn initial_values(&self) -> ParameterValueList {
+        let mut list = ParameterValueList::new();
+        let mut pidxs: Vec<_> = self.space_systems.iter().flat_map(|ss| ss.parameters.values().copied()).collect();
+        pidxs.sort();
+        for pidx in pidxs {
+            let para = self.get_parameter(pidx);
+            let initial_value = para
+                .initial_value
+                .as_ref()
+                .or_else(|| para.ptype.and_then(|ptype| self.get_data_type(ptype).initial_value.as_ref()));
+            if let Some(v) = initial_value {
+                list.push(ParameterValue::new(pidx, v.clone(), v.clone()));
+            }
+        }
+        list
+    }
+
+    /// returns the names of the parameters referenced by the entries of the container, in the order of the entries.
+    ///
+    /// Unlike container_parameters, only the entry list of this container is considered: the inherited entries
+    /// and the parameters of the referenced containers are not included.
+    pub fn container_entry_names(&self, idx: ContainerIdx) -> Vec<&str> {
+        self.get_container(idx)
+            .entries
+            .iter()
+            .filter_map(|entry| match &entry.data {
+                ContainerEntryData::ParameterRef(pidx) => Some(*pidx),
+                ContainerEntryData::ParameterSegmentRef(seg) => Some(seg.pidx),
+                _ => None,
+            })
+            .map(|pidx| self.name2str(self.get_parameter(pidx).name()))
+            .collect()
+    }
+
+    /// checks that the entries of the container, including the ones inherited from the base containers,
+    /// do not overlap and do not extend past the fixed size of the container.
+    ///
+    /// The entry positions are computed from the sizes of the parameter types known without looking at the data.
+    /// After an entry whose size is not known (e.g. a terminated string), the following entries are skipped until
+    /// one is located relative to the container start. The entries having an include condition may be mutually
+    /// exclusive so they are not checked against each other.
+    ///
+    /// Returns an InvalidMdb error listing all the problems found.
+    pub fn check_container_layout(&self, idx: ContainerIdx) -> Result<()> {
+        let mut layout = EntryLayout { pos: Some(0), placed: Vec::new(), problems: Vec::new() };
+        let mut visiting = Vec::new();
+        for cidx in self.inheritance_chain(idx) {
+            self.layout_entries(cidx, false, &mut layout, &mut visiting);
+        }
+
+        if let Some(size) = self.get_container(idx).size_in_bits {
+            for e in layout.placed.iter().filter(|e| e.end > size as i64) {
+                layout.problems.push(format!(
+                    "entry {} (bits {}..{}) extends past the container size {}",
+                    e.name, e.start, e.end, size
+                ));
+            }
+        }
+
+        if layout.problems.is_empty() {
+            Ok(())
+        } else {
+            Err(MdbError::InvalidMdb(format!(
+                "Invalid layout of container {}: {}",
+                self.container_qn(idx),
+                layout.problems.join("; ")
+            )))
+        }
+    }
+
+    // returns the container and its base containers, starting with the root of the inheritance chain
+    fn inheritance_chain(&self, idx: ContainerIdx) -> Vec<ContainerIdx> {
+        let mut chain = vec![idx];
+        let mut cidx = idx;
+        while let Some((base_idx, _)) = self.get_container(cidx).base_container {
+            if chain.contains(&base_idx) {
+                break;
+            }
+            chain.push(base_idx);
+            cidx = base_idx;
+        }
+        chain.reverse();
+        chain
+    }
+
+    // places the entries of the container in the layout, recursing into the container entries
+    fn layout_entries(
+        &self,
+        cidx: ContainerIdx,
+        conditional: bool,
+        layout: &mut EntryLayout,
+        visiting: &mut Vec<ContainerIdx>,
+    ) {
+        if visiting.contains(&cidx) {
+            return;
+        }
+        visiting.push(cidx);
+
+        for entry in &self.get_container(cidx).entries {
+            if let Some(lic) = &entry.location_in_container {
+                let loc = lic.location_in_bits as i64;
+                layout.pos = match lic.reference_location {
+                    ReferenceLocationType::ContainerStart => Some(loc),
+                    ReferenceLocationType::PreviousEntry => layout.pos.map(|p| p + loc),
+                };
+            }
+            let conditional = conditional || entry.include_condition.is_some();
+            if entry.repeat.is_some() {
+                log::info!(
+                    "Layout of container {}: skipping the size of a repeated entry",
+                    self.container_qn(cidx)
+                );
+                layout.pos = None;
+                continue;
+            }
+
+            let (name, size) = match &entry.data {
+                ContainerEntryData::ParameterRef(pidx) => {
+                    let size = self
+                        .get_parameter(*pidx)
+                        .ptype
+                        .and_then(|ptype| self.get_data_type(ptype).size_in_bits(self));
+                    (self.parameter_qn(*pidx), size)
+                }
+                ContainerEntryData::ContainerRef(ref_idx) => {
+                    self.layout_entries(*ref_idx, conditional, layout, visiting);
+                    continue;
+                }
+                ContainerEntryData::ParameterSegmentRef(seg) => {
+                    (format!("{} segment", self.parameter_qn(seg.pidx)), Some(seg.size_in_bits))
+                }
+                ContainerEntryData::ContainerSegmentRef(seg) => {
+                    (format!("{} segment", self.container_qn(seg.cidx)), Some(seg.size_in_bits))
+                }
+                ContainerEntryData::IndirectParameterRef(_) | ContainerEntryData::ArrayParameterRef(_) => {
+                    log::info!(
+                        "Layout of container {}: skipping the size of an indirect or array entry",
+                        self.container_qn(cidx)
+                    );
+                    layout.pos = None;
+                    continue;
+                }
+            };
+
+            let Some(start) = layout.pos else {
+                log::info!("Layout of container {}: skipping entry {} with unknown position", self.container_qn(cidx), name);
+                continue;
+            };
+            let Some(size) = size else {
+                log::info!("Layout of container {}: skipping entry {} with dynamic size", self.container_qn(cidx), name);
+                layout.pos = None;
+                continue;
+            };
+
+            let end = start + size as i64;
+            if start < 0 {
+                layout.problems.push(format!("entry {} starts before the container start (bit {})", name, start));
+            }
+            for e in layout.placed.iter().filter(|e| !(e.conditional && conditional)) {
+                if start < e.end && e.start < end {
+                    layout.problems.push(format!(
+                        "entry {} (bits {}..{}) overlaps entry {} (bits {}..{})",
+                        name, start, end, e.name, e.start, e.end
+                    ));
+                }
+            }
+            layout.placed.push(PlacedEntry { name, start, end, conditional });
+            layout.pos = Some(end);
+        }
+
+        visiting.pop();
+    }
+
+    // adds to the result the parameters from the entry list of the container, recursing into the container entries
+    fn collect_entry_parameters(
+        &self,
+        cidx: ContainerIdx,
+        result: &mut Vec<ParameterIdx>,
+        seen: &mut HashSet<ParameterIdx>,
+        visited: &mut HashSet<ContainerIdx>,
+    ) {
+        if !visited.insert(cidx) {
+            return;
+        }
+        for entry in &self.get_container(cidx).entries {
+            match entry.data {
+                ContainerEntryData::ParameterRef(pidx)
+                | ContainerEntryData::ParameterSegmentRef(ParameterSegmentRefEntry { pidx, .. }) => {
+                    if seen.insert(pidx) {
+                        result.push(pidx);
+                    }
+                }
+                ContainerEntryData::ContainerRef(ref_idx)
+                | ContainerEntryData::ContainerSegmentRef(ContainerSegmentRefEntry { cidx: ref_idx, .. }) => {
+                    self.collect_entry_parameters(ref_idx, result, seen, visited)
+                }
+                ContainerEntryData::IndirectParameterRef(_) | ContainerEntryData::ArrayParameterRef(_) => {}
+            }
+        }
+    }
 }
 
 
 
+// the state of the container layout check
+struct EntryLayout {
+    // position of the next entry relative to the container start, None if not known
+    pos: Option<i64>,
+    placed: Vec<PlacedEntry>,
+    problems: Vec<String>,
+}
+
+struct PlacedEntry {
+    name: String,
+    start: i64,
+    end: i64,
+    conditional: bool,
+}
+
 impl From<std::num::ParseIntError> for MdbError {
     fn from(e: std::num::ParseIntError) -> MdbError {
         return MdbError::InvalidValue(format!("{}", e));
@@ -0,0 +1,58 @@
+//! serde helpers for the MissionDatabase fields which cannot simply be derived
+
+/// The names are written as a list ordered by key and interned again in the same order when reading,
+/// such that each name gets back its NameIdx.
+pub(super) mod name_db {
+    use std::sync::Arc;
+
+    use lasso::{Key, ThreadedRodeo};
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    use crate::mdb::{NameDb, NameIdx};
+
+    pub fn serialize<S: Serializer>(name_db: &NameDb, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut names: Vec<(NameIdx, &str)> = name_db.iter().collect();
+        names.sort_unstable_by_key(|(k, _)| k.into_usize());
+        if names.iter().enumerate().any(|(i, (k, _))| k.into_usize() != i) {
+            return Err(S::Error::custom("the name database keys are not contiguous"));
+        }
+        serializer.collect_seq(names.iter().map(|(_, name)| name))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NameDb, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+        let name_db = ThreadedRodeo::<NameIdx>::with_capacity(lasso::Capacity::for_strings(names.len()));
+        for (i, name) in names.iter().enumerate() {
+            if name_db.get_or_intern(name).into_usize() != i {
+                return Err(D::Error::custom(format!("duplicate name '{}' in the name database", name)));
+            }
+        }
+        Ok(Arc::new(name_db))
+    }
+}
+
+/// Maps with keys which are not strings or numbers (not supported by some formats like JSON)
+/// are written as a list of (key, value) pairs.
+pub(super) mod map_as_vec {
+    use std::{collections::HashMap, hash::Hash};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(|v| v.into_iter().collect())
+    }
+}
@@ -1,25 +1,38 @@
-use std::fmt::{self, Formatter};
+use std::{
+    collections::HashMap,
+    fmt::{self, Formatter},
+};
 
 use smallvec::SmallVec;
 
-use crate::{bitbuffer::ByteOrder, value::Value};
+use crate::{
+    bitbuffer::ByteOrder,
+    value::{EnumeratedValue, Value},
+};
 
 use super::{
-    DataTypeIdx, DynamicValueType, IntegerValue, MissionDatabase, NameDescription, NameIdx,
-    NamedItem, UnitType, Result, MdbError,
+    DataTypeIdx, DynamicValueType, IntegerValue, MatchCriteriaIdx, MathOp, MissionDatabase,
+    NameDescription, NameIdx,
+    NamedItem, UnitType, Result, MdbError, utils::parse_integer_text,
 };
 
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataType {
     pub ndescr: NameDescription,
     pub encoding: DataEncoding,
     pub type_data: TypeData,
     pub units: Vec<UnitType>,
     pub calibrator: Option<Calibrator>,
+    /// the first calibrator whose criteria matches is used instead of the default calibrator
+    pub context_calibrators: Vec<ContextCalibrator>,
+    /// initial value (engineering) of the parameters of this type, the initial value of the parameter has precedence
+    pub initial_value: Option<Value>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeData {
     Integer(IntegerDataType),
     Float(FloatDataType),
@@ -34,6 +47,7 @@ pub enum TypeData {
 
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DataEncoding {
     None,
     Binary(BinaryDataEncoding),
@@ -43,68 +57,152 @@ pub enum DataEncoding {
     String(StringDataEncoding),
 }
 
+impl DataEncoding {
+    /// returns the change threshold of the numeric encodings or None if not specified
+    pub fn change_threshold(&self) -> Option<f64> {
+        match self {
+            DataEncoding::Integer(ide) => ide.change_threshold,
+            DataEncoding::Float(fde) => fde.change_threshold,
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BooleanDataEncoding {
     pub size_in_bits: BinarySize,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FloatDataEncoding {
     pub size_in_bits: u8,
     pub encoding: FloatEncodingType,
     pub byte_order: ByteOrder,
+    /// minimum change of the raw value considered significant
+    pub change_threshold: Option<f64>,
+}
+
+impl IntegerDataEncoding {
+    /// returns the minimum and maximum raw values which can be encoded
+    ///
+    /// The size in bits is expected between 1 and 64 (as checked by the parser).
+    pub fn raw_range(&self) -> (i128, i128) {
+        let n = self.size_in_bits as u32;
+        match self.encoding {
+            IntegerEncodingType::Unsigned => (0, (1i128 << n) - 1),
+            IntegerEncodingType::TwosComplement => (-(1i128 << (n - 1)), (1i128 << (n - 1)) - 1),
+            IntegerEncodingType::SignMagnitude | IntegerEncodingType::OnesComplement => {
+                (-(1i128 << (n - 1)) + 1, (1i128 << (n - 1)) - 1)
+            }
+            IntegerEncodingType::Bcd => (0, 10i128.pow(n / 8) - 1),
+            IntegerEncodingType::PackedBcd => (0, 10i128.pow(n / 4) - 1),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IntegerEncodingType {
     Unsigned,
     TwosComplement,
     SignMagnitude,
     OnesComplement,
+    /// one decimal digit per byte
+    Bcd,
+    /// one decimal digit per nibble
+    PackedBcd,
+}
+
+impl IntegerEncodingType {
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            IntegerEncodingType::TwosComplement
+                | IntegerEncodingType::SignMagnitude
+                | IntegerEncodingType::OnesComplement
+        )
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntegerDataEncoding {
     pub size_in_bits: u8,
     pub encoding: IntegerEncodingType,
     pub byte_order: ByteOrder,
+    /// minimum change of the raw value considered significant
+    pub change_threshold: Option<f64>,
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FloatEncodingType {
     IEEE754_1985,
     Milstd1750a,
+    /// IEEE 754 like layout with non standard widths: the sign bit, the biased exponent and the
+    /// mantissa without its leading bit.
+    ///
+    /// There is no XTCE syntax for it, the widths are given by the `FloatExponentBits` and
+    /// `FloatMantissaBits` ancillary data of the parameter type.
+    Custom { exponent_bits: u8, mantissa_bits: u8 },
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StringSize {
     ///
     /// fixed size in bits    
     Fixed(u32),
     ///
     /// Like C strings, they are terminated with a special string, usually a null character.
-    TerminationChar(u8),
+    /// The terminator can have between 1 and 4 bytes (e.g. 0x0000 for UTF-16 or 0x0D0A for CRLF terminated strings).
+    TerminationChar(SmallVec<[u8; 4]>),
     ///
     /// Like PASCAL strings, the size of the string is given as an integer at the start of the string. SizeTag must
     /// be an unsigned Integer.
-    /// The tag size is given in bytes (not bits!)
-    LeadingSize(u32),
+    LeadingSize(LeadingSize),
     ///
-    ///  an alogirthm will be used to decode the data - not yet supported
+    /// the string is decoded by the FromBinaryTransformAlgorithm of the encoding.
+    /// The algorithms are not executed, a decoder with the same name has to be registered in the ProcessorData
     ///
     Custom,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringDataEncoding {
     pub size_in_bits: StringSize,
     pub box_size_in_bits: StringBoxSize,
     pub encoding: String,
-    pub max_box_size_in_bytes: Option<u32>,
+    /// byte order of the UTF-16 and UTF-32 code units, ignored for the single byte encodings
+    pub byte_order: ByteOrder,
+    /// the maxSizeInBits of a Variable size string; with a leading size tag not multiple of 8 bits,
+    /// the box is not a whole number of bytes
+    pub max_box_size_in_bits: Option<u32>,
+    /// set if the size in bits is Custom
+    pub from_binary_transform_algorithm: Option<TransformAlgorithm>,
+}
+
+impl StringDataEncoding {
+    /// returns the size in bytes of the code units of the encoding: 2 for UTF-16, 4 for UTF-32 and 1 otherwise
+    pub fn code_unit_size(&self) -> usize {
+        code_unit_size(&self.encoding)
+    }
+}
+
+// size in bytes of the code units of the string encoding given by its name
+pub(crate) fn code_unit_size(encoding: &str) -> usize {
+    match encoding {
+        "UTF-16" | "UTF-16BE" | "UTF-16LE" => 2,
+        "UTF-32" | "UTF-32BE" | "UTF-32LE" => 4,
+        _ => 1,
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StringBoxSize {
     Undefined,
     Fixed(u32),
@@ -113,39 +211,184 @@ pub enum StringBoxSize {
 
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BinaryDataEncoding {
-    pub size_in_bits: BinarySize
+    pub size_in_bits: BinarySize,
+    /// algorithm converting the binary data into the raw value.
+    /// The algorithm is passed to the CustomAlgorithmExecutor registered in the ProcessorData;
+    /// without executor the raw value is the binary data and a warning is logged.
+    pub from_binary_transform_algorithm: Option<TransformAlgorithm>,
+    /// algorithm converting the raw value into the binary data when encoding a command argument
+    pub to_binary_transform_algorithm: Option<TransformAlgorithm>,
+    /// if set, the binary data is a CRC which is verified when the parameter is extracted
+    pub crc: Option<Crc>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinarySize {
     Fixed(u32),
-    LeadingSize(u32),
-    Dynamic(DynamicValueType)
+    LeadingSize(LeadingSize),
+    Dynamic(DynamicValueType),
+    /// the size is determined by the transform algorithm
+    /// (specified in XTCE as a fixed value of -1 or as a dynamic value referring to _yamcs_ignore)
+    Algorithm,
+}
+
+/// The unsigned integer preceding the data which gives its size in bytes
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LeadingSize {
+    /// the tag size in bits, from 1 to 32; the data following the tag has to start at byte boundary
+    pub tag_size_in_bits: u32,
+    pub byte_order: ByteOrder,
+}
+
+/// An algorithm used in a data encoding to transform the binary data.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransformAlgorithm {
+    pub name: String,
+    pub algorithm_text: Option<AlgorithmText>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlgorithmText {
+    pub language: String,
+    pub text: String,
+}
+
+/// Cyclic Redundancy Check definition, from the ErrorDetectCorrect element of the data encoding.
+///
+/// The CRC is computed over the data from the container start plus bits_from_reference up to the start
+/// of the parameter carrying the CRC.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Crc {
+    /// size of the CRC in bits: 8 to 64
+    pub width: u32,
+    pub polynomial: u64,
+    pub init_remainder: u64,
+    pub final_xor: u64,
+    /// if true, the bits of each input byte are processed least significant first
+    pub reflect_data: bool,
+    /// if true, the remainder is bit reversed before the final xor
+    pub reflect_remainder: bool,
+    pub bits_from_reference: u32,
 }
 
 
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NumericAlarm {}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NumericContextAlarm {}
 
+/// Alarm severity levels, from the least to the most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AlarmLevel {
+    Normal,
+    Watch,
+    Warning,
+    Distress,
+    Critical,
+    Severe,
+}
+
+impl fmt::Display for AlarmLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            AlarmLevel::Normal => "normal",
+            AlarmLevel::Watch => "watch",
+            AlarmLevel::Warning => "warning",
+            AlarmLevel::Distress => "distress",
+            AlarmLevel::Critical => "critical",
+            AlarmLevel::Severe => "severe",
+        };
+        f.write_str(s)
+    }
+}
+
+/// The alarm levels of the labels of an enumerated type, from the EnumerationAlarmList
 #[derive(Debug)]
-pub struct EnumerationAlarm {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumerationAlarm {
+    /// level of the labels not in the list
+    pub default_level: AlarmLevel,
+    /// label -> alarm level
+    pub levels: HashMap<String, AlarmLevel>,
+}
 
+impl EnumerationAlarm {
+    /// returns the alarm level of the enumerated value, the values are identified by their label
+    pub fn alarm_level(&self, value: &EnumeratedValue) -> AlarmLevel {
+        self.levels.get(&value.value).copied().unwrap_or(self.default_level)
+    }
+}
+
+/// Alarm applied instead of the default one when the criteria matches
 #[derive(Debug)]
-pub struct EnumerationContextAlarm {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumerationContextAlarm {
+    pub criteria: MatchCriteriaIdx,
+    pub alarm: EnumerationAlarm,
+}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BinaryDataType {
     pub size_in_bits: u32,
 }
 
 #[derive(Debug)]
-pub enum Calibrator {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Calibrator {
+    Polynomial(PolynomialCalibrator),
+    Spline(SplineCalibrator),
+    /// the operands and operators of a MathOperationCalibrator in postfix notation, evaluated with
+    /// the raw value as the initial stack element
+    MathOperation(Vec<MathOp>),
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolynomialCalibrator {
+    /// coefficients[i] is the coefficient of x^i; the missing terms have the coefficient 0
+    pub coefficients: Vec<f64>,
+}
+
+/// Piecewise interpolation between a list of points sorted by the raw value
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplineCalibrator {
+    /// 0 (flat steps) or 1 (linear interpolation)
+    pub order: u32,
+    /// if true, the raw values outside of the points are calibrated by extending the first/last segment
+    pub extrapolate: bool,
+    pub points: Vec<SplinePoint>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplinePoint {
+    pub raw: f64,
+    pub calibrated: f64,
+}
+
+/// Calibrator applied instead of the default one when the criteria matches
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContextCalibrator {
+    pub criteria: MatchCriteriaIdx,
+    pub calibrator: Calibrator,
+}
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueEnumeration {
     pub value: i64,
     /// If max value is given, the label maps to a range where value is less than or equal to maxValue.
@@ -179,9 +422,9 @@ impl DataType {
         if calibrated {
             match &self.type_data {
                 TypeData::Integer(idt) => parse_integer(value, idt.signed, idt.size_in_bits),
-                TypeData::Float(_) => todo!(),
-                TypeData::String(_) => todo!(),
-                TypeData::Binary(_) => todo!(),
+                TypeData::Float(_) => parse_float(value),
+                TypeData::String(_) => Ok(Value::StringValue(Box::new(value.to_owned()))),
+                TypeData::Binary(_) => parse_hex_binary(value),
                 TypeData::Boolean(bdt) => parse_eng_boolean(value, bdt),
                 TypeData::Enumerated(edt) => parse_eng_enumerated(value, edt),
                 TypeData::Aggregate(_) => todo!(),
@@ -192,21 +435,119 @@ impl DataType {
             match self.encoding {
                 DataEncoding::Integer(ide) => parse_integer(
                     value,
-                    ide.encoding != IntegerEncodingType::Unsigned,
+                    ide.encoding.is_signed(),
                     ide.size_in_bits as u32,
                 ),
-                DataEncoding::Float(_) => todo!(),
+                DataEncoding::Float(_) => parse_float(value),
                 DataEncoding::Binary(_) => todo!(),
                 DataEncoding::Boolean(_) => todo!(),
-                DataEncoding::String(_) => todo!(),
+                DataEncoding::String(_) => Ok(Value::StringValue(Box::new(value.to_owned()))),
                 DataEncoding::None => todo!(),
             }
         }
     }
+
+    /// Converts an engineering value of the given data type to a string, the reverse of [DataType::from_str]
+    ///
+    /// The booleans are rendered with the one/zero string values of the type, the other values as
+    /// they are displayed.
+    pub fn to_str(&self, value: &Value) -> String {
+        match (&self.type_data, value) {
+            (TypeData::Boolean(bdt), Value::Boolean(v)) => bdt.to_str(*v).to_owned(),
+            _ => value.to_string(),
+        }
+    }
+}
+
+impl DataType {
+    /// returns the valid ranges of the numeric types, empty if the type has no valid range specified
+    pub fn valid_ranges(&self) -> &[ValidRange] {
+        match &self.type_data {
+            TypeData::Integer(idt) => &idt.valid_ranges,
+            TypeData::Float(fdt) => &fdt.valid_ranges,
+            _ => &[],
+        }
+    }
+
+    /// checks the value against the valid ranges of the type.
+    /// calibrated specifies if the value is an engineering value or a raw value.
+    ///
+    /// Returns an OutOfRange error if the ranges apply to the value and the value is outside all of them.
+    pub fn check_valid_range(&self, value: &Value, calibrated: bool) -> Result<()> {
+        let ranges: Vec<&ValidRange> =
+            self.valid_ranges().iter().filter(|vr| vr.applies_to_calibrated == calibrated).collect();
+        if ranges.is_empty() {
+            return Ok(());
+        }
+        let x: f64 = match value.try_into() {
+            Ok(x) => x,
+            Err(_) => return Ok(()),
+        };
+        if ranges.iter().any(|vr| vr.contains(x)) {
+            Ok(())
+        } else {
+            let ranges: Vec<String> = ranges.iter().map(|vr| vr.to_string()).collect();
+            Err(MdbError::OutOfRange(format!(
+                "Value {} is outside the valid range {} of the type",
+                value,
+                ranges.join(" or ")
+            )))
+        }
+    }
+
+    /// returns the range of the engineering values of the numeric types, for example to be used as default
+    /// limits of the plot axes; see [crate::proc::types::engineering_range].
+    pub fn engineering_range(&self) -> Option<(f64, f64)> {
+        crate::proc::types::engineering_range(self)
+    }
+
+    /// returns the size in bits of the encoded values if it is known without looking at the data,
+    /// or None for the dynamic, terminated, leading size or algorithm determined sizes.
+    ///
+    /// The size of an aggregate is the sum of the sizes of its members, the size of an array is
+    /// known if all its dimensions are fixed.
+    pub fn size_in_bits(&self, mdb: &MissionDatabase) -> Option<u32> {
+        match &self.encoding {
+            DataEncoding::Integer(ide) => Some(ide.size_in_bits as u32),
+            DataEncoding::Float(fde) => Some(fde.size_in_bits as u32),
+            DataEncoding::Boolean(bde) => fixed_binary_size(&bde.size_in_bits),
+            DataEncoding::Binary(bde) => fixed_binary_size(&bde.size_in_bits),
+            DataEncoding::String(sde) => match (&sde.box_size_in_bits, &sde.size_in_bits) {
+                (StringBoxSize::Fixed(size), _) => Some(*size),
+                (StringBoxSize::Undefined, StringSize::Fixed(size)) => Some(*size),
+                _ => None,
+            },
+            DataEncoding::None => match &self.type_data {
+                TypeData::Aggregate(adt) => {
+                    adt.members.iter().map(|m| mdb.get_data_type(m.dtype).size_in_bits(mdb)).sum()
+                }
+                TypeData::Array(adt) => {
+                    let mut num_elements = 1;
+                    for d in &adt.dim {
+                        match (&d.starting_index, &d.ending_index) {
+                            (IntegerValue::FixedValue(start), IntegerValue::FixedValue(end)) => {
+                                num_elements *= u32::try_from(end - start + 1).ok()?
+                            }
+                            _ => return None,
+                        }
+                    }
+                    mdb.get_data_type(adt.dtype).size_in_bits(mdb).map(|s| s * num_elements)
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+fn fixed_binary_size(size: &BinarySize) -> Option<u32> {
+    match size {
+        BinarySize::Fixed(size) => Some(*size),
+        _ => None,
+    }
 }
 
 fn parse_integer(value: &str, signed: bool, size_in_bits: u32) -> Result<Value> {
-    let x = value.parse::<i128>()?;
+    let x: i128 = parse_integer_text(value)?;
     let max = if signed { (1i128 << (size_in_bits - 1)) - 1 } else { (1i128 << size_in_bits) - 1 };
     let min = if signed { -(1i128 << (size_in_bits - 1)) } else { 0 };
 
@@ -224,10 +565,25 @@ fn parse_integer(value: &str, signed: bool, size_in_bits: u32) -> Result<Value>
     }
 }
 
+fn parse_float(value: &str) -> Result<Value> {
+    value
+        .parse::<f64>()
+        .map(Value::Double)
+        .map_err(|_| MdbError::InvalidValue(format!("Cannot parse '{}' as a floating point number", value)))
+}
+
+// the binary values are given as hexadecimal strings
+fn parse_hex_binary(value: &str) -> Result<Value> {
+    hex::decode(value)
+        .map(|b| Value::Binary(Box::new(b)))
+        .map_err(|e| MdbError::InvalidValue(format!("Invalid hex binary value '{}': {}", value, e)))
+}
+
+// true/false are accepted besides the one/zero strings, as for the xs:boolean values
 fn parse_eng_boolean(value: &str, bdt: &BooleanDataType) -> Result<Value> {
-    if value == bdt.zero_string_value {
+    if value == bdt.zero_string_value || value == "false" {
         Ok(Value::Boolean(false))
-    } else if value == bdt.one_string_value {
+    } else if value == bdt.one_string_value || value == "true" {
         Ok(Value::Boolean(true))
     } else {
         Err(MdbError::InvalidValue(format!(
@@ -241,14 +597,16 @@ fn parse_eng_enumerated(value: &str, edt: &EnumeratedDataType) -> Result<Value>
     edt.enumeration
         .iter()
         .find(|ev| ev.label == value)
-        .map(|v| Value::StringValue(Box::new(v.label.clone())))
+        .map(|ev| Value::Enumerated(Box::new(EnumeratedValue { key: ev.value, value: ev.label.clone() })))
         .ok_or_else(|| MdbError::InvalidValue(format!("Value {} not valid for type", value)))
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AbsoluteTimeDataType {}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumeratedDataType {
     pub enumeration: Vec<ValueEnumeration>,
     pub default_alarm: Option<EnumerationAlarm>,
@@ -256,35 +614,117 @@ pub struct EnumeratedDataType {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FloatDataType {
     pub size_in_bits: u32,
     pub default_alarm: Option<NumericAlarm>,
     pub context_alarm: Vec<NumericContextAlarm>,
+    /// the ValidRange or the ranges of the ValidRangeSet, a value is valid if it is inside any of them
+    pub valid_ranges: Vec<ValidRange>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntegerDataType {
     pub size_in_bits: u32,
     pub signed: bool,
     pub default_alarm: Option<NumericAlarm>,
     pub context_alarm: Vec<NumericContextAlarm>,
+    /// the ValidRange or the ranges of the ValidRangeSet, a value is valid if it is inside any of them
+    pub valid_ranges: Vec<ValidRange>,
+}
+
+/// The range of valid values for a numeric type;
+/// the bounds may be inclusive or exclusive and any of them may be missing.
+/// It is used for both integer and float types, the bounds are stored as f64.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidRange {
+    pub min: Option<f64>,
+    pub min_inclusive: bool,
+    pub max: Option<f64>,
+    pub max_inclusive: bool,
+    /// if true, the range applies to the engineering value, otherwise to the raw value
+    pub applies_to_calibrated: bool,
+}
+
+impl ValidRange {
+    /// returns true if the value is inside the range
+    pub fn contains(&self, v: f64) -> bool {
+        let min_ok = match self.min {
+            Some(min) => {
+                if self.min_inclusive {
+                    v >= min
+                } else {
+                    v > min
+                }
+            }
+            None => true,
+        };
+        let max_ok = match self.max {
+            Some(max) => {
+                if self.max_inclusive {
+                    v <= max
+                } else {
+                    v < max
+                }
+            }
+            None => true,
+        };
+        min_ok && max_ok
+    }
+}
+
+impl std::fmt::Display for ValidRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.min {
+            Some(min) => write!(f, "{}{}", if self.min_inclusive { '[' } else { '(' }, min)?,
+            None => write!(f, "(-inf")?,
+        }
+        match self.max {
+            Some(max) => write!(f, ", {}{}", max, if self.max_inclusive { ']' } else { ')' }),
+            None => write!(f, ", +inf)"),
+        }
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringDataType {}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BooleanDataType {
     pub one_string_value: String,
     pub zero_string_value: String,
 }
 
+impl BooleanDataType {
+    /// returns the one or zero string value corresponding to the boolean
+    pub fn to_str(&self, value: bool) -> &str {
+        if value {
+            &self.one_string_value
+        } else {
+            &self.zero_string_value
+        }
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AggregateDataType {
     pub members: Vec<Member>,
 }
 
+impl AggregateDataType {
+    /// returns the member with the given name
+    pub fn member(&self, name: NameIdx) -> Option<&Member> {
+        self.members.iter().find(|m| m.name() == name)
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Member {
     pub ndescr: NameDescription,
     pub dtype: DataTypeIdx,
@@ -297,9 +737,19 @@ impl NamedItem for Member {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArrayDataType {
     pub dtype: DataTypeIdx,
-    pub dim: Vec<IntegerValue>,
+    pub dim: Vec<ArrayDimension>,
+}
+
+/// The range of indices of one dimension of an array, the number of elements is
+/// ending_index - starting_index + 1
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArrayDimension {
+    pub starting_index: IntegerValue,
+    pub ending_index: IntegerValue,
 }
 
 ///
@@ -311,6 +761,7 @@ pub struct ArrayDataType {
 ///
 /// name can be None and index can be empty
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PathElement {
     pub name: Option<NameIdx>,
     //SmallVec of size 4 will occupy on a 64 bits machine the same amont of memory (24 bytes) as an empty Vec.
@@ -1,10 +1,12 @@
+use std::time::Duration;
+
 use smallvec::SmallVec;
 
 use crate::{value::{Value}};
 
 use super::{
     types::{DataType, MemberPath, PathElement, TypeData},
-    MissionDatabase, NameDb, NamedItem, MdbError, Result,
+    IntegerValue, MissionDatabase, NameDb, MdbError, Result,
 };
 
 ///
@@ -12,7 +14,7 @@ use super::{
 ///
 /// name1[idx_1][idx_2][idx_n].name2[jdx_1][jdx_n].name3.name4
 ///
-/// If any of the name is not known in the name db, an InvalidReference error is returned
+/// If any of the name is not known in the name db, an InvalidName error is returned
 pub(crate) fn parse_aggregate_member_path(
     name_db: &NameDb,
     path: Vec<&str>,
@@ -42,7 +44,7 @@ pub(crate) fn parse_aggregate_path_element(
 
     let name = if let Some(x) = name_str {
         if !name_db.contains(x) {
-            return Err(MdbError::InvalidValue(format!(
+            return Err(MdbError::InvalidName(format!(
                 "Cannot parse aggregate member path '{}'; '{}' is not known in the name database",
                 path, x
             )));
@@ -84,7 +86,7 @@ pub(crate) fn get_member_type<'a>(
     for pe in path {
         if let Some(name) = pe.name {
             if let TypeData::Aggregate(atype) = &rtype.type_data {
-                if let Some(m) = atype.members.iter().find(|m| m.name() == name) {
+                if let Some(m) = atype.member(name) {
                     rtype = mdb.get_data_type(m.dtype);
                 } else {
                     return None;
@@ -109,32 +111,132 @@ pub(crate) fn get_member_type<'a>(
     Some(rtype)
 }
 
+/// returns the member of the value found at the given path or None if not found
+///
+/// The path indices are relative to the starting indices of the array dimensions, which are
+/// obtained with the start_index function.
 pub(crate) fn get_member_value<'a>(
+    mdb: &MissionDatabase,
+    dtype: &DataType,
     value: &'a Value,
     path: &MemberPath,
+    start_index: impl Fn(&IntegerValue) -> Option<i64>,
 ) -> Option<&'a Value> {
+    let mut rtype = dtype;
     let mut val = value;
 
     for pe in path {
         if let Some(name) = pe.name {
-            if let Value::Aggregate(aggrv) = val {
-                if let Some(v) = aggrv.0.get(&name) {
-                    val = v;
-                }  else {
-                    return None;
-                }
-            } else {
+            let (TypeData::Aggregate(atype), Value::Aggregate(aggrv)) = (&rtype.type_data, val) else {
                 return None;
-            }
+            };
+            rtype = mdb.get_data_type(atype.member(name)?.dtype);
+            val = aggrv.0.get(&name)?;
         }
-        if pe.index.len() > 0 {
-            todo!()
+
+        if !pe.index.is_empty() {
+            let TypeData::Array(atype) = &rtype.type_data else {
+                return None;
+            };
+            if atype.dim.len() != pe.index.len() {
+                return None;
+            }
+            for (idx, dim) in pe.index.iter().zip(&atype.dim) {
+                let Value::Array(arrv) = val else {
+                    return None;
+                };
+                let i = (*idx as i64).checked_sub(start_index(&dim.starting_index)?)?;
+                val = arrv.get(usize::try_from(i).ok()?)?;
+            }
+            rtype = mdb.get_data_type(atype.dtype);
         }
     }
 
     Some(val)
 }
 
+/// Parses an integer written in decimal or, with a 0x, 0o or 0b prefix, in hexadecimal, octal or binary.
+/// The number may be preceded by a sign.
+///
+/// Returns an OutOfRange error if the value does not fit into the target type.
+pub fn parse_integer_text<T: TryFrom<i128>>(text: &str) -> Result<T> {
+    let s = text.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (radix, digits) = if let Some(d) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        (16, d)
+    } else if let Some(d) = s.strip_prefix("0o").or_else(|| s.strip_prefix("0O")) {
+        (8, d)
+    } else if let Some(d) = s.strip_prefix("0b").or_else(|| s.strip_prefix("0B")) {
+        (2, d)
+    } else {
+        (10, s)
+    };
+    // from_str_radix accepts itself a sign which would allow things like "-+5" or "0x-5"
+    if digits.starts_with(['+', '-']) {
+        return Err(MdbError::InvalidValue(format!("Cannot parse '{}' as an integer", text)));
+    }
+    let x = i128::from_str_radix(digits, radix)
+        .map_err(|e| MdbError::InvalidValue(format!("Cannot parse '{}' as an integer: {}", text, e)))?;
+    let x = if negative { -x } else { x };
+
+    T::try_from(x).map_err(|_| MdbError::OutOfRange(format!("Value {} out of range of the target type", text)))
+}
+
+/// Parses an ISO 8601 duration like PT10S, PT1M30.5S or P1DT2H.
+///
+/// The years and months are accepted only if they are zero (e.g. P0Y0M0DT0H0M10.000S) because their length
+/// is not fixed; negative durations are not accepted either. Only the last component may have a fractional part.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let invalid = || MdbError::InvalidValue(format!("Cannot parse '{}' as an ISO 8601 duration", text));
+    let s = text.trim().strip_prefix('P').ok_or_else(invalid)?;
+    let (date, time) = match s.split_once('T') {
+        Some((_, "")) => return Err(invalid()),
+        Some((d, t)) => (d, t),
+        None => (s, ""),
+    };
+    if date.is_empty() && time.is_empty() {
+        return Err(invalid());
+    }
+
+    // the number of seconds of each unit, None for the years and months
+    let date_units: &[(char, Option<f64>)] = &[('Y', None), ('M', None), ('W', Some(604800.0)), ('D', Some(86400.0))];
+    let time_units: &[(char, Option<f64>)] = &[('H', Some(3600.0)), ('M', Some(60.0)), ('S', Some(1.0))];
+    let mut secs = 0f64;
+    let mut fraction_seen = false;
+    for (part, units) in [(date, date_units), (time, time_units)] {
+        let mut rest = part;
+        let mut units = units.iter();
+        while !rest.is_empty() {
+            let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',').ok_or_else(invalid)?;
+            let designator = rest[end..].chars().next().unwrap();
+            let number = rest[..end].replace(',', ".");
+            if number.is_empty() || fraction_seen {
+                return Err(invalid());
+            }
+            // the designators have to appear in order, each at most once
+            let &(_, unit) = units.by_ref().find(|(d, _)| *d == designator).ok_or_else(invalid)?;
+            fraction_seen = number.contains('.');
+            let x = number.parse::<f64>().map_err(|_| invalid())?;
+            match unit {
+                Some(unit) => secs += x * unit,
+                None if x == 0.0 => {}
+                None => {
+                    return Err(MdbError::InvalidValue(format!(
+                        "Cannot parse '{}': years and months are not supported",
+                        text
+                    )))
+                }
+            }
+            rest = &rest[end + designator.len_utf8()..];
+        }
+    }
+
+    Duration::try_from_secs_f64(secs).map_err(|_| invalid())
+}
+
 
 
 #[cfg(test)]
@@ -181,4 +283,46 @@ mod tests {
         let r = parse_aggregate_member_path(&mdb.name_db, vec!["a[2]", "b", "c"]).unwrap();
         assert_eq!(3, r.len());
     }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Duration::from_secs(10), parse_duration("PT10S").unwrap());
+        assert_eq!(Duration::from_millis(90_500), parse_duration("PT1M30.5S").unwrap());
+        assert_eq!(Duration::from_secs(93_600), parse_duration("P1DT2H").unwrap());
+        assert_eq!(Duration::from_secs(604_800), parse_duration("P1W").unwrap());
+        assert_eq!(Duration::from_millis(250), parse_duration("PT0,25S").unwrap());
+        assert_eq!(Duration::ZERO, parse_duration("PT0S").unwrap());
+        assert_eq!(Duration::from_secs(10), parse_duration("P0Y0M0DT0H0M10.000S").unwrap());
+
+        for s in ["", "P", "PT", "10S", "PT10", "PTS", "PT1S2M", "PT1.5M2S", "P1M", "P1Y", "-PT1S", "PT1H1H", "P1S"] {
+            assert!(parse_duration(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_integer_text() {
+        assert_eq!(26i64, parse_integer_text("0x1A").unwrap());
+        assert_eq!(26i64, parse_integer_text("0X1a").unwrap());
+        assert_eq!(-26i64, parse_integer_text("-0x1A").unwrap());
+        assert_eq!(8u32, parse_integer_text("0o10").unwrap());
+        assert_eq!(5u8, parse_integer_text("0b101").unwrap());
+        assert_eq!(42i32, parse_integer_text("+42").unwrap());
+        assert_eq!(-42i32, parse_integer_text(" -42 ").unwrap());
+
+        assert_eq!(u64::MAX, parse_integer_text::<u64>("0xFFFFFFFFFFFFFFFF").unwrap());
+        assert_eq!(u64::MAX, parse_integer_text::<u64>("18446744073709551615").unwrap());
+        assert!(matches!(parse_integer_text::<u64>("0x10000000000000000"), Err(MdbError::OutOfRange(_))));
+        assert!(matches!(parse_integer_text::<u64>("-1"), Err(MdbError::OutOfRange(_))));
+        assert!(matches!(parse_integer_text::<i64>("0xFFFFFFFFFFFFFFFF"), Err(MdbError::OutOfRange(_))));
+        assert_eq!(i64::MIN, parse_integer_text::<i64>("-0x8000000000000000").unwrap());
+        assert_eq!(i64::MAX, parse_integer_text::<i64>("0x7FFFFFFFFFFFFFFF").unwrap());
+        assert_eq!(255u8, parse_integer_text::<u8>("0xFF").unwrap());
+        assert!(parse_integer_text::<u8>("0x100").is_err());
+
+        assert!(matches!(parse_integer_text::<i64>("0x"), Err(MdbError::InvalidValue(_))));
+        assert!(matches!(parse_integer_text::<i64>("0x-5"), Err(MdbError::InvalidValue(_))));
+        assert!(matches!(parse_integer_text::<i64>("--5"), Err(MdbError::InvalidValue(_))));
+        assert!(matches!(parse_integer_text::<i64>("0b102"), Err(MdbError::InvalidValue(_))));
+        assert!(matches!(parse_integer_text::<i64>("1.5"), Err(MdbError::InvalidValue(_))));
+    }
 }
@@ -0,0 +1,126 @@
+use std::{collections::HashMap, str::FromStr};
+
+use roxmltree::Node;
+
+use super::{misc::parse_match_criteria, *};
+
+use crate::mdb::{
+    types::{AlarmLevel, EnumerationAlarm, ValueEnumeration},
+    *,
+};
+
+/// The alarms read from an enumerated type.
+///
+/// The context alarm criteria are not yet added to the mdb, see parse_match_criteria.
+#[derive(Default)]
+pub(super) struct EnumerationAlarms {
+    pub default: Option<EnumerationAlarm>,
+    pub context: Vec<(MatchCriteria, EnumerationAlarm)>,
+}
+
+/// reads the DefaultAlarm and ContextAlarmList of the enumerated type
+///
+/// the labels of the alarms have to be part of the enumeration
+pub(super) fn read_enumeration_alarms(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    enumeration: &[ValueEnumeration],
+) -> Result<EnumerationAlarms> {
+    let mut alarms = EnumerationAlarms::default();
+
+    for cnode in children(&ctx.node) {
+        match cnode.tag_name().name() {
+            "DefaultAlarm" => alarms.default = Some(read_enumeration_alarm(ctx, &cnode, enumeration)?),
+            "ContextAlarmList" => {
+                for canode in children(&cnode) {
+                    if canode.tag_name().name() == "ContextAlarm" {
+                        alarms.context.push(read_context_alarm(mdb, ctx, &canode, enumeration)?);
+                    } else {
+                        ctx.warn(
+                            &canode,
+                            format!(
+                                "ignoring context alarm list unknown property '{}'",
+                                canode.tag_name().name()
+                            ),
+                        )?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(alarms)
+}
+
+fn read_context_alarm(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+    enumeration: &[ValueEnumeration],
+) -> Result<(MatchCriteria, EnumerationAlarm)> {
+    let criteria = match children(node).find(|n| n.tag_name().name() == "ContextMatch") {
+        Some(cnode) => parse_match_criteria(mdb, ctx, &cnode)?,
+        None => return Err(missing("ContextMatch", node)),
+    };
+    let alarm = read_enumeration_alarm(ctx, node, enumeration)?;
+
+    Ok((criteria, alarm))
+}
+
+// reads the DefaultAlarm or ContextAlarm element of an enumerated type
+fn read_enumeration_alarm(
+    ctx: &ParseContext,
+    node: &Node,
+    enumeration: &[ValueEnumeration],
+) -> Result<EnumerationAlarm> {
+    let default_level = read_attribute::<AlarmLevel>(node, "defaultAlarmLevel")?.unwrap_or(AlarmLevel::Normal);
+    let mut levels = HashMap::new();
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "EnumerationAlarmList" => {
+                for eanode in children(&cnode) {
+                    let level = read_mandatory_attribute::<AlarmLevel>(&eanode, "alarmLevel")?;
+                    // enumerationLabel in XTCE 1.2, enumerationValue in XTCE 1.1
+                    let label = match eanode.attribute("enumerationLabel").or(eanode.attribute("enumerationValue")) {
+                        Some(label) => label,
+                        None => return Err(get_parse_error("Cannot find attribute enumerationLabel", &eanode)),
+                    };
+                    if !enumeration.iter().any(|ve| ve.label == label) {
+                        return Err(get_parse_error(
+                            format!("Invalid enumeration alarm: unknown label '{}'", label),
+                            &eanode,
+                        ));
+                    }
+                    levels.insert(label.to_owned(), level);
+                }
+            }
+            "ContextMatch" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring enumeration alarm unknown property '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+
+    Ok(EnumerationAlarm { default_level, levels })
+}
+
+impl FromStr for AlarmLevel {
+    type Err = XtceError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "normal" => Ok(AlarmLevel::Normal),
+            "watch" => Ok(AlarmLevel::Watch),
+            "warning" => Ok(AlarmLevel::Warning),
+            "distress" => Ok(AlarmLevel::Distress),
+            "critical" => Ok(AlarmLevel::Critical),
+            "severe" => Ok(AlarmLevel::Severe),
+            _ => Err(XtceError::InvalidValue(
+                "please use one of normal, watch, warning, distress, critical or severe".to_owned(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::str::FromStr;
+
+use roxmltree::Node;
+
+use crate::mdb::{
+    types::AlgorithmText, Algorithm, AlgorithmInput, AlgorithmOutput, AlgorithmTrigger,
+    AlgorithmType, MathOp, MathOperator, MissionDatabase, NameDescription, NameReferenceType,
+};
+
+use super::{
+    misc::{read_para_insta_ref, resolve_ref},
+    utils::{
+        children, get_parse_error, read_attribute, read_mandatory_attribute, read_name_description,
+        unsupported,
+    },
+    ParseContext, Result, XtceError,
+};
+
+pub(super) fn add_algorithm(mdb: &mut MissionDatabase, ctx: &ParseContext) -> Result<()> {
+    let node = &ctx.node;
+    let ndescr = read_name_description(ctx)?;
+
+    let r = match node.tag_name().name() {
+        "MathAlgorithm" => read_math_algorithm(mdb, ctx, ndescr),
+        "CustomAlgorithm" => read_custom_algorithm(mdb, ctx, ndescr),
+        // the name tree contains only the supported algorithms
+        _ => return Err(unsupported("algorithm", node)),
+    };
+
+    match r {
+        Ok(algo) => {
+            mdb.add_algorithm(ctx.path, algo);
+            Ok(())
+        }
+        // the algorithms are not executed so they can be skipped without affecting the rest;
+        // this happens for example with the Yamcs system parameters (/yamcs/...)
+        Err(XtceError::UndefinedReference(reference, _)) => ctx.warn(
+            node,
+            format!(
+                "ignoring algorithm {} which refers to the undefined parameter {}",
+                mdb.name2str(ctx.name),
+                reference
+            ),
+        ),
+        Err(e) => Err(e),
+    }
+}
+
+fn read_math_algorithm(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    ndescr: NameDescription,
+) -> Result<Algorithm> {
+    let mut operation = None;
+    let mut triggers = Vec::new();
+
+    for cnode in children(&ctx.node) {
+        match cnode.tag_name().name() {
+            "MathOperation" => {
+                let oref = read_mandatory_attribute::<String>(&cnode, "outputParameterRef")?;
+                let pidx = resolve_ref(mdb, ctx, &oref, NameReferenceType::Parameter)?;
+                let ops = read_math_operation(mdb, ctx, &cnode)?;
+                operation = Some((AlgorithmOutput { name: None, pidx }, ops));
+                // the triggers of the math algorithms are part of the math operation
+                if let Some(tnode) = children(&cnode).find(|n| n.tag_name().name() == "TriggerSet") {
+                    triggers = read_trigger_set(mdb, ctx, &tnode)?;
+                }
+            }
+            "LongDescription" | "AliasSet" | "AncillaryDataSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring math algorithm unknown property '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+
+    let (output, ops) = operation
+        .ok_or_else(|| get_parse_error("MathAlgorithm without MathOperation", &ctx.node))?;
+
+    // the algorithm is computed from all the parameters used as operands
+    let inputs = ops
+        .iter()
+        .filter_map(|op| match op {
+            MathOp::ParameterInstance(pref) => {
+                Some(AlgorithmInput { name: None, param_instance: pref.clone() })
+            }
+            _ => None,
+        })
+        .collect();
+
+    Ok(Algorithm {
+        ndescr,
+        inputs,
+        outputs: vec![output],
+        algorithm_type: AlgorithmType::Math(ops),
+        triggers,
+    })
+}
+
+fn read_custom_algorithm(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    ndescr: NameDescription,
+) -> Result<Algorithm> {
+    let mut algorithm_text = None;
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut triggers = Vec::new();
+
+    for cnode in children(&ctx.node) {
+        match cnode.tag_name().name() {
+            "AlgorithmText" => algorithm_text = Some(read_algorithm_text(&cnode)?),
+            "InputSet" => inputs = read_input_set(mdb, ctx, &cnode)?,
+            "OutputSet" => outputs = read_output_set(mdb, ctx, &cnode)?,
+            "TriggerSet" => triggers = read_trigger_set(mdb, ctx, &cnode)?,
+            "LongDescription" | "AliasSet" | "AncillaryDataSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring custom algorithm unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    let algorithm_text = algorithm_text
+        .ok_or_else(|| get_parse_error("CustomAlgorithm without AlgorithmText", &ctx.node))?;
+
+    Ok(Algorithm {
+        ndescr,
+        inputs,
+        outputs,
+        algorithm_type: AlgorithmType::Custom(algorithm_text),
+        triggers,
+    })
+}
+
+/// reads the language and the script of an AlgorithmText element
+pub(super) fn read_algorithm_text(node: &Node) -> Result<AlgorithmText> {
+    let language = read_mandatory_attribute::<String>(node, "language")?;
+    let text = node.text().unwrap_or("").trim().to_owned();
+    Ok(AlgorithmText { language, text })
+}
+
+fn read_input_set(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Vec<AlgorithmInput>> {
+    let mut inputs = Vec::new();
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "InputParameterInstanceRef" => {
+                let name = read_attribute::<String>(&cnode, "inputName")?;
+                let param_instance = read_para_insta_ref(mdb, ctx, &cnode, false)?;
+                inputs.push(AlgorithmInput { name, param_instance });
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring unsupported algorithm input '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+    Ok(inputs)
+}
+
+fn read_output_set(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Vec<AlgorithmOutput>> {
+    let mut outputs = Vec::new();
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "OutputParameterRef" => {
+                let name = read_attribute::<String>(&cnode, "outputName")?;
+                let pref = read_mandatory_attribute::<String>(&cnode, "parameterRef")?;
+                let pidx = resolve_ref(mdb, ctx, &pref, NameReferenceType::Parameter)?;
+                outputs.push(AlgorithmOutput { name, pidx });
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring unsupported algorithm output '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+    Ok(outputs)
+}
+
+fn read_trigger_set(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Vec<AlgorithmTrigger>> {
+    let mut triggers = Vec::new();
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "OnParameterUpdateTrigger" => {
+                let pref = read_mandatory_attribute::<String>(&cnode, "parameterRef")?;
+                let pidx = resolve_ref(mdb, ctx, &pref, NameReferenceType::Parameter)?;
+                triggers.push(AlgorithmTrigger::OnParameterUpdate(pidx));
+            }
+            "OnPeriodicRateTrigger" => {
+                let rate = read_mandatory_attribute::<f64>(&cnode, "fireRateInSeconds")?;
+                let millis = (rate * 1000.0).round();
+                if !(millis >= 1.0 && millis <= u64::MAX as f64) {
+                    return Err(get_parse_error(
+                        format!("Invalid fireRateInSeconds {}, it has to be at least 0.001", rate),
+                        &cnode,
+                    ));
+                }
+                triggers.push(AlgorithmTrigger::OnPeriodicRate(millis as u64));
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring unsupported algorithm trigger '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+    Ok(triggers)
+}
+
+/// reads the operands and operators of a MathOperation or MathOperationCalibrator, in the order
+/// they appear in the document
+pub(super) fn read_math_operation(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Vec<MathOp>> {
+    let mut ops = Vec::new();
+
+    for cnode in children(node) {
+        let op = match cnode.tag_name().name() {
+            "ValueOperand" => {
+                let text = cnode.text().unwrap_or("").trim();
+                MathOp::Value(text.parse::<f64>().map_err(|_| {
+                    get_parse_error(format!("Invalid value operand '{}'", text), &cnode)
+                })?)
+            }
+            "ThisParameterOperand" => MathOp::ThisParameter,
+            "ParameterInstanceRefOperand" => {
+                MathOp::ParameterInstance(read_para_insta_ref(mdb, ctx, &cnode, false)?)
+            }
+            "Operator" => {
+                let text = cnode.text().unwrap_or("").trim();
+                MathOp::Operator(text.parse::<MathOperator>().map_err(|_| {
+                    get_parse_error(format!("Invalid math operator '{}'", text), &cnode)
+                })?)
+            }
+            // read by the math algorithms, the calibrators have no use for them
+            "TriggerSet" => continue,
+            // skipping an element would change the result of the operation
+            _ => return Err(unsupported("math operation element", &cnode)),
+        };
+        ops.push(op);
+    }
+
+    if ops.is_empty() {
+        return Err(get_parse_error("Empty math operation", node));
+    }
+
+    Ok(ops)
+}
+
+impl FromStr for MathOperator {
+    type Err = XtceError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        use MathOperator::*;
+
+        let op = match s {
+            "+" => Add,
+            "-" => Subtract,
+            "*" => Multiply,
+            "/" => Divide,
+            "%" => Modulo,
+            "^" => Power,
+            "y^x" => ReversePower,
+            "ln" => Ln,
+            "log" => Log,
+            "e^x" => Exp,
+            "1/x" => Inverse,
+            "x!" => Factorial,
+            "tan" => Tan,
+            "cos" => Cos,
+            "sin" => Sin,
+            "atan" => Atan,
+            "atan2" => Atan2,
+            "acos" => Acos,
+            "asin" => Asin,
+            "tanh" => Tanh,
+            "cosh" => Cosh,
+            "sinh" => Sinh,
+            "atanh" => Atanh,
+            "acosh" => Acosh,
+            "asinh" => Asinh,
+            "swap" => Swap,
+            "drop" => Drop,
+            "dup" => Dup,
+            "over" => Over,
+            "<<" => LeftShift,
+            ">>" => RightShift,
+            "&" => BitwiseAnd,
+            "|" => BitwiseOr,
+            "&&" => LogicalAnd,
+            "||" => LogicalOr,
+            "!" => LogicalNot,
+            "abs" => Abs,
+            "div" => IntegerDivide,
+            "==" => Equal,
+            "!=" => NotEqual,
+            "<" => LessThan,
+            "<=" => LessOrEqual,
+            ">" => GreaterThan,
+            ">=" => GreaterOrEqual,
+            _ => return Err(XtceError::InvalidValue(format!("unknown math operator '{}'", s))),
+        };
+        Ok(op)
+    }
+}
@@ -0,0 +1,201 @@
+use std::cmp::Ordering;
+
+use roxmltree::Node;
+
+use super::{algorithms::read_math_operation, misc::parse_match_criteria, *};
+
+use crate::mdb::{
+    types::{Calibrator, PolynomialCalibrator, SplineCalibrator, SplinePoint},
+    *,
+};
+
+/// The calibrators read from a numeric data encoding.
+///
+/// The context calibrator criteria are not yet added to the mdb, see parse_match_criteria.
+#[derive(Default)]
+pub(super) struct Calibrators {
+    pub default: Option<Calibrator>,
+    pub context: Vec<(MatchCriteria, Calibrator)>,
+}
+
+/// reads the DefaultCalibrator and ContextCalibratorList of the integer or float encoding of the type
+pub(super) fn read_calibrators(mdb: &MissionDatabase, ctx: &ParseContext) -> Result<Calibrators> {
+    let mut calibrators = Calibrators::default();
+
+    let enc_node = match children(&ctx.node)
+        .find(|n| matches!(n.tag_name().name(), "IntegerDataEncoding" | "FloatDataEncoding"))
+    {
+        Some(n) => n,
+        None => return Ok(calibrators),
+    };
+
+    for cnode in children(&enc_node) {
+        match cnode.tag_name().name() {
+            "DefaultCalibrator" => calibrators.default = read_calibrator(mdb, ctx, &cnode)?,
+            "ContextCalibratorList" => {
+                for ccnode in children(&cnode) {
+                    if ccnode.tag_name().name() == "ContextCalibrator" {
+                        if let Some(cc) = read_context_calibrator(mdb, ctx, &ccnode)? {
+                            calibrators.context.push(cc);
+                        }
+                    } else {
+                        ctx.warn(
+                            &ccnode,
+                            format!(
+                                "ignoring context calibrator list unknown property '{}'",
+                                ccnode.tag_name().name()
+                            ),
+                        )?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(calibrators)
+}
+
+// returns None if the calibrator is not supported
+fn read_context_calibrator(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Option<(MatchCriteria, Calibrator)>> {
+    let mut criteria = None;
+    let mut calibrator = None;
+    let mut found_calibrator = false;
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "ContextMatch" => criteria = Some(parse_match_criteria(mdb, ctx, &cnode)?),
+            "Calibrator" => {
+                calibrator = read_calibrator(mdb, ctx, &cnode)?;
+                found_calibrator = true;
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring context calibrator unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    let criteria = criteria.ok_or_else(|| missing("ContextMatch", node))?;
+    if !found_calibrator {
+        return Err(missing("Calibrator", node));
+    }
+
+    Ok(calibrator.map(|c| (criteria, c)))
+}
+
+// reads the calibrator from a DefaultCalibrator or Calibrator element
+// returns None (and reports a warning) if the calibrator is not supported
+fn read_calibrator(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Option<Calibrator>> {
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "PolynomialCalibrator" => {
+                return Ok(Some(Calibrator::Polynomial(read_polynomial_calibrator(ctx, &cnode)?)))
+            }
+            "SplineCalibrator" => {
+                return Ok(Some(Calibrator::Spline(read_spline_calibrator(ctx, &cnode)?)))
+            }
+            "MathOperationCalibrator" => {
+                let ops = read_math_operation(mdb, ctx, &cnode)?;
+                // the calibrators are computed from the raw value only
+                if ops.iter().any(|op| matches!(op, MathOp::ParameterInstance(_))) {
+                    return Err(get_parse_error(
+                        "Parameter operands are not supported in the math operation calibrators",
+                        &cnode,
+                    ));
+                }
+                return Ok(Some(Calibrator::MathOperation(ops)));
+            }
+            "AncillaryDataSet" => {}
+            _ => {
+                let msg = format!("ignoring unsupported calibrator '{}'", cnode.tag_name().name());
+                ctx.warn(&cnode, msg)?;
+                return Ok(None);
+            }
+        }
+    }
+
+    Err(missing("calibrator", node))
+}
+
+fn read_polynomial_calibrator(ctx: &ParseContext, node: &Node) -> Result<PolynomialCalibrator> {
+    let mut coefficients = Vec::new();
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "Term" => {
+                let coefficient = read_mandatory_attribute::<f64>(&cnode, "coefficient")?;
+                let exponent = read_mandatory_attribute::<u32>(&cnode, "exponent")? as usize;
+                if exponent >= coefficients.len() {
+                    coefficients.resize(exponent + 1, 0.0);
+                }
+                coefficients[exponent] += coefficient;
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring polynomial calibrator unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    if coefficients.is_empty() {
+        return Err(missing("Term", node));
+    }
+
+    Ok(PolynomialCalibrator { coefficients })
+}
+
+fn read_spline_calibrator(ctx: &ParseContext, node: &Node) -> Result<SplineCalibrator> {
+    let order = read_attribute::<u32>(node, "order")?.unwrap_or(1);
+    if order > 1 {
+        return Err(get_parse_error(
+            format!("Unsupported spline order {}, only 0 and 1 are supported", order),
+            node,
+        ));
+    }
+    let extrapolate = read_bool_attribute(node, "extrapolate")?.unwrap_or(false);
+    let mut points = Vec::new();
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "SplinePoint" => {
+                let raw = read_mandatory_attribute::<f64>(&cnode, "raw")?;
+                let calibrated = read_mandatory_attribute::<f64>(&cnode, "calibrated")?;
+                points.push(SplinePoint { raw, calibrated });
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring spline calibrator unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    if points.len() < 2 {
+        return Err(get_parse_error("A spline calibrator needs at least two points", node));
+    }
+    if points.windows(2).any(|w| w[0].raw.partial_cmp(&w[1].raw) != Some(Ordering::Less)) {
+        return Err(get_parse_error(
+            "The spline points have to be sorted in increasing order of the raw value",
+            node,
+        ));
+    }
+
+    Ok(SplineCalibrator { order, extrapolate, points })
+}
@@ -0,0 +1,521 @@
+use std::{str::FromStr, time::Duration};
+
+use roxmltree::Node;
+
+use crate::mdb::{
+    Argument, ArgumentAssignment, CheckWindow, CheckWindowStart, CommandContainer, CommandContainerEntry,
+    CommandEntryData, CommandVerifier, ConsequenceLevel, ContainerIdx, FixedValueEntry, LocationInContainerInBits,
+    MatchCriteria, MetaCommand, MetaCommandIdx, MissionDatabase, NameReferenceType, NamedItem, Significance,
+    TransmissionConstraint, VerifierCheck, VerifierStage,
+};
+
+use super::{
+    containers::read_location_in_container,
+    types::{parse_initial_value, supports_initial_value},
+    misc::{find_unsupported_criteria_element, parse_criteria_element, parse_match_criteria, resolve_ref},
+    utils::{
+        children, get_parse_error, missing, read_attribute, read_bool_attribute, read_duration_attribute,
+        read_integer_attribute, read_mandatory_attribute, read_mandatory_integer_attribute, read_mandatory_name,
+        read_name_description,
+    },
+    ParseContext, Result, XtceError,
+};
+
+pub(super) fn add_meta_command(mdb: &mut MissionDatabase, ctx: &ParseContext) -> Result<()> {
+    let node = &ctx.node;
+    if node.tag_name().name() != "MetaCommand" {
+        let msg = format!("ignoring unsupported command '{}'", node.tag_name().name());
+        return ctx.warn(node, msg);
+    }
+
+    let abstract_ = read_bool_attribute(node, "abstract")?.unwrap_or(false);
+    let ndescr = read_name_description(ctx)?;
+    let mut base_meta_command = None;
+    let mut argument_assignments = Vec::new();
+    let mut arguments = Vec::new();
+    let mut container_node = None;
+    let mut verifiers = Vec::new();
+    let mut default_significance = None;
+    let mut constraints = Vec::new();
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "BaseMetaCommand" => {
+                let (base, assignments) = read_base_meta_command(mdb, ctx, &cnode)?;
+                base_meta_command = Some(base);
+                argument_assignments = assignments;
+            }
+            "ArgumentList" => arguments = read_argument_list(mdb, ctx, &cnode)?,
+            // read at the end because its entries refer to the arguments
+            "CommandContainer" => container_node = Some(cnode),
+            "VerifierSet" => verifiers = read_verifier_set(mdb, ctx, &cnode)?,
+            "DefaultSignificance" => default_significance = Some(read_significance(&cnode)?),
+            "TransmissionConstraintList" => constraints = read_transmission_constraints(mdb, ctx, &cnode)?,
+            "LongDescription" | "AliasSet" | "AncillaryDataSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring meta command unknown property '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+
+    let command_container = match container_node {
+        Some(cnode) => {
+            Some(read_command_container(mdb, ctx, &cnode, base_meta_command, &arguments)?)
+        }
+        None => None,
+    };
+
+    // the criteria are added only once everything has been parsed, see parse_match_criteria
+    let verifiers = verifiers
+        .into_iter()
+        .map(|(stage, check, check_window)| {
+            let check = match check {
+                ParsedCheck::Container(cidx) => VerifierCheck::Container(cidx),
+                ParsedCheck::Criteria(mc) => VerifierCheck::Criteria(mdb.add_match_criteria(mc)),
+            };
+            CommandVerifier { stage, check, check_window }
+        })
+        .collect();
+    let transmission_constraints = constraints
+        .into_iter()
+        .map(|(mc, timeout)| TransmissionConstraint { criteria: mdb.add_match_criteria(mc), timeout })
+        .collect();
+
+    let cmd = MetaCommand {
+        ndescr,
+        base_meta_command,
+        argument_assignments,
+        abstract_,
+        arguments,
+        command_container,
+        verifiers,
+        default_significance,
+        transmission_constraints,
+    };
+    mdb.add_meta_command(ctx.path, cmd);
+    Ok(())
+}
+
+// the criteria are not added to the mdb, see parse_match_criteria
+fn read_transmission_constraints(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Vec<(MatchCriteria, Option<Duration>)>> {
+    let mut constraints = Vec::new();
+
+    for cnode in children(node) {
+        if cnode.tag_name().name() != "TransmissionConstraint" {
+            ctx.warn(
+                &cnode,
+                format!("ignoring transmission constraint list unknown property '{}'", cnode.tag_name().name()),
+            )?;
+            continue;
+        }
+        let criteria = match parse_match_criteria(mdb, ctx, &cnode) {
+            Ok(criteria) => criteria,
+            // for example the Yamcs command parameters (/yamcs/cmd/...), see read_verifier_set
+            Err(XtceError::UndefinedReference(reference, _)) => {
+                ctx.warn(
+                    &cnode,
+                    format!("ignoring transmission constraint which refers to the undefined item {}", reference),
+                )?;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let timeout = read_duration_attribute(&cnode, "timeOut")?;
+        constraints.push((criteria, timeout));
+    }
+
+    Ok(constraints)
+}
+
+fn read_significance(node: &Node) -> Result<Significance> {
+    let consequence_level =
+        read_attribute::<ConsequenceLevel>(node, "consequenceLevel")?.unwrap_or(ConsequenceLevel::Normal);
+    let reason_for_warning = node.attribute("reasonForWarning").map(|s| s.to_owned());
+
+    Ok(Significance { consequence_level, reason_for_warning })
+}
+
+// the check of a verifier whose criteria is not yet added to the mdb
+enum ParsedCheck {
+    Container(ContainerIdx),
+    Criteria(MatchCriteria),
+}
+
+fn read_verifier_set(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Vec<(VerifierStage, ParsedCheck, CheckWindow)>> {
+    let mut verifiers = Vec::new();
+
+    for cnode in children(node) {
+        let Some(stage) = cnode.tag_name().name().strip_suffix("Verifier").and_then(|s| s.parse().ok()) else {
+            ctx.warn(&cnode, format!("ignoring verifier set unknown property '{}'", cnode.tag_name().name()))?;
+            continue;
+        };
+        match read_verifier(mdb, ctx, &cnode) {
+            Ok(Some((check, check_window))) => verifiers.push((stage, check, check_window)),
+            Ok(None) => {}
+            // the verifiers are not executed so they can be skipped without affecting the rest;
+            // this happens for example with the Yamcs command parameters (/yamcs/cmd/...)
+            Err(XtceError::UndefinedReference(reference, _)) => ctx.warn(
+                &cnode,
+                format!("ignoring {} which refers to the undefined item {}", cnode.tag_name().name(), reference),
+            )?,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(verifiers)
+}
+
+// returns None if the verifier uses an unsupported check or window
+fn read_verifier(mdb: &MissionDatabase, ctx: &ParseContext, node: &Node) -> Result<Option<(ParsedCheck, CheckWindow)>> {
+    let mut check = None;
+    let mut check_window = None;
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "ContainerRef" => {
+                let cref = read_mandatory_attribute::<String>(&cnode, "containerRef")?;
+                let cidx = resolve_ref(mdb, ctx, &cref, NameReferenceType::SequenceContainer)?;
+                check = Some(ParsedCheck::Container(cidx));
+            }
+            "CheckWindow" => check_window = Some(read_check_window(&cnode)?),
+            "LongDescription" | "AncillaryDataSet" | "ReturnParmRef" => {}
+            name => {
+                // some tools write conditions which are not supported by the match criteria (e.g. comparing
+                // with an argument), that does not prevent the command from being used
+                if let Some(unode) = find_unsupported_criteria_element(&cnode) {
+                    let uname = unode.tag_name().name();
+                    ctx.warn(&unode, format!("ignoring verifier with unsupported criteria element '{}'", uname))?;
+                    return Ok(None);
+                }
+                match parse_criteria_element(mdb, ctx, &cnode)? {
+                    Some(mc) => check = Some(ParsedCheck::Criteria(mc)),
+                    None => {
+                        ctx.warn(&cnode, format!("ignoring verifier with unsupported property '{}'", name))?;
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+
+    match (check, check_window) {
+        (Some(check), Some(check_window)) => Ok(Some((check, check_window))),
+        (None, _) => Err(missing("verifier check (ContainerRef or criteria)", node)),
+        (_, None) => Err(missing("element CheckWindow", node)),
+    }
+}
+
+fn read_check_window(node: &Node) -> Result<CheckWindow> {
+    let time_to_start_checking = read_duration_attribute(node, "timeToStartChecking")?;
+    let time_to_stop_checking = match read_duration_attribute(node, "timeToStopChecking")? {
+        Some(d) => d,
+        None => return Err(get_parse_error("Cannot find attribute timeToStopChecking", node)),
+    };
+    if time_to_start_checking.is_some_and(|start| start > time_to_stop_checking) {
+        return Err(get_parse_error("timeToStartChecking is after timeToStopChecking", node));
+    }
+    let relative_to = read_attribute::<CheckWindowStart>(node, "timeWindowIsRelativeTo")?
+        .unwrap_or(CheckWindowStart::TimeLastVerifierPassed);
+
+    Ok(CheckWindow { time_to_start_checking, time_to_stop_checking, relative_to })
+}
+
+fn read_base_meta_command(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<(MetaCommandIdx, Vec<ArgumentAssignment>)> {
+    let cref = read_mandatory_attribute::<String>(node, "metaCommandRef")?;
+    let cmdidx = resolve_ref(mdb, ctx, &cref, NameReferenceType::MetaCommand)?;
+    let mut assignments = Vec::new();
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "ArgumentAssignmentList" => {
+                for anode in children(&cnode) {
+                    if anode.tag_name().name() == "ArgumentAssignment" {
+                        assignments.push(read_argument_assignment(mdb, ctx, &anode, cmdidx)?);
+                    } else {
+                        ctx.warn(
+                            &anode,
+                            format!(
+                                "ignoring argument assignment list unknown property '{}'",
+                                anode.tag_name().name()
+                            ),
+                        )?;
+                    }
+                }
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring base meta command unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    Ok((cmdidx, assignments))
+}
+
+// the argument has to be defined by the base command or one of its ancestors
+fn read_argument_assignment(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+    base: MetaCommandIdx,
+) -> Result<ArgumentAssignment> {
+    let name = read_mandatory_attribute::<String>(node, "argumentName")?;
+    let value = read_mandatory_attribute::<String>(node, "argumentValue")?;
+    let cmd_qn = || format!("{}/{}", mdb.qn_to_string(ctx.path), mdb.name2str(ctx.name));
+
+    let arg = match mdb.name_db_ref().get(&name).and_then(|name| mdb.get_argument(base, name)) {
+        Some(arg) => arg,
+        None => {
+            return Err(get_parse_error(
+                format!(
+                    "Command {}: cannot assign the argument '{}', not defined by any of the base commands",
+                    cmd_qn(),
+                    name
+                ),
+                node,
+            ))
+        }
+    };
+    let dtype = mdb.get_data_type(arg.atype);
+    if !supports_initial_value(dtype) {
+        return Err(get_parse_error(
+            format!(
+                "Command {}: cannot assign the argument '{}', the values of its type cannot be given as text",
+                cmd_qn(),
+                name
+            ),
+            node,
+        ));
+    }
+    let value = dtype.from_str(&value, true).map_err(|err| {
+        get_parse_error(
+            format!("Command {}: invalid value '{}' for the argument '{}': {}", cmd_qn(), value, name, err),
+            node,
+        )
+    })?;
+
+    Ok(ArgumentAssignment { name: arg.name(), value })
+}
+
+fn read_argument_list(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Vec<Argument>> {
+    let mut arguments: Vec<Argument> = Vec::new();
+
+    for cnode in children(node) {
+        if cnode.tag_name().name() != "Argument" {
+            ctx.warn(
+                &cnode,
+                format!("ignoring argument list unknown property '{}'", cnode.tag_name().name()),
+            )?;
+            continue;
+        }
+        let name_str = read_mandatory_name(&cnode)?;
+        let name = mdb.get_or_intern(name_str);
+        if arguments.iter().any(|a| a.name() == name) {
+            return Err(get_parse_error(format!("duplicate argument '{}'", name_str), &cnode));
+        }
+        let tref = read_mandatory_attribute::<String>(&cnode, "argumentTypeRef")?;
+        let atype = resolve_ref(mdb, ctx, &tref, NameReferenceType::ArgumentType)?;
+
+        let ctx1 = ParseContext { node: cnode, name, ..*ctx };
+        let ndescr = read_name_description(&ctx1)?;
+        let dtype = mdb.get_data_type(atype);
+        let initial_value = match read_attribute::<String>(&cnode, "initialValue")? {
+            Some(_) if !supports_initial_value(dtype) => {
+                ctx.warn(&cnode, format!("ignoring unsupported initial value of argument {}", name_str))?;
+                None
+            }
+            Some(v) => Some(parse_initial_value(dtype, &v, &cnode)?),
+            None => None,
+        };
+        arguments.push(Argument { ndescr, atype, initial_value });
+    }
+
+    Ok(arguments)
+}
+
+// the arguments are those of the command being read, the ones of the base commands are in the mdb
+fn read_command_container(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+    base_meta_command: Option<MetaCommandIdx>,
+    arguments: &[Argument],
+) -> Result<CommandContainer> {
+    let name = mdb.get_or_intern(read_mandatory_name(node)?);
+    let ctx1 = ParseContext { node: *node, name, ..*ctx };
+    let ndescr = read_name_description(&ctx1)?;
+    let idle_pattern = read_integer_attribute::<u8>(node, "idlePattern")?.unwrap_or(0);
+    let mut entries = Vec::new();
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "EntryList" => {
+                for enode in children(&cnode) {
+                    let data = match enode.tag_name().name() {
+                        "ArgumentRefEntry" => {
+                            read_argument_ref(mdb, &enode, base_meta_command, arguments)?
+                        }
+                        "FixedValueEntry" => read_fixed_value_entry(&enode)?,
+                        _ => {
+                            ctx.warn(
+                                &enode,
+                                format!(
+                                    "ignoring unsupported command container entry '{}'",
+                                    enode.tag_name().name()
+                                ),
+                            )?;
+                            continue;
+                        }
+                    };
+                    let location_in_container = read_entry_location(mdb, ctx, &enode)?;
+                    entries.push(CommandContainerEntry { location_in_container, data });
+                }
+            }
+            // the entries of the base container are encoded as part of the base meta command
+            "BaseContainer" | "LongDescription" | "AliasSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring command container unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    Ok(CommandContainer { ndescr, entries, idle_pattern })
+}
+
+fn read_argument_ref(
+    mdb: &MissionDatabase,
+    node: &Node,
+    base_meta_command: Option<MetaCommandIdx>,
+    arguments: &[Argument],
+) -> Result<CommandEntryData> {
+    let aref = read_mandatory_attribute::<String>(node, "argumentRef")?;
+    let known = mdb.name_db_ref().get(&aref).filter(|name| {
+        arguments.iter().any(|a| a.name() == *name)
+            || base_meta_command.is_some_and(|base| mdb.get_argument(base, *name).is_some())
+    });
+
+    match known {
+        Some(name) => Ok(CommandEntryData::ArgumentRef(name)),
+        None => Err(get_parse_error(format!("unknown argument '{}'", aref), node)),
+    }
+}
+
+fn read_fixed_value_entry(node: &Node) -> Result<CommandEntryData> {
+    let hexv = read_mandatory_attribute::<String>(node, "binaryValue")?;
+    let binary_value = hex::decode(&hexv).map_err(|_| {
+        get_parse_error(format!("Cannot decode binaryValue as hex: '{}'", hexv), node)
+    })?;
+    let size_in_bits = read_mandatory_integer_attribute::<u32>(node, "sizeInBits")?;
+    if size_in_bits as usize > 8 * binary_value.len() {
+        return Err(get_parse_error(
+            format!(
+                "sizeInBits {} exceeds the size of the binary value '{}'",
+                size_in_bits, hexv
+            ),
+            node,
+        ));
+    }
+
+    Ok(CommandEntryData::FixedValue(FixedValueEntry { binary_value, size_in_bits }))
+}
+
+fn read_entry_location(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Option<LocationInContainerInBits>> {
+    let mut location = None;
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "LocationInContainerInBits" => {
+                location = Some(read_location_in_container(mdb, ctx, &cnode)?);
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring command container entry unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+    Ok(location)
+}
+
+impl FromStr for VerifierStage {
+    type Err = XtceError;
+
+    // the name of the XTCE element without the Verifier suffix
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "TransferredToRange" => Ok(VerifierStage::TransferredToRange),
+            "SentFromRange" => Ok(VerifierStage::SentFromRange),
+            "Received" => Ok(VerifierStage::Received),
+            "Accepted" => Ok(VerifierStage::Accepted),
+            "Queued" => Ok(VerifierStage::Queued),
+            "Execution" => Ok(VerifierStage::Execution),
+            "Complete" => Ok(VerifierStage::Complete),
+            "Failed" => Ok(VerifierStage::Failed),
+            _ => Err(XtceError::InvalidValue(format!("unknown verifier stage '{}'", s))),
+        }
+    }
+}
+
+impl FromStr for CheckWindowStart {
+    type Err = XtceError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "commandRelease" => Ok(CheckWindowStart::CommandRelease),
+            "timeLastVerifierPassed" => Ok(CheckWindowStart::TimeLastVerifierPassed),
+            _ => Err(XtceError::InvalidValue(
+                "please use one of commandRelease or timeLastVerifierPassed".to_owned(),
+            )),
+        }
+    }
+}
+
+impl FromStr for ConsequenceLevel {
+    type Err = XtceError;
+
+    // accepts the XTCE 1.2 and the XTCE 1.1 levels
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" | "normal" => Ok(ConsequenceLevel::Normal),
+            "watch" => Ok(ConsequenceLevel::Watch),
+            "warning" | "vital" => Ok(ConsequenceLevel::Warning),
+            "distress" => Ok(ConsequenceLevel::Distress),
+            "critical" => Ok(ConsequenceLevel::Critical),
+            "severe" | "forbidden" => Ok(ConsequenceLevel::Severe),
+            "user1" => Ok(ConsequenceLevel::User1),
+            "user2" => Ok(ConsequenceLevel::User2),
+            _ => Err(XtceError::InvalidValue(
+                "please use one of none, watch, warning, distress, critical or severe".to_owned(),
+            )),
+        }
+    }
+}
@@ -4,16 +4,21 @@ use roxmltree::Node;
 
 use crate::{
     mdb::{
-        ContainerEntry, ContainerEntryData, ContainerIdx, IntegerValue,
+        types::{BinarySize, DataEncoding},
+        ContainerEntry, ContainerEntryData, ContainerIdx, ContainerSegmentRefEntry, IntegerValue,
         LocationInContainerInBits, MatchCriteriaIdx, MissionDatabase,
-        NameReferenceType, ReferenceLocationType, SequenceContainer, Index,
+        NameReferenceType, ParameterSegmentRefEntry, ReferenceLocationType, RepeatEntry, SequenceContainer, Index,
+    },
+    parser::utils::{
+        read_attribute, read_bool_attribute, read_integer_attribute, read_mandatory_attribute, read_mandatory_integer_attribute,
+        read_name_description, warn_duplicate_aliases,
     },
-    parser::utils::{read_attribute, read_mandatory_attribute, read_name_description},
 };
 
 use super::{
-    misc::{read_integer_value, read_match_criteria, resolve_para_ref, resolve_ref},
-    utils::get_parse_error,
+    encodings::read_binary_data_encoding,
+    misc::{read_integer_value, read_match_criteria, read_rate_in_stream, resolve_para_ref, resolve_ref},
+    utils::{children, get_parse_error},
     ParseContext, XtceError,
 };
 
@@ -21,14 +26,16 @@ pub(super) fn add_container(
     mdb: &mut MissionDatabase,
     ctx: &ParseContext,
 ) -> Result<(), XtceError> {
-    let abstract_ = read_attribute::<bool>(&ctx.node, "abstract")?.unwrap_or(true);
-    let ndescr = read_name_description(ctx);
+    let abstract_ = read_bool_attribute(&ctx.node, "abstract")?.unwrap_or(true);
+    let ndescr = read_name_description(ctx)?;
 
     let mut entry_list: Vec<ContainerEntry> = Vec::new();
 
     let mut base_container = None;
+    let mut size_in_bits = None;
+    let mut default_rate_in_stream = None;
 
-    for cnode in ctx.node.children() {
+    for cnode in children(&ctx.node) {
         match cnode.tag_name().name() {
             "EntryList" => {
                 read_entry_list(mdb, ctx, &cnode, &mut entry_list)?;
@@ -36,16 +43,49 @@ pub(super) fn add_container(
             "BaseContainer" => {
                 base_container.replace(read_base_container(mdb, ctx, &cnode)?);
             }
-            "LongDescription" | "" => continue,
-            _ => log::warn!("ignoring container unknown property '{}'", cnode.tag_name().name()),
+            "BinaryEncoding" => size_in_bits = read_container_size(mdb, ctx, &cnode)?,
+            "DefaultRateInStream" => default_rate_in_stream = Some(read_rate_in_stream(&cnode)?),
+            "LongDescription" | "AliasSet" | "" => continue,
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring container unknown property '{}'", cnode.tag_name().name()),
+            )?,
         };
     }
     
-    let sc = SequenceContainer { ndescr, base_container, abstract_, entries: entry_list, idx: Index::invalid() };
+    let sc = SequenceContainer {
+        ndescr,
+        base_container,
+        abstract_,
+        entries: entry_list,
+        size_in_bits,
+        default_rate_in_stream,
+        idx: Index::invalid(),
+    };
+    warn_duplicate_aliases(mdb, ctx, &sc.ndescr)?;
     mdb.add_container(ctx.path, sc);
     Ok(())
 }
 
+// reads the fixed size of the container; the other sizes are not used for the container extraction
+fn read_container_size(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Option<u32>, XtceError> {
+    let bde = read_binary_data_encoding(mdb, ctx, node, &DataEncoding::None)?;
+    match bde.size_in_bits {
+        BinarySize::Fixed(size) => Ok(Some(size)),
+        _ => {
+            ctx.warn(
+                &ctx.node,
+                format!("ignoring non fixed size of container {}", mdb.name2str(ctx.name)),
+            )?;
+            Ok(None)
+        }
+    }
+}
+
 fn read_base_container(
     mdb: &mut MissionDatabase,
     ctx: &ParseContext,
@@ -55,12 +95,18 @@ fn read_base_container(
     let cidx = resolve_ref(mdb, ctx, &pref, NameReferenceType::SequenceContainer)?;
     let mut mcidx = None;
 
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
             "RestrictionCriteria" => mcidx = Some(read_match_criteria(mdb, ctx, &cnode)?),
             "" => continue,
             _ => {
-                log::warn!("ignoring base container unknown property '{}'", cnode.tag_name().name())
+                ctx.warn(
+                    &cnode,
+                    format!(
+                        "ignoring base container unknown property '{}'",
+                        cnode.tag_name().name()
+                    ),
+                )?
             }
         }
     }
@@ -74,17 +120,22 @@ fn read_entry_list(
     node: &Node,
     list: &mut Vec<ContainerEntry>,
 ) -> Result<(), XtceError> {
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
             "ParameterRefEntry" => list.push(read_para_entry(mdb, ctx, &cnode)?),
-            "ContainerRefEntry" => {}
+            "ContainerRefEntry" => list.push(read_container_entry(mdb, ctx, &cnode)?),
+            "ParameterSegmentRefEntry" => list.push(read_para_segment_entry(mdb, ctx, &cnode)?),
+            "ContainerSegmentRefEntry" => list.push(read_container_segment_entry(mdb, ctx, &cnode)?),
             "IndirectParameterRefEntry" => {}
             "ArrayParameterRefEntry" => {}
             "" => continue,
-            _ => log::warn!(
-                "ignoring sequence container entry list unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring sequence container entry list unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
@@ -109,6 +160,7 @@ fn read_para_entry(
     let mut entry = ContainerEntry {
         location_in_container: None,
         include_condition: None,
+        repeat: None,
         data: ContainerEntryData::ParameterRef(pidx),
     };
 
@@ -117,13 +169,84 @@ fn read_para_entry(
     Ok(entry)
 }
 
+fn read_container_entry(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<ContainerEntry, XtceError> {
+    let cref = read_mandatory_attribute::<String>(node, "containerRef")?;
+    let cidx = resolve_ref(mdb, ctx, &cref, NameReferenceType::SequenceContainer)?;
+
+    let mut entry = ContainerEntry {
+        location_in_container: None,
+        include_condition: None,
+        repeat: None,
+        data: ContainerEntryData::ContainerRef(cidx),
+    };
+
+    read_common_entry_elements(mdb, ctx, node, &mut entry)?;
+
+    Ok(entry)
+}
+
+fn read_para_segment_entry(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<ContainerEntry, XtceError> {
+    let pref = read_mandatory_attribute::<String>(node, "parameterRef")?;
+    let (pidx, aggr_path) = resolve_para_ref(mdb, ctx, &pref)?;
+
+    if aggr_path.is_some() {
+        return Err(XtceError::InvalidReference(format!(
+            "Cannot reference a aggregate member in the container parameter segment entry: {}",
+            pref
+        )));
+    }
+    let order = read_integer_attribute::<u32>(node, "order")?;
+    let size_in_bits = read_mandatory_integer_attribute::<u32>(node, "sizeInBits")?;
+
+    let mut entry = ContainerEntry {
+        location_in_container: None,
+        include_condition: None,
+        repeat: None,
+        data: ContainerEntryData::ParameterSegmentRef(ParameterSegmentRefEntry { pidx, order, size_in_bits }),
+    };
+
+    read_common_entry_elements(mdb, ctx, node, &mut entry)?;
+
+    Ok(entry)
+}
+
+fn read_container_segment_entry(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<ContainerEntry, XtceError> {
+    let cref = read_mandatory_attribute::<String>(node, "containerRef")?;
+    let cidx = resolve_ref(mdb, ctx, &cref, NameReferenceType::SequenceContainer)?;
+    let order = read_integer_attribute::<u32>(node, "order")?;
+    let size_in_bits = read_mandatory_integer_attribute::<u32>(node, "sizeInBits")?;
+
+    let mut entry = ContainerEntry {
+        location_in_container: None,
+        include_condition: None,
+        repeat: None,
+        data: ContainerEntryData::ContainerSegmentRef(ContainerSegmentRefEntry { cidx, order, size_in_bits }),
+    };
+
+    read_common_entry_elements(mdb, ctx, node, &mut entry)?;
+
+    Ok(entry)
+}
+
 fn read_common_entry_elements(
     mdb: &mut MissionDatabase,
     ctx: &ParseContext,
     node: &Node,
     entry: &mut ContainerEntry,
 ) -> Result<(), XtceError> {
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
             "LocationInContainerInBits" => {
                 let lic = read_location_in_container(mdb, ctx, &cnode)?;
@@ -132,15 +255,18 @@ fn read_common_entry_elements(
             "IncludeCondition" => {
                 entry.include_condition.replace(read_match_criteria(mdb, ctx, &cnode)?);
             }
+            "RepeatEntry" => {
+                entry.repeat.replace(read_repeat_entry(mdb, ctx, &cnode)?);
+            }
             "" => continue,
-            _ => log::warn!("ignoring unknown  '{}'", cnode.tag_name().name()),
+            _ => ctx.warn(&cnode, format!("ignoring unknown  '{}'", cnode.tag_name().name()))?,
         };
     }
     Ok(())
 }
 
-fn read_location_in_container(
-    mdb: &MissionDatabase,
+pub(super) fn read_location_in_container(
+    mdb: &mut MissionDatabase,
     ctx: &ParseContext,
     node: &Node,
 ) -> Result<LocationInContainerInBits, XtceError> {
@@ -169,6 +295,40 @@ fn read_location_in_container(
     Ok(loc)
 }
 
+fn read_repeat_entry(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<RepeatEntry, XtceError> {
+    let mut count = None;
+    let mut offset_in_bits = 0;
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "Count" => count = Some(read_integer_value(mdb, ctx, &cnode)?),
+            "Offset" => {
+                offset_in_bits = match read_integer_value(mdb, ctx, &cnode)? {
+                    IntegerValue::FixedValue(v) => u32::try_from(v).map_err(|_| {
+                        get_parse_error(format!("Value {} specified for the repeat offset is out of range", v), &cnode)
+                    })?,
+                    IntegerValue::DynamicValue(_) => {
+                        return Err(get_parse_error("DynamicValue not supported for the repeat offset", &cnode))
+                    }
+                }
+            }
+            "" => continue,
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring repeat entry unknown property '{}'", cnode.tag_name().name()),
+            )?,
+        };
+    }
+
+    let count = count.ok_or_else(|| get_parse_error("RepeatEntry without Count", node))?;
+
+    Ok(RepeatEntry { count, offset_in_bits })
+}
+
 impl FromStr for ReferenceLocationType {
     type Err = String;
 
@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use super::{
-    misc::{read_dynamic_value, read_integer_value},
+    misc::{read_discrete_lookup_list, read_dynamic_value, read_integer_value},
     *,
 };
 
@@ -11,15 +11,17 @@ use crate::{
         types::{
             DataEncoding, FloatDataEncoding, FloatEncodingType, IntegerDataEncoding,
             IntegerEncodingType, StringBoxSize, StringDataEncoding, StringSize, BinaryDataEncoding,
+            BinarySize, TransformAlgorithm, AlgorithmText, Crc, LeadingSize,
         },
         *,
     },
 };
 use roxmltree::Node;
+use smallvec::SmallVec;
 
 pub(super) fn read_integer_data_encoding(
     _mdb: &MissionDatabase,
-    _path: &QualifiedName,
+    ctx: &ParseContext,
     node: &Node,
     base_encoding: &DataEncoding,
 ) -> Result<IntegerDataEncoding> {
@@ -40,25 +42,54 @@ pub(super) fn read_integer_data_encoding(
         }
     });
 
+    // the raw values are extracted into 64 bits integers
+    if !(1..=64).contains(&size_in_bits) {
+        return Err(get_parse_error(
+            format!("Invalid size in bits {} for the integer encoding, should be between 1 and 64", size_in_bits),
+            node,
+        ));
+    }
+
+    let digit_size = match encoding {
+        IntegerEncodingType::Bcd => 8,
+        IntegerEncodingType::PackedBcd => 4,
+        _ => 1,
+    };
+    if size_in_bits % digit_size != 0 {
+        return Err(get_parse_error(
+            format!(
+                "Invalid size in bits {} for the {:?} encoding, should be a multiple of {}",
+                size_in_bits, encoding, digit_size
+            ),
+            node,
+        ));
+    }
+
     let byte_order =
         (read_attribute::<ByteOrder>(node, "byteOrder")?).unwrap_or(ByteOrder::BigEndian);
 
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
-            "" => {}
-            _ => log::warn!(
-                "ignoring integer data encoding unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            // read together with the data type, see read_calibrators
+            "" | "DefaultCalibrator" | "ContextCalibratorList" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring integer data encoding unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
-    Ok(IntegerDataEncoding { size_in_bits, encoding, byte_order })
+    let change_threshold = read_change_threshold(node, base_encoding)?;
+
+    Ok(IntegerDataEncoding { size_in_bits, encoding, byte_order, change_threshold })
 }
 
 pub(super) fn read_float_data_encoding(
     _mdb: &MissionDatabase,
-    _path: &QualifiedName,
+    ctx: &ParseContext,
     node: &Node,
     base_encoding: &DataEncoding,
 ) -> Result<FloatDataEncoding> {
@@ -69,14 +100,7 @@ pub(super) fn read_float_data_encoding(
             32
         }
     });
-    if size_in_bits != 32 && size_in_bits != 64 {
-        return Err(get_parse_error(
-            format!("Invalid size in bits {}, should be 32 or 64", size_in_bits),
-            &node,
-        )
-        .into());
-    }
-    let encoding;
+    let mut encoding;
 
     if let Some(encs) = node.attribute("encoding") {
         encoding = match encs {
@@ -96,23 +120,109 @@ pub(super) fn read_float_data_encoding(
         encoding = FloatEncodingType::IEEE754_1985;
     }
 
+    if let Some((exponent_bits, mantissa_bits)) = read_custom_float_layout(ctx)? {
+        if let FloatEncodingType::Milstd1750a = encoding {
+            return Err(get_parse_error(
+                "A custom float layout cannot be used with the MILSTD_1750A encoding",
+                node,
+            ));
+        }
+        encoding = FloatEncodingType::Custom { exponent_bits, mantissa_bits };
+    }
+
+    let valid_sizes: &[u8] = match encoding {
+        FloatEncodingType::IEEE754_1985 => &[16, 32, 64],
+        FloatEncodingType::Milstd1750a => &[32, 48],
+        FloatEncodingType::Custom { exponent_bits, mantissa_bits } => {
+            if 1 + exponent_bits as u32 + mantissa_bits as u32 != size_in_bits as u32 {
+                return Err(get_parse_error(
+                    format!(
+                        "The custom float layout of 1 + {} + {} bits does not match the size in \
+                         bits {}",
+                        exponent_bits, mantissa_bits, size_in_bits
+                    ),
+                    node,
+                ));
+            }
+            // the size is given by the layout
+            &[]
+        }
+    };
+    if !valid_sizes.is_empty() && !valid_sizes.contains(&size_in_bits) {
+        return Err(get_parse_error(
+            format!(
+                "Invalid size in bits {} for the {:?} encoding, should be one of {:?}",
+                size_in_bits, encoding, valid_sizes
+            ),
+            node,
+        ));
+    }
+
     let byte_order =
     (read_attribute::<ByteOrder>(node, "byteOrder")?).unwrap_or(ByteOrder::BigEndian);
 
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
-            "" => {}
-            _ => log::warn!(
-                "ignoring float data encoding unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            // read together with the data type, see read_calibrators
+            "" | "DefaultCalibrator" | "ContextCalibratorList" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring float data encoding unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
-    Ok(FloatDataEncoding { size_in_bits, encoding, byte_order })
+    let change_threshold = read_change_threshold(node, base_encoding)?;
+
+    Ok(FloatDataEncoding { size_in_bits, encoding, byte_order, change_threshold })
+}
+
+// reads the exponent and mantissa widths from the ancillary data of the parameter type
+fn read_custom_float_layout(ctx: &ParseContext) -> Result<Option<(u8, u8)>> {
+    let exponent = read_ancillary_data(&ctx.node, "FloatExponentBits");
+    let mantissa = read_ancillary_data(&ctx.node, "FloatMantissaBits");
+    let (exponent, mantissa) = match (exponent, mantissa) {
+        (None, None) => return Ok(None),
+        (Some(e), Some(m)) => (e, m),
+        _ => {
+            return Err(get_parse_error(
+                "Both FloatExponentBits and FloatMantissaBits have to be specified",
+                &ctx.node,
+            ))
+        }
+    };
+
+    // the exponent has to fit the one of f64 for the values to be representable and the value with its sign bit
+    // has to fit in 64 bits
+    match (exponent.parse::<u8>(), mantissa.parse::<u8>()) {
+        (Ok(e @ 2..=11), Ok(m @ 1..=62)) if 1 + e as u32 + m as u32 <= 64 => Ok(Some((e, m))),
+        _ => Err(get_parse_error(
+            format!(
+                "Invalid custom float layout with {} exponent bits and {} mantissa bits, \
+                 the exponent should have 2 to 11 bits, the mantissa 1 to 62 bits and the total size with the sign \
+                 bit should not exceed 64 bits",
+                exponent, mantissa
+            ),
+            &ctx.node,
+        )),
+    }
+}
+
+// the threshold is inherited from the base encoding if not specified
+fn read_change_threshold(node: &Node, base_encoding: &DataEncoding) -> Result<Option<f64>> {
+    let threshold = read_attribute::<f64>(node, "changeThreshold")?.or_else(|| base_encoding.change_threshold());
+    if let Some(t) = threshold {
+        if t.is_nan() || t < 0.0 {
+            return Err(get_parse_error(format!("Invalid changeThreshold {}, it should be a positive number", t), node));
+        }
+    }
+    Ok(threshold)
 }
 
 pub(super) fn read_string_data_encoding(
-    mdb: &MissionDatabase,
+    mdb: &mut MissionDatabase,
     ctx: &ParseContext,
     node: &Node,
     base_encoding: &DataEncoding,
@@ -125,9 +235,12 @@ pub(super) fn read_string_data_encoding(
         }
     });
     let mut size_in_bits = None;
+    // the byte order of the UTF-16/UTF-32 code units and the default one of the leading size tag
+    let byte_order = (read_attribute::<ByteOrder>(node, "byteOrder")?).unwrap_or(ByteOrder::BigEndian);
 
-    let mut max_box_size_in_bytes = None;
+    let mut max_box_size_in_bits = None;
     let mut box_size_in_bits = StringBoxSize::Undefined;
+    let mut from_binary_transform_algorithm = None;
 
     for cnode in children(&node) {
         match cnode.tag_name().name() {
@@ -155,7 +268,7 @@ pub(super) fn read_string_data_encoding(
                         }
                         "LeadingSize" => {
                             size_in_bits.replace(StringSize::LeadingSize(
-                                parse_leading_size(&cnode1)?
+                                parse_leading_size(&cnode1, byte_order)?
                             ));
                         }
                         _ => {
@@ -166,7 +279,7 @@ pub(super) fn read_string_data_encoding(
             }
             "Variable" => {
                 let msb = read_mandatory_attribute::<u32>(&cnode, "maxSizeInBits")?;
-                max_box_size_in_bytes.replace(msb / 8);
+                max_box_size_in_bits.replace(msb);
                 for cnode1 in children(&cnode) {
                     match cnode1.tag_name().name() {
                         "TerminationChar" => {
@@ -174,84 +287,293 @@ pub(super) fn read_string_data_encoding(
                                 parse_terminator_char(&cnode1)?,
                             ));
                         }
-                        "LeadingSize" => {                            
+                        "LeadingSize" => {
                             size_in_bits.replace(StringSize::LeadingSize(
-                                parse_leading_size(&cnode1)?
+                                parse_leading_size(&cnode1, byte_order)?
                             ));
                         }
                         "DynamicValue" => {
                             let dv = read_dynamic_value(mdb, ctx, &cnode1, true)?;
-                            if dv.para_ref.pidx != INVALID_PARAM_IDX {
+                            let ignored = matches!(&dv, DynamicValueType::Parameter(pdv) if pdv.para_ref.pidx == INVALID_PARAM_IDX);
+                            if !ignored {
                                 box_size_in_bits = StringBoxSize::Dynamic(dv);
                             }
                         }
+                        "DiscreteLookupList" => {
+                            box_size_in_bits = StringBoxSize::Dynamic(read_discrete_lookup_list(mdb, ctx, &cnode1)?);
+                        }
 
                         _ => return Err(unsupported("size type", &cnode1)),
                     }
                 }
             }
-            _ => log::warn!(
-                "ignoring string data encoding unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            "FromBinaryTransformAlgorithm" => {
+                from_binary_transform_algorithm = Some(read_transform_algorithm(ctx, &cnode)?);
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring string data encoding unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
+    // the algorithm determines the size of the string inside the box given by the SizeInBits or Variable elements
+    if from_binary_transform_algorithm.is_some() {
+        size_in_bits = Some(StringSize::Custom);
+    }
+
     if size_in_bits.is_none() {
         return Err(get_parse_error("Size in bits not specified", &node).into());
     }
 
-    Ok(StringDataEncoding {
+    let sde = StringDataEncoding {
         encoding,
-        max_box_size_in_bytes,
+        byte_order,
+        max_box_size_in_bits,
         size_in_bits: size_in_bits.unwrap(),
         box_size_in_bits,
-    })
+        from_binary_transform_algorithm,
+    };
+
+    // the terminator is searched at the code unit boundaries, it has to contain entire code units
+    if let StringSize::TerminationChar(tc) = &sde.size_in_bits {
+        let unit_size = sde.code_unit_size();
+        if tc.len() % unit_size != 0 {
+            return Err(get_parse_error(
+                format!(
+                    "The {} bytes termination char is not a multiple of the {} bytes code unit \
+                     of the {} encoding",
+                    tc.len(),
+                    unit_size,
+                    sde.encoding
+                ),
+                node,
+            ));
+        }
+    }
+
+    Ok(sde)
 }
 
 
 
 pub(super) fn read_binary_data_encoding(
-    mdb: &MissionDatabase,
+    mdb: &mut MissionDatabase,
     ctx: &ParseContext,
     node: &Node,
     base_encoding: &DataEncoding,
 ) -> Result<BinaryDataEncoding> {
-    for cnode in children(&node) {
+    let mut size_in_bits = None;
+    let mut from_binary_transform_algorithm = None;
+    let mut to_binary_transform_algorithm = None;
+    let mut crc = None;
+
+    for cnode in children(node) {
         match cnode.tag_name().name() {
+            "SizeInBits" if is_ignored_size(&cnode) => {
+                size_in_bits = Some(BinarySize::Algorithm);
+            }
             "SizeInBits" => {
-                let iv = read_integer_value(mdb, ctx, &cnode)?;
+                let size = match read_integer_value(mdb, ctx, &cnode)? {
+                    // by convention -1 means that the size is determined by the algorithm
+                    IntegerValue::FixedValue(-1) => BinarySize::Algorithm,
+                    IntegerValue::FixedValue(v) => BinarySize::Fixed(u32::try_from(v).map_err(|_| {
+                        get_parse_error(format!("Invalid size in bits {}", v), &cnode)
+                    })?),
+                    IntegerValue::DynamicValue(dv) => BinarySize::Dynamic(dv),
+                };
+                size_in_bits = Some(size);
             }
-            _ => log::warn!("Ignorng unsupported element {} for binary data encoding", cnode.tag_name().name())
+            "FromBinaryTransformAlgorithm" => {
+                from_binary_transform_algorithm = Some(read_transform_algorithm(ctx, &cnode)?);
+            }
+            "ToBinaryTransformAlgorithm" => {
+                to_binary_transform_algorithm = Some(read_transform_algorithm(ctx, &cnode)?);
+            }
+            "ErrorDetectCorrect" => crc = read_error_detect_correct(ctx, &cnode)?,
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "Ignorng unsupported element {} for binary data encoding",
+                    cnode.tag_name().name()
+                ),
+            )?
         }
     }
-todo!()
-   // Ok(BinaryDataEncoding{})
+
+    let size_in_bits = match (size_in_bits, base_encoding) {
+        (Some(s), _) => s,
+        (None, DataEncoding::Binary(BinaryDataEncoding { size_in_bits: BinarySize::Fixed(s), .. })) => {
+            BinarySize::Fixed(*s)
+        }
+        (None, _) => return Err(get_parse_error("Size in bits not specified", node)),
+    };
+    if matches!(size_in_bits, BinarySize::Algorithm)
+        && from_binary_transform_algorithm.is_none()
+        && to_binary_transform_algorithm.is_none()
+    {
+        return Err(get_parse_error(
+            "Size in bits -1 can only be used together with a transform algorithm",
+            node,
+        ));
+    }
+
+    // the extraction of such data fails; the type is accepted in lenient mode because it is used for padding
+    if let BinarySize::Fixed(size) = size_in_bits {
+        if size % 8 != 0 {
+            ctx.warn(node, format!("The fixed size in bits of the binary data {} is not a multiple of 8", size))?;
+        }
+    }
+
+    if let (Some(crc), BinarySize::Fixed(size)) = (&crc, &size_in_bits) {
+        if crc.width != *size {
+            return Err(get_parse_error(
+                format!("The CRC width {} does not match the size in bits {}", crc.width, size),
+                node,
+            ));
+        }
+    }
+
+    Ok(BinaryDataEncoding {
+        size_in_bits,
+        from_binary_transform_algorithm,
+        to_binary_transform_algorithm,
+        crc,
+    })
 }
 
+// true if the size is a dynamic value referring to _yamcs_ignore, used by Yamcs for the sizes
+// determined by the transform algorithm
+fn is_ignored_size(node: &Node) -> bool {
+    children(node)
+        .filter(|n| n.tag_name().name() == "DynamicValue")
+        .flat_map(|n| children(&n))
+        .any(|n| {
+            n.tag_name().name() == "ParameterInstanceRef"
+                && n.attribute("parameterRef") == Some(IGNORE_PARAM_NAME)
+        })
+}
 
-fn parse_leading_size(node: &Node) -> Result<u32> {
-    let v = read_attribute::<u32>(&node, "sizeInBitsOfSizeTag")?
-    .unwrap_or(16);
+// only the CRC is supported, the checksums and the parity are ignored
+fn read_error_detect_correct(ctx: &ParseContext, node: &Node) -> Result<Option<Crc>> {
+    let mut crc = None;
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "CRC" => crc = Some(read_crc(ctx, &cnode)?),
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring unsupported error detection '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+    Ok(crc)
+}
 
-    if v%8 !=0 {
-        Err(get_parse_error(format!("Invalid value {} for sizeInBitsOfSizeTag; only multiples of 8 are supported'", v), node))?
-    } else {
-        Ok(v/8)
+fn read_crc(ctx: &ParseContext, node: &Node) -> Result<Crc> {
+    let width = read_mandatory_integer_attribute::<u32>(node, "width")?;
+    if !(8..=64).contains(&width) || width % 8 != 0 {
+        return Err(get_parse_error(
+            format!("Invalid CRC width {}; only multiples of 8 up to 64 are supported", width),
+            node,
+        ));
+    }
+    let reflect_data = read_bool_attribute(node, "reflectData")?.unwrap_or(false);
+    let reflect_remainder = read_bool_attribute(node, "reflectRemainder")?.unwrap_or(false);
+    let bits_from_reference = read_integer_attribute::<u32>(node, "bitsFromReference")?.unwrap_or(0);
+    if bits_from_reference % 8 != 0 {
+        return Err(get_parse_error(
+            format!("Invalid bitsFromReference {} for CRC; only multiples of 8 are supported", bits_from_reference),
+            node,
+        ));
+    }
+    if let Some(reference) = node.attribute("reference") {
+        if reference != "start" {
+            return Err(unsupported("CRC reference", node));
+        }
     }
 
+    let mut polynomial = None;
+    let mut init_remainder = 0;
+    let mut final_xor = 0;
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "Polynomial" => polynomial = Some(read_hex_text(&cnode)?),
+            "InitRemainder" => init_remainder = read_hex_text(&cnode)?,
+            "FinalXOR" => final_xor = read_hex_text(&cnode)?,
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring CRC unknown property '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+    let polynomial = polynomial.ok_or_else(|| get_parse_error("CRC Polynomial not specified", node))?;
+
+    Ok(Crc { width, polynomial, init_remainder, final_xor, reflect_data, reflect_remainder, bits_from_reference })
+}
+
+// the hexBinary values are written without prefix, most significant byte first
+fn read_hex_text(node: &Node) -> Result<u64> {
+    let hexv = read_mandatory_text::<String>(node)?;
+    u64::from_str_radix(&hexv, 16)
+        .map_err(|_| get_parse_error(format!("Cannot decode '{}' as a hexadecimal number", hexv), node))
+}
+
+// reads an inline algorithm of a data encoding
+fn read_transform_algorithm(ctx: &ParseContext, node: &Node) -> Result<TransformAlgorithm> {
+    let name = read_mandatory_name(node)?.to_owned();
+    let mut algorithm_text = None;
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "AlgorithmText" => {
+                let language = read_mandatory_attribute::<String>(&cnode, "language")?;
+                let text = cnode.text().unwrap_or("").trim().to_owned();
+                algorithm_text = Some(AlgorithmText { language, text });
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring transform algorithm unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    Ok(TransformAlgorithm { name, algorithm_text })
+}
+
+
+// the byte order can be given on the LeadingSize element, by default it is the one of the encoding
+fn parse_leading_size(node: &Node, encoding_byte_order: ByteOrder) -> Result<LeadingSize> {
+    let v = read_attribute::<u32>(node, "sizeInBitsOfSizeTag")?.unwrap_or(16);
+    let byte_order = (read_attribute::<ByteOrder>(node, "byteOrder")?).unwrap_or(encoding_byte_order);
+
+    if v == 0 || v > 32 {
+        Err(get_parse_error(
+            format!("Invalid value {} for sizeInBitsOfSizeTag; it should be between 1 and 32", v),
+            node,
+        ))
+    } else {
+        Ok(LeadingSize { tag_size_in_bits: v, byte_order })
+    }
 }
-fn parse_terminator_char(node: &Node) -> Result<u8> {
+
+fn parse_terminator_char(node: &Node) -> Result<SmallVec<[u8; 4]>> {
     let hexv = read_mandatory_text::<String>(node)?;
     let v = hex::decode(&hexv).or_else(|_e| {
         return Err(get_parse_error(format!("Cannot decode string as hex: '{}'", &hexv), node));
     })?;
-    if v.len() != 1 {
-        return Err(
-            get_parse_error(format!("Expected hex byte (2 characters): '{}'", hexv), node).into()
-        );
+    if v.is_empty() || v.len() > 4 {
+        return Err(get_parse_error(
+            format!("Expected 1 to 4 hex bytes (2 to 8 characters): '{}'", hexv),
+            node,
+        ));
     }
-    Ok(v[0])
+    Ok(SmallVec::from_vec(v))
 }
 
 impl FromStr for ByteOrder {
@@ -276,8 +598,12 @@ impl FromStr for IntegerEncodingType {
             "signmagnitude" => Ok(IntegerEncodingType::SignMagnitude),
             "twoscomplement" | "twoscompliment" => Ok(IntegerEncodingType::TwosComplement),
             "onescomplement" => Ok(IntegerEncodingType::OnesComplement),
-            _ => Err(XtceError::InvalidValue("please use one of unsigned, signMagnitude, towsComplement, onesComplement"
-                .to_owned())),
+            "bcd" => Ok(IntegerEncodingType::Bcd),
+            "packedbcd" => Ok(IntegerEncodingType::PackedBcd),
+            _ => Err(XtceError::InvalidValue(
+                "please use one of unsigned, signMagnitude, towsComplement, onesComplement, BCD, packedBCD"
+                    .to_owned(),
+            )),
         }
     }
 }
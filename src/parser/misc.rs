@@ -3,15 +3,18 @@ use std::str::FromStr;
 use roxmltree::Node;
 
 use crate::mdb::{
-    types::MemberPath, Comparison, ComparisonOperator, DynamicValueType, Index, IntegerValue,
-    LinearAdjustment, MatchCriteria, MatchCriteriaIdx, MissionDatabase, NameReferenceType,
-    ParameterInstanceRef,
+    types::MemberPath, BooleanExpression, Comparison, ComparisonOperator,
+    CustomAlgorithm, DiscreteLookup, MdbError,
+    DynamicValueType, Index, IntegerValue,
+    LinearAdjustment, ParameterDynamicValue, MatchCriteria, MatchCriteriaIdx, MissionDatabase, NameReferenceType,
+    ParameterInstanceRef, RateBasis, RateInStream,
 };
 
 use super::{
+    algorithms::read_algorithm_text,
     utils::{
-        children, get_parse_error, missing, read_attribute, read_mandatory_attribute,
-        read_mandatory_text,
+        children, get_parse_error, missing, read_attribute, read_bool_attribute, read_mandatory_attribute,
+        read_mandatory_integer_text, unsupported,
     },
     ParseContext, XtceError, XtceParseError, IGNORE_PARAM_NAME, INVALID_PARAM_IDX, Result,
 };
@@ -22,34 +25,53 @@ pub(super) fn read_match_criteria(
     ctx: &ParseContext,
     node: &Node,
 ) -> Result<MatchCriteriaIdx> {
-    for cnode in node.children() {
-        let mc = match cnode.tag_name().name() {
-            "Comparison" => MatchCriteria::Comparison(read_comparison(mdb, ctx, &cnode)?),
-            "ComparisonList" => {
-                MatchCriteria::ComparisonList(read_comparison_list(mdb, ctx, &cnode)?)
-            }
-            "BooleanExpression" => {
-                todo!()
-            }
-            "CustomAlgorithm" => {
-                todo!()
-            }
-            "" => continue,
-            _ => {
-                log::warn!(
-                    "ignoring unknown element in match criteria '{}'",
-                    cnode.tag_name().name()
-                );
-                continue;
-            }
-        };
+    let mc = parse_match_criteria(mdb, ctx, node)?;
+    Ok(mdb.add_match_criteria(mc))
+}
 
-        return Ok(mdb.add_match_criteria(mc));
+/// parses the match criteria without adding it to the mdb
+///
+/// Used when several items have to be parsed before adding any of them, such that nothing is added
+/// if one of them has an unresolved reference and has to be parsed again.
+pub(super) fn parse_match_criteria(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<MatchCriteria> {
+    for cnode in children(node) {
+        if cnode.tag_name().name().is_empty() {
+            continue;
+        }
+        match parse_criteria_element(mdb, ctx, &cnode)? {
+            Some(mc) => return Ok(mc),
+            None => ctx.warn(
+                &cnode,
+                format!("ignoring unknown element in match criteria '{}'", cnode.tag_name().name()),
+            )?,
+        }
     }
 
     Err(get_parse_error("No criteria specified", node))
 }
 
+/// parses a Comparison, ComparisonList, BooleanExpression or CustomAlgorithm element;
+/// returns None if the node is none of them
+pub(super) fn parse_criteria_element(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Option<MatchCriteria>> {
+    let mc = match node.tag_name().name() {
+        "Comparison" => MatchCriteria::Comparison(read_comparison(mdb, ctx, node)?),
+        "ComparisonList" => MatchCriteria::ComparisonList(read_comparison_list(mdb, ctx, node)?),
+        "BooleanExpression" => MatchCriteria::BooleanExpression(read_boolean_expression(mdb, ctx, node)?),
+        "CustomAlgorithm" => MatchCriteria::CustomAlgorithm(read_custom_algorithm(mdb, ctx, node)?),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(mc))
+}
+
 pub(super) fn read_comparison(
     mdb: &MissionDatabase,
     ctx: &ParseContext,
@@ -69,15 +91,18 @@ pub(super) fn read_comparison_list(
     node: &Node,
 ) -> Result<Vec<Comparison>> {
     let mut r = Vec::new();
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
             "Comparison" => r.push(read_comparison(mdb, ctx, &cnode)?),
             "" => continue,
             _ => {
-                log::warn!(
-                    "ignoring unknown element in comparison list '{}'",
-                    cnode.tag_name().name()
-                );
+                ctx.warn(
+                    &cnode,
+                    format!(
+                        "ignoring unknown element in comparison list '{}'",
+                        cnode.tag_name().name()
+                    ),
+                )?;
                 continue;
             }
         }
@@ -86,6 +111,125 @@ pub(super) fn read_comparison_list(
     Ok(r)
 }
 
+// reads the algorithm text and the input parameters of a CustomAlgorithm match criteria
+fn read_custom_algorithm(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<CustomAlgorithm> {
+    let name = read_mandatory_attribute::<String>(node, "name")?;
+    let mut algorithm_text = None;
+    let mut inputs = Vec::new();
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "AlgorithmText" => algorithm_text = Some(read_algorithm_text(&cnode)?),
+            "InputSet" => {
+                for inode in children(&cnode) {
+                    match inode.tag_name().name() {
+                        "InputParameterInstanceRef" => {
+                            inputs.push(read_para_insta_ref(mdb, ctx, &inode, false)?)
+                        }
+                        // the inputs are passed by position, none of them can be skipped
+                        _ => return Err(unsupported("algorithm input", &inode)),
+                    }
+                }
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring custom algorithm unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    Ok(CustomAlgorithm { name, algorithm_text, inputs })
+}
+
+// reads the single Condition, ANDedConditions or ORedConditions of the BooleanExpression element
+fn read_boolean_expression(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<BooleanExpression> {
+    match children(node).next() {
+        Some(cnode) => read_boolean_term(mdb, ctx, &cnode),
+        None => Err(get_parse_error("No condition specified", node)),
+    }
+}
+
+// the elements of the expression cannot be ignored without changing its meaning
+fn read_boolean_term(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<BooleanExpression> {
+    match node.tag_name().name() {
+        "Condition" => Ok(BooleanExpression::Condition(read_condition(mdb, ctx, node)?)),
+        "ANDedConditions" => Ok(BooleanExpression::And(read_boolean_terms(mdb, ctx, node)?)),
+        "ORedConditions" => Ok(BooleanExpression::Or(read_boolean_terms(mdb, ctx, node)?)),
+        _ => Err(unsupported("boolean expression element", node)),
+    }
+}
+
+fn read_boolean_terms(
+    mdb: &MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<Vec<BooleanExpression>> {
+    let terms = children(node)
+        .map(|cnode| read_boolean_term(mdb, ctx, &cnode))
+        .collect::<Result<Vec<_>>>()?;
+    if terms.is_empty() {
+        return Err(get_parse_error("No condition specified", node));
+    }
+    Ok(terms)
+}
+
+// returns the first element of the criteria which is not supported by the match criteria:
+// the argument references and the conditions comparing two parameters
+pub(super) fn find_unsupported_criteria_element<'a, 'input>(node: &Node<'a, 'input>) -> Option<Node<'a, 'input>> {
+    node.descendants().find(|n| match n.tag_name().name() {
+        "ArgumentInstanceRef" => true,
+        "Condition" => children(n).filter(|cnode| cnode.tag_name().name() == "ParameterInstanceRef").count() > 1,
+        _ => false,
+    })
+}
+
+// reads a condition comparing a parameter with a value, the comparisons between two parameters
+// are not supported
+fn read_condition(mdb: &MissionDatabase, ctx: &ParseContext, node: &Node) -> Result<Comparison> {
+    let mut param_instance = None;
+    let mut comparison_operator = None;
+    let mut value = None;
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "ParameterInstanceRef" if param_instance.is_none() => {
+                param_instance = Some(read_para_insta_ref(mdb, ctx, &cnode, false)?)
+            }
+            "ParameterInstanceRef" => return Err(unsupported("condition operand", &cnode)),
+            "ComparisonOperator" => {
+                let op = cnode.text().unwrap_or("").trim();
+                comparison_operator = Some(op.parse::<ComparisonOperator>().map_err(|_| {
+                    get_parse_error(format!("Invalid comparison operator '{}'", op), &cnode)
+                })?)
+            }
+            "Value" => value = Some(cnode.text().unwrap_or("").trim().to_owned()),
+            _ => return Err(unsupported("condition element", &cnode)),
+        }
+    }
+
+    Ok(Comparison {
+        param_instance: param_instance.ok_or_else(|| missing("ParameterInstanceRef", node))?,
+        comparison_operator: comparison_operator
+            .ok_or_else(|| missing("ComparisonOperator", node))?,
+        value: value.ok_or_else(|| missing("Value", node))?,
+    })
+}
+
 impl FromStr for ComparisonOperator {
     type Err = XtceError;
 
@@ -121,7 +265,7 @@ pub(super) fn read_para_insta_ref(
 
     let instance = (read_attribute::<i32>(node, "instance")?).unwrap_or(0);
     let use_calibrated_value =
-        (read_attribute::<bool>(node, "useCalibratedValue")?).unwrap_or(true);
+        (read_bool_attribute(node, "useCalibratedValue")?).unwrap_or(true);
 
     Ok(ParameterInstanceRef { pidx, instance, use_calibrated_value, member_path })
 }
@@ -132,7 +276,7 @@ pub(super) fn resolve_ref(
     name: &str,
     rtype: NameReferenceType,
 ) -> Result<Index> {
-    let (qn, rname) = match ctx.name_tree.resolve_ref(name, ctx.path, rtype) {
+    let (qn, rname) = match ctx.name_tree.resolve_ref(name, ctx.path, rtype)? {
         Some((qn, ptype_idx, _)) => (qn, ptype_idx),
         None => {
             return Err(XtceError::UndefinedReference(name.to_string(), rtype));
@@ -143,7 +287,9 @@ pub(super) fn resolve_ref(
         NameReferenceType::ParameterType => mdb.get_parameter_type_idx(qn, rname),
         NameReferenceType::Parameter => mdb.get_parameter_idx(qn, rname),
         NameReferenceType::SequenceContainer => mdb.get_container_idx(qn, rname),
-        NameReferenceType::Algorithm => todo!(),
+        NameReferenceType::Algorithm => mdb.get_algorithm_idx(qn, rname),
+        NameReferenceType::ArgumentType => mdb.get_argument_type_idx(qn, rname),
+        NameReferenceType::MetaCommand => mdb.get_meta_command_idx(qn, rname),
     }
     .ok_or_else(|| XtceError::UnresolvedReference(name.to_string(), rtype))
 }
@@ -155,10 +301,15 @@ pub(super) fn resolve_para_ref(
 ) -> Result<(Index, Option<MemberPath>)> {
     let rtype = NameReferenceType::Parameter;
     let (qn, rname, aggr_path) = match ctx.name_tree.resolve_ref(name, ctx.path, rtype) {
-        Some((qn, ptype_idx, aggr_path)) => (qn, ptype_idx, aggr_path),
-        None => {
+        Ok(Some((qn, ptype_idx, aggr_path))) => (qn, ptype_idx, aggr_path),
+        Ok(None) => {
             return Err(XtceError::UndefinedReference(name.to_string(), rtype));
         }
+        // the member names are known only once the aggregate type has been read
+        Err(XtceError::Mdb(MdbError::InvalidName(_))) => {
+            return Err(XtceError::UnresolvedReference(name.to_string(), rtype));
+        }
+        Err(e) => return Err(e),
     };
     let idx = mdb
         .get_parameter_idx(qn, rname)
@@ -170,16 +321,15 @@ pub(super) fn resolve_para_ref(
 
 
 pub(super) fn read_integer_value(
-    _mdb: &MissionDatabase,
-    _ctx: &ParseContext,
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
     node: &Node,
 ) -> Result<IntegerValue> {
-    for cnode in node.children() {
+    for cnode in children(node) {
         let iv = match cnode.tag_name().name() {
-            "FixedValue" => IntegerValue::FixedValue(read_mandatory_text::<i64>(&cnode)?),
-            "DynamicValue" => {
-                todo!()
-            }
+            "FixedValue" => IntegerValue::FixedValue(read_mandatory_integer_text::<i64>(&cnode)?),
+            "DynamicValue" => IntegerValue::DynamicValue(read_dynamic_value(mdb, ctx, &cnode, false)?),
+            "DiscreteLookupList" => IntegerValue::DynamicValue(read_discrete_lookup_list(mdb, ctx, &cnode)?),
             "" => continue,
             _ => {
                 return Err(get_parse_error(
@@ -191,14 +341,17 @@ pub(super) fn read_integer_value(
         return Ok(iv);
     }
 
-    Err(get_parse_error("Invalid IntegerValue, expected FixedValue or DynamicValue element", node))
+    Err(get_parse_error(
+        "Invalid IntegerValue, expected FixedValue, DynamicValue or DiscreteLookupList element",
+        node,
+    ))
 }
 
 /// Read a dynamic value - that is a value that makes reference to a parameter and has an optional liner adjust factor
 /// if allow_ignore is true, a reference to "__yamcs_ignore" parameter will be accepted and result in a INVALID_PARAM_IDX parameter
 /// The caller has to check for that and not use that invalid parameterr
 pub(super) fn read_dynamic_value(
-    mdb: &MissionDatabase,
+    mdb: &mut MissionDatabase,
     ctx: &ParseContext,
     node: &Node,
     allow_ignore: bool
@@ -216,10 +369,13 @@ pub(super) fn read_dynamic_value(
                 adjustment.replace(LinearAdjustment { slope, intercept });
             }
             _ => {
-                log::warn!(
-                    "ignoring string data encoding dynamic value unknown property '{}'",
-                    cnode.tag_name().name()
-                );
+                ctx.warn(
+                    &cnode,
+                    format!(
+                        "ignoring string data encoding dynamic value unknown property '{}'",
+                        cnode.tag_name().name()
+                    ),
+                )?;
             }
         }
     }
@@ -229,5 +385,148 @@ pub(super) fn read_dynamic_value(
     }
    
 
-    Ok(DynamicValueType { adjustment, para_ref: pref.unwrap() })
+    Ok(DynamicValueType::Parameter(ParameterDynamicValue { adjustment, para_ref: pref.unwrap() }))
+}
+
+/// Read a list of DiscreteLookup elements, each one with a value and a match criteria
+///
+/// The criteria are added to the mdb only once all of them have been parsed; if the item they belong to
+/// has to be parsed again, they are removed by add_item.
+pub(super) fn read_discrete_lookup_list(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<DynamicValueType> {
+    let mut lookups = Vec::new();
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "DiscreteLookup" => {
+                let value = read_mandatory_attribute::<i64>(&cnode, "value")?;
+                lookups.push((parse_match_criteria(mdb, ctx, &cnode)?, value));
+            }
+            _ => {
+                ctx.warn(
+                    &cnode,
+                    format!(
+                        "ignoring discrete lookup list unknown property '{}'",
+                        cnode.tag_name().name()
+                    ),
+                )?;
+            }
+        }
+    }
+
+    if lookups.is_empty() {
+        return Err(missing("element DiscreteLookup from", node));
+    }
+
+    let lookups =
+        lookups.into_iter().map(|(mc, value)| DiscreteLookup { criteria: mdb.add_match_criteria(mc), value }).collect();
+    Ok(DynamicValueType::DiscreteLookupList(lookups))
+}
+
+/// Read a DefaultRateInStream or RateInStream element
+pub(super) fn read_rate_in_stream(node: &Node) -> Result<RateInStream> {
+    let basis = read_attribute::<RateBasis>(node, "basis")?.unwrap_or(RateBasis::PerSecond);
+    let minimum_value = read_attribute::<f64>(node, "minimumValue")?;
+    let maximum_value = read_attribute::<f64>(node, "maximumValue")?;
+
+    if let (Some(min), Some(max)) = (minimum_value, maximum_value) {
+        if min > max {
+            return Err(get_parse_error(
+                format!("Invalid rate in stream: minimumValue {} greater than maximumValue {}", min, max),
+                node,
+            ));
+        }
+    }
+
+    Ok(RateInStream { basis, minimum_value, maximum_value })
+}
+
+impl FromStr for RateBasis {
+    type Err = XtceError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "perSecond" => Ok(RateBasis::PerSecond),
+            "perContainerUpdate" => Ok(RateBasis::PerContainerUpdate),
+            _ => Err(XtceError::InvalidValue("please use one of perSecond or perContainerUpdate".to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::PathBuf};
+
+    use super::*;
+    use crate::{
+        mdb::QualifiedName,
+        parser::{
+            build_mdb,
+            nametree::{read_document, Documents, NameTree},
+            ParseOptions, Warnings,
+        },
+    };
+
+    // no element refers to an algorithm yet, the reference is resolved directly
+    #[test]
+    fn test_resolve_algorithm_ref() {
+        // the algorithm is defined in the second document
+        let texts = [
+            r#"<SpaceSystem name="Raw"><TelemetryMetaData>
+                <ParameterTypeSet><FloatParameterType name="float" /></ParameterTypeSet>
+                <ParameterSet>
+                    <Parameter name="temp_c" parameterTypeRef="float" />
+                    <Parameter name="temp_f" parameterTypeRef="float" />
+                </ParameterSet>
+            </TelemetryMetaData></SpaceSystem>"#,
+            r#"<SpaceSystem name="Derived"><TelemetryMetaData><AlgorithmSet>
+                <MathAlgorithm name="fahrenheit"><MathOperation outputParameterRef="/Raw/temp_f">
+                    <ParameterInstanceRefOperand parameterRef="/Raw/temp_c" />
+                    <ValueOperand>1.8</ValueOperand><Operator>*</Operator>
+                </MathOperation></MathAlgorithm>
+            </AlgorithmSet></TelemetryMetaData></SpaceSystem>"#,
+        ];
+        let options = ParseOptions::default();
+        let warnings = Warnings::new(&options);
+        let mut docs = Documents::new(options.max_include_depth);
+        let mut mdb = MissionDatabase::new();
+        let mut name_tree =
+            NameTree { name_db: mdb.name_db(), systems: HashMap::new(), system_nodes: HashMap::new() };
+        for (i, text) in texts.iter().enumerate() {
+            let file = PathBuf::from(format!("doc{}", i));
+            read_document(&mut name_tree, &mut docs, &mut QualifiedName::empty(), text.to_string(), file, &warnings)
+                .unwrap();
+        }
+        let parsed = docs.parse().unwrap();
+
+        // the context of the temp_c parameter of the first document
+        let raw = name_tree.qn("/Raw");
+        let ctx = ParseContext {
+            name_tree: &name_tree,
+            file: &docs.files[0],
+            node: parsed[0].root_element(),
+            path: &raw,
+            name: name_tree.name_db.get_or_intern("temp_c"),
+            rtype: NameReferenceType::Parameter,
+            warnings: &warnings,
+        };
+        let rtype = NameReferenceType::Algorithm;
+
+        // known by the name tree but not added yet to the mdb, the referencing item is parsed again later
+        let r = resolve_ref(&mdb, &ctx, "/Derived/fahrenheit", rtype);
+        assert!(matches!(r, Err(XtceError::UnresolvedReference(name, _)) if name == "/Derived/fahrenheit"));
+        let r = resolve_ref(&mdb, &ctx, "/Derived/celsius", rtype);
+        assert!(matches!(r, Err(XtceError::UndefinedReference(name, _)) if name == "/Derived/celsius"));
+
+        build_mdb(&mut mdb, &name_tree, &parsed, &docs.files, &warnings).unwrap();
+        let aidx = mdb.search_algorithm("/Derived/fahrenheit").unwrap();
+        assert_eq!(aidx, resolve_ref(&mdb, &ctx, "/Derived/fahrenheit", rtype).unwrap());
+        // relative to the space system of the parameter
+        assert_eq!(aidx, resolve_ref(&mdb, &ctx, "../Derived/fahrenheit", rtype).unwrap());
+        // the parameters are not algorithms
+        let r = resolve_ref(&mdb, &ctx, "/Raw/temp_c", rtype);
+        assert!(matches!(r, Err(XtceError::UndefinedReference(..))));
+    }
 }
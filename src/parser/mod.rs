@@ -1,3 +1,7 @@
+mod alarms;
+mod algorithms;
+mod calibrators;
+mod commands;
 mod containers;
 mod encodings;
 mod nametree;
@@ -14,11 +18,14 @@ use utils::*;
 
 //use crate::parser::types::*;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use self::algorithms::add_algorithm;
+use self::commands::add_meta_command;
 use self::containers::add_container;
-use self::nametree::{build_name_tree, NameTree};
+use self::nametree::{read_document, Documents, NameTree};
 use self::parameters::add_parameter;
 
 // references to _yamcs_ignore are resolved automatically to an unexisting parameter.
@@ -32,13 +39,15 @@ use thiserror::Error;
 pub struct XtceParseError {
     pub msg: String,
     pub pos: TextPos,
+    /// the file or the source name of the document containing the error
+    pub source: Option<String>,
 }
 
 #[derive(Error, Debug)]
 pub enum XtceError {
     #[error("IO error")]
     Io(std::io::Error),
-    #[error("parse error")]
+    #[error("parse error: {0}")]
     Parse(XtceParseError),
     #[error("XML parse error")]
     XMLParse(roxmltree::Error),
@@ -51,38 +60,178 @@ pub enum XtceError {
     // added to the MDB because either is encountered later in the file or it depends on other item which is not added
     #[error("unresolved reference")]
     UnresolvedReference(String, NameReferenceType),
-    #[error("unresolved reference")]
-    UnresolvedReferences(String),
+    #[error("unresolved references:{}", format_unresolved(.0))]
+    UnresolvedReferences(Vec<UnresolvedRef>),
     #[error("invalid reference")]
     InvalidReference(String),
     #[error("invalid value")]
     InvalidValue(String),
-    
+    #[error("mdb error: {0}")]
+    Mdb(MdbError),
 }
 
 type Result<T> = std::result::Result<T, XtceError>;
 
+/// A reference which could not be resolved when building the MDB, together with
+/// the file and the position of the item containing it.
+#[derive(Debug)]
+pub struct UnresolvedRef {
+    pub reference: String,
+    pub rtype: NameReferenceType,
+    pub file: PathBuf,
+    pub pos: TextPos,
+}
+
+impl std::fmt::Display for UnresolvedRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{} {} ({:?})", self.file.display(), self.pos.row, self.pos.col, self.reference, self.rtype)
+    }
+}
+
+fn format_unresolved(refs: &[UnresolvedRef]) -> String {
+    refs.iter().map(|r| format!("\n\t{}", r)).collect()
+}
+
+
+/// How the parser treats the XTCE elements and constructs it does not support
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// the unsupported elements are ignored and reported as [ParseWarning]s
+    #[default]
+    Lenient,
+    /// the first unsupported element makes the parsing fail with an [XtceError::Parse] error
+    Strict,
+}
+
+/// The default limit of nested xi:include elements
+pub const DEFAULT_MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    pub strictness: Strictness,
+    /// how deep the xi:include elements may be nested, 0 means that no document can be included
+    pub max_include_depth: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            strictness: Strictness::default(),
+            max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+        }
+    }
+}
+
+/// An element or construct ignored by the parser in lenient mode
+#[derive(Clone, Debug)]
+pub struct ParseWarning {
+    pub msg: String,
+    /// the tag name of the offending element
+    pub tag: String,
+    pub file: PathBuf,
+    pub pos: TextPos,
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{} {}", self.file.display(), self.pos.row, self.pos.col, self.msg)
+    }
+}
+
+// collects the warnings of one parsing, in strict mode they are returned as errors instead
+struct Warnings {
+    strictness: Strictness,
+    list: RefCell<Vec<ParseWarning>>,
+}
+
+impl Warnings {
+    fn new(options: &ParseOptions) -> Self {
+        Warnings { strictness: options.strictness, list: RefCell::new(Vec::new()) }
+    }
+
+    fn warn(&self, file: &Path, node: &Node, msg: String) -> Result<()> {
+        if self.strictness == Strictness::Strict {
+            return Err(add_source(get_parse_error(msg, node), file));
+        }
+        self.list.borrow_mut().push(ParseWarning {
+            msg,
+            tag: node.tag_name().name().to_owned(),
+            file: file.to_path_buf(),
+            pos: node.document().text_pos_at(node.range().start),
+        });
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.list.borrow().len()
+    }
+
+    // removes the warnings of an item which will be parsed again
+    fn truncate(&self, len: usize) {
+        self.list.borrow_mut().truncate(len);
+    }
+
+    fn into_vec(self) -> Vec<ParseWarning> {
+        self.list.into_inner()
+    }
+}
+
+// used by the functions keeping the behaviour from before the parse options
+fn log_warnings(warnings: &[ParseWarning]) {
+    for w in warnings {
+        log::warn!("{}", w);
+    }
+}
 
 #[derive(Copy, Clone)]
 struct ParseContext<'a> {
     name_tree: &'a NameTree,
+    // the file from where the node has been read
+    file: &'a Path,
     node: Node<'a, 'a>,
     path: &'a QualifiedName,
     name: NameIdx,
     rtype: NameReferenceType,
+    warnings: &'a Warnings,
+}
+
+impl ParseContext<'_> {
+    /// reports an element of the item which is ignored or not supported
+    fn warn<S: Into<String>>(&self, node: &Node, msg: S) -> Result<()> {
+        self.warnings.warn(self.file, node, msg.into())
+    }
 }
 #[derive(Debug)]
 pub struct Reference {
     reference: String,
     rtype: NameReferenceType,
 }
-/*
-impl fmt::Display for XtceParseError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "An Error Occurred, Please Try Again!") // user-facing output
+
+impl std::fmt::Display for XtceParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(source) = &self.source {
+            write!(f, "{}:", source)?;
+        }
+        write!(f, "{}:{}: {}", self.pos.row, self.pos.col, self.msg)
+    }
+}
+
+// sets the source of the parse error if not already set; the XML errors are turned into parse errors
+// such that they are reported with the source too
+fn add_source(err: XtceError, source: &Path) -> XtceError {
+    match err {
+        XtceError::Parse(mut e) if e.source.is_none() => {
+            e.source = Some(source.display().to_string());
+            XtceError::Parse(e)
+        }
+        XtceError::XMLParse(e) => XtceError::Parse(XtceParseError {
+            msg: format!("invalid XML: {}", e),
+            pos: e.pos(),
+            source: Some(source.display().to_string()),
+        }),
+        err => err,
     }
 }
-*/
 
 impl std::convert::From<XtceParseError> for XtceError {
     fn from(err: XtceParseError) -> Self {
@@ -102,76 +251,295 @@ impl std::convert::From<roxmltree::Error> for XtceError {
     }
 }
 
+impl std::convert::From<MdbError> for XtceError {
+    fn from(err: MdbError) -> Self {
+        XtceError::Mdb(err)
+    }
+}
 
+
+/// Parses the XTCE file into the mdb, the unsupported elements are logged as warnings.
 pub fn parse(mdb: &mut MissionDatabase, path: &Path) -> Result<()> {
+    let warnings = parse_with_options(mdb, path, &ParseOptions::default())?;
+    log_warnings(&warnings);
+    Ok(())
+}
+
+/// Parses the XTCE file into the mdb and returns the warnings collected in lenient mode.
+pub fn parse_with_options(
+    mdb: &mut MissionDatabase,
+    path: &Path,
+    options: &ParseOptions,
+) -> Result<Vec<ParseWarning>> {
     let text = std::fs::read_to_string(path)?;
-    let doc = roxmltree::Document::parse(&text).unwrap();
-    let root_element = doc.root_element();
-    let mut path = QualifiedName::empty();
+    parse_text(mdb, &text, path, options)
+}
+
+/// Parses the XTCE document given as a string into the mdb, the unsupported elements are logged as
+/// warnings.
+pub fn parse_str(mdb: &mut MissionDatabase, text: &str) -> Result<()> {
+    let warnings = parse_str_with_options(mdb, text, None, &ParseOptions::default())?;
+    log_warnings(&warnings);
+    Ok(())
+}
+
+/// Parses the XTCE document given as a string into the mdb and returns the warnings collected in
+/// lenient mode.
+///
+/// The source name is used instead of the file name in the warnings and errors.
+/// If not given, the document is reported as `<document 0>`.
+pub fn parse_str_with_options(
+    mdb: &mut MissionDatabase,
+    text: &str,
+    source: Option<&str>,
+    options: &ParseOptions,
+) -> Result<Vec<ParseWarning>> {
+    parse_text(mdb, text, &source_name(source, 0), options)
+}
+
+fn parse_text(
+    mdb: &mut MissionDatabase,
+    text: &str,
+    source: &Path,
+    options: &ParseOptions,
+) -> Result<Vec<ParseWarning>> {
+    let warnings = Warnings::new(options);
+    let mut docs = Documents::new(options.max_include_depth);
+    let mut qn = QualifiedName::empty();
     let mut name_tree = NameTree {
         name_db: mdb.name_db(),
         systems: HashMap::new(),
+        system_nodes: HashMap::new(),
     };
-    build_name_tree(&mut name_tree, &mut path, 0, &root_element)?;
+    let text = text.to_owned();
+    read_document(&mut name_tree, &mut docs, &mut qn, text, source.to_path_buf(), &warnings)?;
 
-    build_mdb(mdb, &name_tree, &vec![doc])?;
+    build_mdb(mdb, &name_tree, &docs.parse()?, &docs.files, &warnings)?;
     //println!("Have {} xtce nodes", ctx.nodes.len());
     // create_details(mdb, &mut ctx, &doc);
     //  read_space_system(mdb, &mut QualifiedName::empty(), &root_element).or_else(|e| Err(e.into()))
-    Ok(())
+    Ok(warnings.into_vec())
+}
+
+// the name under which a document not read from a file is reported
+fn source_name(source: Option<&str>, doc_id: usize) -> PathBuf {
+    match source {
+        Some(name) => PathBuf::from(name),
+        None => PathBuf::from(format!("<document {}>", doc_id)),
+    }
 }
 
 pub fn parse_files(paths: &[&Path]) -> Result<MissionDatabase> {
-    // Read all given files
-    //
-    // TODO: do this in one iter chain instead of two separate ones?
-    // ran into borrow checker complications though
-    let contents: Result<Vec<String>> = paths
-        .iter()
-        .map(|&path| std::fs::read_to_string(path).map_err(XtceError::from))
-        .collect();
-    let contents = contents?;
-
-    let documents: Result<Vec<roxmltree::Document>> = contents
-        .iter()
-        .map(|content| roxmltree::Document::parse(&content).map_err(XtceError::from))
-        .collect();
-    let documents = documents?;
-
-    let mut mdb = MissionDatabase::new();
-    let mut name_tree = NameTree {
-        name_db: mdb.name_db(),
-        systems: HashMap::new(),
-    };
+    let (mdb, warnings) = parse_files_with_options(paths, &ParseOptions::default())?;
+    log_warnings(&warnings);
+    Ok(mdb)
+}
+
+pub fn parse_files_with_options(
+    paths: &[&Path],
+    options: &ParseOptions,
+) -> Result<(MissionDatabase, Vec<ParseWarning>)> {
+    let mut loader = MdbLoader::with_options(options);
+    loader.add_files(paths)?;
+    loader.finish_with_warnings()
+}
+
+/// Builds a MissionDatabase from a set of XTCE documents given as strings.
+///
+/// The documents are reported as `<document N>` in the errors, N being the index in the slice;
+/// use [`MdbLoader::add_str`] to give them names.
+pub fn parse_documents(texts: &[&str]) -> Result<MissionDatabase> {
+    let (mdb, warnings) = parse_documents_with_options(texts, &ParseOptions::default())?;
+    log_warnings(&warnings);
+    Ok(mdb)
+}
 
-    for (i, doc) in documents.iter().enumerate() {
-        let root_element = doc.root_element();
-        let mut path = QualifiedName::empty();
-        build_name_tree(&mut name_tree, &mut path, i, &root_element)?;
+pub fn parse_documents_with_options(
+    texts: &[&str],
+    options: &ParseOptions,
+) -> Result<(MissionDatabase, Vec<ParseWarning>)> {
+    let mut loader = MdbLoader::with_options(options);
+    for text in texts {
+        loader.add_str(text, None)?;
     }
+    loader.finish_with_warnings()
+}
 
-    build_mdb(&mut mdb, &name_tree, &documents)?;
+/// Loads a MissionDatabase from a set of XTCE files which do not have to be known in advance.
+///
+/// Each call to [`MdbLoader::add_file`] reads the file and adds its space systems to the name tree.
+/// The documents which are not in files can be added with [`MdbLoader::add_str`].
+/// The MDB items are only built in [`MdbLoader::finish`], so references between files
+/// are resolved independently of the order in which the files have been added.
+///
+/// In case of error the loader is left in an undefined state and should not be used further.
+pub struct MdbLoader {
+    mdb: MissionDatabase,
+    name_tree: NameTree,
+    // the documents added so far together with the ones they include
+    docs: Documents,
+    warnings: Warnings,
+}
 
-    Ok(mdb)
+impl MdbLoader {
+    pub fn new() -> Self {
+        Self::with_options(&ParseOptions::default())
+    }
+
+    pub fn with_options(options: &ParseOptions) -> Self {
+        let mut mdb = MissionDatabase::new();
+        let name_tree = NameTree {
+            name_db: mdb.name_db(),
+            systems: HashMap::new(),
+            system_nodes: HashMap::new(),
+        };
+        let warnings = Warnings::new(options);
+        let docs = Documents::new(options.max_include_depth);
+        MdbLoader { mdb, name_tree, docs, warnings }
+    }
+
+    /// Reads the XTCE file and adds its space systems to the name tree.
+    /// Returns an error if the file cannot be read, it is not valid XML or
+    /// it contains names already defined by the previous files.
+    pub fn add_file(&mut self, path: &Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        self.add_document(text, path.to_path_buf())
+    }
+
+    /// Reads the XTCE files and adds their space systems to the name tree, with the same result as
+    /// calling [`MdbLoader::add_file`] for each of them in order.
+    ///
+    /// With the `parallel` feature, the files are read and parsed as XML in parallel; the name tree
+    /// is still built sequentially in the order of the paths.
+    pub fn add_files(&mut self, paths: &[&Path]) -> Result<()> {
+        #[cfg(feature = "parallel")]
+        {
+            use self::nametree::add_parsed_document;
+            use rayon::prelude::*;
+
+            let mut texts: Vec<std::io::Result<String>> =
+                paths.par_iter().map(std::fs::read_to_string).collect();
+            // the files before the first one which cannot be read are added before returning the error
+            let num_read = texts.iter().position(|t| t.is_err()).unwrap_or(texts.len());
+            let read_error = texts.drain(num_read..).next();
+            let texts: Vec<String> = texts.into_iter().flatten().collect();
+
+            let docs: Vec<_> = texts.par_iter().map(|text| Document::parse(text)).collect();
+            let mut doc_ids = Vec::with_capacity(docs.len());
+            let mut result = Ok(());
+            for (doc, path) in docs.iter().zip(paths) {
+                let doc = match doc {
+                    Ok(doc) => doc,
+                    Err(e) => {
+                        result = Err(add_source(XtceError::from(e.clone()), path));
+                        break;
+                    }
+                };
+                let mut qn = QualifiedName::empty();
+                let path = path.to_path_buf();
+                match add_parsed_document(&mut self.name_tree, &mut self.docs, &mut qn, doc, path, &self.warnings) {
+                    Ok(doc_id) => doc_ids.push(doc_id),
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                }
+            }
+            drop(docs);
+            // the texts of the documents added are stored also if one of the next documents failed
+            for (doc_id, text) in doc_ids.into_iter().zip(texts) {
+                self.docs.contents[doc_id] = text;
+            }
+
+            result?;
+            match read_error {
+                Some(Err(e)) => Err(e.into()),
+                _ => Ok(()),
+            }
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            paths.iter().try_for_each(|path| self.add_file(path))
+        }
+    }
+
+    /// Adds the space systems of the XTCE document given as a string to the name tree.
+    ///
+    /// The source name is used instead of the file name in the warnings and errors.
+    /// If not given, the document is reported as `<document N>`, N being the number of documents
+    /// added before.
+    pub fn add_str(&mut self, text: &str, source: Option<&str>) -> Result<()> {
+        let source = source_name(source, self.docs.contents.len());
+        self.add_document(text.to_owned(), source)
+    }
+
+    fn add_document(&mut self, text: String, source: PathBuf) -> Result<()> {
+        let mut qn = QualifiedName::empty();
+        read_document(&mut self.name_tree, &mut self.docs, &mut qn, text, source, &self.warnings)
+    }
+
+    /// Builds the MissionDatabase from all the files added, the unsupported elements are logged as
+    /// warnings.
+    pub fn finish(self) -> Result<MissionDatabase> {
+        let (mdb, warnings) = self.finish_with_warnings()?;
+        log_warnings(&warnings);
+        Ok(mdb)
+    }
+
+    /// Builds the MissionDatabase from all the files added and returns it together with the
+    /// warnings collected in lenient mode.
+    pub fn finish_with_warnings(mut self) -> Result<(MissionDatabase, Vec<ParseWarning>)> {
+        let documents = self.docs.parse()?;
+        build_mdb(&mut self.mdb, &self.name_tree, &documents, &self.docs.files, &self.warnings)?;
+
+        Ok((self.mdb, self.warnings.into_vec()))
+    }
+}
+
+impl Default for MdbLoader {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /*************** details **************/
-fn build_mdb(mdb: &mut MissionDatabase, name_tree: &NameTree, doc: &Vec<Document>) -> Result<()> {
+// files contains the paths of the documents, used for error reporting
+fn build_mdb(
+    mdb: &mut MissionDatabase,
+    name_tree: &NameTree,
+    doc: &[Document],
+    files: &[PathBuf],
+    warnings: &Warnings,
+) -> Result<()> {
     let mut unresolved: Vec<(ParseContext, Reference)> = vec![];
 
-    for (path, ssn) in &name_tree.systems {
+    // the items are created in a fixed order such that the indices do not depend on the hashing:
+    // the space systems sorted by name (the parents before their children) and their items in the document order
+    let mut systems: Vec<_> = name_tree.systems.iter().collect();
+    systems.sort_by_key(|&(qn, _)| qn);
+
+    for (path, ssn) in systems {
         log::debug!("Creating space system {}", mdb.qn_to_string(path));
-        mdb.new_space_system(path.clone()).unwrap();
+        mdb.new_space_system(path.clone())?;
+        if let Some((doc_id, node_id)) = name_tree.system_nodes.get(path) {
+            let node = doc[*doc_id].get_node(*node_id).unwrap();
+            let ss = mdb.get_space_system_mut(path).unwrap();
+            read_header(ss, &node).map_err(|e| add_source(e, &files[*doc_id]))?;
+        }
         //create space system
         for (ntype, m) in ssn {
-            for (name, (doc_id, node_id)) in m {
+            let mut items: Vec<_> = m.iter().collect();
+            items.sort_by_key(|(_, (doc_id, node_id))| (*doc_id, node_id.get()));
+            for (name, (doc_id, node_id)) in items {
                 let node = doc[*doc_id].get_node(*node_id).unwrap();
                 let ctx = ParseContext {
                     name_tree,
+                    file: &files[*doc_id],
                     path,
                     name: *name,
                     node,
                     rtype: ntype,
+                    warnings,
                 };
                 add_item(mdb, &ctx, &mut unresolved)?;
             }
@@ -184,14 +552,23 @@ fn build_mdb(mdb: &mut MissionDatabase, name_tree: &NameTree, doc: &Vec<Document
             add_item(mdb, ctx, &mut unresolved1)?;
         }
         if unresolved.len() == unresolved1.len() {
-            let refs: Vec<String> = unresolved.into_iter().map(|x| x.1.reference).collect();
-            return Err(XtceError::UnresolvedReferences(format!(
-                "Unresolved references: {}",
-                refs.join(", ")
-            )));
+            let refs = unresolved
+                .into_iter()
+                .map(|(ctx, r)| UnresolvedRef {
+                    reference: r.reference,
+                    rtype: r.rtype,
+                    file: ctx.file.to_path_buf(),
+                    pos: ctx.node.document().text_pos_at(ctx.node.range().start),
+                })
+                .collect();
+            return Err(XtceError::UnresolvedReferences(refs));
         }
         unresolved = unresolved1;
     }
+    // a container inheriting from itself or an aggregate or array containing itself would make the extraction
+    // recurse without end
+    mdb.validate()?;
+
     Ok(())
 }
 
@@ -200,25 +577,37 @@ fn add_item<'a>(
     ctx: &ParseContext<'a>,
     unresolved: &mut Vec<(ParseContext<'a>, Reference)>,
 ) -> Result<()> {
+    let num_warnings = ctx.warnings.len();
+    let num_criteria = mdb.match_criteria.len();
     let r = match ctx.rtype {
         NameReferenceType::ParameterType => add_parameter_type(mdb, ctx),
         NameReferenceType::Parameter => add_parameter(mdb, ctx),
         NameReferenceType::SequenceContainer => add_container(mdb, ctx),
-        _ => {
-            println!("todo node type {:?}", ctx.rtype);
-            Ok(())
-        }
+        NameReferenceType::Algorithm => add_algorithm(mdb, ctx),
+        NameReferenceType::ArgumentType => add_parameter_type(mdb, ctx),
+        NameReferenceType::MetaCommand => add_meta_command(mdb, ctx),
     };
 
     if let Err(err) = r {
         if let XtceError::UnresolvedReference(reference, rtype) = err {
+            // the item is parsed again later, together with its warnings and the match criteria
+            // added while parsing it
+            ctx.warnings.truncate(num_warnings);
+            mdb.match_criteria.truncate(num_criteria);
             unresolved.push((*ctx, Reference { reference, rtype }));
         } else {
-            return Err(err);
+            return Err(add_source(err, ctx.file));
         }
     }
     Ok(())
 }
-pub(super) fn read_header(_ss: &mut SpaceSystem, _node: &Node) -> Result<()> {
+
+/// reads the operationalStatus of the SpaceSystem element and the version and date of its Header
+pub(super) fn read_header(ss: &mut SpaceSystem, node: &Node) -> Result<()> {
+    ss.operational_status = read_attribute::<String>(node, "operationalStatus")?;
+    if let Some(hnode) = children(node).find(|n| n.tag_name().name() == "Header") {
+        ss.version = read_attribute::<String>(&hnode, "version")?;
+        ss.date = read_attribute::<String>(&hnode, "date")?;
+    }
     Ok(())
 }
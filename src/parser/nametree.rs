@@ -1,13 +1,31 @@
 use crate::mdb::{NameDb, NameIdx, NameReferenceType, QualifiedName, types::MemberPath, utils::parse_aggregate_member_path};
 use enum_map::EnumMap;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-use super::{utils::read_mandatory_name, XtceError};
+use super::{
+    add_source,
+    utils::{children, get_parse_error, read_mandatory_attribute, read_mandatory_name},
+    Warnings, XtceError,
+};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+const XINCLUDE_NAMESPACE: &str = "http://www.w3.org/2001/XInclude";
+
+/// a resolved reference: the space system containing the item, the item name and, for the references
+/// to aggregate members or array elements of parameters, the member path
+type ResolvedRef<'a> = (&'a QualifiedName, NameIdx, Option<MemberPath>);
 
 pub(crate) struct NameTree {
     pub name_db: NameDb,
     pub systems:
         HashMap<QualifiedName, EnumMap<NameReferenceType, HashMap<NameIdx, (usize, roxmltree::NodeId)>>>,
+    /// the (doc_id, node) of the SpaceSystem element of each space system
+    pub system_nodes: HashMap<QualifiedName, (usize, roxmltree::NodeId)>,
 }
 
 impl NameTree {
@@ -33,6 +51,7 @@ impl NameTree {
         &mut self,
         qn_parent: &QualifiedName,
         name: &str,
+        doc_id: usize,
         node: roxmltree::NodeId,
     ) -> Result<NameIdx, XtceError> {
         let mut qn = qn_parent.clone();
@@ -42,7 +61,8 @@ impl NameTree {
         if self.systems.contains_key(&qn) {
             return Err(XtceError::DuplicateName(name_idx, node));
         }
-        self.systems.insert(qn, EnumMap::default());
+        self.systems.insert(qn.clone(), EnumMap::default());
+        self.system_nodes.insert(qn, (doc_id, node));
 
         Ok(name_idx)
     }
@@ -75,12 +95,14 @@ impl NameTree {
     ///
     /// If rtype is anything else than Parameter, the member path will always be None
     ///
+    /// An error is returned if the member path cannot be parsed.
+    ///
     pub(crate) fn resolve_ref(
         &self,
         reference: &str,
         relative_to: &QualifiedName,
         rtype: NameReferenceType,
-    ) -> Option<(&QualifiedName, NameIdx, Option<MemberPath>)> {
+    ) -> Result<Option<ResolvedRef<'_>>, XtceError> {
         if reference.starts_with("/") {
             return self.find_ref(reference, &QualifiedName::empty(), rtype);
         } else if reference.starts_with("./") || reference.starts_with("..") {
@@ -90,12 +112,12 @@ impl NameTree {
             let mut start_ss = relative_to.clone();
 
             loop {
-                let rr = self.find_ref(reference, &start_ss, rtype);
+                let rr = self.find_ref(reference, &start_ss, rtype)?;
                 if rr.is_some() {
-                    return rr;
+                    return Ok(rr);
                 }
                 if start_ss.is_root() {
-                    return None;
+                    return Ok(None);
                 }
                 start_ss.pop();
             }
@@ -108,7 +130,7 @@ impl NameTree {
         reference: &str,
         relative_to: &QualifiedName,
         rtype: NameReferenceType,
-    ) -> Option<(&QualifiedName, NameIdx, Option<MemberPath>)> {
+    ) -> Result<Option<ResolvedRef<'_>>, XtceError> {
         let mut ss = relative_to.clone();
         let mut it = reference.split('/').peekable();
 
@@ -130,85 +152,219 @@ impl NameTree {
                 let mut member_path = None;
                 let mut pname = p;
                 if rtype == NameReferenceType::Parameter {
-                    if let Some(n) = p.find('.') {
+                    // a.b.c or a[2].b or a[1][2]
+                    if let Some(n) = p.find(['.', '[']) {
                         let (a, b) = p.split_at(n);
-                        member_path = Some(parse_aggregate_member_path(&self.name_db, b[1..].split('.').collect()).ok()?);
+                        let b = b.strip_prefix('.').unwrap_or(b);
+                        let path = b.split('.').collect();
+                        member_path = Some(parse_aggregate_member_path(&self.name_db, path)?);
                         pname = a;
                     }
                 }
 
-                let pidx = self.name_db.get(pname)?;
+                let Some(pidx) = self.name_db.get(pname) else {
+                    return Ok(None);
+                };
 
-                return self.systems.get_key_value(&ss).and_then(|(k, v)| {
+                return Ok(self.systems.get_key_value(&ss).and_then(|(k, v)| {
                     if v[rtype].contains_key(&pidx) {
                         Some((k, pidx, member_path))
                     } else {
                         None
                     }
-                });
+                }));
             }
 
-            let pidx = self.name_db.get(p)?;
+            let Some(pidx) = self.name_db.get(p) else {
+                return Ok(None);
+            };
             ss.push(pidx);
 
             if !self.systems.contains_key(&ss) {
                 if rtype != NameReferenceType::Parameter {
-                    return None;
+                    return Ok(None);
                 } else {
                     //even if we did not reach the end, if we find a parameter with the given name,
                     // we assume the rest is part of a possible aggregate path
                     ss.pop();
 
-                    return self.systems.get_key_value(&ss).and_then(|(k, v)| {
-                        if v[rtype].contains_key(&pidx) {
-                            let member_path = parse_aggregate_member_path(&self.name_db, it.collect::<Vec<&str>>()).ok()?;
-                            Some((k, pidx, Some(member_path)))
-                        } else {
-                            None
+                    return match self.systems.get_key_value(&ss) {
+                        Some((k, v)) if v[rtype].contains_key(&pidx) => {
+                            let member_path =
+                                parse_aggregate_member_path(&self.name_db, it.collect())?;
+                            Ok(Some((k, pidx, Some(member_path))))
                         }
-                    });
+                        _ => Ok(None),
+                    };
                 }
             }
         }
 
-        None
+        Ok(None)
     }
 
    pub fn qn(&self, qnstr: &str) -> QualifiedName {
-        let mut r = QualifiedName::empty();
-        for p in qnstr.split("/") {
-            if !p.is_empty() {
-                r.push(self.name_db.get_or_intern(p));
-            }
-        }
-        r
+        QualifiedName::from_str_interning(&self.name_db, qnstr)
+    }
+}
+
+/// The XTCE documents read into the name tree, including those pulled in by xi:include elements.
+/// The index in the vectors is the doc_id stored in the name tree.
+pub(crate) struct Documents {
+    pub contents: Vec<String>,
+    pub files: Vec<PathBuf>,
+    // the doc_ids of the documents being read, used to detect the include cycles
+    open: Vec<usize>,
+    max_include_depth: usize,
+}
+
+impl Documents {
+    pub fn new(max_include_depth: usize) -> Self {
+        Documents { contents: Vec::new(), files: Vec::new(), open: Vec::new(), max_include_depth }
+    }
+
+    /// parses again all the documents, in parallel with the `parallel` feature
+    ///
+    /// the node ids stored in the name tree remain valid because the same text is parsed again
+    pub fn parse(&self) -> Result<Vec<roxmltree::Document<'_>>, XtceError> {
+        #[cfg(feature = "parallel")]
+        let contents = self.contents.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let contents = self.contents.iter();
+
+        contents.map(|content| roxmltree::Document::parse(content).map_err(XtceError::from)).collect()
     }
 }
 
+/// Adds the space system of the document to the name tree as a sub-system of path,
+/// the documents included by it are read recursively.
+pub(crate) fn read_document(
+    tree: &mut NameTree,
+    docs: &mut Documents,
+    path: &mut QualifiedName,
+    text: String,
+    file: PathBuf,
+    warnings: &Warnings,
+) -> Result<(), XtceError> {
+    let doc = roxmltree::Document::parse(&text).map_err(|e| add_source(e.into(), &file))?;
+    let doc_id = add_parsed_document(tree, docs, path, &doc, file, warnings)?;
+
+    drop(doc);
+    docs.contents[doc_id] = text;
+
+    Ok(())
+}
+
+/// Same as [read_document] for a document already parsed, returns the doc_id assigned to it.
+///
+/// The text of the document has to be stored by the caller in `docs.contents[doc_id]` once the
+/// document is not borrowed anymore.
+pub(crate) fn add_parsed_document(
+    tree: &mut NameTree,
+    docs: &mut Documents,
+    path: &mut QualifiedName,
+    doc: &roxmltree::Document,
+    file: PathBuf,
+    warnings: &Warnings,
+) -> Result<usize, XtceError> {
+    // the text is stored after reading the document, the included documents get the next doc_ids
+    let doc_id = docs.contents.len();
+    docs.contents.push(String::new());
+    docs.files.push(file.clone());
+
+    docs.open.push(doc_id);
+    let root = doc.root_element();
+    let r = build_name_tree(tree, docs, path, doc_id, &root, &file, warnings);
+    docs.open.pop();
+    r.map_err(|e| add_source(e, &file))?;
+
+    Ok(doc_id)
+}
+
+// reads the document referenced by the xi:include element as a sub-system of path
+// the href is relative to the directory of the including file
+fn include_document(
+    tree: &mut NameTree,
+    docs: &mut Documents,
+    path: &mut QualifiedName,
+    node: &roxmltree::Node,
+    file: &Path,
+    warnings: &Warnings,
+) -> Result<(), XtceError> {
+    let href: String = read_mandatory_attribute(node, "href")?;
+    if let Some(parse) = node.attribute("parse").filter(|&p| p != "xml") {
+        let msg = format!("unsupported xi:include parse='{}', only xml can be included", parse);
+        return Err(get_parse_error(msg, node));
+    }
+    if docs.open.len() > docs.max_include_depth {
+        let msg = format!(
+            "cannot include {}: the maximum include depth {} is exceeded",
+            href, docs.max_include_depth
+        );
+        return Err(get_parse_error(msg, node));
+    }
+
+    let inc_file = file.parent().map_or_else(|| PathBuf::from(&href), |dir| dir.join(&href));
+    let read_error = |e: std::io::Error| {
+        let msg = format!("cannot read the included file {}: {}", inc_file.display(), e);
+        get_parse_error(msg, node)
+    };
+    let canonical = inc_file.canonicalize().map_err(read_error)?;
+    let text = std::fs::read_to_string(&inc_file).map_err(read_error)?;
+
+    let is_open = |id: &usize| docs.files[*id].canonicalize().is_ok_and(|f| f == canonical);
+    if docs.open.iter().any(is_open) {
+        let msg = format!("include cycle: {} is already being read", inc_file.display());
+        return Err(get_parse_error(msg, node));
+    }
+
+    // the XML errors are reported with the included file as source
+    read_document(tree, docs, path, text, inc_file, warnings)
+}
+
+fn is_xinclude(node: &roxmltree::Node) -> bool {
+    node.is_element()
+        && node.tag_name().name() == "include"
+        && node.tag_name().namespace() == Some(XINCLUDE_NAMESPACE)
+}
+
 pub(crate) fn build_name_tree(
     tree: &mut NameTree,
+    docs: &mut Documents,
     path: &mut QualifiedName,
     doc_id: usize,
     node: &roxmltree::Node,
+    file: &Path,
+    warnings: &Warnings,
 ) -> Result<(), XtceError> {
     let name_str = read_mandatory_name(node)?;
-    let name_idx = tree.add_sub_system(&path, name_str, node.id())?;
+    let name_idx = tree.add_sub_system(&path, name_str, doc_id, node.id())?;
 
     path.push(name_idx);
 
-    for cnode in node.children() {
+    // the root space systems of the included documents become sub-systems of this one
+    for inode in node.children().filter(is_xinclude) {
+        include_document(tree, docs, path, &inode, file, warnings)?;
+    }
+
+    for cnode in children(node) {
         match cnode.tag_name().name() {
             "SpaceSystem" => {
-                build_name_tree(tree, path, doc_id, &cnode)?;
+                build_name_tree(tree, docs, path, doc_id, &cnode, file, warnings)?;
             }
             "TelemetryMetaData" => {
-                build_tm_name_tree(tree, path, doc_id, &cnode)?;
+                build_tm_name_tree(tree, path, doc_id, &cnode, file, warnings)?;
             }
             "CommandMetaData" => {
-                //  read_command_meta_data(mdb, ctx, &cnode)?;
+                build_cmd_name_tree(tree, path, doc_id, &cnode, file, warnings)?;
             }
+            // read when building the mdb
+            "Header" => {}
             "" => {}
-            _ => log::warn!("ignoring global property '{}'", cnode.tag_name().name()),
+            _ => {
+                let msg = format!("ignoring global property '{}'", cnode.tag_name().name());
+                warnings.warn(file, &cnode, msg)?
+            }
         };
     }
     path.pop();
@@ -221,37 +377,85 @@ fn build_tm_name_tree(
     path: &mut QualifiedName,
     doc_id: usize,
     node: &roxmltree::Node,
+    file: &Path,
+    warnings: &Warnings,
 ) -> Result<(), XtceError> {
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
             "ParameterTypeSet" => {
-                for ptnode in cnode.children().filter(|n| n.tag_name().name() != "") {
+                for ptnode in children(&cnode) {
                     let name = read_mandatory_name(&ptnode)?;
                     tree.add_node(path, name, NameReferenceType::ParameterType, doc_id, ptnode.id())?;
                 }
             }
             "ParameterSet" => {
-                for ptnode in cnode.children().filter(|n| !n.tag_name().name().is_empty()) {
+                for ptnode in children(&cnode) {
                     let name = read_mandatory_name(&ptnode)?;
                     tree.add_node(path, name, NameReferenceType::Parameter, doc_id, ptnode.id())?;
                 }
             }
             "ContainerSet" => {
-                for ptnode in cnode.children().filter(|n| n.tag_name().name() != "") {
+                for ptnode in children(&cnode) {
                     let name = read_mandatory_name(&ptnode)?;
                     tree.add_node(path, name, NameReferenceType::SequenceContainer, doc_id, ptnode.id())?;
                 }
             }
             "AlgorithmSet" => {
-                //read_algorithm_set(mdb, ctx, &cnode)?;
+                for anode in children(&cnode) {
+                    let name = read_mandatory_name(&anode)?;
+                    match anode.tag_name().name() {
+                        "MathAlgorithm" | "CustomAlgorithm" => tree.add_node(
+                            path,
+                            name,
+                            NameReferenceType::Algorithm,
+                            doc_id,
+                            anode.id(),
+                        )?,
+                        _ => {
+                            let msg = format!(
+                                "ignoring unsupported algorithm '{}'",
+                                anode.tag_name().name()
+                            );
+                            warnings.warn(file, &anode, msg)?
+                        }
+                    }
+                }
             }
             "" => {}
-            _ => log::warn!("ignoring '{}'", cnode.tag_name().name()),
+            _ => warnings.warn(file, &cnode, format!("ignoring '{}'", cnode.tag_name().name()))?,
         };
     }
     Ok(())
 }
 
+fn build_cmd_name_tree(
+    tree: &mut NameTree,
+    path: &mut QualifiedName,
+    doc_id: usize,
+    node: &roxmltree::Node,
+    file: &Path,
+    warnings: &Warnings,
+) -> Result<(), XtceError> {
+    for cnode in children(&node) {
+        let rtype = match cnode.tag_name().name() {
+            "ParameterTypeSet" => NameReferenceType::ParameterType,
+            "ParameterSet" => NameReferenceType::Parameter,
+            "ArgumentTypeSet" => NameReferenceType::ArgumentType,
+            "MetaCommandSet" => NameReferenceType::MetaCommand,
+            "" => continue,
+            _ => {
+                warnings.warn(file, &cnode, format!("ignoring '{}'", cnode.tag_name().name()))?;
+                continue;
+            }
+        };
+        for inode in children(&cnode) {
+            let name = read_mandatory_name(&inode)?;
+            tree.add_node(path, name, rtype, doc_id, inode.id())?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::{collections::HashMap, sync::Arc};
@@ -261,7 +465,7 @@ mod tests {
 
     use crate::{
         mdb::{NameIdx, NameReferenceType, QualifiedName},
-        parser::nametree::NameTree,
+        parser::{nametree::NameTree, XtceError},
     };
 
     #[test]
@@ -269,6 +473,7 @@ mod tests {
         let mut ntree = NameTree {
             name_db: Arc::new(ThreadedRodeo::<NameIdx>::new()),
             systems: HashMap::new(),
+            system_nodes: HashMap::new(),
         };
 
         let node_id = NodeId::new(0);
@@ -287,40 +492,43 @@ mod tests {
         ntree.add_node(&qn_abc, "para2", ptype, 0, node_id).unwrap();
         ntree.add_node(&qn_b, "para3", ptype, 0, node_id).unwrap();
 
-        let x = ntree.find_ref("/x", &root, ptype);
+        let x = ntree.find_ref("/x", &root, ptype).unwrap();
         assert!(x.is_none());
 
-        let x = ntree.find_ref("/a/x", &root, ptype);
+        let x = ntree.find_ref("/a/x", &root, ptype).unwrap();
         assert!(x.is_none());
 
-        let (x, _, _) = ntree.find_ref("/a/b/para1", &root, ptype).unwrap();
+        let (x, _, _) = ntree.find_ref("/a/b/para1", &root, ptype).unwrap().unwrap();
         assert_eq!(x, &qn_ab);
 
-        let (x, _, _) = ntree.find_ref("c/para2", &qn_ab, ptype).unwrap();
+        let (x, _, _) = ntree.find_ref("c/para2", &qn_ab, ptype).unwrap().unwrap();
         assert_eq!(x, &qn_abc);
 
-        let (x, _, _) = ntree.resolve_ref("../b/para1", &qn_ab, ptype).unwrap();
+        let (x, _, _) = ntree.resolve_ref("../b/para1", &qn_ab, ptype).unwrap().unwrap();
         assert_eq!(x, &qn_ab);
 
-        let (x, _, _) = ntree.resolve_ref("b/c/para2", &qn_a, ptype).unwrap();
+        let (x, _, _) = ntree.resolve_ref("b/c/para2", &qn_a, ptype).unwrap().unwrap();
         assert_eq!(x, &qn_abc);
 
-        let x = ntree.resolve_ref("b/c/para1", &qn_a, ptype);
+        let x = ntree.resolve_ref("b/c/para1", &qn_a, ptype).unwrap();
         assert!(x.is_none());
 
-        let (x, _, _) = ntree.resolve_ref("a/b/para1", &qn_abc, ptype).unwrap();
+        let (x, _, _) = ntree.resolve_ref("a/b/para1", &qn_abc, ptype).unwrap().unwrap();
         assert_eq!(x, &qn_ab);
 
-        let (x, _, pn) = ntree.resolve_ref("b/para3/a/b/c", &qn_abc, ptype).unwrap();
+        let (x, _, pn) = ntree.resolve_ref("b/para3/a/b/c", &qn_abc, ptype).unwrap().unwrap();
         assert_eq!(x, &qn_b);
         assert_eq!(3, pn.unwrap().len());
 
-        let (x, _, pn) = ntree.resolve_ref("b/para3.a.b.c", &qn_abc, ptype).unwrap();
+        let (x, _, pn) = ntree.resolve_ref("b/para3.a.b.c", &qn_abc, ptype).unwrap().unwrap();
         assert_eq!(x, &qn_b);
         assert_eq!(3, pn.unwrap().len());
 
-        let (x, _, pn) = ntree.resolve_ref("/b/para3", &qn_abc, ptype).unwrap();
+        let (x, _, pn) = ntree.resolve_ref("/b/para3", &qn_abc, ptype).unwrap().unwrap();
         assert_eq!(x, &qn_b);
         assert!(pn.is_none());
+
+        let x = ntree.resolve_ref("b/para3[x]", &qn_abc, ptype);
+        assert!(matches!(x, Err(XtceError::Mdb(_))));
     }
 }
@@ -1,9 +1,9 @@
 use std::str::FromStr;
 
-use super::{*, misc::resolve_ref};
-use crate::mdb::*;
-
+use roxmltree::Node;
 
+use super::{*, misc::{read_rate_in_stream, resolve_ref}, types::{parse_initial_value, supports_initial_value}};
+use crate::mdb::*;
 
 pub(super) fn add_parameter(mdb: &mut MissionDatabase, ctx: &ParseContext) -> Result<()> {
     let node = &ctx.node;
@@ -11,34 +11,105 @@ pub(super) fn add_parameter(mdb: &mut MissionDatabase, ctx: &ParseContext) -> Re
     let rtype = NameReferenceType::ParameterType;
 
     let type_idx = resolve_ref(mdb, ctx, &ptype_str, rtype)?;
-    let ndescr = read_name_description(ctx);
+    let ndescr = read_name_description(ctx)?;
+
+    let mut data_source = (read_attribute::<DataSource>(node, "dataSource")?).unwrap_or(DataSource::Telemetered);
+    let mut properties = None;
+    // the initial value and the node where it has been found (used for error reporting)
+    let mut initial_value_str = read_attribute::<String>(node, "initialValue")?.map(|v| (v, *node));
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "ParameterProperties" => {
+                if let Some(ds) = read_attribute::<DataSource>(&cnode, "dataSource")? {
+                    data_source = ds;
+                }
+                if initial_value_str.is_none() {
+                    initial_value_str = read_attribute::<String>(&cnode, "initialValue")?.map(|v| (v, cnode));
+                }
+                properties = Some(read_parameter_properties(ctx, &cnode)?);
+            }
+            "LongDescription" | "AliasSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring parameter unknown property '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
 
-    
-    let data_source = (read_attribute::<DataSource>(node, "dataSource")?).unwrap_or(DataSource::Telemetered);
+    let dtype = mdb.get_data_type(type_idx);
+    let initial_value = match initial_value_str {
+        Some((_, vnode)) if !supports_initial_value(dtype) => {
+            let msg = format!("ignoring unsupported initial value of parameter {}", mdb.name2str(ctx.name));
+            ctx.warn(&vnode, msg)?;
+            None
+        }
+        Some((v, vnode)) => Some(parse_initial_value(dtype, &v, &vnode)?),
+        None => None,
+    };
 
+    warn_duplicate_aliases(mdb, ctx, &ndescr)?;
     mdb.add_parameter(
         ctx.path,
         Parameter {
             ndescr,
             ptype: Some(type_idx),
             data_source,
+            properties,
+            initial_value,
         },
     );
 
     Ok(())
 }
 
+fn read_parameter_properties(ctx: &ParseContext, node: &Node) -> Result<ParameterProperties> {
+    let read_only = read_bool_attribute(node, "readOnly")?.unwrap_or(false);
+    let persistence = read_bool_attribute(node, "persistence")?.unwrap_or(true);
+    let mut system_name = None;
+    let mut rate_in_stream = None;
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "SystemName" => system_name = cnode.text().map(|s| s.trim().to_owned()),
+            "RateInStreamSet" => {
+                // the streams are not modelled, only the first rate is kept
+                for rnode in children(&cnode) {
+                    if rnode.tag_name().name() != "RateInStream" {
+                        ctx.warn(
+                            &rnode,
+                            format!("ignoring rate in stream set unknown property '{}'", rnode.tag_name().name()),
+                        )?;
+                    } else if rate_in_stream.is_none() {
+                        rate_in_stream = Some(read_rate_in_stream(&rnode)?);
+                    }
+                }
+            }
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring parameter properties unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+
+    Ok(ParameterProperties { system_name, read_only, persistence, rate_in_stream })
+}
+
 impl FromStr for DataSource {
     type Err = XtceError;
 
     fn from_str(s: &str) -> Result<Self> {
+        // the capitalized values are not XTCE but have been accepted before
         match s {
-            "Telemetered" => Ok(DataSource::Telemetered),
-            "Derived" => Ok(DataSource::Derived),
-            "Constant" => Ok(DataSource::Constant),
-            "Local" => Ok(DataSource::Local),
-            "System" => Ok(DataSource::System),
-            _ => Err(XtceError::InvalidValue("please use one of Telemetered, Derived, Constant, Local or System".to_owned())),
+            "telemetered" | "Telemetered" => Ok(DataSource::Telemetered),
+            "derived" | "Derived" => Ok(DataSource::Derived),
+            "constant" | "Constant" => Ok(DataSource::Constant),
+            "local" | "Local" => Ok(DataSource::Local),
+            "system" | "System" => Ok(DataSource::System),
+            _ => Err(XtceError::InvalidValue("please use one of telemetered, derived, constant, local or system".to_owned())),
         }
     }
 }
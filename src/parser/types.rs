@@ -1,14 +1,21 @@
 use roxmltree::Node;
 
-use super::{misc::resolve_ref, *};
+use super::{
+    alarms::{read_enumeration_alarms, EnumerationAlarms},
+    calibrators::read_calibrators,
+    misc::{read_integer_value, resolve_ref},
+    *,
+};
 
 use encodings::*;
 
+use crate::value::Value;
+
 use crate::mdb::{
     types::{
-        AbsoluteTimeDataType, AggregateDataType, ArrayDataType, BinaryDataType, BooleanDataType,
-        DataEncoding, DataType, EnumeratedDataType, FloatDataType, IntegerDataType, Member,
-        StringDataType, TypeData, ValueEnumeration,
+        AbsoluteTimeDataType, AggregateDataType, ArrayDataType, ArrayDimension, BinaryDataType, BooleanDataType, ContextCalibrator,
+        DataEncoding, DataType, EnumeratedDataType, EnumerationContextAlarm, FloatDataType, IntegerDataType, Member,
+        StringDataType, TypeData, ValidRange, ValueEnumeration,
     },
     *,
 };
@@ -17,60 +24,170 @@ pub(super) fn add_parameter_type(
     mdb: &mut MissionDatabase,
     ctx: &ParseContext,
 ) -> Result<()> {
-    let (encoding, type_data) = match ctx.node.tag_name().name() {
-        "IntegerParameterType" => read_integer_parameter_type(mdb, ctx)?,
-        "FloatParameterType" => read_float_parameter_type(mdb, ctx)?,
-        "EnumeratedParameterType" => read_enumerated_parameter_type(mdb, ctx)?,
-        "BooleanParameterType" => read_boolean_parameter_type(mdb, ctx)?,
-        "StringParameterType" => read_string_parameter_type(mdb, ctx)?,
-        "BinaryParameterType" => read_binary_parameter_type(mdb, ctx)?,
-        "AbsoluteTimeParameterType" => read_absolute_time_parameter_type(mdb, ctx)?,
-        "AggregateParameterType" => read_aggregate_parameter_type(mdb, ctx)?,
-        "ArrayParameterType" => read_array_parameter_type(mdb, ctx)?,
+    let (encoding, mut type_data) = match ctx.node.tag_name().name() {
+        "IntegerParameterType" | "IntegerArgumentType" => read_integer_parameter_type(mdb, ctx)?,
+        "FloatParameterType" | "FloatArgumentType" => read_float_parameter_type(mdb, ctx)?,
+        "EnumeratedParameterType" | "EnumeratedArgumentType" => {
+            read_enumerated_parameter_type(mdb, ctx)?
+        }
+        "BooleanParameterType" | "BooleanArgumentType" => read_boolean_parameter_type(mdb, ctx)?,
+        "StringParameterType" | "StringArgumentType" => read_string_parameter_type(mdb, ctx)?,
+        "BinaryParameterType" | "BinaryArgumentType" => read_binary_parameter_type(mdb, ctx)?,
+        "AbsoluteTimeParameterType" | "AbsoluteTimeArgumentType" => {
+            read_absolute_time_parameter_type(mdb, ctx)?
+        }
+        "AggregateParameterType" | "AggregateArgumentType" => {
+            read_aggregate_parameter_type(mdb, ctx)?
+        }
+        "ArrayParameterType" | "ArrayArgumentType" => read_array_parameter_type(mdb, ctx)?,
         _ => {
-            log::warn!("ignoring parameter type '{}'", ctx.node.tag_name().name());
+            ctx.warn(
+                &ctx.node,
+                format!("ignoring parameter type '{}'", ctx.node.tag_name().name()),
+            )?;
             return Ok(());
         }
     };
-    let dtype = DataType {
-        ndescr: read_name_description(ctx),
+    let calibrators = read_calibrators(mdb, ctx)?;
+    let alarms = match &type_data {
+        TypeData::Enumerated(edt) => read_enumeration_alarms(mdb, ctx, &edt.enumeration)?,
+        _ => EnumerationAlarms::default(),
+    };
+    let units = read_unit_set(ctx, &ctx.node)?;
+
+    // everything is parsed, the criteria can be added without risk of having to parse them again
+    let context_calibrators = calibrators
+        .context
+        .into_iter()
+        .map(|(mc, calibrator)| ContextCalibrator { criteria: mdb.add_match_criteria(mc), calibrator })
+        .collect();
+    if let TypeData::Enumerated(edt) = &mut type_data {
+        edt.default_alarm = alarms.default;
+        edt.context_alarm = alarms
+            .context
+            .into_iter()
+            .map(|(mc, alarm)| EnumerationContextAlarm { criteria: mdb.add_match_criteria(mc), alarm })
+            .collect();
+    }
+
+    let mut dtype = DataType {
+        ndescr: read_name_description(ctx)?,
         encoding,
-        units: read_unit_set(&ctx.node)?,
+        units,
         type_data,
-        calibrator: None,
+        calibrator: calibrators.default,
+        context_calibrators,
+        initial_value: None,
     };
+    if let Some(v) = read_attribute::<String>(&ctx.node, "initialValue")? {
+        if !supports_initial_value(&dtype) {
+            ctx.warn(
+                &ctx.node,
+                format!(
+                    "ignoring unsupported initial value of parameter type {}",
+                    mdb.name2str(ctx.name)
+                ),
+            )?;
+        } else {
+            dtype.initial_value = Some(parse_initial_value(&dtype, &v, &ctx.node)?);
+        }
+    }
 
-    mdb.add_parameter_type(ctx.path, dtype);
+    if ctx.rtype == NameReferenceType::ArgumentType {
+        mdb.add_argument_type(ctx.path, dtype);
+    } else {
+        mdb.add_parameter_type(ctx.path, dtype);
+    }
     Ok(())
 }
 
+/// returns false for the types whose values cannot be converted from a string (aggregates, arrays and
+/// absolute times), see [DataType::from_str]
+pub(super) fn supports_initial_value(dtype: &DataType) -> bool {
+    !matches!(dtype.type_data, TypeData::Aggregate(_) | TypeData::Array(_) | TypeData::AbsoluteTime(_))
+}
+
+/// converts the engineering initial value of a type or parameter, checking it against the valid range of the type
+pub(super) fn parse_initial_value(dtype: &DataType, value: &str, node: &Node) -> Result<Value> {
+    dtype
+        .from_str(value, true)
+        .and_then(|x| {
+            dtype.check_valid_range(&x, true)?;
+            Ok(x)
+        })
+        .map_err(|e| get_parse_error(format!("Invalid initial value '{}': {}", value, e), node))
+}
+
 pub(super) fn read_integer_parameter_type(
     mdb: &MissionDatabase,
     ctx: &ParseContext,
 ) -> Result<(DataEncoding, TypeData)> {
     let mut encoding = DataEncoding::None;
-    let signed = read_attribute::<bool>(&ctx.node, "signed")?.unwrap_or(true);
-    let size_in_bits = read_attribute::<u32>(&ctx.node, "sizeInBits")?.unwrap_or(32);
+    let signed = read_bool_attribute(&ctx.node, "signed")?.unwrap_or(true);
+    let size_attr = read_attribute::<u32>(&ctx.node, "sizeInBits")?;
+    let mut valid_ranges = Vec::new();
 
-    for cnode in ctx.node.children() {
+    for cnode in children(&ctx.node) {
         match cnode.tag_name().name() {
             "IntegerDataEncoding" => {
                 encoding = DataEncoding::Integer(read_integer_data_encoding(
                     mdb,
-                    ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
             }
-            "" | "LongDescription" | "UnitSet" => {}
-            _ => log::warn!(
-                "ignoring integer parameter type  unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            "ValidRange" => valid_ranges = vec![read_valid_range(&cnode)?],
+            "ValidRangeSet" => valid_ranges = read_valid_range_set(ctx, &cnode)?,
+            "" | "LongDescription" | "AliasSet" | "UnitSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring integer parameter type unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
-    let ipt = IntegerDataType { size_in_bits, signed, default_alarm: None, context_alarm: vec![] };
+    // the engineering values are limited to the size of the type, it has to fit the encoding
+    let mut size_in_bits = size_attr.unwrap_or(32);
+    if let DataEncoding::Integer(ide) = &encoding {
+        let enc_size = ide.size_in_bits as u32;
+        if enc_size > size_in_bits {
+            if size_attr.is_some() {
+                ctx.warn(
+                    &ctx.node,
+                    format!(
+                        "integer parameter type {}: the {} bits encoding does not fit the {} bits \
+                         engineering type, the values will be limited",
+                        mdb.name2str(ctx.name),
+                        enc_size,
+                        size_in_bits
+                    ),
+                )?;
+            } else {
+                size_in_bits = 64;
+                ctx.warn(
+                    &ctx.node,
+                    format!(
+                        "integer parameter type {}: no size in bits specified, using 64 bits to \
+                         fit the {} bits encoding",
+                        mdb.name2str(ctx.name),
+                        enc_size
+                    ),
+                )?;
+            }
+        }
+    }
+
+    let ipt = IntegerDataType {
+        size_in_bits,
+        signed,
+        default_alarm: None,
+        context_alarm: vec![],
+        valid_ranges,
+    };
 
     Ok((encoding, TypeData::Integer(ipt)))
 }
@@ -80,13 +197,21 @@ pub(super) fn read_float_parameter_type(
     ctx: &ParseContext,
 ) -> Result<(DataEncoding, TypeData)> {
     let mut encoding = DataEncoding::None;
+    let mut valid_ranges = Vec::new();
+    let size_attr = read_attribute::<u32>(&ctx.node, "sizeInBits")?;
+    if let Some(size_in_bits) = size_attr.filter(|&s| s != 32 && s != 64) {
+        return Err(get_parse_error(
+            format!("Invalid float size in bits {}, only 32 and 64 are supported", size_in_bits),
+            &ctx.node,
+        ));
+    }
 
-    for cnode in ctx.node.children() {
+    for cnode in children(&ctx.node) {
         match cnode.tag_name().name() {
             "IntegerDataEncoding" => {
                 encoding = DataEncoding::Integer(read_integer_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
@@ -94,20 +219,50 @@ pub(super) fn read_float_parameter_type(
             "FloatDataEncoding" => {
                 encoding = DataEncoding::Float(read_float_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
             }
-            "" | "LongDescription" | "UnitSet" => {}
-            _ => log::warn!(
-                "ignoring float parameter type unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            "ValidRange" => valid_ranges = vec![read_valid_range(&cnode)?],
+            "ValidRangeSet" => valid_ranges = read_valid_range_set(ctx, &cnode)?,
+            "" | "LongDescription" | "AliasSet" | "UnitSet" => {}
+            // may contain the custom float layout, see read_float_data_encoding
+            "AncillaryDataSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring float parameter type unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
-    let fpt = FloatDataType { size_in_bits: 0, default_alarm: None, context_alarm: vec![] };
+    // without explicit size, the type has the precision of the encoding
+    let enc_size = match &encoding {
+        DataEncoding::Float(fde) => Some(fde.size_in_bits as u32),
+        DataEncoding::Integer(ide) => Some(ide.size_in_bits as u32),
+        _ => None,
+    };
+    let size_in_bits = size_attr.unwrap_or(if enc_size > Some(32) { 64 } else { 32 });
+
+    if let DataEncoding::Float(fde) = &encoding {
+        if fde.size_in_bits as u32 > size_in_bits {
+            ctx.warn(
+                &ctx.node,
+                format!(
+                    "float parameter type {}: the {} bits encoding does not fit the {} bits \
+                     engineering type, precision will be lost",
+                    mdb.name2str(ctx.name),
+                    fde.size_in_bits,
+                    size_in_bits
+                ),
+            )?;
+        }
+    }
+
+    let fpt = FloatDataType { size_in_bits, default_alarm: None, context_alarm: vec![], valid_ranges };
 
     Ok((encoding, TypeData::Float(fpt)))
 }
@@ -122,12 +277,12 @@ pub(super) fn read_boolean_parameter_type(
 
     let mut encoding = DataEncoding::None;
 
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
             "IntegerDataEncoding" => {
                 encoding = DataEncoding::Integer(read_integer_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
@@ -135,16 +290,19 @@ pub(super) fn read_boolean_parameter_type(
             "FloatDataEncoding" => {
                 encoding = DataEncoding::Float(read_float_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
             }
-            "" | "LongDescription" | "UnitSet" => {}
-            _ => log::warn!(
-                "ignoring boolean parameter type unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            "" | "LongDescription" | "AliasSet" | "UnitSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring boolean parameter type unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
@@ -160,12 +318,12 @@ pub(super) fn read_enumerated_parameter_type(
     let mut encoding = DataEncoding::None;
     let mut enumeration = Vec::<ValueEnumeration>::new();
 
-    for cnode in ctx.node.children() {
+    for cnode in children(&ctx.node) {
         match cnode.tag_name().name() {
             "IntegerDataEncoding" => {
                 encoding = DataEncoding::Integer(read_integer_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
@@ -173,7 +331,7 @@ pub(super) fn read_enumerated_parameter_type(
             "FloatDataEncoding" => {
                 encoding = DataEncoding::Float(read_float_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
@@ -181,11 +339,16 @@ pub(super) fn read_enumerated_parameter_type(
             "EnumerationList" => {
                 read_enumeration_list(&mut enumeration, &cnode)?;
             }
-            "" | "LongDescription" | "UnitSet" => {}
-            _ => log::warn!(
-                "ignoring enumerated parameter type unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            // read by read_enumeration_alarms once the enumeration is known
+            "DefaultAlarm" | "ContextAlarmList" => {}
+            "" | "LongDescription" | "AliasSet" | "UnitSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring enumerated parameter type unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
@@ -199,12 +362,12 @@ pub(super) fn read_string_parameter_type(
 ) -> Result<(DataEncoding, TypeData)> {
     let mut encoding = DataEncoding::None;
 
-    for cnode in ctx.node.children() {
+    for cnode in children(&ctx.node) {
         match cnode.tag_name().name() {
             "IntegerDataEncoding" => {
                 encoding = DataEncoding::Integer(read_integer_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
@@ -212,7 +375,7 @@ pub(super) fn read_string_parameter_type(
             "FloatDataEncoding" => {
                 encoding = DataEncoding::Float(read_float_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
@@ -225,11 +388,14 @@ pub(super) fn read_string_parameter_type(
                     &DataEncoding::None,
                 )?);
             }
-            "" | "LongDescription" | "UnitSet" => {}
-            _ => log::warn!(
-                "ignoring string parameter type unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            "" | "LongDescription" | "AliasSet" | "UnitSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring string parameter type unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
@@ -244,12 +410,12 @@ pub(super) fn read_binary_parameter_type(
 ) -> Result<(DataEncoding, TypeData)> {
     let mut encoding = DataEncoding::None;
 
-    for cnode in ctx.node.children() {
+    for cnode in children(&ctx.node) {
         match cnode.tag_name().name() {
             "IntegerDataEncoding" => {
                 encoding = DataEncoding::Integer(read_integer_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
@@ -257,7 +423,7 @@ pub(super) fn read_binary_parameter_type(
             "FloatDataEncoding" => {
                 encoding = DataEncoding::Float(read_float_data_encoding(
                     mdb,
-                    &ctx.path,
+                    ctx,
                     &cnode,
                     &DataEncoding::None,
                 )?);
@@ -278,11 +444,14 @@ pub(super) fn read_binary_parameter_type(
                     &DataEncoding::None,
                 )?);
             }
-            "" | "LongDescription" | "UnitSet" => {}
-            _ => log::warn!(
-                "ignoring binary parameter type unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            "" | "LongDescription" | "AliasSet" | "UnitSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring binary parameter type unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
@@ -298,25 +467,31 @@ pub(super) fn read_aggregate_parameter_type(
 ) -> Result<(DataEncoding, TypeData)> {
     let mut members = Vec::new();
 
-    for cnode in ctx.node.children() {
+    for cnode in children(&ctx.node) {
         match cnode.tag_name().name() {
             "MemberList" => {
-                for mnode in cnode.children() {
+                for mnode in children(&cnode) {
                     match mnode.tag_name().name() {
                         "Member" => members.push(read_member(mdb, ctx, &mnode)?),
                         "" => continue,
-                        _ => log::warn!(
-                            "ignoring member list unknown property '{}'",
-                            mnode.tag_name().name()
-                        ),
+                        _ => ctx.warn(
+                            &mnode,
+                            format!(
+                                "ignoring member list unknown property '{}'",
+                                mnode.tag_name().name()
+                            ),
+                        )?,
                     }
                 }
             }
-            "" | "LongDescription" => {}
-            _ => log::warn!(
-                "ignoring aggreagate parameter type unknown property '{}'",
-                cnode.tag_name().name()
-            ),
+            "" | "LongDescription" | "AliasSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring aggreagate parameter type unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
         };
     }
 
@@ -327,41 +502,102 @@ pub(super) fn read_aggregate_parameter_type(
 // reads a member of an aggregate type from the XTCE
 fn read_member(mdb: &mut MissionDatabase, ctx: &ParseContext, node: &Node) -> Result<Member> {
     let ptype_str = read_mandatory_attribute::<String>(node, "typeRef")?;
-    let rtype = NameReferenceType::ParameterType;
+    // the members of the argument types are argument types
+    let rtype = ctx.rtype;
 
     let dtype = resolve_ref(mdb, ctx, &ptype_str, rtype)?;
     let name_str = read_mandatory_name(node)?;
     let name = mdb.get_or_intern(name_str);
     let ctx1 = ParseContext {
         name_tree: ctx.name_tree,
+        file: ctx.file,
         node: *node,
         path: ctx.path,
         name: name,
         rtype: ctx.rtype,
+        warnings: ctx.warnings,
     };
-    let ndescr = read_name_description(&ctx1);
+    let ndescr = read_name_description(&ctx1)?;
 
     Ok(Member { ndescr, dtype })
 }
 
 pub(super) fn read_array_parameter_type(
-    mdb: &MissionDatabase,
+    mdb: &mut MissionDatabase,
     ctx: &ParseContext,
 ) -> Result<(DataEncoding, TypeData)> {
     let ptype_str = read_mandatory_attribute::<String>(&ctx.node, "arrayTypeRef")?;
-    let rtype = NameReferenceType::ParameterType;
+    let rtype = ctx.rtype;
     let dtype = resolve_ref(mdb, ctx, &ptype_str, rtype)?;
+    let mut dim = Vec::new();
+
+    for cnode in children(&ctx.node) {
+        match cnode.tag_name().name() {
+            "DimensionList" => {
+                for dnode in children(&cnode) {
+                    if dnode.tag_name().name() == "Dimension" {
+                        dim.push(read_array_dimension(mdb, ctx, &dnode)?);
+                    } else {
+                        ctx.warn(
+                            &dnode,
+                            format!(
+                                "ignoring dimension list unknown property '{}'",
+                                dnode.tag_name().name()
+                            ),
+                        )?;
+                    }
+                }
+            }
+            "LongDescription" | "AliasSet" | "UnitSet" => {}
+            _ => ctx.warn(
+                &cnode,
+                format!(
+                    "ignoring array parameter type unknown property '{}'",
+                    cnode.tag_name().name()
+                ),
+            )?,
+        }
+    }
+    // XTCE 1.1 gives only the numberOfDimensions, the sizes being given by the ArrayParameterRefEntry
+    if dim.is_empty() && ctx.node.attribute("numberOfDimensions").is_none() {
+        return Err(missing("DimensionList", &ctx.node));
+    }
 
-    let apt = ArrayDataType { dim: Vec::new(), dtype };
+    let apt = ArrayDataType { dim, dtype };
 
     Ok((DataEncoding::None, TypeData::Array(apt)))
 }
 
+fn read_array_dimension(
+    mdb: &mut MissionDatabase,
+    ctx: &ParseContext,
+    node: &Node,
+) -> Result<ArrayDimension> {
+    let mut starting_index = None;
+    let mut ending_index = None;
+
+    for cnode in children(node) {
+        match cnode.tag_name().name() {
+            "StartingIndex" => starting_index = Some(read_integer_value(mdb, ctx, &cnode)?),
+            "EndingIndex" => ending_index = Some(read_integer_value(mdb, ctx, &cnode)?),
+            _ => ctx.warn(
+                &cnode,
+                format!("ignoring dimension unknown property '{}'", cnode.tag_name().name()),
+            )?,
+        }
+    }
+
+    Ok(ArrayDimension {
+        starting_index: starting_index.ok_or_else(|| missing("StartingIndex", node))?,
+        ending_index: ending_index.ok_or_else(|| missing("EndingIndex", node))?,
+    })
+}
+
 pub(super) fn read_absolute_time_parameter_type(
     mdb: &MissionDatabase,
     ctx: &ParseContext,
 ) -> Result<(DataEncoding, TypeData)> {
-    for cnode in ctx.node.children() {
+    for cnode in children(&ctx.node) {
         match cnode.tag_name().name() {
             "Encoding" => {
                 //TODO
@@ -371,10 +607,13 @@ pub(super) fn read_absolute_time_parameter_type(
             }
             "" => {}
             _ => {
-                log::warn!(
-                    "ignoring read_absolute_time_parameter_type '{}'",
-                    cnode.tag_name().name()
-                )
+                ctx.warn(
+                    &cnode,
+                    format!(
+                        "ignoring read_absolute_time_parameter_type '{}'",
+                        cnode.tag_name().name()
+                    ),
+                )?
             }
         };
     }
@@ -383,10 +622,16 @@ pub(super) fn read_absolute_time_parameter_type(
 }
 
 fn read_enumeration_list(elist: &mut Vec<ValueEnumeration>, node: &Node) -> Result<()> {
-    for cnode in node.children().filter(|n| !n.tag_name().name().is_empty()) {
-        let value = read_mandatory_attribute::<i64>(&cnode, "value")?;
+    for cnode in children(node) {
+        let value = read_mandatory_integer_attribute::<i64>(&cnode, "value")?;
         let label = read_mandatory_attribute::<String>(&cnode, "label")?;
-        let max_value = read_attribute::<i64>(&cnode, "value")?.unwrap_or(value);
+        let max_value = read_integer_attribute::<i64>(&cnode, "maxValue")?.unwrap_or(value);
+        if max_value < value {
+            return Err(get_parse_error(
+                format!("Invalid enumeration '{}': maxValue {} < value {}", label, max_value, value),
+                &cnode,
+            ));
+        }
         let description = read_attribute::<String>(&cnode, "shortDescription")?;
 
         elist.push(ValueEnumeration { value, label, max_value, description });
@@ -394,34 +639,80 @@ fn read_enumeration_list(elist: &mut Vec<ValueEnumeration>, node: &Node) -> Resu
     Ok(())
 }
 
-fn read_unit_set(node: &Node) -> Result<Vec<UnitType>> {
+// reads the <ValidRange> element of the integer and float types
+// each of the min/max bounds can be given either as inclusive or as exclusive
+fn read_valid_range(node: &Node) -> Result<ValidRange> {
+    let min_inclusive = read_attribute::<f64>(node, "minInclusive")?;
+    let min_exclusive = read_attribute::<f64>(node, "minExclusive")?;
+    let max_inclusive = read_attribute::<f64>(node, "maxInclusive")?;
+    let max_exclusive = read_attribute::<f64>(node, "maxExclusive")?;
+    let applies_to_calibrated =
+        read_bool_attribute(node, "validRangeAppliesToCalibrated")?.unwrap_or(true);
+
+    if min_inclusive.is_some() && min_exclusive.is_some() {
+        return Err(get_parse_error("Only one of minInclusive and minExclusive can be specified", node));
+    }
+    if max_inclusive.is_some() && max_exclusive.is_some() {
+        return Err(get_parse_error("Only one of maxInclusive and maxExclusive can be specified", node));
+    }
+    let min = min_inclusive.or(min_exclusive);
+    let max = max_inclusive.or(max_exclusive);
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(get_parse_error(format!("Invalid range: min {} > max {}", min, max), node));
+        }
+    }
+
+    Ok(ValidRange {
+        min,
+        min_inclusive: min_exclusive.is_none(),
+        max,
+        max_inclusive: max_exclusive.is_none(),
+        applies_to_calibrated,
+    })
+}
+
+// reads the <ValidRangeSet> element of the integer and float argument types
+// validRangeAppliesToCalibrated is given by the set for all its ranges
+fn read_valid_range_set(ctx: &ParseContext, node: &Node) -> Result<Vec<ValidRange>> {
+    let applies_to_calibrated = read_bool_attribute(node, "validRangeAppliesToCalibrated")?.unwrap_or(true);
+    let mut valid_ranges = Vec::new();
+
+    for cnode in children(node) {
+        if cnode.tag_name().name() != "ValidRange" {
+            ctx.warn(&cnode, format!("ignoring valid range set unknown property '{}'", cnode.tag_name().name()))?;
+        } else {
+            valid_ranges.push(ValidRange { applies_to_calibrated, ..read_valid_range(&cnode)? });
+        }
+    }
+
+    Ok(valid_ranges)
+}
+
+fn read_unit_set(ctx: &ParseContext, node: &Node) -> Result<Vec<UnitType>> {
     let mut units = Vec::new();
-    for pnode in node.children() {
-        match pnode.tag_name().name() {
-            "UnitSet" => {
-                for cnode in node.children() {
-                    match cnode.tag_name().name() {
-                        "Unit" => {
-                            let power = read_attribute::<f64>(&cnode, "power")?.unwrap_or(1f64);
-                            let factor = read_attribute::<String>(&cnode, "factor")?
-                                .unwrap_or("1".to_owned());
-                            let description = read_attribute::<String>(&cnode, "shortDescription")?;
-                            let unit = cnode.text().ok_or_else(|| {
-                                get_parse_error("No unit present".to_owned(), &cnode)
-                            })?;
-
-                            units.push(UnitType {
-                                unit: unit.to_owned(),
-                                power,
-                                factor,
-                                description,
-                            });
-                        }
-                        _ => {}
-                    }
-                }
+    for pnode in children(node) {
+        if pnode.tag_name().name() != "UnitSet" {
+            continue;
+        }
+        for cnode in children(&pnode) {
+            if cnode.tag_name().name() != "Unit" {
+                ctx.warn(
+                    &cnode,
+                    format!("ignoring unit set unknown property '{}'", cnode.tag_name().name()),
+                )?;
+                continue;
             }
-            _ => {}
+            let power = read_attribute::<f64>(&cnode, "power")?.unwrap_or(1f64);
+            let factor = read_attribute::<String>(&cnode, "factor")?.unwrap_or_else(|| "1".to_owned());
+            let description = read_attribute::<String>(&cnode, "description")?;
+            let unit = cnode
+                .text()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| get_parse_error("No unit present", &cnode))?;
+
+            units.push(UnitType { unit: unit.to_owned(), power, factor, description });
         }
     }
 
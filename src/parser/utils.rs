@@ -2,9 +2,13 @@ use super::*;
 
 use roxmltree::{Node, Children};
 
+use std::time::Duration;
+
+use crate::mdb::utils::{parse_duration, parse_integer_text};
+
 pub(super) fn get_parse_error<S: AsRef<str>>(msg: S, node: &Node) -> XtceError {
     let pos = node.document().text_pos_at(node.range().start);
-    XtceError::Parse(XtceParseError { msg: msg.as_ref().to_owned(), pos })
+    XtceError::Parse(XtceParseError { msg: msg.as_ref().to_owned(), pos, source: None })
 }
 
 pub (super) fn unsupported(what: &str, node: &Node) -> XtceError {
@@ -45,18 +49,99 @@ pub(super) fn read_mandatory_attribute<T: std::str::FromStr>(
     }
 }
 
-pub(super) fn read_name_description(ctx: &ParseContext) -> NameDescription {
+/// reads an integer attribute which may be written in decimal, hexadecimal (0x), octal (0o) or binary (0b)
+pub(super) fn read_integer_attribute<T: TryFrom<i128>>(node: &Node, attr_name: &str) -> Result<Option<T>> {
+    match node.attribute(attr_name) {
+        Some(strv) => parse_integer_text(strv).map(Some).map_err(|e| {
+            get_parse_error(format!("Cannot parse value '{}' for attribute {}: {}", strv, attr_name, e), node)
+        }),
+        None => Ok(None),
+    }
+}
+
+pub(super) fn read_mandatory_integer_attribute<T: TryFrom<i128>>(node: &Node, attr_name: &str) -> Result<T> {
+    match read_integer_attribute::<T>(node, attr_name)? {
+        None => Err(get_parse_error(format!("Cannot find attribute {}", attr_name), node)),
+        Some(v) => Ok(v),
+    }
+}
+
+/// reads the text of the node as an integer, see [read_integer_attribute] for the accepted formats
+pub(super) fn read_mandatory_integer_text<T: TryFrom<i128>>(node: &Node) -> Result<T> {
+    let strv = node.text().ok_or_else(|| get_parse_error("Cannot find text", node))?;
+    parse_integer_text(strv).map_err(|e| get_parse_error(format!("Cannot parse value '{}': {}", strv, e), node))
+}
+
+pub(super) fn read_name_description(ctx: &ParseContext) -> Result<NameDescription> {
     let node = &ctx.node;
     let mut nd = NameDescription::new(ctx.name);
     nd.short_description = node.attribute("shortDescription").map(|s| s.to_string());
 
-    for cnode in node.children() {
+    for cnode in children(node) {
         match cnode.tag_name().name() {
-            "LongDescription" => nd.long_description = node.text().map(|s| s.to_string()),
+            "LongDescription" => nd.long_description = cnode.text().map(|s| s.to_string()),
+            "AliasSet" => {
+                for anode in children(&cnode) {
+                    match (anode.attribute("nameSpace"), anode.attribute("alias")) {
+                        (Some(name_space), Some(alias)) => {
+                            if nd.aliases.contains_key(name_space) {
+                                ctx.warn(
+                                    &anode,
+                                    format!(
+                                        "{}: ignoring the second alias in namespace '{}'",
+                                        node.attribute("name").unwrap_or_default(),
+                                        name_space
+                                    ),
+                                )?;
+                            } else {
+                                nd.aliases.insert(name_space.to_owned(), alias.to_owned());
+                            }
+                        }
+                        _ => {
+                            let msg = "ignoring alias without nameSpace or alias attribute";
+                            ctx.warn(&anode, msg)?
+                        }
+                    }
+                }
+            }
             _ => {}
         }
     }
-    nd
+    Ok(nd)
+}
+
+// warns about the aliases of the parameter or container already used by another item in the same namespace;
+// the lookup by alias returns the item with the smallest qualified name
+pub(super) fn warn_duplicate_aliases(mdb: &MissionDatabase, ctx: &ParseContext, nd: &NameDescription) -> Result<()> {
+    let mut qn = ctx.path.clone();
+    qn.push(ctx.name);
+    let item_qn = mdb.qn_to_string(&qn);
+
+    let mut aliases: Vec<_> = nd.aliases.iter().collect();
+    aliases.sort();
+    for (name_space, alias) in aliases {
+        if let Some(existing) = mdb.find_by_alias(name_space, alias) {
+            let existing_qn = mdb.alias_target_qn(existing);
+            let kept_qn = existing_qn.as_str().min(item_qn.as_str());
+            ctx.warn(
+                &ctx.node,
+                format!(
+                    "Duplicate alias '{}' in namespace '{}': used by {} and {}; keeping {}",
+                    alias, name_space, existing_qn, item_qn, kept_qn
+                ),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// returns the text of the ancillary data with the given name from the AncillaryDataSet of the node
+pub(super) fn read_ancillary_data<'a>(node: &Node<'a, '_>, name: &str) -> Option<&'a str> {
+    children(node)
+        .filter(|n| n.tag_name().name() == "AncillaryDataSet")
+        .flat_map(|n| n.children().filter(is_xtce_element))
+        .find(|n| n.tag_name().name() == "AncillaryData" && n.attribute("name") == Some(name))
+        .map(|n| n.text().unwrap_or("").trim())
 }
 
 pub(super) fn read_mandatory_name<'a>(node: &'a Node) -> Result<&'a str> {
@@ -81,9 +166,85 @@ pub(super) fn read_attribute<T: std::str::FromStr>(
     }
 }
 
+/// reads an attribute containing an ISO 8601 duration, see [parse_duration] for the accepted formats
+pub(super) fn read_duration_attribute(node: &Node, attr_name: &str) -> Result<Option<Duration>> {
+    match node.attribute(attr_name) {
+        Some(strv) => parse_duration(strv).map(Some).map_err(|e| {
+            get_parse_error(format!("Invalid value for attribute {}: {}", attr_name, e), node)
+        }),
+        None => Ok(None),
+    }
+}
+
+/// reads a boolean attribute, accepting true/false and 1/0 case-insensitively as written by various XTCE tools
+pub(super) fn read_bool_attribute(node: &Node, attr_name: &str) -> Result<Option<bool>> {
+    match node.attribute(attr_name) {
+        Some(strv) => match strv.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" => Ok(Some(true)),
+            "false" | "0" => Ok(Some(false)),
+            _ => Err(get_parse_error(
+                format!("Cannot parse value '{}' for boolean attribute {}", strv, attr_name),
+                node,
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+/// the namespaces of XTCE 1.2 and XTCE 1.0/1.1
+const XTCE_NAMESPACES: [&str; 2] =
+    ["http://www.omg.org/spec/XTCE/20180204", "http://www.omg.org/space/xtce"];
 
-pub(super) fn children<'a>(node: &'a Node<'a, 'a>) -> std::iter::Filter<Children, fn (node: &Node) -> bool> {
-    node.children().filter(|n| !n.tag_name().name().is_empty())
+/// returns true if the node is an XTCE element
+///
+/// The elements without namespace are also accepted for backwards compatibility with the documents
+/// which do not declare the XTCE namespace.
+pub(super) fn is_xtce_element(node: &Node) -> bool {
+    node.is_element()
+        && node.tag_name().namespace().is_none_or(|ns| XTCE_NAMESPACES.contains(&ns))
 }
 
+/// returns the XTCE child elements of the node,
+/// the text, comments and the elements from other namespaces (e.g. extensions) are skipped
+pub(super) fn children<'a, 'input>(
+    node: &Node<'a, 'input>,
+) -> std::iter::Filter<Children<'a, 'input>, fn(&Node) -> bool> {
+    node.children().filter(is_xtce_element)
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_bool(value: &str) -> Result<Option<bool>> {
+        let text = format!("<SpaceSystem name=\"a\" abstract=\"{}\"/>", value);
+        let doc = roxmltree::Document::parse(&text).unwrap();
+        read_bool_attribute(&doc.root_element(), "abstract")
+    }
+
+    #[test]
+    fn test_read_bool_attribute() {
+        for v in ["true", "True", "TRUE", "1"] {
+            assert_eq!(Some(true), read_bool(v).unwrap(), "{}", v);
+        }
+        for v in ["false", "False", "FALSE", "0"] {
+            assert_eq!(Some(false), read_bool(v).unwrap(), "{}", v);
+        }
+
+        let doc = roxmltree::Document::parse("<SpaceSystem name=\"a\"/>").unwrap();
+        assert_eq!(None, read_bool_attribute(&doc.root_element(), "abstract").unwrap());
+    }
+
+    #[test]
+    fn test_read_invalid_bool_attribute() {
+        for v in ["yes", "2", ""] {
+            match read_bool(v) {
+                Err(XtceError::Parse(e)) => {
+                    assert_eq!(1, e.pos.row);
+                    assert!(e.msg.contains("abstract"), "{}", e.msg);
+                }
+                r => panic!("expected parse error for '{}', got {:?}", v, r),
+            }
+        }
+    }
+}
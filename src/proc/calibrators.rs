@@ -0,0 +1,314 @@
+use crate::mdb::{
+    types::{Calibrator, PolynomialCalibrator, SplineCalibrator},
+    MathOp, MathOperator,
+};
+
+use super::{ProcError, Result};
+
+/// computes the calibrated value of x
+pub(crate) fn apply(cal: &Calibrator, x: f64) -> Result<f64> {
+    match cal {
+        Calibrator::Polynomial(pc) => Ok(polynomial(pc, x)),
+        Calibrator::Spline(sc) => spline(sc, x),
+        Calibrator::MathOperation(ops) => math_operation(ops, x),
+    }
+}
+
+/// computes the raw value x such that apply(cal, x) = y
+///
+/// Only the linear polynomials can be inverted for the moment, the other calibrators return an error.
+pub(crate) fn inverse(cal: &Calibrator, y: f64) -> Result<f64> {
+    match cal {
+        Calibrator::Polynomial(pc) => inverse_polynomial(pc, y),
+        Calibrator::Spline(_) => {
+            Err(ProcError::InvalidMdb("The inverse of spline calibrators is not supported".to_owned()))
+        }
+        Calibrator::MathOperation(_) => Err(ProcError::InvalidMdb(
+            "The inverse of math operation calibrators is not supported".to_owned(),
+        )),
+    }
+}
+
+fn inverse_polynomial(pc: &PolynomialCalibrator, y: f64) -> Result<f64> {
+    let degree = pc.coefficients.iter().rposition(|&c| c != 0.0).unwrap_or(0);
+    match degree {
+        1 => Ok((y - pc.coefficients[0]) / pc.coefficients[1]),
+        0 => Err(ProcError::InvalidMdb(
+            "Cannot invert a constant polynomial calibrator".to_owned(),
+        )),
+        _ => Err(ProcError::InvalidMdb(format!(
+            "The inverse of polynomial calibrators of degree {} is not supported",
+            degree
+        ))),
+    }
+}
+
+fn polynomial(pc: &PolynomialCalibrator, x: f64) -> f64 {
+    // Horner's method
+    pc.coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+// the points are sorted and there are at least two of them (checked by the parser)
+fn spline(sc: &SplineCalibrator, x: f64) -> Result<f64> {
+    let points = &sc.points;
+    let first = &points[0];
+    let last = &points[points.len() - 1];
+
+    if (x < first.raw || x > last.raw) && !sc.extrapolate {
+        return Err(ProcError::OutOfRange(format!(
+            "Raw value {} is outside the range [{}, {}] of the spline calibrator",
+            x, first.raw, last.raw
+        )));
+    }
+
+    // index of the segment [p[i], p[i+1]] containing x, the first or last one when extrapolating
+    let i = points[1..points.len() - 1].partition_point(|p| p.raw <= x);
+    let (p0, p1) = (&points[i], &points[i + 1]);
+
+    if sc.order == 0 {
+        return Ok(if x < p1.raw { p0.calibrated } else { p1.calibrated });
+    }
+
+    Ok(p0.calibrated + (x - p0.raw) * (p1.calibrated - p0.calibrated) / (p1.raw - p0.raw))
+}
+
+// evaluates the postfix expression starting with x on the stack, the result is the top of the stack
+fn math_operation(ops: &[MathOp], x: f64) -> Result<f64> {
+    let mut stack = vec![x];
+
+    for op in ops {
+        match op {
+            MathOp::Value(v) => stack.push(*v),
+            MathOp::ThisParameter => stack.push(x),
+            // rejected by the parser
+            MathOp::ParameterInstance(_) => {
+                return Err(ProcError::InvalidMdb(
+                    "The parameter operands are not supported in the calibrators".to_owned(),
+                ))
+            }
+            MathOp::Operator(operator) => apply_operator(*operator, &mut stack)?,
+        }
+    }
+
+    stack.pop().ok_or_else(|| {
+        ProcError::InvalidValue("The math operation leaves no value on the stack".to_owned())
+    })
+}
+
+fn apply_operator(operator: MathOperator, stack: &mut Vec<f64>) -> Result<()> {
+    use MathOperator::*;
+
+    let mut pop = || {
+        stack.pop().ok_or_else(|| {
+            ProcError::InvalidValue(format!("Stack underflow applying the operator {:?}", operator))
+        })
+    };
+    let as_bool = |v: f64| v != 0.0;
+    let from_bool = |b: bool| if b { 1.0 } else { 0.0 };
+
+    let r = match operator {
+        // the operators modifying the stack
+        Drop => {
+            pop()?;
+            return Ok(());
+        }
+        Dup => {
+            let x = pop()?;
+            stack.extend([x, x]);
+            return Ok(());
+        }
+        Swap => {
+            let (y, x) = (pop()?, pop()?);
+            stack.extend([y, x]);
+            return Ok(());
+        }
+        Over => {
+            let (y, x) = (pop()?, pop()?);
+            stack.extend([x, y, x]);
+            return Ok(());
+        }
+        // the unary operators
+        Ln | Log | Exp | Inverse | Factorial | Tan | Cos | Sin | Atan | Acos | Asin | Tanh | Cosh
+        | Sinh | Atanh | Acosh | Asinh | LogicalNot | Abs => {
+            let x = pop()?;
+            match operator {
+                Ln => x.ln(),
+                Log => x.log10(),
+                Exp => x.exp(),
+                Inverse => 1.0 / x,
+                Factorial => factorial(x)?,
+                Tan => x.tan(),
+                Cos => x.cos(),
+                Sin => x.sin(),
+                Atan => x.atan(),
+                Acos => x.acos(),
+                Asin => x.asin(),
+                Tanh => x.tanh(),
+                Cosh => x.cosh(),
+                Sinh => x.sinh(),
+                Atanh => x.atanh(),
+                Acosh => x.acosh(),
+                Asinh => x.asinh(),
+                LogicalNot => from_bool(!as_bool(x)),
+                _ => x.abs(),
+            }
+        }
+        // the binary operators, y is the top of the stack
+        _ => {
+            let (y, x) = (pop()?, pop()?);
+            match operator {
+                Add => x + y,
+                Subtract => x - y,
+                Multiply => x * y,
+                Divide => x / y,
+                Modulo => x % y,
+                Power => x.powf(y),
+                ReversePower => y.powf(x),
+                Atan2 => x.atan2(y),
+                LeftShift => ((x as i64) << (y as i64 & 63)) as f64,
+                RightShift => ((x as i64) >> (y as i64 & 63)) as f64,
+                BitwiseAnd => ((x as i64) & (y as i64)) as f64,
+                BitwiseOr => ((x as i64) | (y as i64)) as f64,
+                LogicalAnd => from_bool(as_bool(x) && as_bool(y)),
+                LogicalOr => from_bool(as_bool(x) || as_bool(y)),
+                IntegerDivide => (x / y).trunc(),
+                Equal => from_bool(x == y),
+                NotEqual => from_bool(x != y),
+                LessThan => from_bool(x < y),
+                LessOrEqual => from_bool(x <= y),
+                GreaterThan => from_bool(x > y),
+                _ => from_bool(x >= y),
+            }
+        }
+    };
+
+    // division by zero, logarithm of a negative number, etc
+    if !r.is_finite() {
+        return Err(ProcError::InvalidValue(format!(
+            "The operator {:?} gives the invalid result {}",
+            operator, r
+        )));
+    }
+    stack.push(r);
+    Ok(())
+}
+
+fn factorial(x: f64) -> Result<f64> {
+    if x < 0.0 || x.fract() != 0.0 || x > 170.0 {
+        return Err(ProcError::InvalidValue(format!("Cannot compute the factorial of {}", x)));
+    }
+    Ok((1..=x as u32).fold(1.0, |acc, i| acc * i as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mdb::types::SplinePoint;
+
+    use super::*;
+
+    fn spline_cal(order: u32, extrapolate: bool) -> Calibrator {
+        let points = [(0.0, 10.0), (10.0, 20.0), (20.0, 0.0)]
+            .iter()
+            .map(|&(raw, calibrated)| SplinePoint { raw, calibrated })
+            .collect();
+        Calibrator::Spline(SplineCalibrator { order, extrapolate, points })
+    }
+
+    #[test]
+    fn test_polynomial() {
+        let cal = Calibrator::Polynomial(PolynomialCalibrator { coefficients: vec![1.0, 0.0, 2.0] });
+        assert_eq!(1.0, apply(&cal, 0.0).unwrap());
+        assert_eq!(19.0, apply(&cal, 3.0).unwrap());
+        assert_eq!(9.0, apply(&cal, -2.0).unwrap());
+    }
+
+    #[test]
+    fn test_inverse_polynomial() {
+        let cal = Calibrator::Polynomial(PolynomialCalibrator { coefficients: vec![1.0, 2.0, 0.0] });
+        assert_eq!(3.0, inverse(&cal, 7.0).unwrap());
+        assert_eq!(7.0, apply(&cal, inverse(&cal, 7.0).unwrap()).unwrap());
+
+        let cal = Calibrator::Polynomial(PolynomialCalibrator { coefficients: vec![1.0, 0.0, 2.0] });
+        assert!(matches!(inverse(&cal, 7.0), Err(ProcError::InvalidMdb(_))));
+
+        let cal = Calibrator::Polynomial(PolynomialCalibrator { coefficients: vec![1.0] });
+        assert!(matches!(inverse(&cal, 7.0), Err(ProcError::InvalidMdb(_))));
+    }
+
+    #[test]
+    fn test_linear_spline() {
+        let cal = spline_cal(1, false);
+        assert_eq!(10.0, apply(&cal, 0.0).unwrap());
+        assert_eq!(15.0, apply(&cal, 5.0).unwrap());
+        assert_eq!(20.0, apply(&cal, 10.0).unwrap());
+        assert_eq!(10.0, apply(&cal, 15.0).unwrap());
+        assert_eq!(0.0, apply(&cal, 20.0).unwrap());
+        assert!(matches!(apply(&cal, -1.0), Err(ProcError::OutOfRange(_))));
+        assert!(matches!(apply(&cal, 21.0), Err(ProcError::OutOfRange(_))));
+    }
+
+    #[test]
+    fn test_spline_extrapolate() {
+        let cal = spline_cal(1, true);
+        assert_eq!(9.0, apply(&cal, -1.0).unwrap());
+        assert_eq!(-2.0, apply(&cal, 21.0).unwrap());
+    }
+
+    fn math_cal(ops: &[MathOp]) -> Calibrator {
+        Calibrator::MathOperation(ops.to_vec())
+    }
+
+    #[test]
+    fn test_math_operation() {
+        use MathOp::*;
+        use MathOperator::*;
+
+        // sqrt(x) = x^0.5
+        let cal = math_cal(&[Value(0.5), Operator(Power)]);
+        assert_eq!(3.0, apply(&cal, 9.0).unwrap());
+
+        // (x + 1) * (x - 1), the raw value is pushed again by ThisParameter
+        let cal = math_cal(&[
+            Value(1.0),
+            Operator(Add),
+            ThisParameter,
+            Value(1.0),
+            Operator(Subtract),
+            Operator(Multiply),
+        ]);
+        assert_eq!(15.0, apply(&cal, 4.0).unwrap());
+
+        let cal = math_cal(&[Operator(Ln), Value(2.0), Operator(Swap), Operator(Divide)]);
+        assert!((apply(&cal, 1f64.exp()).unwrap() - 2.0).abs() < 1e-12);
+
+        let cal = math_cal(&[Operator(Log), Operator(Sin), Operator(Cos)]);
+        assert_eq!(1.0, apply(&cal, 1.0).unwrap());
+    }
+
+    #[test]
+    fn test_math_operation_errors() {
+        use MathOp::*;
+        use MathOperator::*;
+
+        let cal = math_cal(&[Value(0.0), Operator(Divide)]);
+        assert!(matches!(apply(&cal, 1.0), Err(ProcError::InvalidValue(_))));
+
+        let cal = math_cal(&[Operator(Add)]);
+        assert!(matches!(apply(&cal, 1.0), Err(ProcError::InvalidValue(_))));
+
+        let cal = math_cal(&[Operator(Drop)]);
+        assert!(matches!(apply(&cal, 1.0), Err(ProcError::InvalidValue(_))));
+
+        assert!(matches!(inverse(&cal, 1.0), Err(ProcError::InvalidMdb(_))));
+    }
+
+    #[test]
+    fn test_flat_spline() {
+        let cal = spline_cal(0, false);
+        assert_eq!(10.0, apply(&cal, 0.0).unwrap());
+        assert_eq!(10.0, apply(&cal, 9.9).unwrap());
+        assert_eq!(20.0, apply(&cal, 10.0).unwrap());
+        assert_eq!(20.0, apply(&cal, 19.0).unwrap());
+        assert_eq!(0.0, apply(&cal, 20.0).unwrap());
+    }
+}
@@ -0,0 +1,416 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use crate::{
+    bitbuffer::ByteOrder,
+    mdb::{
+        types::{
+            BinaryDataEncoding, BinarySize, DataEncoding, FloatDataEncoding, FloatEncodingType,
+            IntegerDataEncoding, IntegerEncodingType, LeadingSize, StringBoxSize, StringDataEncoding,
+            StringSize,
+        },
+        Argument, CommandContainer, CommandEntryData, FixedValueEntry, MdbError, MetaCommandIdx,
+        MissionDatabase, NameIdx, NamedItem, ReferenceLocationType,
+    },
+    pvlist::ParameterValueList,
+    value::Value,
+};
+
+use super::{ProcError, ProcessorData, Result};
+
+/// Encodes the command with the given argument values (engineering values, given by argument name).
+///
+/// The arguments assigned by the command or its base commands (see [MissionDatabase::effective_arguments])
+/// take their assigned value and cannot be given in the argument list.
+/// The arguments not given in the list take their initial value, if defined. All the values are checked
+/// with [MissionDatabase::validate_argument] before encoding.
+///
+/// The command containers of the base commands are encoded first. Each entry is placed at its
+/// LocationInContainerInBits or, if not specified, just after the previous entry; the containerStart
+/// locations are relative to the beginning of the command. The bits not covered by any entry are set
+/// to the idle pattern of the container.
+pub fn encode_command(
+    mdb: &MissionDatabase,
+    pdata: &ProcessorData,
+    cmdidx: MetaCommandIdx,
+    args: &[(&str, Value)],
+) -> Result<Vec<u8>> {
+    let cmd = mdb.get_meta_command(cmdidx);
+    if cmd.abstract_ {
+        return Err(ProcError::InvalidValue(format!(
+            "Cannot encode the abstract command {}",
+            mdb.meta_command_qn(cmdidx)
+        )));
+    }
+
+    let mut values = HashMap::new();
+    for (name, value) in args {
+        let arg = mdb
+            .name_db_ref()
+            .get(name)
+            .and_then(|name| mdb.get_argument(cmdidx, name))
+            .ok_or_else(|| {
+                ProcError::InvalidValue(format!(
+                    "Unknown argument '{}' for command {}",
+                    name,
+                    mdb.meta_command_qn(cmdidx)
+                ))
+            })?;
+        values.insert(arg.name(), (arg, value));
+    }
+    for aa in mdb.argument_assignments(cmdidx) {
+        if values.contains_key(&aa.name) {
+            return Err(ProcError::InvalidValue(format!(
+                "The argument '{}' of command {} is assigned by the command definition and cannot be given a value",
+                mdb.name2str(aa.name),
+                mdb.meta_command_qn(cmdidx)
+            )));
+        }
+        // the assigned argument is defined by one of the base commands
+        let arg = mdb.get_argument(cmdidx, aa.name).unwrap();
+        values.insert(aa.name, (arg, &aa.value));
+    }
+    for arg in mdb.effective_arguments(cmdidx) {
+        if let (false, Some(v)) = (values.contains_key(&arg.name()), &arg.initial_value) {
+            values.insert(arg.name(), (arg, v));
+        }
+    }
+    for (arg, value) in values.values() {
+        mdb.validate_argument(arg, value).map_err(|e| match e {
+            MdbError::OutOfRange(msg) => ProcError::OutOfRange(msg),
+            MdbError::InvalidValue(msg) => ProcError::InvalidValue(msg),
+            e => e.into(),
+        })?;
+    }
+
+    // the root command comes last
+    let mut hierarchy = vec![cmdidx];
+    while let Some(base) = mdb.get_meta_command(*hierarchy.last().unwrap()).base_meta_command {
+        hierarchy.push(base);
+    }
+
+    let mut ctx = EncodeCtx { mdb, pdata, cmdidx, values, buf: CommandBuf::new() };
+    for idx in hierarchy.iter().rev() {
+        if let Some(container) = &mdb.get_meta_command(*idx).command_container {
+            ctx.encode_container(container)?;
+        }
+    }
+
+    Ok(ctx.buf.data)
+}
+
+struct EncodeCtx<'a> {
+    mdb: &'a MissionDatabase,
+    pdata: &'a ProcessorData,
+    cmdidx: MetaCommandIdx,
+    values: HashMap<NameIdx, (&'a Argument, &'a Value)>,
+    buf: CommandBuf,
+}
+
+impl EncodeCtx<'_> {
+    fn encode_container(&mut self, container: &CommandContainer) -> Result<()> {
+        self.buf.idle_pattern = container.idle_pattern;
+
+        for entry in &container.entries {
+            if let Some(lic) = &entry.location_in_container {
+                let pos = self.buf.position;
+                let newpos = match lic.reference_location {
+                    ReferenceLocationType::ContainerStart => lic.location_in_bits as i64,
+                    ReferenceLocationType::PreviousEntry => pos as i64 + lic.location_in_bits as i64,
+                };
+                if newpos < 0 {
+                    return Err(ProcError::OutOfBounds(format!(
+                        "Error when encoding command {}: invalid negative bit position {}",
+                        self.mdb.meta_command_qn(self.cmdidx),
+                        newpos
+                    )));
+                }
+                self.buf.position = newpos as usize;
+            }
+            match &entry.data {
+                CommandEntryData::ArgumentRef(name) => self.encode_argument(*name)?,
+                CommandEntryData::FixedValue(fv) => self.buf.put_fixed_value(fv),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn encode_argument(&mut self, name: NameIdx) -> Result<()> {
+        let mdb = self.mdb;
+        let (arg, value) = *self.values.get(&name).ok_or_else(|| {
+            ProcError::MissingValue(format!(
+                "No value for the argument {} of command {}",
+                mdb.name2str(name),
+                mdb.meta_command_qn(self.cmdidx)
+            ))
+        })?;
+        let dtype = mdb.get_data_type(arg.atype);
+        let raw_value = self.pdata.decalibrate(mdb, dtype, value, &ParameterValueList::new())?;
+
+        self.encode_raw(&dtype.encoding, &raw_value).map_err(|e| match e {
+            ProcError::InvalidValue(msg) => ProcError::InvalidValue(format!(
+                "Error encoding the argument {}: {}",
+                mdb.name2str(name),
+                msg
+            )),
+            _ => e,
+        })
+    }
+
+    fn encode_raw(&mut self, encoding: &DataEncoding, raw_value: &Value) -> Result<()> {
+        match (encoding, raw_value) {
+            (DataEncoding::Integer(ide), Value::Uint64(x)) => self.encode_integer(ide, *x as i128),
+            (DataEncoding::Integer(ide), Value::Int64(x)) => self.encode_integer(ide, *x as i128),
+            (DataEncoding::Float(fde), Value::Double(x)) => self.encode_float(fde, *x),
+            (DataEncoding::Boolean(bde), Value::Boolean(b)) => match bde.size_in_bits {
+                BinarySize::Fixed(n) if n <= 64 => {
+                    self.buf.put_bits(*b as u64, n as usize, ByteOrder::BigEndian);
+                    Ok(())
+                }
+                _ => Err(unsupported(encoding)),
+            },
+            (DataEncoding::String(sde), Value::StringValue(s)) => self.encode_string(sde, s),
+            (DataEncoding::Binary(bde), Value::Binary(b)) => self.encode_binary(bde, b),
+            _ => Err(ProcError::InvalidValue(format!(
+                "Cannot encode the raw value {:?} with {:?}",
+                raw_value, encoding
+            ))),
+        }
+    }
+
+    fn encode_integer(&mut self, ide: &IntegerDataEncoding, x: i128) -> Result<()> {
+        let numbits = ide.size_in_bits as usize;
+        if numbits > 64 {
+            return Err(ProcError::InvalidMdb(format!("Cannot encode integers on {} bits", numbits)));
+        }
+        let mask = if numbits == 64 { u64::MAX } else { (1 << numbits) - 1 };
+
+        // the range has been checked when computing the raw value
+        let bv = match ide.encoding {
+            IntegerEncodingType::Unsigned | IntegerEncodingType::TwosComplement => x as u64 & mask,
+            IntegerEncodingType::SignMagnitude if x < 0 => (1 << (numbits - 1)) | (-x) as u64,
+            IntegerEncodingType::OnesComplement if x < 0 => !((-x) as u64) & mask,
+            IntegerEncodingType::SignMagnitude | IntegerEncodingType::OnesComplement => x as u64,
+            IntegerEncodingType::Bcd => encode_bcd(x as u64, numbits, 8),
+            IntegerEncodingType::PackedBcd => encode_bcd(x as u64, numbits, 4),
+        };
+        self.buf.put_bits(bv, numbits, ide.byte_order);
+
+        Ok(())
+    }
+
+    fn encode_float(&mut self, fde: &FloatDataEncoding, x: f64) -> Result<()> {
+        let bv = match (fde.encoding, fde.size_in_bits) {
+            (FloatEncodingType::IEEE754_1985, 32) => (x as f32).to_bits() as u64,
+            (FloatEncodingType::IEEE754_1985, 64) => x.to_bits(),
+            _ => return Err(unsupported(fde)),
+        };
+        self.buf.put_bits(bv, fde.size_in_bits as usize, fde.byte_order);
+
+        Ok(())
+    }
+
+    fn encode_string(&mut self, sde: &StringDataEncoding, s: &str) -> Result<()> {
+        let b = match sde.encoding.as_str() {
+            "UTF-8" => s.as_bytes(),
+            "US-ASCII" if s.is_ascii() => s.as_bytes(),
+            "US-ASCII" => {
+                return Err(ProcError::InvalidValue(format!("The string '{}' is not US-ASCII", s)));
+            }
+            _ => {
+                return Err(ProcError::InvalidValue(format!(
+                    "Encoding of {} strings not supported",
+                    sde.encoding
+                )));
+            }
+        };
+
+        let start = self.buf.position;
+        match &sde.size_in_bits {
+            StringSize::Fixed(n) => {
+                if 8 * b.len() > *n as usize {
+                    return Err(too_long(b.len(), *n as usize));
+                }
+                self.buf.put_bytes(b);
+                // the remaining bits are filled with zeros
+                self.buf.put_zeros(*n as usize - 8 * b.len());
+            }
+            StringSize::TerminationChar(term) => {
+                self.buf.put_bytes(b);
+                self.buf.put_bytes(term);
+            }
+            StringSize::LeadingSize(ls) => {
+                self.put_size_tag(ls, b.len())?;
+                self.buf.put_bytes(b);
+            }
+            StringSize::Custom => return Err(unsupported(sde)),
+        }
+
+        match &sde.box_size_in_bits {
+            StringBoxSize::Undefined => {}
+            StringBoxSize::Fixed(n) => {
+                let used = self.buf.position - start;
+                if used > *n as usize {
+                    return Err(too_long(used / 8, *n as usize));
+                }
+                self.buf.put_zeros(*n as usize - used);
+            }
+            StringBoxSize::Dynamic(_) => {
+                return Err(ProcError::InvalidValue(
+                    "Encoding of the strings with a dynamic box size not supported".to_owned(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn encode_binary(&mut self, bde: &BinaryDataEncoding, b: &[u8]) -> Result<()> {
+        if let Some(algo) = &bde.to_binary_transform_algorithm {
+            let executor = self.pdata.get_algorithm_executor().ok_or_else(|| {
+                ProcError::InvalidValue(format!(
+                    "Cannot execute the ToBinaryTransformAlgorithm {}: no algorithm executor set",
+                    algo.name
+                ))
+            })?;
+            let outputs = executor.execute(
+                &algo.name,
+                algo.algorithm_text.as_ref(),
+                &[Value::Binary(Box::new(b.to_vec()))],
+            )?;
+            return match outputs.first() {
+                Some(Value::Binary(out)) => {
+                    self.buf.put_bytes(out);
+                    Ok(())
+                }
+                other => Err(ProcError::InvalidValue(format!(
+                    "The ToBinaryTransformAlgorithm {} returned {:?} instead of binary data",
+                    algo.name, other
+                ))),
+            };
+        }
+
+        match &bde.size_in_bits {
+            BinarySize::Fixed(n) => {
+                if 8 * b.len() != *n as usize {
+                    return Err(ProcError::InvalidValue(format!(
+                        "The binary value has {} bytes, the encoding requires {} bits",
+                        b.len(),
+                        n
+                    )));
+                }
+                self.buf.put_bytes(b);
+            }
+            BinarySize::LeadingSize(ls) => {
+                self.put_size_tag(ls, b.len())?;
+                self.buf.put_bytes(b);
+            }
+            BinarySize::Dynamic(_) | BinarySize::Algorithm => {
+                return Err(ProcError::InvalidValue(
+                    "Encoding of the binary data with a dynamic size not supported".to_owned(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn put_size_tag(&mut self, ls: &LeadingSize, size: usize) -> Result<()> {
+        let numbits = ls.tag_size_in_bits as usize;
+        if numbits < 64 && size >> numbits != 0 {
+            return Err(ProcError::OutOfRange(format!(
+                "The size {} does not fit into the {} bits size tag",
+                size, numbits
+            )));
+        }
+        self.buf.put_bits(size as u64, numbits, ls.byte_order);
+        Ok(())
+    }
+}
+
+fn unsupported(encoding: &impl Debug) -> ProcError {
+    ProcError::InvalidValue(format!("Encoding with {:?} not supported", encoding))
+}
+
+fn too_long(num_bytes: usize, max_bits: usize) -> ProcError {
+    ProcError::OutOfRange(format!(
+        "The string has {} bytes, it does not fit into {} bits",
+        num_bytes, max_bits
+    ))
+}
+
+// the digits of x each on digit_size bits (8 for BCD, 4 for packed BCD), the most significant first
+fn encode_bcd(mut x: u64, numbits: usize, digit_size: usize) -> u64 {
+    let mut bv = 0;
+    for i in 0..numbits / digit_size {
+        bv |= (x % 10) << (i * digit_size);
+        x /= 10;
+    }
+    bv
+}
+
+/// The binary command being built. The bytes are added as the entries are written, filled with the
+/// idle pattern of the container being encoded.
+struct CommandBuf {
+    data: Vec<u8>,
+    // bit position of the next entry
+    position: usize,
+    idle_pattern: u8,
+}
+
+impl CommandBuf {
+    fn new() -> CommandBuf {
+        CommandBuf { data: Vec::new(), position: 0, idle_pattern: 0 }
+    }
+
+    // writes the n rightmost bits of x; the bit order follows the BitBuffer
+    fn put_bits(&mut self, x: u64, n: usize, byte_order: ByteOrder) {
+        for i in 0..n {
+            let (pos, mask) = match byte_order {
+                ByteOrder::BigEndian => {
+                    let pos = self.position + n - 1 - i;
+                    (pos, 0x80 >> (pos & 7))
+                }
+                ByteOrder::LittleEndian => {
+                    let pos = self.position + i;
+                    (pos, 1 << (pos & 7))
+                }
+            };
+            self.set_bit(pos, mask, (x >> i) & 1 == 1);
+        }
+        self.position += n;
+    }
+
+    fn put_bytes(&mut self, b: &[u8]) {
+        for x in b {
+            self.put_bits(*x as u64, 8, ByteOrder::BigEndian);
+        }
+    }
+
+    fn put_zeros(&mut self, n: usize) {
+        for _ in 0..n {
+            self.put_bits(0, 1, ByteOrder::BigEndian);
+        }
+    }
+
+    // the value is right aligned in the binary value
+    fn put_fixed_value(&mut self, fv: &FixedValueEntry) {
+        let offset = 8 * fv.binary_value.len() - fv.size_in_bits as usize;
+        for i in offset..8 * fv.binary_value.len() {
+            let bit = (fv.binary_value[i / 8] >> (7 - (i & 7))) & 1;
+            self.put_bits(bit as u64, 1, ByteOrder::BigEndian);
+        }
+    }
+
+    fn set_bit(&mut self, pos: usize, mask: u8, bit: bool) {
+        let byte_pos = pos / 8;
+        if byte_pos >= self.data.len() {
+            self.data.resize(byte_pos + 1, self.idle_pattern);
+        }
+        if bit {
+            self.data[byte_pos] |= mask;
+        } else {
+            self.data[byte_pos] &= !mask;
+        }
+    }
+}
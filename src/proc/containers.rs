@@ -1,46 +1,202 @@
+use std::{borrow::Cow, cell::RefCell, collections::HashMap};
+
 use crate::{
+    bitbuffer::ByteOrder,
     mdb::{
-        ContainerEntryData, ContainerIdx, MissionDatabase, NamedItem, ParameterIdx,
-        ReferenceLocationType, SequenceContainer,
+        types::{BinaryDataEncoding, Crc, DataEncoding},
+        ContainerEntry, ContainerEntryData, ContainerIdx, ContainerSegmentRefEntry, IntegerValue, MdbError,
+        MissionDatabase, NamedItem, ParameterIdx, ParameterSegmentRefEntry, ReferenceLocationType, RepeatEntry,
+        SequenceContainer,
     },
     proc::criteria_evaluator::MatchResult,
     pvlist::ParameterValueList,
-    value::ParameterValue,
+    value::{ErrorCheck, ParameterValue, Value},
 };
 
-use super::{types, ContainerBuf, ProcCtx, ProcessorData, Result, ProcError};
+use super::{crc, types, ContainerBuf, ProcCtx, ProcessorData, Result, ProcError, SegmentBuf, SegmentTarget};
 
 //1GB that should be plenty enough
 const MAX_PACKET_SIZE: usize = (u32::MAX / 4) as usize;
 
+/// Processes the root container.
+///
+/// The processor data is created for each call. When processing a stream of packets, it is better to
+/// create it once and use [`process_with`].
 pub fn process(
     mdb: &MissionDatabase,
     packet: &[u8],
     root_container: ContainerIdx,
 ) -> Result<ParameterValueList> {
-    if packet.len() > MAX_PACKET_SIZE {
-        panic!("Packet too long. max size is {}", MAX_PACKET_SIZE)
+    process_at(mdb, packet, root_container, 0)
+}
+
+/// Processes the root container given by its fully qualified name (e.g. /YSS/SIMULATOR/DHS).
+///
+/// Returns an InvalidName MdbError if no container with that name exists.
+pub fn process_from_name(
+    mdb: &MissionDatabase,
+    packet: &[u8],
+    root_container: &str,
+) -> Result<ParameterValueList> {
+    let cidx = mdb.search_container(root_container).ok_or_else(|| {
+        ProcError::Mdb(MdbError::InvalidName(format!("Cannot find the container {}", root_container)))
+    })?;
+    process(mdb, packet, cidx)
+}
+
+/// Processes the root container using the given processor data, for example with custom decoders registered.
+///
+/// The processor data has to be created from the same MDB.
+pub fn process_with(
+    mdb: &MissionDatabase,
+    pdata: &ProcessorData,
+    packet: &[u8],
+    root_container: ContainerIdx,
+) -> Result<ParameterValueList> {
+    process_packet(mdb, pdata, packet, root_container, 0)
+}
+
+/// Processes each of the packets with the root container, returning the results in the order of the packets.
+///
+/// With the `parallel` feature, the packets are processed in parallel on the rayon thread pool, all of them
+/// sharing the processor data; otherwise they are processed one after the other.
+pub fn process_packets<P: AsRef<[u8]> + Sync>(
+    mdb: &MissionDatabase,
+    pdata: &ProcessorData,
+    packets: &[P],
+    root_container: ContainerIdx,
+) -> Vec<Result<ParameterValueList>> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        packets.par_iter().map(|packet| process_with(mdb, pdata, packet.as_ref(), root_container)).collect()
     }
-    let container = mdb.get_container(root_container);
+    #[cfg(not(feature = "parallel"))]
+    {
+        packets.iter().map(|packet| process_with(mdb, pdata, packet.as_ref(), root_container)).collect()
+    }
+}
+
+/// Processes the root container starting at the given bit position in the packet.
+///
+/// This allows to decode a container after a header which has been processed separately, without copying the data.
+/// The references to the container start are relative to `start_bit`. The reported positions have the `start_offset`
+/// set to `start_bit / 8` and their bit offsets relative to it, such that they locate the values in the packet.
+///
+/// Only byte aligned start positions are supported: an InvalidValue error is returned if `start_bit` is not a multiple
+/// of 8 and an OutOfBounds error if it is after the end of the packet.
+pub fn process_at(
+    mdb: &MissionDatabase,
+    packet: &[u8],
+    root_container: ContainerIdx,
+    start_bit: usize,
+) -> Result<ParameterValueList> {
+    if start_bit & 7 != 0 {
+        return Err(ProcError::InvalidValue(format!(
+            "The start bit position {} is not at a byte boundary",
+            start_bit
+        )));
+    }
+    if start_bit > packet.len() * 8 {
+        return Err(ProcError::OutOfBounds(format!(
+            "The start bit position {} is outside the packet (size in bits: {})",
+            start_bit,
+            packet.len() * 8
+        )));
+    }
+    let pdata = ProcessorData::new(mdb)?;
+    process_packet(mdb, &pdata, packet, root_container, start_bit)
+}
 
-    let mut pdata = ProcessorData::new(mdb)?;
-    let cbuf = ContainerBuf::new(packet);
-    let mut ctx = ProcCtx { mdb, pdata: &mut pdata, cbuf, result: ParameterValueList::new(), pidx: None };
-    extract_container(&mut ctx, container)?;
+fn process_packet(
+    mdb: &MissionDatabase,
+    pdata: &ProcessorData,
+    packet: &[u8],
+    root_container: ContainerIdx,
+    start_bit: usize,
+) -> Result<ParameterValueList> {
+    let mut ctx = packet_ctx(mdb, pdata, packet, start_bit);
+    extract_container(&mut ctx, mdb.get_container(root_container))?;
+    check_segments_complete(&ctx)?;
 
-    Ok(ctx.result)
+    Ok(ctx.result.into_owned())
+}
+
+fn packet_ctx<'a, 'b, 'c>(
+    mdb: &'a MissionDatabase,
+    pdata: &'b ProcessorData,
+    packet: &'c [u8],
+    start_bit: usize,
+) -> ProcCtx<'a, 'b, 'c> {
+    if packet.len() > MAX_PACKET_SIZE {
+        panic!("Packet too long. max size is {}", MAX_PACKET_SIZE)
+    }
+    ProcCtx {
+        mdb,
+        pdata,
+        cbuf: ContainerBuf::new_at(packet, (start_bit / 8) as u32),
+        result: Cow::Owned(ParameterValueList::new()),
+        pidx: None,
+        segments: HashMap::new(),
+        criteria_cache: RefCell::new(HashMap::new()),
+        record_positions: true,
+        container_refs: Vec::new(),
+    }
 }
 
 fn extract_container(ctx: &mut ProcCtx, container: &SequenceContainer) -> Result<()> {
     let mdb = ctx.mdb();
-    log::debug!("Extracting container {}", mdb.name2str(container.name()));
+    log::debug!("Extracting container {}", mdb.container_qn(container.idx));
+
+    extract_entries(ctx, container)?;
+
+    if let Some(children) = mdb.child_containers.get(&container.idx) {
+        // all the matching children are extracted starting from the end of this container
+        let start = ctx.cbuf.get_position();
+        let mut end = start;
+        for c in children {
+            let child = mdb.get_container(*c);
+
+            //unwrap is ok becasue the child has to have the base_container set to its parent
+            let mcidx = child.base_container.unwrap().1;
+            let match_res = match mcidx {
+                Some(mcidx) => ctx.evaluate_criteria(mcidx),
+                //no match criteria means it always matches
+                None => MatchResult::OK,
+            };
+
+            let log_level = match match_res {
+                MatchResult::NOK | MatchResult::OK => log::Level::Trace,
+                MatchResult::UNDEF => log::Level::Info,
+                MatchResult::ERROR => log::Level::Warn,
+            };
+            log::log!(log_level,
+                "Match result for {} -> {} inheritance: {:?}",
+                mdb.container_qn(container.idx),
+                mdb.container_qn(child.idx),
+                match_res
+            );
+    
+            if match_res == MatchResult::OK
+                || (match_res == MatchResult::UNDEF && ctx.pdata.extract_undef_subcontainers)
+            {
+                ctx.cbuf.set_position(start);
+                extract_container(ctx, child)?;
+                end = end.max(ctx.cbuf.get_position());
+            }
+        }
+        ctx.cbuf.set_position(end);
+    }
 
-    //let pdata: &mut ProcessorData = &mut ctx.pdata;
+    Ok(())
+}
 
+// extracts the entries of the container without looking at the inheriting containers
+fn extract_entries(ctx: &mut ProcCtx, container: &SequenceContainer) -> Result<()> {
     for entry in &container.entries {
         if let Some(mcidx) = &entry.include_condition {
-            let evaluator = ctx.pdata.get_criteria_evaluator(*mcidx);
-            if evaluator.evaluate(ctx) != MatchResult::OK {
+            if ctx.evaluate_criteria(*mcidx) != MatchResult::OK {
                 continue;
             }
         }
@@ -60,57 +216,243 @@ fn extract_container(ctx: &mut ProcCtx, container: &SequenceContainer) -> Result
             }
             cbuf.set_position(newpos as usize)
         }
-        extract_entry(&entry.data, ctx)?;
+        match &entry.repeat {
+            Some(repeat) => extract_repeated_entry(ctx, container, entry, repeat)?,
+            None => extract_entry(&entry.data, ctx)?,
+        }
     }
 
-    if let Some(children) = mdb.child_containers.get(&container.idx) {
-        for c in children {
-            let child = mdb.get_container(*c);
+    Ok(())
+}
 
-            //unwrap is ok becasue the child has to have the base_container set to its parent
-            let mcidx = child.base_container.unwrap().1;
-            let match_res = match mcidx {
-                Some(mcidx) => {
-                    let evaluator = ctx.pdata.get_criteria_evaluator(mcidx);
-                    evaluator.evaluate(ctx)
-                }
-                //no match criteria means it always matches
-                None => MatchResult::OK,
-            };
+// a repetition starting at the same position as the previous one would extract the same data again,
+// for a large or dynamic count the extraction would never finish so this is reported as an mdb error
+fn extract_repeated_entry(
+    ctx: &mut ProcCtx,
+    container: &SequenceContainer,
+    entry: &ContainerEntry,
+    repeat: &RepeatEntry,
+) -> Result<()> {
+    let count = match &repeat.count {
+        IntegerValue::FixedValue(v) => u64::try_from(*v).map_err(|_| {
+            ProcError::Mdb(MdbError::InvalidMdb(format!(
+                "Invalid negative repeat count {} in container {}",
+                v,
+                ctx.mdb.container_qn(container.idx)
+            )))
+        })?,
+        IntegerValue::DynamicValue(dv) => ctx.get_dynamic_uint_value(dv)?,
+    };
 
-            let log_level = match match_res {
-                MatchResult::NOK | MatchResult::OK => log::Level::Trace,
-                MatchResult::UNDEF => log::Level::Info,
-                MatchResult::ERROR => log::Level::Warn,
-            };
-            log::log!(log_level,
-                "Match result for {} -> {} inheritance: {:?}",
-                mdb.name2str(container.name()),
-                mdb.name2str(child.name()),
-                match_res
-            );
-    
-            if match_res == MatchResult::OK {
-                extract_container(ctx, child)?;
+    let mut prev_start = None;
+    for i in 0..count {
+        if i > 0 && repeat.offset_in_bits > 0 {
+            let newpos = ctx.cbuf.get_position() + repeat.offset_in_bits as usize;
+            if newpos > ctx.cbuf.bitsize() {
+                return Err(ProcError::OutOfBounds(format!(
+                    "Error when extracting repeated entry from container {}. Bit position {} is outside the container (size in bits: {})",
+                    ctx.mdb.container_qn(container.idx),
+                    newpos,
+                    ctx.cbuf.bitsize()
+                )));
             }
+            ctx.cbuf.set_position(newpos);
+        }
+
+        let start = ctx.cbuf.get_position();
+        if prev_start == Some(start) {
+            return Err(ProcError::Mdb(MdbError::InvalidMdb(format!(
+                "The repeated entry of container {} does not advance the position (bit {}), aborting after {} of {} repetitions",
+                ctx.mdb.container_qn(container.idx),
+                start,
+                i,
+                count
+            ))));
         }
+        prev_start = Some(start);
+
+        extract_entry(&entry.data, ctx)?;
     }
- 
+
     Ok(())
 }
 
 fn extract_entry<'a, 'b>(entry: &'a ContainerEntryData, ctx: &mut ProcCtx) -> Result<()> {
     match *entry {
         ContainerEntryData::ParameterRef(pidx) => extract_parameter(pidx, ctx)?,
-        ContainerEntryData::ContainerRef(_) => todo!(),
+        ContainerEntryData::ContainerRef(cidx) => {
+            let mdb = ctx.mdb();
+            log::debug!("Extracting container entry {}", mdb.container_qn(cidx));
+            // a container referencing itself, directly or through other containers, would be extracted without end
+            if let Some(pos) = ctx.container_refs.iter().position(|c| *c == cidx) {
+                let names: Vec<String> =
+                    ctx.container_refs[pos..].iter().chain([&cidx]).map(|c| mdb.container_qn(*c)).collect();
+                return Err(ProcError::InvalidMdb(format!("Cyclic container reference: {}", names.join(" -> "))));
+            }
+            ctx.container_refs.push(cidx);
+            let res = extract_entries(ctx, mdb.get_container(cidx));
+            ctx.container_refs.pop();
+            res?
+        }
         ContainerEntryData::IndirectParameterRef(_) => todo!(),
         ContainerEntryData::ArrayParameterRef(_) => todo!(),
+        ContainerEntryData::ParameterSegmentRef(ref seg) => extract_parameter_segment(seg, ctx)?,
+        ContainerEntryData::ContainerSegmentRef(ref seg) => extract_container_segment(seg, ctx)?,
     };
 
     Ok(())
 }
 
+// the parameter raw value is decoded once all the segments have been collected
+fn extract_parameter_segment(seg: &ParameterSegmentRefEntry, ctx: &mut ProcCtx) -> Result<()> {
+    let mdb = ctx.mdb();
+    let pname = || format!("parameter {}", mdb.parameter_qn(seg.pidx));
+
+    let size_in_bits = mdb
+        .get_parameter(seg.pidx)
+        .ptype
+        .and_then(|ptype| mdb.get_data_type(ptype).size_in_bits(mdb))
+        .ok_or_else(|| {
+            ProcError::DecodingError(format!("Cannot extract the segments of {}: its size is not fixed", pname()))
+        })?;
+
+    let target = SegmentTarget::Parameter(seg.pidx);
+    if let Some(sbuf) = collect_segment(ctx, target, seg.order, seg.size_in_bits, size_in_bits as usize, pname)? {
+        let pv = {
+            let mut sctx = ProcCtx {
+                mdb,
+                pdata: ctx.pdata,
+                cbuf: ContainerBuf::new(&sbuf.data),
+                result: Cow::Borrowed(&*ctx.result),
+                pidx: None,
+                segments: HashMap::new(),
+                criteria_cache: RefCell::new(HashMap::new()),
+                record_positions: false,
+                container_refs: Vec::new(),
+            };
+            // the CRC of a segmented parameter covers the packet data preceding its first segment
+            let crc_data = &ctx.cbuf.buf.data()[..sbuf.start_position / 8];
+            decode_parameter(seg.pidx, &mut sctx, Some(crc_data))?
+        };
+        ctx.add_value(pv);
+    }
+
+    Ok(())
+}
+
+// the container entries are extracted once all the segments have been collected
+fn extract_container_segment(seg: &ContainerSegmentRefEntry, ctx: &mut ProcCtx) -> Result<()> {
+    let mdb = ctx.mdb();
+    let container = mdb.get_container(seg.cidx);
+    let cname = || format!("container {}", mdb.container_qn(seg.cidx));
+
+    let size_in_bits = container.size_in_bits.ok_or_else(|| {
+        ProcError::DecodingError(format!("Cannot extract the segments of {}: its size is not fixed", cname()))
+    })?;
+
+    let target = SegmentTarget::Container(seg.cidx);
+    if let Some(sbuf) = collect_segment(ctx, target, seg.order, seg.size_in_bits, size_in_bits as usize, cname)? {
+        // the values extracted from the segments are added to the same result
+        let result = std::mem::replace(&mut ctx.result, Cow::Owned(ParameterValueList::new())).into_owned();
+        let mut sctx = ProcCtx {
+            mdb,
+            pdata: ctx.pdata,
+            cbuf: ContainerBuf::new(&sbuf.data),
+            result: Cow::Owned(result),
+            pidx: None,
+            segments: HashMap::new(),
+            criteria_cache: RefCell::new(HashMap::new()),
+            record_positions: false,
+            container_refs: ctx.container_refs.clone(),
+        };
+        let res = extract_entries(&mut sctx, container).and_then(|_| check_segments_complete(&sctx));
+        ctx.result = Cow::Owned(sctx.result.into_owned());
+        ctx.criteria_cache.get_mut().clear();
+        res?;
+    }
+
+    Ok(())
+}
+
+// reads the bits of the segment and adds them to the ones already collected for the target.
+// returns the buffer with all the bits once the last segment has been read
+fn collect_segment(
+    ctx: &mut ProcCtx,
+    target: SegmentTarget,
+    order: Option<u32>,
+    segment_size_in_bits: u32,
+    size_in_bits: usize,
+    target_name: impl Fn() -> String,
+) -> Result<Option<SegmentBuf>> {
+    let position = ctx.cbuf.buf.get_position();
+    let sbuf = ctx.segments.entry(target).or_insert_with(|| SegmentBuf::new(size_in_bits, position));
+
+    if let Some(order) = order {
+        // the first segment has order 1
+        let expected = sbuf.last_order.map_or(1, |last| last + 1);
+        if order != expected {
+            ctx.segments.remove(&target);
+            return Err(ProcError::DecodingError(format!(
+                "Segment {} of {} is out of order, expected segment {}",
+                order,
+                target_name(),
+                expected
+            )));
+        }
+        sbuf.last_order = Some(order);
+    }
+
+    let mut n = segment_size_in_bits as usize;
+    if sbuf.num_bits + n > sbuf.size_in_bits {
+        let msg = format!(
+            "The segments of {} exceed its size of {} bits",
+            target_name(),
+            sbuf.size_in_bits
+        );
+        ctx.segments.remove(&target);
+        return Err(ProcError::DecodingError(msg));
+    }
+    // the bits are copied in the order they appear in the packet, whatever the byte order of the previous entry
+    ctx.cbuf.buf.set_byte_order(ByteOrder::BigEndian);
+    while n > 0 {
+        let k = n.min(64);
+        sbuf.push_bits(ctx.cbuf.get_bits(k)?, k);
+        n -= k;
+    }
+
+    if sbuf.is_complete() {
+        Ok(ctx.segments.remove(&target))
+    } else {
+        Ok(None)
+    }
+}
+
+// returns an error if there are parameters or containers for which not all the segments have been extracted
+fn check_segments_complete(ctx: &ProcCtx) -> Result<()> {
+    if let Some((target, sbuf)) = ctx.segments.iter().next() {
+        let name = match target {
+            SegmentTarget::Parameter(pidx) => format!("parameter {}", ctx.mdb.parameter_qn(*pidx)),
+            SegmentTarget::Container(cidx) => format!("container {}", ctx.mdb.container_qn(*cidx)),
+        };
+        return Err(ProcError::DecodingError(format!(
+            "Missing segments of {}: only {} out of {} bits have been extracted",
+            name, sbuf.num_bits, sbuf.size_in_bits
+        )));
+    }
+    Ok(())
+}
+
 fn extract_parameter(pidx: ParameterIdx, ctx: &mut ProcCtx) -> Result<()> {
+    let pv = decode_parameter(pidx, ctx, None)?;
+    ctx.add_value(pv);
+
+    Ok(())
+}
+
+// extracts and calibrates the parameter value from the current position
+//
+// the CRC is computed over crc_data if given, otherwise over the data of the container preceding the parameter
+fn decode_parameter(pidx: ParameterIdx, ctx: &mut ProcCtx, crc_data: Option<&[u8]>) -> Result<ParameterValue> {
     ctx.pidx.replace(pidx);
     let mdb = ctx.mdb();
     let param = mdb.get_parameter(pidx);
@@ -121,13 +463,46 @@ fn extract_parameter(pidx: ParameterIdx, ctx: &mut ProcCtx) -> Result<()> {
     )))?;
     let dtype = mdb.get_data_type(ptype_idx);
 
-    let (raw_value, cpos) = types::extract(dtype, ctx)?;
+    // the short packets are reported with the name of the parameter which could not be extracted
+    let (raw_value, cpos) = types::extract(dtype, ctx).map_err(|e| match e {
+        ProcError::OutOfBounds(msg) => ProcError::OutOfBounds(format!(
+            "Error decoding parameter {}: {}",
+            mdb.name2str(param.name()),
+            msg
+        )),
+        e => e,
+    })?;
     let eng_value = types::calibrate(&raw_value, dtype, ctx)?;
 
-    let pv = ParameterValue { pidx, raw_value, eng_value };
-
-    ctx.result.push(pv);
+    let error_check = match &dtype.encoding {
+        DataEncoding::Binary(BinaryDataEncoding { crc: Some(crc), .. }) => {
+            let data = crc_data.unwrap_or_else(|| &ctx.cbuf.buf.data()[..(cpos.bit_offset / 8) as usize]);
+            Some(verify_crc(crc, &raw_value, data, ctx)?)
+        }
+        _ => None,
+    };
     ctx.pidx.take();
 
-    Ok(())
+    let position = ctx.record_positions.then_some(cpos);
+
+    Ok(ParameterValue { pidx, raw_value, eng_value, error_check, position })
+}
+
+// computes the CRC of the data preceding the parameter and compares it with the extracted value
+fn verify_crc(crc: &Crc, raw_value: &Value, data: &[u8], ctx: &ProcCtx) -> Result<ErrorCheck> {
+    let Value::Binary(v) = raw_value else {
+        return Err(ctx.decoding_error("the CRC raw value is not binary"));
+    };
+    let start = (crc.bits_from_reference / 8) as usize;
+    let end = data.len();
+    if start > end {
+        return Err(ctx.decoding_error(&format!(
+            "the CRC computation start {} is after the CRC position {}",
+            start, end
+        )));
+    }
+
+    let found = v.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+
+    Ok(crc::check(crc, &data[start..], found))
 }
@@ -0,0 +1,158 @@
+use std::ops::Range;
+
+use crate::{mdb::types::Crc, value::ErrorCheck};
+
+use super::{ProcError, Result};
+
+/// the CRC-16-CCITT used as packet error control by the CCSDS
+pub const CRC16_CCITT: Crc = Crc {
+    width: 16,
+    polynomial: 0x1021,
+    init_remainder: 0xFFFF,
+    final_xor: 0,
+    reflect_data: false,
+    reflect_remainder: false,
+    bits_from_reference: 0,
+};
+
+/// computes the CRC of the data according to the definition
+///
+/// This is the generic bit by bit algorithm supporting any polynomial of 8 to 64 bits. For example
+/// the CRC-16-CCITT is width 16, polynomial 0x1021, init 0xFFFF and the CRC-32 is width 32,
+/// polynomial 0x04C11DB7, init and final xor 0xFFFFFFFF with the data and the remainder reflected.
+///
+/// Returns an InvalidValue error if the width is not a multiple of 8 from 8 to 64.
+pub fn compute(crc: &Crc, data: &[u8]) -> Result<u64> {
+    check_width(crc)?;
+    Ok(compute_unchecked(crc, data))
+}
+
+// the width has to be checked by the caller
+fn compute_unchecked(crc: &Crc, data: &[u8]) -> u64 {
+    let width = crc.width;
+    let top_bit = 1u64 << (width - 1);
+    let mask = if width == 64 { u64::MAX } else { (1u64 << width) - 1 };
+
+    let mut r = crc.init_remainder & mask;
+    for &b in data {
+        let b = if crc.reflect_data { b.reverse_bits() } else { b };
+        r ^= (b as u64) << (width - 8);
+        for _ in 0..8 {
+            r = if r & top_bit != 0 { (r << 1) ^ crc.polynomial } else { r << 1 };
+        }
+        r &= mask;
+    }
+
+    if crc.reflect_remainder {
+        r = r.reverse_bits() >> (64 - width);
+    }
+    (r ^ crc.final_xor) & mask
+}
+
+/// computes the CRC-16-CCITT of the data, see [CRC16_CCITT]
+pub fn crc_ccitt(data: &[u8]) -> u16 {
+    compute_unchecked(&CRC16_CCITT, data) as u16
+}
+
+/// computes the CRC of the given byte range of the packet and compares it with the big endian code found at
+/// crc_offset, the code being width / 8 bytes long.
+///
+/// Returns an InvalidValue error if the width is not a multiple of 8 from 8 to 64 and an OutOfBounds error if
+/// the range or the code do not fit into the packet.
+pub fn verify_range(crc: &Crc, packet: &[u8], range: Range<usize>, crc_offset: usize) -> Result<ErrorCheck> {
+    check_width(crc)?;
+    let crc_end = match crc_offset.checked_add((crc.width / 8) as usize) {
+        Some(end) if end <= packet.len() && range.start <= range.end && range.end <= packet.len() => end,
+        _ => {
+            return Err(ProcError::OutOfBounds(format!(
+                "cannot verify the CRC of the bytes {:?} with the {} bytes code at {}, the packet has {} bytes",
+                range,
+                crc.width / 8,
+                crc_offset,
+                packet.len()
+            )))
+        }
+    };
+    let found = packet[crc_offset..crc_end].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+
+    Ok(check(crc, &packet[range], found))
+}
+
+// checked by the parser for the CRCs of the mdb
+fn check_width(crc: &Crc) -> Result<()> {
+    if !(8..=64).contains(&crc.width) || !crc.width.is_multiple_of(8) {
+        return Err(ProcError::InvalidValue(format!(
+            "Invalid CRC width {}; only multiples of 8 up to 64 are supported",
+            crc.width
+        )));
+    }
+    Ok(())
+}
+
+/// compares the CRC computed over the data with the one found in the packet.
+/// The width of the CRC has to be valid, as for the CRCs of the mdb.
+pub(crate) fn check(crc: &Crc, data: &[u8], found: u64) -> ErrorCheck {
+    let computed = compute_unchecked(crc, data);
+    if computed == found {
+        ErrorCheck::Passed
+    } else {
+        ErrorCheck::Failed { computed }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crc(width: u32, polynomial: u64, init_remainder: u64, final_xor: u64, reflect: bool) -> Crc {
+        Crc {
+            width,
+            polynomial,
+            init_remainder,
+            final_xor,
+            reflect_data: reflect,
+            reflect_remainder: reflect,
+            bits_from_reference: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_values() {
+        // the check value of a CRC is the CRC of the ASCII string "123456789"
+        let data = b"123456789";
+        assert_eq!(0x29B1, compute(&crc(16, 0x1021, 0xFFFF, 0, false), data).unwrap());
+        assert_eq!(0x2189, compute(&crc(16, 0x1021, 0, 0, true), data).unwrap());
+        assert_eq!(0xCBF43926, compute(&crc(32, 0x04C11DB7, 0xFFFFFFFF, 0xFFFFFFFF, true), data).unwrap());
+        assert_eq!(0xF4, compute(&crc(8, 0x07, 0, 0, false), data).unwrap());
+        assert_eq!(
+            0x995DC9BBDF1939FA,
+            compute(&crc(64, 0x42F0E1EBA9EA3693, u64::MAX, u64::MAX, true), data).unwrap()
+        );
+        assert_eq!(0x29B1, crc_ccitt(data));
+    }
+
+    #[test]
+    fn test_verify_range() {
+        // two bytes header, one byte data and the CRC of the header and data
+        let packet = [0x0A, 0x00, 0x01, 0x1B, 0x7C];
+        assert_eq!(ErrorCheck::Passed, verify_range(&CRC16_CCITT, &packet, 0..3, 3).unwrap());
+        // the CRC of the data alone
+        assert!(matches!(verify_range(&CRC16_CCITT, &packet, 2..3, 3), Ok(ErrorCheck::Failed { .. })));
+
+        let corrupted = [0x0A, 0x00, 0x02, 0x1B, 0x7C];
+        assert_eq!(
+            ErrorCheck::Failed { computed: crc_ccitt(&corrupted[..3]) as u64 },
+            verify_range(&CRC16_CCITT, &corrupted, 0..3, 3).unwrap()
+        );
+
+        assert!(matches!(verify_range(&CRC16_CCITT, &packet, 0..3, 4), Err(ProcError::OutOfBounds(_))));
+        assert!(matches!(verify_range(&CRC16_CCITT, &packet, 0..6, 3), Err(ProcError::OutOfBounds(_))));
+        assert!(matches!(verify_range(&CRC16_CCITT, &packet, 0..3, usize::MAX), Err(ProcError::OutOfBounds(_))));
+
+        for width in [0, 12, 72] {
+            let crc = Crc { width, ..CRC16_CCITT };
+            assert!(matches!(verify_range(&crc, &packet, 0..3, 3), Err(ProcError::InvalidValue(_))));
+            assert!(matches!(compute(&crc, &packet), Err(ProcError::InvalidValue(_))));
+        }
+    }
+}
@@ -2,15 +2,18 @@ use std::mem::discriminant;
 
 use crate::{
     mdb::{
-        debug::MdbItemDebug, utils::get_member_type, Comparison, ComparisonOperator,
-        MissionDatabase, NamedItem, ParameterInstanceRef,
+        debug::MdbItemDebug,
+        types::TypeData,
+        utils::{get_member_type, parse_integer_text},
+        BooleanExpression, Comparison, ComparisonOperator, CustomAlgorithm, MissionDatabase, NamedItem,
+        ParameterInstanceRef,
     },
     value::Value, proc::ProcError
 };
 
-use super::{ProcCtx, Result};
+use super::{MaybeSendSync, ProcCtx, Result};
 
-pub(crate) trait CriteriaEvaluator {
+pub(crate) trait CriteriaEvaluator: MaybeSendSync {
     fn evaluate(&self, ctx: &ProcCtx) -> MatchResult;
 }
 
@@ -22,7 +25,7 @@ struct AndEvaluator {
     list: Vec<Box<dyn CriteriaEvaluator>>,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum MatchResult {
     /// condition matches
     OK,
@@ -34,6 +37,11 @@ pub enum MatchResult {
     ERROR,
 }
 
+//the algorithm is run by the executor registered in the ProcessorData
+struct CustomAlgorithmEvaluator {
+    algorithm: CustomAlgorithm,
+}
+
 //for any comparison other than equal
 struct RefValueEvaluator {
     left: ParameterInstanceRef,
@@ -71,7 +79,12 @@ pub(crate) fn from_comparison(
     }
 
     log::debug!(" Creating evaluator for {:?}", MdbItemDebug { mdb, item: comp });
-    let right = ptype.from_str(&comp.value, param_instance.use_calibrated_value)?;
+    // a whole array is compared by its length, the size of its first dimension
+    let right = if let TypeData::Array(_) = ptype.type_data {
+        Value::Uint64(parse_integer_text(&comp.value)?)
+    } else {
+        ptype.from_str(&comp.value, param_instance.use_calibrated_value)?
+    };
 
     if let ComparisonOperator::Equality = comp.comparison_operator {
         Ok(Box::new(RefEqualValueEvaluator { left: param_instance, right }))
@@ -96,6 +109,25 @@ pub(crate) fn from_comparison_list(
     Ok(Box::new(AndEvaluator { list: evlist }))
 }
 
+pub(crate) fn from_boolean_expression(
+    mdb: &MissionDatabase,
+    expr: &BooleanExpression,
+) -> Result<Box<dyn CriteriaEvaluator>> {
+    let from_list = |list: &Vec<BooleanExpression>| {
+        list.iter().map(|e| from_boolean_expression(mdb, e)).collect::<Result<Vec<_>>>()
+    };
+
+    Ok(match expr {
+        BooleanExpression::Condition(comp) => from_comparison(mdb, comp)?,
+        BooleanExpression::And(list) => Box::new(AndEvaluator { list: from_list(list)? }),
+        BooleanExpression::Or(list) => Box::new(OrEvaluator { list: from_list(list)? }),
+    })
+}
+
+pub(crate) fn from_custom_algorithm(algorithm: &CustomAlgorithm) -> Box<dyn CriteriaEvaluator> {
+    Box::new(CustomAlgorithmEvaluator { algorithm: algorithm.clone() })
+}
+
 impl CriteriaEvaluator for OrEvaluator {
     fn evaluate(&self, ctx: &ProcCtx) -> MatchResult {
         for m in &self.list {
@@ -118,6 +150,43 @@ impl CriteriaEvaluator for AndEvaluator {
     }
 }
 
+impl CriteriaEvaluator for CustomAlgorithmEvaluator {
+    fn evaluate(&self, ctx: &ProcCtx) -> MatchResult {
+        let algo = &self.algorithm;
+        let Some(executor) = ctx.pdata.get_algorithm_executor() else {
+            log::warn!("No executor registered for the custom algorithm {}", algo.name);
+            return MatchResult::ERROR;
+        };
+
+        let mut inputs = Vec::with_capacity(algo.inputs.len());
+        for pref in &algo.inputs {
+            match ctx.get_param_value(pref) {
+                Some(v) => inputs.push(v.clone()),
+                None => return MatchResult::UNDEF,
+            }
+        }
+
+        match executor.execute(&algo.name, algo.algorithm_text.as_ref(), &inputs) {
+            Ok(outputs) => match outputs.first() {
+                Some(Value::Boolean(true)) => MatchResult::OK,
+                Some(Value::Boolean(false)) => MatchResult::NOK,
+                v => {
+                    log::warn!(
+                        "The custom algorithm {} returned {:?} instead of a boolean",
+                        algo.name,
+                        v
+                    );
+                    MatchResult::ERROR
+                }
+            },
+            Err(e) => {
+                log::warn!("Error executing the custom algorithm {}: {:?}", algo.name, e);
+                MatchResult::ERROR
+            }
+        }
+    }
+}
+
 //evaluator for equality comparisons
 impl CriteriaEvaluator for RefEqualValueEvaluator {
     fn evaluate(&self, ctx: &ProcCtx) -> MatchResult {
@@ -131,6 +200,10 @@ impl CriteriaEvaluator for RefEqualValueEvaluator {
 }
 
 fn compare_equal(x: &Value, y: &Value) -> MatchResult {
+    // the key of the reference value is the start of the label range, not necessarily the extracted one
+    if let (Value::Enumerated(x), Value::Enumerated(y)) = (x, y) {
+        return check_equals(&x.value, &y.value);
+    }
     if discriminant(x) == discriminant(y) {
         //x and y are the same type
         return if x == y { MatchResult::OK } else { MatchResult::NOK };
@@ -146,6 +219,7 @@ fn compare_equal(x: &Value, y: &Value) -> MatchResult {
         (Value::Double(x), Value::Uint64(y)) => check_equals(*x as f64, *y as f64),
         (Value::StringValue(x), Value::Enumerated(y)) => check_equals(x.as_ref(), &y.value),
         (Value::Enumerated(x), Value::StringValue(y)) => check_equals(&x.value, y),
+        (Value::Array(x), Value::Uint64(y)) => check_equals(x.len() as u64, *y),
 
         //Yamcs java does some weird comparisons between different types
         _ => todo!(),
@@ -183,6 +257,8 @@ fn compare(operator: ComparisonOperator, x: &Value, y: &Value) -> MatchResult {
             compare_values(operator, x.as_ref(), &y.value)
         }
         (Value::Enumerated(x), Value::StringValue(y)) => compare_values(operator, &x.value, y),
+        (Value::Enumerated(x), Value::Enumerated(y)) => compare_values(operator, &x.value, &y.value),
+        (Value::Array(x), Value::Uint64(y)) => compare_values(operator, x.len() as u64, *y),
 
         //Yamcs java does some weird comparisons between different types
         _ => todo!(),
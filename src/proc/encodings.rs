@@ -2,10 +2,12 @@ use core::num;
 
 use crate::{
     mdb::types::{
-        BinaryDataEncoding, DataEncoding, IntegerDataEncoding, IntegerEncodingType, StringBoxSize,
-        StringDataEncoding, StringSize, FloatDataEncoding, FloatEncodingType,
+        BinaryDataEncoding, BinarySize, DataEncoding, IntegerDataEncoding, IntegerEncodingType, StringBoxSize,
+        StringDataEncoding, StringSize, FloatDataEncoding, FloatEncodingType, LeadingSize, code_unit_size,
     },
-    value::{ContainerPosition, ContainerPositionDetails, Value}, proc::ProcError
+    mdb::MdbError,
+    value::{ContainerPosition, ContainerPositionDetails, Value}, proc::ProcError,
+    bitbuffer::ByteOrder,
 };
 
 use super::{ProcCtx, Result};
@@ -30,15 +32,14 @@ fn extract_integer(
     ctx: &mut ProcCtx,
 ) -> Result<(Value, ContainerPosition)> {
     let cctx = &mut ctx.cbuf;
-    let bitbuf = &mut cctx.buf;
 
-    bitbuf.set_byte_order(ide.byte_order);
+    cctx.buf.set_byte_order(ide.byte_order);
     let numbits = ide.size_in_bits as usize;
-    let bit_offset = bitbuf.get_position() as u32;
+    let bit_offset = cctx.get_position() as u32;
 
     let start_offset = cctx.start_offset;
 
-    let bv = bitbuf.get_bits(numbits);
+    let bv = cctx.get_bits(numbits)?;
 
     let v = match ide.encoding {
         IntegerEncodingType::Unsigned => Value::uint_value(numbits, bv),
@@ -70,6 +71,8 @@ fn extract_integer(
                 Value::int_value(numbits, bv as i64)
             }
         }
+        IntegerEncodingType::Bcd => Value::Uint64(decode_bcd(ctx, bv, numbits, 8)?),
+        IntegerEncodingType::PackedBcd => Value::Uint64(decode_bcd(ctx, bv, numbits, 4)?),
     };
     Ok((
         v,
@@ -82,11 +85,118 @@ fn extract_integer(
     ))
 }
 
+// converts the numbits/digit_size digits (8 bits for BCD, 4 bits for packed BCD) read just before
+// the current position into their decimal value, the most significant digit comes first
+fn decode_bcd(ctx: &ProcCtx, bv: u64, numbits: usize, digit_size: usize) -> Result<u64> {
+    let mask = (1 << digit_size) - 1;
+    let mut x = 0;
+    for i in (0..numbits / digit_size).rev() {
+        let d = (bv >> (i * digit_size)) & mask;
+        if d > 9 {
+            // the offset of the digit in the packet
+            let offset = ctx.cbuf.start_offset as usize * 8 + ctx.cbuf.get_position()
+                - (i + 1) * digit_size;
+            return Err(ctx.decoding_error(&format!(
+                "invalid BCD digit {:#x} at bit offset {}",
+                d, offset
+            )));
+        }
+        x = x * 10 + d;
+    }
+    Ok(x)
+}
+
 fn extract_binary(
     bde: &BinaryDataEncoding,
     ctx: &mut ProcCtx,
 ) -> Result<(Value, ContainerPosition)> {
-    todo!()
+    let position = ctx.cbuf.get_position();
+    let start_offset = ctx.cbuf.start_offset;
+    let bit_offset = position as u32;
+
+    // with a leading size it is the data following the size tag which has to start at byte boundary
+    let tag_bits = match &bde.size_in_bits {
+        BinarySize::LeadingSize(ls) => ls.tag_size_in_bits as usize,
+        _ => 0,
+    };
+    if (position + tag_bits) & 7 != 0 {
+        return Err(
+            ctx.decoding_error("the binary data that does not start at byte boundary not supported")
+        );
+    }
+
+    let size_in_bytes = match &bde.size_in_bits {
+        BinarySize::Fixed(x) if x % 8 != 0 => {
+            return Err(ctx.decoding_error(&format!(
+                "the fixed size in bits of the binary data {} is not a multiple of 8",
+                x
+            )))
+        }
+        BinarySize::Fixed(x) => (x / 8) as u64,
+        BinarySize::Dynamic(x) => ctx.get_dynamic_uint_value(x)? / 8,
+        BinarySize::LeadingSize(ls) => {
+            let remaining = ctx.cbuf.buf.remaining_bits();
+            if tag_bits > remaining {
+                return Err(ctx.decoding_error(&format!(
+                    "the size in bits of the size tag {} exceeds the remaining size in bits {}",
+                    tag_bits, remaining
+                )));
+            }
+            read_size_tag(ls, ctx)?
+        }
+        BinarySize::Algorithm => {
+            return Err(ctx.decoding_error(
+                "the size of the binary data is determined by an algorithm; algorithms are not supported",
+            ))
+        }
+    };
+
+    if size_in_bytes > ctx.cbuf.remaining_bytes() as u64 {
+        return Err(ctx.decoding_error(&format!(
+            "the size in bytes of the binary data {} exceeds the remaining size {}",
+            size_in_bytes,
+            ctx.cbuf.remaining_bytes()
+        )));
+    }
+
+    let v = ctx.cbuf.get_bytes_ref(size_in_bytes as usize)?.to_vec();
+    let bit_size = (ctx.cbuf.get_position() - position) as u32;
+    let mut value = Value::Binary(Box::new(v));
+
+    if let Some(algo) = &bde.from_binary_transform_algorithm {
+        match ctx.pdata.get_algorithm_executor() {
+            Some(executor) => {
+                let outputs = executor
+                    .execute(&algo.name, algo.algorithm_text.as_ref(), &[value])
+                    .map_err(|e| {
+                        ctx.decoding_error(&format!("the algorithm {} failed: {:?}", algo.name, e))
+                    })?;
+                value = outputs.into_iter().next().ok_or_else(|| {
+                    ctx.decoding_error(&format!("the algorithm {} returned no value", algo.name))
+                })?;
+            }
+            None => {
+                log::warn!(
+                    "No executor registered for the transform algorithm {}, returning the binary data",
+                    algo.name
+                );
+            }
+        }
+    }
+
+    let cp = ContainerPosition {
+        start_offset,
+        bit_offset,
+        bit_size,
+        details: ContainerPositionDetails::None,
+    };
+    Ok((value, cp))
+}
+
+// reads the unsigned integer giving the size of the data which follows
+fn read_size_tag(ls: &LeadingSize, ctx: &mut ProcCtx) -> Result<u64> {
+    ctx.cbuf.buf.set_byte_order(ls.byte_order);
+    ctx.cbuf.get_bits(ls.tag_size_in_bits as usize)
 }
 
 fn extract_string(
@@ -97,7 +207,12 @@ fn extract_string(
     let start_offset = ctx.cbuf.start_offset;
     let bit_offset = position as u32;
 
-    if position & 7 != 0 {
+    // with a leading size it is the string data following the size tag which has to start at byte boundary
+    let tag_bits = match &sde.size_in_bits {
+        StringSize::LeadingSize(ls) => ls.tag_size_in_bits as usize,
+        _ => 0,
+    };
+    if (position + tag_bits) & 7 != 0 {
         return Err(
             ctx.decoding_error("the string data that does not start at byte boundary not supported")
         );
@@ -106,9 +221,11 @@ fn extract_string(
     let remaining = ctx.cbuf.remaining_bytes() as u32;
 
     // bmr = max box size  or remaining packet size
-    let mut bmr = sde.max_box_size_in_bytes.filter(|m| *m < remaining).unwrap_or(remaining);
+    let mut bmr = sde.max_box_size_in_bits.map(|m| m / 8).filter(|m| *m < remaining).unwrap_or(remaining);
 
-    // first determine the box size
+    // first determine the box size; with a leading size tag not multiple of 8 bits, the box size
+    // is not a whole number of bytes and it is given in bits
+    let mut box_size_in_bits = None;
     let mut box_size = match &sde.box_size_in_bits {
         StringBoxSize::Undefined => None,
         StringBoxSize::Fixed(x) => {
@@ -137,7 +254,7 @@ fn extract_string(
     };
 
     // find the string size
-    let string_size_in_bytes = match sde.size_in_bits {
+    let string_size_in_bytes = match &sde.size_in_bits {
         StringSize::Fixed(x) => {
             let strsize = x / 8;
             if strsize > bmr {
@@ -148,59 +265,79 @@ fn extract_string(
             }
             strsize
         }
-        StringSize::LeadingSize(tag_size) => {
-            if tag_size > bmr {
+        StringSize::LeadingSize(ls) => {
+            let tag_bits = ls.tag_size_in_bits;
+            // if the box size is not given, it is limited by the maximum size in bits and the end of the buffer;
+            // the bits before the first byte boundary (when the tag does not start at byte boundary) are also
+            // available
+            let bmr_bits = match box_size {
+                Some(bsize) => 8 * bsize,
+                None => {
+                    let remaining_bits = ctx.cbuf.buf.remaining_bits() as u32;
+                    sde.max_box_size_in_bits.map_or(remaining_bits, |m| m.min(remaining_bits))
+                }
+            };
+            if tag_bits > bmr_bits {
                 return Err(ctx.decoding_error(&format!(
-                    "the size in bytes of the size tag {} exceeds the box size {}",
-                    tag_size, bmr
+                    "the size in bits of the size tag {} exceeds the box size in bits {}",
+                    tag_bits, bmr_bits
                 )));
             }
-            let size = ctx.cbuf.get_bits((tag_size * 8) as usize) as u32;
-            if tag_size + size > bmr {
+            // the tag is at most 32 bits long
+            let size = read_size_tag(ls, ctx)? as u32;
+            if size as u64 * 8 > (bmr_bits - tag_bits) as u64 {
                 return Err(ctx.decoding_error(&format!(
-                    "the size in bytes of the string {} exceeds the box size {}",
-                    (tag_size + size),
-                    bmr
+                    "the size in bits of the size tag and string {} exceeds the box size in bits {}",
+                    (tag_bits as u64 + 8 * size as u64),
+                    bmr_bits
                 )));
             }
-            box_size.get_or_insert(tag_size + size);
+            if box_size.is_none() {
+                box_size_in_bits = Some(tag_bits + 8 * size);
+            }
             size
         }
         StringSize::TerminationChar(termination_char) => {
-            let mut strsize = 0;
+            let tlen = termination_char.len() as u32;
+            // the terminator is searched only at the start of the code units (e.g. at even
+            // offsets for UTF-16)
+            let unit_size = sde.code_unit_size();
+            let strsize = ctx
+                .cbuf
+                .get_bytes_ref(bmr as usize)?
+                .windows(tlen as usize)
+                .step_by(unit_size)
+                .position(|w| w == termination_char.as_slice())
+                .map_or(bmr, |p| (p * unit_size) as u32);
 
-            while strsize < bmr && ctx.cbuf.get_byte() != termination_char {
-                strsize += 1;
-            }
             if box_size.is_none() {
                 if strsize == bmr {
                     // if the box size is not set we do not want to just eat the remaining of the packet
                     return Err(ctx.decoding_error(&format!(
-                        "cannot find string terminator 0x{:x}",
-                        termination_char
+                        "cannot find string terminator 0x{}",
+                        hex::encode(termination_char)
                     )));
                 }
-                box_size.get_or_insert(strsize + 1);
+                // the terminator is part of the box but not of the string
+                box_size.get_or_insert(strsize + tlen);
             }
             //put back the position at the beginning of the string
             ctx.cbuf.set_position(position);
             strsize
         }
-        StringSize::Custom => todo!(),
+        StringSize::Custom => return extract_custom_string(sde, ctx, position, bmr, box_size),
     };
-    assert!(box_size.is_some());
+    assert!(box_size.is_some() || box_size_in_bits.is_some());
 
     // extract the string
-    let b = ctx.cbuf.get_bytes_ref(string_size_in_bytes as usize);
+    let b = ctx.cbuf.get_bytes_ref(string_size_in_bytes as usize)?;
 
-    let v = match sde.encoding.as_str() {
-        "UTF-8" => String::from_utf8_lossy(b).into_owned(),
-        // "UTF-16" => String::from_utf16_lossy(b),
-        _ => todo!(),
-    };
+    let v = decode_string(&sde.encoding, sde.byte_order, b).ok_or_else(|| {
+        ctx.decoding_error(&format!("unsupported string encoding '{}'", sde.encoding))
+    })?;
 
     //set the buffer position at the end of the box
-    let bit_size = 8 * box_size.unwrap();
+    let bit_size = box_size_in_bits.or(box_size.map(|b| 8 * b)).unwrap();
     ctx.cbuf.set_position(position + bit_size as usize);
 
     let cp = ContainerPosition {
@@ -212,37 +349,165 @@ fn extract_string(
     Ok((Value::StringValue(Box::new(v)), cp))
 }
 
+// decodes the bytes of a string; the byte order is used for the UTF-16 and UTF-32 encodings
+// which do not specify it in their name.
+// The invalid characters and the incomplete code unit at the end are replaced by U+FFFD.
+// Returns None if the encoding is not supported.
+fn decode_string(encoding: &str, byte_order: ByteOrder, b: &[u8]) -> Option<String> {
+    let byte_order = match encoding {
+        "UTF-16BE" | "UTF-32BE" => ByteOrder::BigEndian,
+        "UTF-16LE" | "UTF-32LE" => ByteOrder::LittleEndian,
+        _ => byte_order,
+    };
+    let unit_size = code_unit_size(encoding);
+    let chunks = b.chunks_exact(unit_size);
+    let incomplete = !chunks.remainder().is_empty();
+    let units = chunks.map(|c| {
+        c.iter().enumerate().fold(0u32, |acc, (i, &x)| match byte_order {
+            ByteOrder::BigEndian => acc << 8 | x as u32,
+            ByteOrder::LittleEndian => acc | (x as u32) << (8 * i),
+        })
+    });
+
+    let mut s = match encoding {
+        "UTF-8" | "US-ASCII" => String::from_utf8_lossy(b).into_owned(),
+        "ISO-8859-1" => b.iter().map(|&x| x as char).collect(),
+        "UTF-16" | "UTF-16BE" | "UTF-16LE" => {
+            char::decode_utf16(units.map(|u| u as u16))
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        "UTF-32" | "UTF-32BE" | "UTF-32LE" => {
+            units.map(|u| char::from_u32(u).unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+        }
+        _ => return None,
+    };
+    if incomplete {
+        s.push(char::REPLACEMENT_CHARACTER);
+    }
+    Some(s)
+}
+
+// the string is decoded by the decoder registered for the algorithm of the encoding
+fn extract_custom_string(
+    sde: &StringDataEncoding,
+    ctx: &mut ProcCtx,
+    position: usize,
+    bmr: u32,
+    box_size: Option<u32>,
+) -> Result<(Value, ContainerPosition)> {
+    let start_offset = ctx.cbuf.start_offset;
+    let name = sde.from_binary_transform_algorithm.as_ref().map_or("", |algo| algo.name.as_str());
+
+    let decoder = ctx.pdata.get_string_decoder(name).ok_or_else(|| {
+        ProcError::Mdb(MdbError::InvalidMdb(format!(
+            "No custom string decoder registered for the algorithm '{}'",
+            name
+        )))
+    })?;
+    let (v, num_bits) = decoder.decode(&mut ctx.cbuf)?;
+
+    if num_bits > 8 * bmr as usize {
+        return Err(ctx.decoding_error(&format!(
+            "the size in bits of the custom decoded string {} exceeds the box or remaining size in bytes {}",
+            num_bits, bmr
+        )));
+    }
+
+    let bit_size = box_size.map_or(num_bits as u32, |b| 8 * b);
+    ctx.cbuf.set_position(position + bit_size as usize);
+
+    let cp = ContainerPosition {
+        start_offset,
+        bit_offset: position as u32,
+        bit_size,
+        details: ContainerPositionDetails::None,
+    };
+    Ok((Value::StringValue(Box::new(v)), cp))
+}
+
+/// converts an IEEE 754 half precision float (1 bit sign, 5 bits exponent, 10 bits fraction) to f64
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1F) as i32;
+    let fraction = (bits & 0x3FF) as f64;
+
+    let x = match exponent {
+        // zero and subnormals
+        0 => fraction * 2f64.powi(-24),
+        0x1F if fraction == 0.0 => f64::INFINITY,
+        0x1F => f64::NAN,
+        _ => (1.0 + fraction / 1024.0) * 2f64.powi(exponent - 15),
+    };
+
+    sign * x
+}
+
+/// converts a float made of a sign bit, a biased exponent and a mantissa without its leading bit
+///
+/// Like for the IEEE 754 formats, the bias is 2^(exponent_bits-1) - 1, the zero exponent is used
+/// for the subnormals and the maximum exponent for the infinite and NaN values.
+fn custom_float_to_f64(bv: u64, exponent_bits: u32, mantissa_bits: u32) -> f64 {
+    let sign = if (bv >> (exponent_bits + mantissa_bits)) & 1 != 0 { -1.0 } else { 1.0 };
+    let max_exponent = (1u64 << exponent_bits) - 1;
+    let exponent = (bv >> mantissa_bits) & max_exponent;
+    let fraction = (bv & ((1u64 << mantissa_bits) - 1)) as f64 / 2f64.powi(mantissa_bits as i32);
+    let bias = (max_exponent >> 1) as i32;
+
+    let x = match exponent {
+        // zero and subnormals
+        0 => fraction * 2f64.powi(1 - bias),
+        e if e == max_exponent && fraction == 0.0 => f64::INFINITY,
+        e if e == max_exponent => f64::NAN,
+        e => (1.0 + fraction) * 2f64.powi(e as i32 - bias),
+    };
+
+    sign * x
+}
+
+/// converts a MIL-STD-1750A 32 bits or 48 bits (extended precision) float to f64
+///
+/// The 32 bits format is a 24 bits two's complement mantissa followed by an 8 bits two's complement
+/// exponent. The 48 bits format adds 16 more bits of mantissa after the exponent.
+/// The mantissa is a fraction in [-1, 1) such that the value is mantissa * 2^exponent.
+fn milstd1750a_to_f64(bv: u64, numbits: usize) -> f64 {
+    let (mantissa, exponent, mantissa_bits) = if numbits == 48 {
+        let m = ((bv >> 24) << 16) | (bv & 0xFFFF);
+        // sign extend the 40 bits mantissa
+        (((m << 24) as i64) >> 24, ((bv >> 16) & 0xFF) as u8 as i8, 40)
+    } else {
+        // sign extend the 24 bits mantissa
+        ((((bv >> 8) << 40) as i64) >> 40, (bv & 0xFF) as u8 as i8, 24)
+    };
+
+    mantissa as f64 * 2f64.powi(exponent as i32 - (mantissa_bits - 1))
+}
+
 fn extract_float(
     fde: &FloatDataEncoding,
     ctx: &mut ProcCtx,
 ) -> Result<(Value, ContainerPosition)> {
     let cctx = &mut ctx.cbuf;
-    let bitbuf = &mut cctx.buf;
 
-    bitbuf.set_byte_order(fde.byte_order);
+    cctx.buf.set_byte_order(fde.byte_order);
     let numbits = fde.size_in_bits as usize;
-    let bit_offset = bitbuf.get_position() as u32;
+    let bit_offset = cctx.get_position() as u32;
 
     let start_offset = cctx.start_offset;
 
-    let bv = bitbuf.get_bits(numbits);
+    let bv = cctx.get_bits(numbits)?;
 
     let v = match fde.encoding {
 
-        FloatEncodingType::IEEE754_1985 => {
-            if numbits==32 {
-                Value::Double(f32::from_bits(bv as u32) as f64) 
-            } else {
-                Value::Double(f64::from_bits(bv))
-            }
-            
+        FloatEncodingType::IEEE754_1985 => match numbits {
+            16 => Value::Double(f16_to_f64(bv as u16)),
+            32 => Value::Double(f32::from_bits(bv as u32) as f64),
+            _ => Value::Double(f64::from_bits(bv)),
         },
-        FloatEncodingType::Milstd1750a => {
-            let n = 64 - numbits;
-            // shift left to get the sign and back again
-            let x = bv as i64;
-            Value::int_value(numbits, (x << n) >> n)
-        }       
+        FloatEncodingType::Milstd1750a => Value::Double(milstd1750a_to_f64(bv, numbits)),
+        FloatEncodingType::Custom { exponent_bits, mantissa_bits } => {
+            Value::Double(custom_float_to_f64(bv, exponent_bits as u32, mantissa_bits as u32))
+        }
     };
     Ok((
         v,
@@ -253,4 +518,99 @@ fn extract_float(
             details: ContainerPositionDetails::None,
         },
     ))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f16() {
+        assert_eq!(1.0, f16_to_f64(0x3C00));
+        assert_eq!(-2.0, f16_to_f64(0xC000));
+        assert_eq!(0.333251953125, f16_to_f64(0x3555));
+        assert_eq!(65504.0, f16_to_f64(0x7BFF));
+        // the smallest normal and the smallest and largest subnormals
+        assert_eq!(2f64.powi(-14), f16_to_f64(0x0400));
+        assert_eq!(2f64.powi(-24), f16_to_f64(0x0001));
+        assert_eq!(1023.0 * 2f64.powi(-24), f16_to_f64(0x03FF));
+        assert_eq!(0.0, f16_to_f64(0x0000));
+        assert!(f16_to_f64(0x8000) == 0.0 && f16_to_f64(0x8000).is_sign_negative());
+        assert_eq!(f64::INFINITY, f16_to_f64(0x7C00));
+        assert_eq!(f64::NEG_INFINITY, f16_to_f64(0xFC00));
+        assert!(f16_to_f64(0x7E00).is_nan());
+    }
+
+    #[test]
+    fn test_custom_float() {
+        // the half float layout gives the same values
+        for bits in [0x3C00, 0xC000, 0x3555, 0x7BFF, 0x0400, 0x0001, 0x03FF, 0x7C00, 0xFC00] {
+            assert_eq!(f16_to_f64(bits), custom_float_to_f64(bits as u64, 5, 10));
+        }
+        assert!(custom_float_to_f64(0x7E00, 5, 10).is_nan());
+        // and the single precision layout
+        for x in [1.0f32, -2.5, 0.1, f32::MAX, f32::MIN_POSITIVE, 1e-40] {
+            assert_eq!(x as f64, custom_float_to_f64(x.to_bits() as u64, 8, 23));
+        }
+
+        // 24 bits with 7 bits exponent
+        assert_eq!(1.0, custom_float_to_f64(0x3F0000, 7, 16));
+        assert_eq!(-2.5, custom_float_to_f64(0xC04000, 7, 16));
+        assert_eq!(2f64.powi(-62) / 65536.0, custom_float_to_f64(0x000001, 7, 16));
+    }
+
+    #[test]
+    fn test_milstd1750a_32() {
+        // test vectors from MIL-STD-1750A, section 4.1.6
+        assert_eq!(0.9999998807907104 * 2f64.powi(127), milstd1750a_to_f64(0x7FFFFF7F, 32));
+        assert_eq!(0.5 * 2f64.powi(127), milstd1750a_to_f64(0x4000007F, 32));
+        assert_eq!(10.0, milstd1750a_to_f64(0x50000004, 32));
+        assert_eq!(1.0, milstd1750a_to_f64(0x40000001, 32));
+        assert_eq!(0.5, milstd1750a_to_f64(0x40000000, 32));
+        assert_eq!(0.25, milstd1750a_to_f64(0x400000FF, 32));
+        assert_eq!(0.5 * 2f64.powi(-128), milstd1750a_to_f64(0x40000080, 32));
+        assert_eq!(0.0, milstd1750a_to_f64(0x00000000, 32));
+        assert_eq!(-1.0, milstd1750a_to_f64(0x80000000, 32));
+        assert_eq!(-0.5, milstd1750a_to_f64(0x800000FF, 32));
+        assert_eq!(-0.5000001192092896 * 2f64.powi(-128), milstd1750a_to_f64(0xBFFFFF80, 32));
+        assert_eq!(-0.7500001192092896 * 16.0, milstd1750a_to_f64(0x9FFFFF04, 32));
+    }
+
+    #[test]
+    fn test_milstd1750a_48() {
+        // test vectors from MIL-STD-1750A, section 4.1.6
+        assert_eq!(0.5 * 2f64.powi(127), milstd1750a_to_f64(0x4000007F0000, 48));
+        assert_eq!(0.5, milstd1750a_to_f64(0x400000000000, 48));
+        assert_eq!(0.25, milstd1750a_to_f64(0x400000FF0000, 48));
+        assert_eq!(0.5 * 2f64.powi(-128), milstd1750a_to_f64(0x400000800000, 48));
+        assert_eq!(-0.5 * 2f64.powi(127), milstd1750a_to_f64(0xC000007F0000, 48));
+        assert_eq!(-1.0, milstd1750a_to_f64(0x800000000000, 48));
+        assert_eq!(-0.25, milstd1750a_to_f64(0xC00000FF0000, 48));
+        // the last 16 bits of the mantissa
+        assert_eq!(0.5 + 2f64.powi(-39), milstd1750a_to_f64(0x400000000001, 48));
+        assert_eq!(-1.0 + 2f64.powi(-39), milstd1750a_to_f64(0x800000000001, 48));
+    }
+
+    #[test]
+    fn test_decode_string() {
+        let be = ByteOrder::BigEndian;
+        let le = ByteOrder::LittleEndian;
+        let s = |encoding, byte_order, b: &[u8]| decode_string(encoding, byte_order, b).unwrap();
+
+        assert_eq!("ab", s("UTF-16", be, &[0, b'a', 0, b'b']));
+        assert_eq!("ab", s("UTF-16", le, &[b'a', 0, b'b', 0]));
+        // the byte order from the encoding name takes precedence
+        assert_eq!("ab", s("UTF-16LE", be, &[b'a', 0, b'b', 0]));
+        assert_eq!("€", s("UTF-32", be, &[0, 0, 0x20, 0xAC]));
+        assert_eq!("€", s("UTF-32LE", be, &[0xAC, 0x20, 0, 0]));
+        // single byte encodings ignore the byte order
+        assert_eq!("ab", s("UTF-8", le, b"ab"));
+        assert_eq!("é", s("ISO-8859-1", le, &[0xE9]));
+
+        // unpaired surrogate and incomplete code unit
+        assert_eq!("\u{FFFD}a\u{FFFD}", s("UTF-16", be, &[0xD8, 0x3D, 0, b'a', 0]));
+        assert_eq!("\u{FFFD}", s("UTF-32", be, &[0, 0x11, 0, 0]));
+
+        assert_eq!(None, decode_string("EBCDIC", be, b"ab"));
+    }
+}
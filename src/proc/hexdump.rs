@@ -0,0 +1,89 @@
+use std::fmt::Write;
+
+use crate::mdb::{ContainerIdx, MissionDatabase};
+
+use super::{containers, ProcessorData, Result};
+
+// maximum number of bytes shown on one line, the longer parameters continue on the next lines
+const BYTES_PER_LINE: usize = 16;
+
+/// Processes the packet starting with the given container and returns a hex dump of the packet
+/// where each byte range is labeled with the parameter occupying it and its engineering value.
+///
+/// There is one line per parameter: the byte offset, the bytes in hex and the qualified name of
+/// the parameter. The parameters which do not start or end at a byte boundary show also their bit
+/// range. The bytes which are not covered by any parameter are labeled as `<unknown>`.
+/// The parameters extracted from segments are not shown.
+///
+/// Example:
+/// ```text
+/// 0000  10                                               /YSS/version = 0 (bits 0..3)
+/// 0000  10                                               /YSS/type = 1 (bits 3..4)
+/// 0001  0a 0b                                            /YSS/counter = 2571
+/// 0003  ff                                               <unknown>
+/// ```
+pub fn annotated_hexdump(
+    mdb: &MissionDatabase,
+    packet: &[u8],
+    container: ContainerIdx,
+) -> Result<String> {
+    let pdata = ProcessorData::new(mdb)?;
+    let values = containers::process_with(mdb, &pdata, packet, container)?;
+    let mut positions: Vec<_> =
+        (&values).into_iter().filter_map(|pv| pv.position.as_ref().map(|cpos| (pv, cpos))).collect();
+    positions.sort_by_key(|(_, cpos)| cpos.start_offset as usize * 8 + cpos.bit_offset as usize);
+
+    let mut out = String::new();
+    // the first byte not yet covered by a parameter
+    let mut next = 0;
+
+    for (pv, cpos) in positions {
+        let start_bit = cpos.start_offset as usize * 8 + cpos.bit_offset as usize;
+        let end_bit = start_bit + cpos.bit_size as usize;
+        let start = start_bit / 8;
+        let end = end_bit.div_ceil(8).min(packet.len());
+
+        if start > next {
+            write_range(&mut out, packet, next, start, "<unknown>");
+        }
+
+        let mut label = format!("{} = {}", mdb.parameter_qn(pv.pidx), pv.eng_value);
+        if !start_bit.is_multiple_of(8) || !end_bit.is_multiple_of(8) {
+            write!(label, " (bits {}..{})", start_bit - start * 8, end_bit - start * 8).unwrap();
+        }
+        write_range(&mut out, packet, start, end, &label);
+        next = next.max(end);
+    }
+
+    if next < packet.len() {
+        write_range(&mut out, packet, next, packet.len(), "<unknown>");
+    }
+
+    Ok(out)
+}
+
+// writes the bytes [start, end) of the packet on one or more lines, the label is on the first line
+// an empty range (e.g. an empty string parameter) is written as a line without bytes
+fn write_range(out: &mut String, packet: &[u8], start: usize, end: usize, label: &str) {
+    let mut offset = start;
+    let mut label = label;
+    loop {
+        let chunk_end = end.min(offset + BYTES_PER_LINE);
+        let hex: Vec<String> =
+            packet[offset..chunk_end].iter().map(|b| format!("{:02x}", b)).collect();
+        let line = format!(
+            "{:04x}  {:<width$}  {}",
+            offset,
+            hex.join(" "),
+            label,
+            width = BYTES_PER_LINE * 3 - 1
+        );
+        writeln!(out, "{}", line.trim_end()).unwrap();
+
+        offset = chunk_end;
+        label = "";
+        if offset >= end {
+            break;
+        }
+    }
+}
@@ -1,21 +1,31 @@
 use crate::{
     bitbuffer::BitBuffer,
     mdb::{
-        utils::get_member_value, DynamicValueType, MatchCriteria, MatchCriteriaIdx,
-        MissionDatabase, NamedItem, ParameterIdx, ParameterInstanceRef, MdbError,
+        types::{AlarmLevel, AlgorithmText, Calibrator, DataEncoding, DataType, TypeData},
+        utils::get_member_value, ContainerIdx, DiscreteLookup, DynamicValueType, MatchCriteria,
+        MatchCriteriaIdx, MdbError, MissionDatabase, NamedItem, ParameterDynamicValue, ParameterIdx,
+        ParameterInstanceRef,
     },
     pvlist::ParameterValueList,
-    value::Value,
+    value::{ParameterValue, Value},
 };
 
-use self::criteria_evaluator::CriteriaEvaluator;
+use std::{borrow::Cow, cell::RefCell, collections::HashMap};
 
+use self::criteria_evaluator::{CriteriaEvaluator, MatchResult};
+
+pub(crate) mod calibrators;
+pub mod commands;
 pub mod containers;
+pub mod crc;
 pub mod criteria_evaluator;
 pub mod encodings;
+mod hexdump;
 pub mod misc;
 pub mod types;
 
+pub use hexdump::annotated_hexdump;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -54,23 +64,241 @@ impl From<MdbError> for ProcError {
 }
 
 
+/// With the `parallel` feature, the processor data is shared between threads by [containers::process_packets]
+/// so the decoders and executors registered in it have to be `Send + Sync`. Without the feature they do not,
+/// which allows for example embedding an interpreter which is not thread safe.
+#[cfg(feature = "parallel")]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+/// With the `parallel` feature, the processor data is shared between threads by [containers::process_packets]
+/// so the decoders and executors registered in it have to be `Send + Sync`. Without the feature they do not,
+/// which allows for example embedding an interpreter which is not thread safe.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSendSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// Decodes the strings whose size is determined by an algorithm (`StringSize::Custom`).
+///
+/// The decoders are registered in the ProcessorData under the name of the FromBinaryTransformAlgorithm
+/// of the string encoding. With the `parallel` feature they have to be thread safe (see [MaybeSendSync]).
+pub trait CustomStringDecoder: MaybeSendSync {
+    /// decodes the string starting at the current position of the buffer.
+    ///
+    /// Returns the string and the number of bits it occupies in the packet. The buffer position is set
+    /// after the string by the caller, it does not matter where the decoder leaves it.
+    fn decode(&self, buf: &mut ContainerBuf) -> Result<(String, usize)>;
+}
+
+/// Executes the algorithms not implemented by the crate, for example those written in JavaScript
+/// or Python.
+///
+/// The executor is registered in the ProcessorData and called for the CustomAlgorithm match
+/// criteria and for the FromBinaryTransformAlgorithm of the binary data encodings. As the string
+/// decoders, the executor has to be thread safe with the `parallel` feature.
+pub trait CustomAlgorithmExecutor: MaybeSendSync {
+    /// executes the algorithm with the given inputs and returns its outputs.
+    ///
+    /// For a match criteria the inputs are the values of the input parameters and the first output
+    /// has to be a boolean telling if the criteria matches. For a FromBinaryTransformAlgorithm the
+    /// input is the binary data and the first output becomes the raw value of the parameter.
+    fn execute(
+        &self,
+        name: &str,
+        algorithm_text: Option<&AlgorithmText>,
+        inputs: &[Value],
+    ) -> Result<Vec<Value>>;
+}
+
+/// The engineering value of an enumerated parameter whose raw value does not match any enumeration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UndefinedEnumeration {
+    /// the enumerated value gets the given label, "UNDEF" by default
+    Label(String),
+    /// the parameter cannot be decoded, the processing fails with a DecodingError
+    Error,
+}
+
+impl Default for UndefinedEnumeration {
+    fn default() -> Self {
+        UndefinedEnumeration::Label("UNDEF".to_owned())
+    }
+}
+
 pub struct ProcessorData {
     evaluators: Vec<Box<dyn CriteriaEvaluator>>,
+    string_decoders: HashMap<String, Box<dyn CustomStringDecoder>>,
+    // if true, the inheriting containers are extracted also when their restriction criteria evaluates to UNDEF
+    extract_undef_subcontainers: bool,
+    undefined_enumeration: UndefinedEnumeration,
+    algorithm_executor: Option<Box<dyn CustomAlgorithmExecutor>>,
 }
 
 impl ProcessorData {
+    /// creates the processor data of the mdb.
+    ///
+    /// The mdb is validated again (see [MissionDatabase::validate]) because it can have been modified after
+    /// being loaded; an invalid mdb would make the extraction recurse without end.
     pub fn new(mdb: &MissionDatabase) -> Result<ProcessorData> {
+        mdb.validate()?;
+
         let mut evaluators = Vec::new();
         for criteria in &mdb.match_criteria {
             evaluators.push(ProcessorData::create_evaluator(mdb, criteria)?);
         }
-        Ok(ProcessorData { evaluators })
+        Ok(ProcessorData {
+            evaluators,
+            string_decoders: HashMap::new(),
+            extract_undef_subcontainers: false,
+            undefined_enumeration: UndefinedEnumeration::default(),
+            algorithm_executor: None,
+        })
+    }
+
+    /// sets what happens when a restriction criteria of an inheriting container evaluates to UNDEF, for example
+    /// because it refers to a parameter which has not been extracted.
+    ///
+    /// By default the criteria is considered not matching; if set to true the inheriting container is extracted.
+    pub fn set_extract_undef_subcontainers(&mut self, extract: bool) {
+        self.extract_undef_subcontainers = extract;
+    }
+
+    /// sets the engineering value of the enumerated parameters whose raw value does not match any
+    /// enumeration; by default they get the label "UNDEF".
+    pub fn set_undefined_enumeration(&mut self, undefined: UndefinedEnumeration) {
+        self.undefined_enumeration = undefined;
+    }
+
+    /// registers the decoder for the strings using the algorithm with the given name,
+    /// replacing the decoder previously registered for that name
+    pub fn register_string_decoder(&mut self, algorithm_name: &str, decoder: Box<dyn CustomStringDecoder>) {
+        self.string_decoders.insert(algorithm_name.to_owned(), decoder);
+    }
+
+    /// sets the executor of the custom algorithms, replacing the one previously set
+    pub fn set_algorithm_executor(&mut self, executor: Box<dyn CustomAlgorithmExecutor>) {
+        self.algorithm_executor = Some(executor);
+    }
+
+    fn get_algorithm_executor(&self) -> Option<&dyn CustomAlgorithmExecutor> {
+        self.algorithm_executor.as_deref()
+    }
+
+    fn get_string_decoder(&self, algorithm_name: &str) -> Option<&dyn CustomStringDecoder> {
+        self.string_decoders.get(algorithm_name).map(|d| d.as_ref())
     }
 
     fn get_criteria_evaluator(&self, mcidx: MatchCriteriaIdx) -> &Box<dyn CriteriaEvaluator> {
         &self.evaluators[mcidx.index()]
     }
 
+    /// Evaluates the match criteria using the given parameter values, without any packet decoding.
+    ///
+    /// The criteria referring to parameters not present in the list evaluate to UNDEF.
+    pub fn evaluate(&self, mdb: &MissionDatabase, mcidx: MatchCriteriaIdx, values: &ParameterValueList) -> MatchResult {
+        let ctx = self.values_ctx(mdb, values);
+        self.get_criteria_evaluator(mcidx).evaluate(&ctx)
+    }
+
+    /// Computes the raw value corresponding to the engineering value of the given type, to be used for
+    /// encoding commands.
+    ///
+    /// The parameter values are used to select the context calibrator.
+    pub fn decalibrate(
+        &self,
+        mdb: &MissionDatabase,
+        dtype: &DataType,
+        eng_value: &Value,
+        values: &ParameterValueList,
+    ) -> Result<Value> {
+        let ctx = self.values_ctx(mdb, values);
+        types::decalibrate(eng_value, dtype, &ctx)
+    }
+
+    /// Returns the calibrator which would be used for the parameter given the values of the other parameters:
+    /// the first context calibrator whose criteria matches or the default calibrator if none matches.
+    ///
+    /// Returns None if the parameter has no data type or its type has no calibrator applying.
+    pub fn effective_calibrator<'a>(
+        &self,
+        mdb: &'a MissionDatabase,
+        pidx: ParameterIdx,
+        values: &ParameterValueList,
+    ) -> Option<&'a Calibrator> {
+        let dtype = mdb.get_data_type(mdb.get_parameter(pidx).ptype?);
+        let ctx = self.values_ctx(mdb, values);
+        types::select_calibrator(dtype, &ctx)
+    }
+
+    /// Returns the alarm level of the value of an enumerated parameter given the values of the other parameters:
+    /// the level from the first context alarm whose criteria matches or from the default alarm if none matches.
+    ///
+    /// Returns None if the parameter is not of an enumerated type, its type has no alarm applying or
+    /// the engineering value is not an enumerated value.
+    pub fn enumeration_alarm_level(
+        &self,
+        mdb: &MissionDatabase,
+        pv: &ParameterValue,
+        values: &ParameterValueList,
+    ) -> Option<AlarmLevel> {
+        let dtype = mdb.get_data_type(mdb.get_parameter(pv.pidx).ptype?);
+        let (TypeData::Enumerated(edt), Value::Enumerated(ev)) = (&dtype.type_data, &pv.eng_value) else {
+            return None;
+        };
+        let ctx = self.values_ctx(mdb, values);
+        types::select_enumeration_alarm(edt, &ctx).map(|alarm| alarm.alarm_level(ev))
+    }
+
+    /// Recomputes the engineering values of the list from their raw values, for example after the
+    /// calibrators of the MDB have been modified.
+    ///
+    /// The values are recalibrated in the order of the list and the context calibrators are
+    /// selected using the values preceding each value, as when the packet has been processed.
+    /// The values of the parameters whose type has no encoding are not derived from a raw value and
+    /// are kept unchanged. The list is not modified if an error is returned.
+    pub fn recalibrate(&self, mdb: &MissionDatabase, list: &mut ParameterValueList) -> Result<()> {
+        let empty = ParameterValueList::new();
+        let mut ctx = self.values_ctx(mdb, &empty);
+
+        for pv in &*list {
+            let param = mdb.get_parameter(pv.pidx);
+            let ptype_idx = param.ptype.ok_or_else(|| {
+                ProcError::NoDataTypeAvailable(format!(
+                    "No data type available for parameter {}",
+                    mdb.name2str(param.name())
+                ))
+            })?;
+            let dtype = mdb.get_data_type(ptype_idx);
+            if let DataEncoding::None = dtype.encoding {
+                ctx.add_value(pv.clone());
+                continue;
+            }
+            ctx.pidx.replace(pv.pidx);
+            let eng_value = types::calibrate(&pv.raw_value, dtype, &mut ctx)?;
+            ctx.add_value(ParameterValue { eng_value, ..pv.clone() });
+        }
+        *list = ctx.result.into_owned();
+
+        Ok(())
+    }
+
+    // context for using the given values without any packet
+    fn values_ctx<'a, 'c>(&'a self, mdb: &'a MissionDatabase, values: &'c ParameterValueList) -> ProcCtx<'a, 'a, 'c> {
+        ProcCtx {
+            mdb,
+            pdata: self,
+            cbuf: ContainerBuf::new(&[]),
+            result: Cow::Borrowed(values),
+            pidx: None,
+            segments: HashMap::new(),
+            criteria_cache: RefCell::new(HashMap::new()),
+            record_positions: false,
+            container_refs: Vec::new(),
+        }
+    }
+
     fn create_evaluator(
         mdb: &MissionDatabase,
         criteria: &MatchCriteria,
@@ -80,6 +308,10 @@ impl ProcessorData {
             MatchCriteria::ComparisonList(clist) => {
                 criteria_evaluator::from_comparison_list(mdb, clist)?
             }
+            MatchCriteria::BooleanExpression(expr) => {
+                criteria_evaluator::from_boolean_expression(mdb, expr)?
+            }
+            MatchCriteria::CustomAlgorithm(algo) => criteria_evaluator::from_custom_algorithm(algo),
         };
 
         Ok(res)
@@ -98,6 +330,12 @@ impl<'a> ContainerBuf<'a> {
         ContainerBuf { buf: BitBuffer::wrap(packet), start_offset: 0 }
     }
 
+    /// creates a container buffer starting at the given byte offset in the packet
+    /// panics if the offset is larger than the packet size
+    pub fn new_at(packet: &'a [u8], start_offset: u32) -> ContainerBuf<'a> {
+        ContainerBuf { buf: BitBuffer::wrap(&packet[start_offset as usize..]), start_offset }
+    }
+
     pub fn slice(&'a self) -> ContainerBuf {
         ContainerBuf { buf: self.buf.slice(), start_offset: (self.buf.get_position() / 8) as u32 }
     }
@@ -106,37 +344,146 @@ impl<'a> ContainerBuf<'a> {
         self.buf.set_position(bit_pos);
     }
 
-    fn get_position(&self) -> usize {
+    pub fn get_position(&self) -> usize {
         self.buf.get_position()
     }
 
     /// return the total size in bits of the container buffer
-    fn bitsize(&self) -> usize {
+    pub fn bitsize(&self) -> usize {
         self.buf.bitsize()
     }
-    fn remaining_bytes(&self) -> usize {
-        self.buf.remaining_bytes()
+
+    /// returns the number of whole bytes from the current position until the end of the buffer
+    pub fn remaining_bytes(&self) -> usize {
+        self.buf.remaining_bits() / 8
+    }
+
+    /// reads up to 64 bits (big endian unless changed by the previous extractions)
+    /// returns an OutOfBounds error if the buffer does not contain enough data
+    pub fn get_bits(&mut self, num_bits: usize) -> Result<u64> {
+        // the bit buffer cannot read more than 64 bits at once and misbehaves when reading none
+        if num_bits == 0 {
+            return Ok(0);
+        }
+        if num_bits > 64 {
+            return Err(ProcError::InvalidMdb(format!(
+                "Cannot read {} bits at once, the maximum is 64",
+                num_bits
+            )));
+        }
+        self.check_remaining_bits(num_bits)?;
+        Ok(self.buf.get_bits(num_bits))
+    }
+
+    /// returns an InvalidMdb error if the position is not at a byte boundary
+    /// returns an OutOfBounds error if the buffer does not contain enough data
+    pub fn get_byte(&mut self) -> Result<u8> {
+        self.check_byte_boundary()?;
+        self.check_remaining_bits(8)?;
+        Ok(self.buf.get_byte())
     }
 
-    fn get_bits(&mut self, num_bits: usize) -> u64 {
-        self.buf.get_bits(num_bits)
+    /// returns a reference to the next len bytes and advances the position
+    /// returns an InvalidMdb error if the position is not at a byte boundary
+    /// returns an OutOfBounds error if the buffer does not contain enough data
+    pub fn get_bytes_ref(&mut self, len: usize) -> Result<&[u8]> {
+        self.check_byte_boundary()?;
+        if len > self.remaining_bytes() {
+            return Err(ProcError::OutOfBounds(format!(
+                "Cannot read {} bytes at position {}: only {} bytes remain in the container buffer",
+                len,
+                self.buf.get_position(),
+                self.remaining_bytes()
+            )));
+        }
+        Ok(self.buf.get_bytes_ref(len))
     }
 
-    fn get_byte(&mut self) -> u8 {
-        self.buf.get_byte()
+    // the byte reads from the bit buffer panic or read the wrong data when not at a byte boundary
+    fn check_byte_boundary(&self) -> Result<()> {
+        if self.buf.get_position() & 7 != 0 {
+            return Err(ProcError::InvalidMdb(format!(
+                "Cannot read bytes at bit position {}: not at a byte boundary",
+                self.buf.get_position()
+            )));
+        }
+        Ok(())
     }
 
-    pub fn get_bytes_ref(&mut self, len: usize) -> &[u8] {
-        self.buf.get_bytes_ref(len)
+    // the reads from the bit buffer panic when going past the end of the data
+    fn check_remaining_bits(&self, num_bits: usize) -> Result<()> {
+        if num_bits > self.buf.remaining_bits() {
+            return Err(ProcError::OutOfBounds(format!(
+                "Cannot read {} bits at position {}: the container buffer has only {} bits",
+                num_bits,
+                self.buf.get_position(),
+                self.buf.bitsize()
+            )));
+        }
+        Ok(())
     }
 }
 
 pub(crate) struct ProcCtx<'a, 'b, 'c> {
     mdb: &'a MissionDatabase,
-    pdata: &'b mut ProcessorData,
+    pdata: &'b ProcessorData,
     cbuf: ContainerBuf<'c>,
-    result: ParameterValueList,
+    // the values extracted so far, borrowed when evaluating criteria against a given list
+    result: Cow<'c, ParameterValueList>,
     pidx: Option<ParameterIdx>,
+    // the parameters and containers split in segments for which not all the segments have been extracted
+    segments: HashMap<SegmentTarget, SegmentBuf>,
+    // the results of the criteria evaluated since the last value was added to the result
+    criteria_cache: RefCell<HashMap<MatchCriteriaIdx, MatchResult>>,
+    // if false, the extracted values do not get their position, they are not from a packet or from its segments
+    record_positions: bool,
+    // the containers referenced by the container entries being extracted, to detect the reference cycles
+    container_refs: Vec<ContainerIdx>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum SegmentTarget {
+    Parameter(ParameterIdx),
+    Container(ContainerIdx),
+}
+
+/// accumulates the bits of the segments of one parameter or container
+struct SegmentBuf {
+    data: Vec<u8>,
+    num_bits: usize,
+    // the total number of bits expected
+    size_in_bits: usize,
+    last_order: Option<u32>,
+    // the bit position of the first segment in the container buffer
+    start_position: usize,
+}
+
+impl SegmentBuf {
+    fn new(size_in_bits: usize, start_position: usize) -> SegmentBuf {
+        SegmentBuf {
+            data: Vec::with_capacity(size_in_bits.div_ceil(8)),
+            num_bits: 0,
+            size_in_bits,
+            last_order: None,
+            start_position,
+        }
+    }
+
+    // appends the n rightmost bits of x
+    fn push_bits(&mut self, x: u64, n: usize) {
+        for i in (0..n).rev() {
+            if self.num_bits & 7 == 0 {
+                self.data.push(0);
+            }
+            let bit = ((x >> i) & 1) as u8;
+            *self.data.last_mut().unwrap() |= bit << (7 - (self.num_bits & 7));
+            self.num_bits += 1;
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.num_bits >= self.size_in_bits
+    }
 }
 
 impl<'a> ProcCtx<'a, '_, '_> {
@@ -154,17 +501,62 @@ impl<'a> ProcCtx<'a, '_, '_> {
 
         self.result.last_inserted(para_ref.pidx).map(|pv| &pv.eng_value).map_or(None, |val| {
             if let Some(path) = &para_ref.member_path {
-                get_member_value(val, path)
+                let dtype = self.mdb.get_data_type(self.mdb.get_parameter(para_ref.pidx).ptype?);
+                get_member_value(self.mdb, dtype, val, path, |iv| types::integer_value(iv, self).ok())
             } else {
                 Some(val)
             }
         })
     }
 
+    /// evaluates the criteria against the values extracted so far.
+    ///
+    /// The results are cached until the next value is added with add_value.
+    fn evaluate_criteria(&self, mcidx: MatchCriteriaIdx) -> MatchResult {
+        if let Some(res) = self.criteria_cache.borrow().get(&mcidx) {
+            return *res;
+        }
+        let res = self.pdata.get_criteria_evaluator(mcidx).evaluate(self);
+        self.criteria_cache.borrow_mut().insert(mcidx, res);
+        res
+    }
+
+    /// adds the value to the result, invalidating the cached criteria results
+    fn add_value(&mut self, pv: ParameterValue) {
+        self.result.to_mut().push(pv);
+        self.criteria_cache.get_mut().clear();
+    }
+
     ///
     /// returns the value of the dynamic value as a unsigned integer.
     /// returns an error if the value cannot be extracted from the current context or if it cannot be converted to u64
-    fn get_dynamic_uint_value(&self, dynpara: &DynamicValueType) -> Result<u64> {
+    fn get_dynamic_uint_value(&self, dynvalue: &DynamicValueType) -> Result<u64> {
+        match dynvalue {
+            DynamicValueType::Parameter(dynpara) => self.get_parameter_uint_value(dynpara),
+            DynamicValueType::DiscreteLookupList(lookups) => self.get_lookup_uint_value(lookups),
+        }
+    }
+
+    // the value of the first lookup whose criteria matches
+    fn get_lookup_uint_value(&self, lookups: &[DiscreteLookup]) -> Result<u64> {
+        let mut tried = Vec::with_capacity(lookups.len());
+        for lookup in lookups {
+            let res = self.evaluate_criteria(lookup.criteria);
+            if res == MatchResult::OK {
+                return u64::try_from(lookup.value).map_err(|_| {
+                    ProcError::DecodingError(format!("Invalid negative discrete lookup value {}", lookup.value))
+                });
+            }
+            tried.push(format!("{}: {:?}", lookup.value, res));
+        }
+
+        Err(ProcError::DecodingError(format!(
+            "None of the discrete lookups matched, tried (value: result) [{}]",
+            tried.join(", ")
+        )))
+    }
+
+    fn get_parameter_uint_value(&self, dynpara: &ParameterDynamicValue) -> Result<u64> {
         let para_ref = &dynpara.para_ref;
         //let para_name = self.mdb.name2str(self.mdb.get_parameter(para_ref.pidx).name());
 
@@ -211,3 +603,35 @@ impl<'a> ProcCtx<'a, '_, '_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_bytes_ref() {
+        let packet = [0x01, 0x02, 0x03, 0x04];
+        let mut cbuf = ContainerBuf::new(&packet);
+
+        assert_eq!([0x01, 0x02], cbuf.get_bytes_ref(2).unwrap());
+        assert_eq!(2, cbuf.remaining_bytes());
+        assert!(matches!(cbuf.get_bytes_ref(3), Err(ProcError::OutOfBounds(_))));
+        // the failed read does not move the position
+        assert_eq!(16, cbuf.get_position());
+        assert_eq!([0x03, 0x04], cbuf.get_bytes_ref(2).unwrap());
+        assert_eq!(0, cbuf.remaining_bytes());
+        assert!(matches!(cbuf.get_byte(), Err(ProcError::OutOfBounds(_))));
+    }
+
+    #[test]
+    fn test_unaligned_bytes() {
+        let packet = [0x01, 0x02, 0x03, 0x04];
+        let mut cbuf = ContainerBuf::new(&packet);
+
+        cbuf.set_position(4);
+        assert_eq!(3, cbuf.remaining_bytes());
+        assert!(matches!(cbuf.get_bytes_ref(1), Err(ProcError::InvalidMdb(_))));
+        assert!(matches!(cbuf.get_byte(), Err(ProcError::InvalidMdb(_))));
+        assert_eq!(0x10, cbuf.get_bits(8).unwrap());
+    }
+}
@@ -2,19 +2,29 @@ use std::collections::HashMap;
 
 use crate::{
     mdb::{
-        types::{DataEncoding, DataType, TypeData, AggregateDataType, EnumeratedDataType},
-        NameIdx, NamedItem,
+        types::{
+            AggregateDataType, ArrayDataType, Calibrator, DataEncoding, DataType, EnumeratedDataType, EnumerationAlarm,
+            FloatDataType, TypeData, ValidRange,
+        },
+        IntegerValue, NameIdx, NamedItem,
     },
     value::{AggregateValue, ContainerPosition, EnumeratedValue, Value, ContainerPositionDetails}};
 
-use super::{encodings::extract_encoding, ProcCtx, Result, ProcError};
+use super::{
+    calibrators, criteria_evaluator::MatchResult, encodings::extract_encoding, ProcCtx, ProcError, Result,
+    UndefinedEnumeration,
+};
 
 pub(crate) fn extract(ptype: &DataType, ctx: &mut ProcCtx) -> Result<(Value, ContainerPosition)> {
     let mdb = ctx.mdb();
     if let DataEncoding::None = ptype.encoding {
         match &ptype.type_data {
             TypeData::Aggregate(atype) => extract_aggregate(atype, ctx),
-            TypeData::Array(_) => todo!(),
+            TypeData::Array(atype) if atype.dim.is_empty() => Err(ProcError::InvalidMdb(format!(
+                "array data type without dimension list: {}",
+                mdb.name2str(ptype.name())
+            ))),
+            TypeData::Array(atype) => extract_array(atype, ctx),
             _ => {
                 return Err(ProcError::InvalidMdb(format!(
                     "base data type without encoding: {}",
@@ -52,7 +62,7 @@ fn extract_aggregate(
     let rv = Value::Aggregate(Box::new(aggrv));
     let cpos = ContainerPosition {
             start_offset,
-            bit_offset: bit_offset1 as u32,
+            bit_offset: bit_offset0 as u32,
             bit_size: (bit_offset1 - bit_offset0) as u32,
             details: ContainerPositionDetails::Aggregate(posm)
         };
@@ -61,6 +71,94 @@ fn extract_aggregate(
     Ok((rv, cpos))
 }
 
+// extracts an array from a packet, the multi-dimensional arrays are extracted in row-major order
+fn extract_array(atype: &ArrayDataType, ctx: &mut ProcCtx) -> Result<(Value, ContainerPosition)> {
+    let mut sizes = Vec::with_capacity(atype.dim.len());
+    for d in &atype.dim {
+        let start = integer_value(&d.starting_index, ctx)?;
+        let end = integer_value(&d.ending_index, ctx)?;
+        if end < start - 1 {
+            return Err(ctx.decoding_error(&format!(
+                "invalid array dimension: the ending index {} is smaller than the starting index {}",
+                end, start
+            )));
+        }
+        sizes.push((end - start + 1) as usize);
+    }
+    // each element takes at least one bit, this bounds the work done for the dynamic dimensions
+    let remaining = ctx.cbuf.buf.remaining_bits();
+    let num_elements = sizes.iter().try_fold(1usize, |n, &s| n.checked_mul(s));
+    if num_elements.is_none_or(|n| n > remaining) {
+        return Err(ctx.decoding_error(&format!(
+            "the array dimensions {:?} exceed the remaining size in bits {}",
+            sizes, remaining
+        )));
+    }
+    let etype = ctx.mdb().get_data_type(atype.dtype);
+
+    extract_array_dim(etype, &sizes, ctx)
+}
+
+// extracts the elements of the first dimension, each of them is an array of the remaining dimensions
+fn extract_array_dim(
+    etype: &DataType,
+    sizes: &[usize],
+    ctx: &mut ProcCtx,
+) -> Result<(Value, ContainerPosition)> {
+    let bit_offset0 = ctx.cbuf.buf.get_position();
+    let start_offset = ctx.cbuf.start_offset;
+    let mut values = Vec::new();
+    let mut positions = Vec::new();
+
+    for _ in 0..sizes[0] {
+        let (v, cpos) = if sizes.len() > 1 {
+            extract_array_dim(etype, &sizes[1..], ctx)?
+        } else {
+            extract(etype, ctx)?
+        };
+        values.push(v);
+        positions.push(cpos);
+    }
+
+    let bit_offset1 = ctx.cbuf.buf.get_position();
+    let cpos = ContainerPosition {
+        start_offset,
+        bit_offset: bit_offset0 as u32,
+        bit_size: (bit_offset1 - bit_offset0) as u32,
+        details: ContainerPositionDetails::Array(positions),
+    };
+
+    Ok((Value::Array(Box::new(values)), cpos))
+}
+
+pub(super) fn integer_value(iv: &IntegerValue, ctx: &ProcCtx) -> Result<i64> {
+    match iv {
+        IntegerValue::FixedValue(v) => Ok(*v),
+        IntegerValue::DynamicValue(dv) => {
+            let v = ctx.get_dynamic_uint_value(dv)?;
+            i64::try_from(v).map_err(|_| ProcError::OutOfRange(format!("Value {} is too large", v)))
+        }
+    }
+}
+
+// the first context calibrator whose criteria matches or the default calibrator if none matches
+pub(crate) fn select_calibrator<'a>(dt: &'a DataType, ctx: &ProcCtx) -> Option<&'a Calibrator> {
+    dt.context_calibrators
+        .iter()
+        .find(|cc| ctx.evaluate_criteria(cc.criteria) == MatchResult::OK)
+        .map(|cc| &cc.calibrator)
+        .or(dt.calibrator.as_ref())
+}
+
+// the alarm of the first context alarm whose criteria matches or the default alarm if none matches
+pub(crate) fn select_enumeration_alarm<'a>(edt: &'a EnumeratedDataType, ctx: &ProcCtx) -> Option<&'a EnumerationAlarm> {
+    edt.context_alarm
+        .iter()
+        .find(|ca| ctx.evaluate_criteria(ca.criteria) == MatchResult::OK)
+        .map(|ca| &ca.alarm)
+        .or(edt.default_alarm.as_ref())
+}
+
 // transforms the raw value into an egineering value
 pub(crate) fn calibrate(
     rawv: &Value,
@@ -71,17 +169,21 @@ pub(crate) fn calibrate(
         Value::Int64(v) => from_signed_integer(*v, dtype, ctx),
         Value::Uint64(v) => from_unsigned_integer(*v, dtype, ctx),
         Value::Double(v) => from_double(*v, dtype, ctx),
-        Value::Boolean(_) => todo!(),
         Value::StringValue(v) => from_string(v, dtype, ctx),
-        Value::Binary(v) => todo!(),
+        Value::Binary(v) => from_binary(v, dtype, ctx),
         Value::Aggregate(v) => from_aggregate(v, dtype, ctx),
-        _ => panic!("Unexpected raw data type {:?}", rawv),
+        Value::Array(v) => from_array(v, dtype, ctx),
+        // the booleans, enumerated values and timestamps are engineering values only
+        Value::Boolean(_) | Value::Enumerated(_) | Value::Timestamp(_) => Err(ProcError::InvalidValue(format!(
+            "Cannot calibrate the value {:?}, it is not a raw value",
+            rawv
+        ))),
     }
 }
 
-fn from_signed_integer(v: i64, dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
-    if let Some(cal) = &dt.calibrator {
-        todo!()
+fn from_signed_integer(v: i64, dt: &DataType, ctx: &ProcCtx) -> Result<Value> {
+    if let Some(cal) = select_calibrator(dt, ctx) {
+        return from_calibrated_double(calibrators::apply(cal, v as f64)?, dt, ctx);
     }
 
     let x = match &dt.type_data {
@@ -94,10 +196,10 @@ fn from_signed_integer(v: i64, dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
                 Value::uint_value(bitsize, v1)
             }
         }
-        TypeData::Float(_) => Value::Double(v as f64),
+        TypeData::Float(fdt) => float_value(fdt, v as f64),
         TypeData::String(_) => Value::StringValue(Box::new(v.to_string())),
         TypeData::Boolean(_) => Value::Boolean(v != 0),
-        TypeData::Enumerated(edt) => Value::Enumerated(get_enumeration(edt, v)),
+        TypeData::Enumerated(edt) => Value::Enumerated(get_enumeration(edt, v, ctx)?),
         TypeData::AbsoluteTime(_) => todo!(),
         _ => {
             return Err(ProcError::InvalidValue(format!(
@@ -111,27 +213,24 @@ fn from_signed_integer(v: i64, dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
 }
 
 // computes the engineering value from a unsigned integer raw value
-fn from_unsigned_integer(rv: u64, dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
-    if let Some(cal) = &dt.calibrator {
-        todo!()
+fn from_unsigned_integer(rv: u64, dt: &DataType, ctx: &ProcCtx) -> Result<Value> {
+    if let Some(cal) = select_calibrator(dt, ctx) {
+        return from_calibrated_double(calibrators::apply(cal, rv as f64)?, dt, ctx);
     }
     let x = match &dt.type_data {
         TypeData::Integer(idt) => {
             let bitsize = idt.size_in_bits as usize;
             if idt.signed {
-                if rv > i64::MAX as u64 {
-                    Value::uint_value(bitsize, i64::MAX as u64)
-                } else {
-                    Value::uint_value(bitsize, rv)
-                }
+                let v1 = if rv > i64::MAX as u64 { i64::MAX } else { rv as i64 };
+                Value::int_value(bitsize, v1)
             } else {
                 Value::uint_value(bitsize, rv)
             }
         }
-        TypeData::Float(_) => Value::Double(rv as f64),
+        TypeData::Float(fdt) => float_value(fdt, rv as f64),
         TypeData::String(_) => Value::StringValue(Box::new(rv.to_string())),
         TypeData::Boolean(_) => Value::Boolean(rv != 0),
-        TypeData::Enumerated(edt) => Value::Enumerated(get_enumeration(edt, rv as i64)),
+        TypeData::Enumerated(edt) => Value::Enumerated(get_enumeration(edt, rv as i64, ctx)?),
         TypeData::AbsoluteTime(_) => todo!(),
         _ => {
             return Err(ProcError::InvalidValue(format!(
@@ -147,11 +246,16 @@ fn from_unsigned_integer(rv: u64, dt: &DataType, _ctx: &ProcCtx) -> Result<Value
 
 
 // computes the engineering value from a double value
-fn from_double(rv: f64, dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
-    if let Some(cal) = &dt.calibrator {
-        todo!()
-    }
-    
+fn from_double(rv: f64, dt: &DataType, ctx: &ProcCtx) -> Result<Value> {
+    let v = match select_calibrator(dt, ctx) {
+        Some(cal) => calibrators::apply(cal, rv)?,
+        None => rv,
+    };
+    from_calibrated_double(v, dt, ctx)
+}
+
+// converts the output of the calibrator (or the uncalibrated double raw value) to the engineering type
+fn from_calibrated_double(rv: f64, dt: &DataType, ctx: &ProcCtx) -> Result<Value> {
     let x = match &dt.type_data {
         TypeData::Integer(idt) => {
             let bitsize = idt.size_in_bits as usize;
@@ -161,14 +265,14 @@ fn from_double(rv: f64, dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
                 Value::uint_value(bitsize, rv as u64)
             }
         }
-        TypeData::Float(_) => Value::Double(rv),
+        TypeData::Float(fdt) => float_value(fdt, rv),
         TypeData::String(_) => Value::StringValue(Box::new(rv.to_string())),
         TypeData::Boolean(_) => Value::Boolean(rv != 0.0),
-        TypeData::Enumerated(edt) => Value::Enumerated(get_enumeration(edt, rv as i64)),
+        TypeData::Enumerated(edt) => Value::Enumerated(get_enumeration(edt, rv as i64, ctx)?),
         TypeData::AbsoluteTime(_) => todo!(),
         _ => {
             return Err(ProcError::InvalidValue(format!(
-                "cannot convert unsigned integer to {:?}",
+                "cannot convert double to {:?}",
                 dt.type_data
             )))
         }
@@ -176,6 +280,26 @@ fn from_double(rv: f64, dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
 
     Ok(x)
 }
+
+// the values of the 32 bits float types are rounded to single precision,
+// the finite values outside the single precision range are clamped to the largest finite values
+fn float_value(fdt: &FloatDataType, v: f64) -> Value {
+    if fdt.size_in_bits == 32 {
+        let v = if v.is_finite() { v.clamp(f32::MIN as f64, f32::MAX as f64) } else { v };
+        Value::Double(v as f32 as f64)
+    } else {
+        Value::Double(v)
+    }
+}
+
+// computes the engineering value from a binary raw value
+fn from_binary(rv: &[u8], dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
+    match &dt.type_data {
+        TypeData::Binary(_) => Ok(Value::Binary(Box::new(rv.to_vec()))),
+        _ => Err(ProcError::InvalidValue(format!("cannot convert binary to {:?}", dt.type_data))),
+    }
+}
+
 // computes an aggregate engineering value from an aggregate raw value
 fn from_aggregate(
     aggr_rv: &Box<AggregateValue>,
@@ -209,15 +333,63 @@ fn from_aggregate(
     Ok(ev)
 }
 
+// computes an array engineering value by calibrating each element of the raw array
+fn from_array(arr_rv: &[Value], dt: &DataType, ctx: &mut ProcCtx) -> Result<Value> {
+    if let TypeData::Array(atype) = &dt.type_data {
+        let etype = ctx.mdb().get_data_type(atype.dtype);
+        calibrate_array_dim(arr_rv, etype, atype.dim.len(), ctx)
+    } else {
+        Err(ProcError::InvalidValue(format!("Got array value for type {:?})", dt)))
+    }
+}
+
+// calibrates the elements of an array with num_dim dimensions
+fn calibrate_array_dim(
+    arr_rv: &[Value],
+    etype: &DataType,
+    num_dim: usize,
+    ctx: &mut ProcCtx,
+) -> Result<Value> {
+    let mut values = Vec::with_capacity(arr_rv.len());
+    for rv in arr_rv {
+        let ev = match rv {
+            Value::Array(v) if num_dim > 1 => calibrate_array_dim(v, etype, num_dim - 1, ctx)?,
+            _ if num_dim > 1 => {
+                return Err(ProcError::InvalidValue(format!(
+                    "Expected an array of {} dimensions, got {:?}",
+                    num_dim, rv
+                )))
+            }
+            _ => calibrate(rv, etype, ctx)?,
+        };
+        values.push(ev);
+    }
+
+    Ok(Value::Array(Box::new(values)))
+}
+
 // computes an enumerated engineering value from a signed integer raw values
-fn get_enumeration(edt: &EnumeratedDataType, rv: i64) -> Box<EnumeratedValue> {
+// the values not matching any enumeration are treated as configured in the processor data
+fn get_enumeration(
+    edt: &EnumeratedDataType,
+    rv: i64,
+    ctx: &ProcCtx,
+) -> Result<Box<EnumeratedValue>> {
     for e in &edt.enumeration {
         if e.value <= rv && rv <= e.max_value {
-            return Box::new(EnumeratedValue { key: rv, value: e.label.clone() });
+            return Ok(Box::new(EnumeratedValue { key: rv, value: e.label.clone() }));
         }
     }
 
-    return Box::new(EnumeratedValue { key: rv, value: String::from("UNDEF") });
+    match &ctx.pdata.undefined_enumeration {
+        UndefinedEnumeration::Label(label) => {
+            Ok(Box::new(EnumeratedValue { key: rv, value: label.clone() }))
+        }
+        UndefinedEnumeration::Error => Err(ctx.decoding_error(&format!(
+            "the raw value {} does not match any enumeration value",
+            rv
+        ))),
+    }
 }
 
 
@@ -238,4 +410,196 @@ fn from_string(rv: &str, dt: &DataType, _ctx: &ProcCtx) -> Result<Value> {
     };
 
     Ok(x)
-}
\ No newline at end of file
+}
+
+/// returns the range of the engineering values of the numeric types, for example to be used as default
+/// limits of the plot axes.
+///
+/// If the valid ranges apply to the engineering value and all have both limits, the range containing them is
+/// returned. Otherwise the range of the raw values (given by the encoding and the raw valid ranges) is mapped
+/// through the default calibrator; the context calibrators are not considered.
+pub fn engineering_range(dtype: &DataType) -> Option<(f64, f64)> {
+    let type_range = match &dtype.type_data {
+        TypeData::Integer(idt) => {
+            let n = idt.size_in_bits;
+            if idt.signed {
+                (-(2f64.powi(n as i32 - 1)), 2f64.powi(n as i32 - 1) - 1.0)
+            } else {
+                (0.0, 2f64.powi(n as i32) - 1.0)
+            }
+        }
+        TypeData::Float(fdt) if fdt.size_in_bits == 32 => (f32::MIN as f64, f32::MAX as f64),
+        TypeData::Float(_) => (f64::MIN, f64::MAX),
+        _ => return None,
+    };
+
+    let ranges = dtype.valid_ranges();
+    if let Some((Some(min), Some(max))) = range_hull(ranges.iter().filter(|vr| vr.applies_to_calibrated)) {
+        return Some((min, max));
+    }
+
+    let (mut rmin, mut rmax) = match &dtype.encoding {
+        DataEncoding::Integer(ide) => {
+            let (min, max) = ide.raw_range();
+            (min as f64, max as f64)
+        }
+        DataEncoding::Float(fde) if fde.size_in_bits == 32 => (f32::MIN as f64, f32::MAX as f64),
+        DataEncoding::Float(_) => (f64::MIN, f64::MAX),
+        _ => return None,
+    };
+    if let Some((min, max)) = range_hull(ranges.iter().filter(|vr| !vr.applies_to_calibrated)) {
+        rmin = min.map_or(rmin, |min| rmin.max(min));
+        rmax = max.map_or(rmax, |max| rmax.min(max));
+    }
+
+    let (min, max) = match &dtype.calibrator {
+        Some(cal) => calibrated_range(cal, rmin, rmax)?,
+        None => (rmin, rmax),
+    };
+
+    // the engineering values are limited to the range of the type
+    Some((min.max(type_range.0), max.min(type_range.1)))
+}
+
+// the smallest interval containing all the valid ranges, None if there is no range;
+// a bound is None if one of the ranges is not limited on that side
+fn range_hull<'a>(mut ranges: impl Iterator<Item = &'a ValidRange>) -> Option<(Option<f64>, Option<f64>)> {
+    let first = ranges.next()?;
+    Some(ranges.fold((first.min, first.max), |(min, max), vr| {
+        (min.zip(vr.min).map(|(a, b)| a.min(b)), max.zip(vr.max).map(|(a, b)| a.max(b)))
+    }))
+}
+
+// the minimum and maximum of the calibrator output for the inputs in [rmin, rmax]
+fn calibrated_range(cal: &Calibrator, rmin: f64, rmax: f64) -> Option<(f64, f64)> {
+    // the extremes are reached at the ends of the interval or at the inner points given here
+    let mut inputs = vec![rmin, rmax];
+    match cal {
+        Calibrator::Polynomial(pc) => match pc.coefficients.len() {
+            0..=2 => {}
+            3 if pc.coefficients[2] != 0.0 => inputs.push(-pc.coefficients[1] / (2.0 * pc.coefficients[2])),
+            _ => inputs.extend(sample_interval(rmin, rmax)),
+        },
+        Calibrator::MathOperation(_) => inputs.extend(sample_interval(rmin, rmax)),
+        Calibrator::Spline(sc) => {
+            if !sc.extrapolate {
+                // outside of the points there is no calibrated value
+                inputs = vec![rmin.max(sc.points[0].raw), rmax.min(sc.points[sc.points.len() - 1].raw)];
+                if inputs[0] > inputs[1] {
+                    return None;
+                }
+            }
+            inputs.extend(sc.points.iter().map(|p| p.raw));
+        }
+    }
+
+    inputs
+        .into_iter()
+        .filter(|x| (rmin..=rmax).contains(x))
+        .filter_map(|x| calibrators::apply(cal, x).ok())
+        .fold(None, |acc, y| match acc {
+            None => Some((y, y)),
+            Some((min, max)) => Some((f64::min(min, y), f64::max(max, y))),
+        })
+}
+
+// no closed form for the higher degree polynomials and the math operations,
+// the range is approximated by sampling the interval
+fn sample_interval(rmin: f64, rmax: f64) -> impl Iterator<Item = f64> {
+    const SAMPLES: usize = 256;
+    (1..SAMPLES).map(move |i| rmin + (rmax - rmin) * i as f64 / SAMPLES as f64)
+}
+
+/// transforms the engineering value into the raw value to be encoded, the inverse of calibrate
+///
+/// The raw value type corresponds to the data encoding: Uint64 for unsigned integers, Int64 for the other
+/// integer encodings, Double for floats.
+/// Returns an error if the value does not fit into the encoding or if the calibrator cannot be inverted.
+pub(crate) fn decalibrate(engv: &Value, dtype: &DataType, ctx: &ProcCtx) -> Result<Value> {
+    match &dtype.type_data {
+        TypeData::Enumerated(edt) => {
+            let label = match engv {
+                Value::Enumerated(ev) => ev.value.as_str(),
+                Value::StringValue(s) => s.as_str(),
+                _ => return Err(cannot_decalibrate(engv, dtype)),
+            };
+            let e = edt.enumeration.iter().find(|e| e.label == label).ok_or_else(|| {
+                ProcError::InvalidValue(format!(
+                    "Invalid label '{}' for the enumerated type {}",
+                    label,
+                    ctx.mdb.name2str(dtype.name())
+                ))
+            })?;
+            raw_from_integer(e.value as i128, dtype)
+        }
+        TypeData::Boolean(bdt) => {
+            let b = match engv {
+                Value::Boolean(b) => *b,
+                Value::StringValue(s) if **s == bdt.one_string_value => true,
+                Value::StringValue(s) if **s == bdt.zero_string_value => false,
+                _ => return Err(cannot_decalibrate(engv, dtype)),
+            };
+            raw_from_integer(b as i128, dtype)
+        }
+        TypeData::Integer(_) | TypeData::Float(_) => {
+            if let Some(cal) = select_calibrator(dtype, ctx) {
+                let y: f64 = engv.try_into().map_err(|_| cannot_decalibrate(engv, dtype))?;
+                return raw_from_double(calibrators::inverse(cal, y)?, dtype);
+            }
+            match engv {
+                Value::Int64(x) => raw_from_integer(*x as i128, dtype),
+                Value::Uint64(x) => raw_from_integer(*x as i128, dtype),
+                Value::Double(x) => raw_from_double(*x, dtype),
+                _ => Err(cannot_decalibrate(engv, dtype)),
+            }
+        }
+        TypeData::String(_) | TypeData::Binary(_) => match (engv, &dtype.encoding) {
+            (Value::StringValue(_), DataEncoding::String(_)) | (Value::Binary(_), DataEncoding::Binary(_)) => {
+                Ok(engv.clone())
+            }
+            _ => Err(cannot_decalibrate(engv, dtype)),
+        },
+        _ => Err(cannot_decalibrate(engv, dtype)),
+    }
+}
+
+fn cannot_decalibrate(engv: &Value, dtype: &DataType) -> ProcError {
+    ProcError::InvalidValue(format!("Cannot convert value {:?} to a raw value of {:?}", engv, dtype.type_data))
+}
+
+// the double is rounded to the nearest integer for the integer encodings
+fn raw_from_double(x: f64, dtype: &DataType) -> Result<Value> {
+    match &dtype.encoding {
+        DataEncoding::Float(_) => Ok(Value::Double(x)),
+        DataEncoding::Integer(_) => {
+            if !x.is_finite() {
+                return Err(ProcError::OutOfRange(format!("Cannot encode {} as an integer", x)));
+            }
+            raw_from_integer(x.round() as i128, dtype)
+        }
+        _ => Err(cannot_decalibrate(&Value::Double(x), dtype)),
+    }
+}
+
+fn raw_from_integer(x: i128, dtype: &DataType) -> Result<Value> {
+    match &dtype.encoding {
+        DataEncoding::Integer(ide) => {
+            let (min, max) = ide.raw_range();
+            if x < min || x > max {
+                return Err(ProcError::OutOfRange(format!(
+                    "Raw value {} does not fit into the {} bits {:?} encoding",
+                    x, ide.size_in_bits, ide.encoding
+                )));
+            }
+            if ide.encoding.is_signed() {
+                Ok(Value::Int64(x as i64))
+            } else {
+                Ok(Value::Uint64(x as u64))
+            }
+        }
+        DataEncoding::Float(_) => Ok(Value::Double(x as f64)),
+        DataEncoding::Boolean(_) => Ok(Value::Boolean(x != 0)),
+        DataEncoding::String(_) => Ok(Value::StringValue(Box::new(x.to_string()))),
+        _ => Err(cannot_decalibrate(&Value::Int64(x as i64), dtype)),
+    }
+}
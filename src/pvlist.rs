@@ -2,8 +2,9 @@ use std::{
     collections::HashMap,
 };
 
-use crate::{mdb::ParameterIdx, value::{ParameterValue, Value}};
+use crate::{mdb::{MissionDatabase, ParameterIdx}, value::{ParameterValue, Value}};
 
+#[derive(Clone)]
 struct Entry {
     //the index of the previous entry for the same parameter
     prev: u32,
@@ -11,6 +12,7 @@ struct Entry {
 }
 
 /// Parameter Value list indexed by parameter
+#[derive(Clone)]
 pub struct ParameterValueList {
     // list of parameter values
     entries: Vec<Entry>,
@@ -30,6 +32,7 @@ impl ParameterValueList {
         self.entries.push(Entry { prev, pv });
     }
 
+    /// returns the last value inserted for the parameter, equivalent to get_by_param
     pub fn last_inserted<'a>(&'a self, pidx: ParameterIdx) -> Option<&'a ParameterValue> {
         self.last_idx.get(&pidx).and_then(|&lidx| {
             if lidx < u32::MAX {
@@ -39,17 +42,52 @@ impl ParameterValueList {
             }
         })
     }
+
+    /// returns the last value of the parameter or None if the list does not contain any value for it
+    pub fn get_by_param(&self, pidx: ParameterIdx) -> Option<&ParameterValue> {
+        self.last_inserted(pidx)
+    }
+
     pub fn len(&self) -> usize {
         self.entries.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// returns the value at the position idx in the insertion order or None if idx is out of range
+    pub fn get(&self, idx: usize) -> Option<&ParameterValue> {
+        self.entries.get(idx).map(|e| &e.pv)
+    }
+
+    /// returns the engineering value at the position idx in the insertion order
+    ///
+    /// Like the indexing with list[idx], panics if idx is out of range; use get for a non panicking access.
     pub fn eng(&self, idx: usize) -> &Value {
         &self.entries[idx].pv.eng_value
     }
+
+    /// returns the raw value at the position idx in the insertion order
+    ///
+    /// Like the indexing with list[idx], panics if idx is out of range; use get for a non panicking access.
     pub fn raw(&self, idx: usize) -> &Value {
         &self.entries[idx].pv.raw_value
     }
 
+    /// converts the list into a map from the parameter qualified name to the value.
+    ///
+    /// Only the last instance of each parameter is kept.
+    pub fn into_named_map(self, mdb: &MissionDatabase) -> HashMap<String, ParameterValue> {
+        let last_idx = self.last_idx;
+        self.entries
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, e)| last_idx.get(&e.pv.pidx) == Some(&(*idx as u32)))
+            .map(|(_, e)| (mdb.parameter_qn(e.pv.pidx), e.pv))
+            .collect()
+    }
+
 }
 
 /// this is to allow to do "for pv in pvlist"
@@ -93,6 +131,7 @@ impl<'a> Iterator for IntoIterRef<'a> {
     }
 }
 
+/// the values in the insertion order; panics if the index is out of range
 impl std::ops::Index<usize> for ParameterValueList {
     type Output = ParameterValue;
 
@@ -101,3 +140,38 @@ impl std::ops::Index<usize> for ParameterValueList {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pv(pidx: usize, v: u64) -> ParameterValue {
+        ParameterValue::new(ParameterIdx::new(pidx), Value::Uint64(v), Value::Uint64(v + 100))
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut list = ParameterValueList::new();
+        assert!(list.is_empty());
+        list.push(pv(0, 1));
+        list.push(pv(1, 2));
+        list.push(pv(0, 3));
+
+        assert_eq!(3, list.len());
+        assert_eq!(&Value::Uint64(2), list.raw(1));
+        assert_eq!(&Value::Uint64(102), list.eng(1));
+        assert_eq!(Value::Uint64(3), list[2].raw_value);
+        assert_eq!(Value::Uint64(3), list.get(2).unwrap().raw_value);
+        assert!(list.get(3).is_none());
+
+        assert_eq!(Value::Uint64(3), list.get_by_param(ParameterIdx::new(0)).unwrap().raw_value);
+        assert_eq!(Value::Uint64(2), list.get_by_param(ParameterIdx::new(1)).unwrap().raw_value);
+        assert!(list.get_by_param(ParameterIdx::new(2)).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_range() {
+        let list = ParameterValueList::new();
+        let _ = list.eng(0);
+    }
+}
@@ -1,24 +1,74 @@
 use std::{
     collections::HashMap,
     fmt::{self, Debug, Formatter},
+    time::Duration,
 };
 
 use hex::ToHex;
+use lasso::Key;
 
-use crate::mdb::{MissionDatabase, NameIdx, NamedItem, ParameterIdx};
+use crate::mdb::{MissionDatabase, NameIdx, NamedItem, ParameterIdx, RateBasis, RateInStream};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParameterValue {
     pub pidx: ParameterIdx,
     pub raw_value: Value,
     pub eng_value: Value,
+    /// result of the error detection check for the parameters carrying a CRC, None for the other parameters
+    pub error_check: Option<ErrorCheck>,
+    /// the bits occupied in the packet, None if the value has not been extracted from a packet or has been
+    /// extracted from segments (it is not contiguous in the packet)
+    pub position: Option<ContainerPosition>,
+}
+
+impl ParameterValue {
+    pub fn new(pidx: ParameterIdx, raw_value: Value, eng_value: Value) -> Self {
+        ParameterValue { pidx, raw_value, eng_value, error_check: None, position: None }
+    }
+
+    /// returns true if the raw value differs significantly from the raw value of the previous parameter value.
+    ///
+    /// The threshold is normally the change threshold of the parameter type encoding. The numeric values are
+    /// significantly different if they differ by more than the threshold. Without a threshold or for non numeric
+    /// values any change is significant.
+    pub fn significant_change(&self, previous: &ParameterValue, threshold: Option<f64>) -> bool {
+        let numeric = (f64::try_from(&self.raw_value), f64::try_from(&previous.raw_value));
+        match (threshold, numeric) {
+            (Some(t), (Ok(x), Ok(y))) => (x - y).abs() > t,
+            _ => self.raw_value != previous.raw_value,
+        }
+    }
+
+    /// returns the duration after which a value of a parameter with the given rate in stream should be considered
+    /// expired (stale) if no new value has been received.
+    ///
+    /// It is the longest expected interval between two values, derived from the minimum rate. Returns None if the
+    /// minimum rate is not specified or not positive, or if the rate is per container update (the interval
+    /// depends on the rate of the container).
+    pub fn expiry(rate: &RateInStream) -> Option<Duration> {
+        match (rate.basis, rate.minimum_value) {
+            (RateBasis::PerSecond, Some(min)) if min > 0.0 => Duration::try_from_secs_f64(1.0 / min).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of verifying an error detection code extracted from a packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorCheck {
+    Passed,
+    /// the code extracted from the packet differs from the computed one
+    Failed { computed: u64 },
 }
 
 
 /// Unlike the Java Yamcs, we do not support the 32 bits integers or floats.
 /// It simplifies the code and no extra space is consumed becuase the enum is taking 16 bytes anyway.
 /// Note that the integer parameter extraction will shrink the numbers to fit into the size in bits specified in the type
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Int64(i64),
     Uint64(u64),
@@ -29,17 +79,101 @@ pub enum Value {
     Enumerated(Box<EnumeratedValue>),
     Binary(Box<Vec<u8>>),
     Aggregate(Box<AggregateValue>),
+    /// the multi-dimensional arrays are arrays of arrays
+    Array(Box<Vec<Value>>),
+    Timestamp(Timestamp),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumeratedValue {
     pub key: i64,
     pub value: String,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AggregateValue(pub HashMap<NameIdx, Value>);
 
+/// An instant in time as milliseconds since 1970-01-01T00:00:00Z, not counting the leap seconds.
+///
+/// It is displayed in the ISO-8601 format with millisecond resolution, e.g. 2024-03-01T12:30:05.250Z
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(pub i64);
+
+impl Timestamp {
+    pub fn millis(&self) -> i64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const MILLIS_IN_DAY: i64 = 86_400_000;
+        let days = self.0.div_euclid(MILLIS_IN_DAY);
+        let ms = self.0.rem_euclid(MILLIS_IN_DAY);
+        let (year, month, day) = civil_from_days(days);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            ms / 3_600_000,
+            ms / 60_000 % 60,
+            ms / 1000 % 60,
+            ms % 1000
+        )
+    }
+}
+
+// converts the number of days since 1970-01-01 into (year, month, day) in the proleptic Gregorian calendar
+// see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097); // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11] starting from March
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Deserializes the timestamp from the structure written by the Serialize implementation,
+/// only the milliseconds are used.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Timestamp {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // the same fields as written by serialize, the iso string is redundant
+        #[derive(serde::Deserialize)]
+        struct TimestampFields {
+            millis: i64,
+            #[allow(dead_code)]
+            iso: String,
+        }
+        TimestampFields::deserialize(deserializer).map(|t| Timestamp(t.millis))
+    }
+}
+
+/// Serializes the timestamp as a structure containing both the milliseconds and the ISO-8601 string:
+/// `{"millis": 1709296205250, "iso": "2024-03-01T12:30:05.250Z"}`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Timestamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut st = serializer.serialize_struct("Timestamp", 2)?;
+        st.serialize_field("millis", &self.0)?;
+        st.serialize_field("iso", &self.to_string())?;
+        st.end()
+    }
+}
+
 
 impl Value {
     pub fn int_value(num_bits: usize, x: i64) -> Value {
@@ -83,13 +217,120 @@ impl std::fmt::Display for Value {
             Value::Double(x) => write!(f, "{}", x),
             Value::Boolean(x) => write!(f, "{}", x),
             Value::StringValue(x) => write!(f, "{}", x),
-            Value::Enumerated(x) => todo!(),
-            Value::Binary(x) => todo!(),
-            Value::Aggregate(x) => write!(f, "{:?}", x),
+            Value::Enumerated(x) => write!(f, "{}", x.value),
+            Value::Binary(x) => write!(f, "{}", x.encode_hex::<String>()),
+            Value::Aggregate(x) => {
+                // the member names are in the name db of the mdb, see ParameterValue::dbg for displaying them
+                let mut members: Vec<_> = x.0.iter().map(|(name, v)| (name.into_usize(), v)).collect();
+                members.sort_unstable_by_key(|(name, _)| *name);
+                f.write_str("{")?;
+                for (i, (name, v)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "#{}: {}", name, v)?;
+                }
+                f.write_str("}")
+            }
+            Value::Array(x) => {
+                f.write_str("[")?;
+                for (i, v) in x.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_str("]")
+            }
+            Value::Timestamp(x) => write!(f, "{}", x),
         }        
     }
 }
 
+/// Options for rendering values as text, used for example by CSV/JSON exporters.
+///
+/// The default renders the values in the same way as the Display implementation.
+#[derive(Debug, Clone)]
+pub struct ValueFormat {
+    /// number of digits after the decimal point for the floating point values.
+    /// None means the shortest representation that round-trips.
+    pub precision: Option<usize>,
+    /// radix for the integer values: 2, 8, 10 or 16.
+    /// The non decimal values are prefixed by 0b, 0o or 0x respectively.
+    pub radix: u32,
+    /// if set, it is used to separate the groups of thousands in the decimal integers
+    /// and in the integer part of the floating point values
+    pub thousands_separator: Option<char>,
+}
+
+impl Default for ValueFormat {
+    fn default() -> Self {
+        ValueFormat { precision: None, radix: 10, thousands_separator: None }
+    }
+}
+
+impl Value {
+    /// Renders the value as a string according to the given format.
+    /// The format options apply only to the numeric values, the other values are rendered as with Display.
+    pub fn format(&self, fmt: &ValueFormat) -> String {
+        match self {
+            Value::Int64(x) => {
+                let s = format_unsigned(x.unsigned_abs(), fmt);
+                if *x < 0 {
+                    format!("-{}", s)
+                } else {
+                    s
+                }
+            }
+            Value::Uint64(x) => format_unsigned(*x, fmt),
+            Value::Double(x) => {
+                let s = match fmt.precision {
+                    Some(p) => format!("{:.*}", p, x),
+                    None => x.to_string(),
+                };
+                match fmt.thousands_separator {
+                    Some(sep) if x.is_finite() => {
+                        let (sign, digits) = s.split_at(if s.starts_with('-') { 1 } else { 0 });
+                        let (int_part, frac_part) = digits.split_at(digits.find('.').unwrap_or(digits.len()));
+                        format!("{}{}{}", sign, group_thousands(int_part, sep), frac_part)
+                    }
+                    _ => s,
+                }
+            }
+            Value::Enumerated(x) => x.value.clone(),
+            Value::Binary(x) => x.encode_hex::<String>(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+fn format_unsigned(x: u64, fmt: &ValueFormat) -> String {
+    match fmt.radix {
+        2 => format!("0b{:b}", x),
+        8 => format!("0o{:o}", x),
+        16 => format!("0x{:x}", x),
+        _ => {
+            let s = x.to_string();
+            match fmt.thousands_separator {
+                Some(sep) => group_thousands(&s, sep),
+                None => s,
+            }
+        }
+    }
+}
+
+// inserts the separator between each group of three digits, counting from the right
+fn group_thousands(digits: &str, sep: char) -> String {
+    let mut r = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            r.push(sep);
+        }
+        r.push(c);
+    }
+    r
+}
+
 impl TryFrom<Value> for i64 {
     type Error = ();
 
@@ -137,7 +378,8 @@ impl TryFrom<&Value> for f64 {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContainerPosition {
     // the start of the container in the packet in bytes
     // this is the start of the top container in the hierarchy
@@ -152,11 +394,12 @@ pub struct ContainerPosition {
     pub details: ContainerPositionDetails,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ContainerPositionDetails {
     None,
     Aggregate(HashMap<NameIdx, ContainerPosition>),
-    //TODO arrays
+    Array(Vec<ContainerPosition>),
 }
 pub struct ParameterValueDebug<'a> {
     pv: &'a ParameterValue,
@@ -196,6 +439,8 @@ fn write_value(f: &mut Formatter<'_>, mdb: &MissionDatabase, v: &Value) -> fmt::
         Value::Enumerated(v) => write_enumerated(f, v)?,
         Value::Binary(v) => write!(f, "{}", v.encode_hex::<String>())?,
         Value::Aggregate(v) => write_aggregate(f, mdb, v)?,
+        Value::Array(v) => write_array(f, mdb, v)?,
+        Value::Timestamp(v) => write!(f, "{}", v)?,
     }
 
     Ok(())
@@ -222,6 +467,20 @@ fn write_aggregate(
     Ok(())
 }
 
+// the elements are written with write_value such that the aggregate members show their name
+fn write_array(f: &mut Formatter<'_>, mdb: &MissionDatabase, v: &[Value]) -> fmt::Result {
+    f.write_str("[")?;
+    for (i, element) in v.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_value(f, mdb, element)?;
+    }
+    f.write_str("]")?;
+
+    Ok(())
+}
+
 fn write_enumerated(
     f: &mut Formatter<'_>,
     v: &EnumeratedValue,
@@ -242,6 +501,63 @@ mod tests {
         println!("size of ParameterValue: {}", std::mem::size_of::<ParameterValue>());
     }
 
+    #[test]
+    fn test_format() {
+        let fmt = ValueFormat { precision: Some(3), ..Default::default() };
+        assert_eq!("1.235", Value::Double(1.23456).format(&fmt));
+        assert_eq!("-0.500", Value::Double(-0.5).format(&fmt));
+        assert_eq!("42", Value::Uint64(42).format(&fmt));
+
+        let fmt = ValueFormat { radix: 16, ..Default::default() };
+        assert_eq!("0xff", Value::Uint64(255).format(&fmt));
+        assert_eq!("-0x1a", Value::Int64(-26).format(&fmt));
+
+        let fmt = ValueFormat { precision: Some(1), thousands_separator: Some(','), ..Default::default() };
+        assert_eq!("1,234,567", Value::Int64(1234567).format(&fmt));
+        assert_eq!("-123", Value::Int64(-123).format(&fmt));
+        assert_eq!("-1,234.6", Value::Double(-1234.56).format(&fmt));
+
+        assert_eq!("2.5", Value::Double(2.5).format(&ValueFormat::default()));
+    }
+
+    #[test]
+    fn test_array_display() {
+        let row = |a, b| Value::Array(Box::new(vec![Value::Uint64(a), Value::Uint64(b)]));
+        assert_eq!("[1, 2]", row(1, 2).to_string());
+        let matrix = Value::Array(Box::new(vec![row(1, 2), row(3, 4)]));
+        assert_eq!("[[1, 2], [3, 4]]", matrix.to_string());
+        assert_eq!("[]", Value::Array(Box::default()).to_string());
+    }
+
+    #[test]
+    fn test_aggregate_display() {
+        let name = |i| NameIdx::try_from_usize(i).unwrap();
+        let members = HashMap::from([
+            (name(3), Value::Array(Box::new(vec![Value::Uint64(1), Value::Uint64(2)]))),
+            (name(1), Value::Double(2.5)),
+        ]);
+        let v = Value::Aggregate(Box::new(AggregateValue(members)));
+        assert_eq!("{#1: 2.5, #3: [1, 2]}", v.to_string());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!("1970-01-01T00:00:00.000Z", Value::Timestamp(Timestamp(0)).to_string());
+        assert_eq!("2024-03-01T12:30:05.250Z", Timestamp(1_709_296_205_250).to_string());
+        assert_eq!("2000-02-29T23:59:59.999Z", Timestamp(951_868_799_999).to_string());
+        assert_eq!("1969-12-31T23:59:59.999Z", Timestamp(-1).to_string());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let rate = |basis, min| RateInStream { basis, minimum_value: min, maximum_value: None };
+        assert_eq!(Some(Duration::from_secs(10)), ParameterValue::expiry(&rate(RateBasis::PerSecond, Some(0.1))));
+        assert_eq!(Some(Duration::from_millis(500)), ParameterValue::expiry(&rate(RateBasis::PerSecond, Some(2.0))));
+        assert_eq!(None, ParameterValue::expiry(&rate(RateBasis::PerSecond, Some(0.0))));
+        assert_eq!(None, ParameterValue::expiry(&rate(RateBasis::PerSecond, None)));
+        assert_eq!(None, ParameterValue::expiry(&rate(RateBasis::PerContainerUpdate, Some(1.0))));
+    }
+
     #[test]
     fn test_i64() {
         let x: i64 = 0x3FFF_FFFF_FFFF_FFFF;
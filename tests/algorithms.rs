@@ -0,0 +1,174 @@
+use std::path::Path;
+
+use xtce_rs::{
+    mdb::{AlgorithmTrigger, AlgorithmType, MathOp, MathOperator, MissionDatabase, NamedItem},
+    parser::{self, ParseOptions, Strictness, XtceError},
+};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+pub fn init_logging() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+fn init_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/algorithms.xml");
+    parser::parse(&mut mdb, path).unwrap();
+    mdb
+}
+
+#[test]
+fn math_algorithm() {
+    let mdb = init_mdb();
+    let temp_c = mdb.search_parameter("/Algorithms/temp_c").unwrap();
+    let temp_f = mdb.search_parameter("/Algorithms/temp_f").unwrap();
+
+    let aidx = mdb.search_algorithm("/Algorithms/fahrenheit").unwrap();
+    assert_eq!("/Algorithms/fahrenheit", mdb.algorithm_qn(aidx));
+    let algo = mdb.get_algorithm(aidx);
+    assert_eq!("fahrenheit", mdb.name2str(algo.name()));
+    let descr = algo.ndescr.short_description.as_deref();
+    assert_eq!(Some("Converts the temperature to Fahrenheit"), descr);
+
+    assert_eq!(1, algo.inputs.len());
+    assert_eq!(temp_c, algo.inputs[0].param_instance.pidx);
+    assert_eq!(1, algo.outputs.len());
+    assert_eq!(temp_f, algo.outputs[0].pidx);
+
+    let AlgorithmType::Math(ops) = &algo.algorithm_type else {
+        panic!("expected math algorithm, got {:?}", algo.algorithm_type);
+    };
+    assert_eq!(5, ops.len());
+    assert!(matches!(&ops[0], MathOp::ParameterInstance(pref) if pref.pidx == temp_c));
+    assert!(matches!(ops[1], MathOp::Value(v) if v == 1.8));
+    assert!(matches!(ops[2], MathOp::Operator(MathOperator::Multiply)));
+    assert!(matches!(ops[3], MathOp::Value(v) if v == 32.0));
+    assert!(matches!(ops[4], MathOp::Operator(MathOperator::Add)));
+}
+
+#[test]
+fn custom_algorithm() {
+    let mdb = init_mdb();
+    let position = mdb.search_parameter("/Algorithms/position").unwrap();
+    let distance = mdb.search_parameter("/Algorithms/distance").unwrap();
+
+    let algo = mdb.get_algorithm(mdb.search_algorithm("/Algorithms/distance_from_origin").unwrap());
+    let AlgorithmType::Custom(text) = &algo.algorithm_type else {
+        panic!("expected custom algorithm, got {:?}", algo.algorithm_type);
+    };
+    assert_eq!("JavaScript", text.language);
+    assert_eq!("out.value = Math.sqrt(x.value * x.value + y.value * y.value);", text.text);
+
+    let inputs: Vec<_> = algo
+        .inputs
+        .iter()
+        .map(|inp| (inp.name.as_deref(), inp.param_instance.to_string(&mdb)))
+        .collect();
+    assert_eq!(
+        vec![(Some("x"), "position.x.eng".to_owned()), (Some("y"), "position.y.raw".to_owned())],
+        inputs
+    );
+    assert!(algo.inputs.iter().all(|inp| inp.param_instance.pidx == position));
+
+    assert_eq!(1, algo.outputs.len());
+    assert_eq!(Some("out"), algo.outputs[0].name.as_deref());
+    assert_eq!(distance, algo.outputs[0].pidx);
+}
+
+#[test]
+fn algorithm_triggers() {
+    let mdb = init_mdb();
+    let temp_c = mdb.search_parameter("/Algorithms/temp_c").unwrap();
+    let position = mdb.search_parameter("/Algorithms/position").unwrap();
+
+    let algo = mdb.get_algorithm(mdb.search_algorithm("/Algorithms/fahrenheit").unwrap());
+    assert!(matches!(algo.triggers[..], [AlgorithmTrigger::OnParameterUpdate(p)] if p == temp_c));
+
+    let algo = mdb.get_algorithm(mdb.search_algorithm("/Algorithms/distance_from_origin").unwrap());
+    assert_eq!(2, algo.triggers.len());
+    assert!(matches!(algo.triggers[0], AlgorithmTrigger::OnParameterUpdate(p) if p == position));
+    assert!(matches!(algo.triggers[1], AlgorithmTrigger::OnPeriodicRate(2500)));
+
+    // without triggers the algorithm runs when its inputs are updated
+    let algo = mdb.get_algorithm(mdb.search_algorithm("/Algorithms/Sub/kelvin").unwrap());
+    assert!(algo.triggers.is_empty());
+}
+
+#[test]
+fn algorithm_in_subsystem() {
+    let mdb = init_mdb();
+    let temp_c = mdb.search_parameter("/Algorithms/temp_c").unwrap();
+    let temp_k = mdb.search_parameter("/Algorithms/Sub/temp_k").unwrap();
+
+    let algo = mdb.get_algorithm(mdb.search_algorithm("/Algorithms/Sub/kelvin").unwrap());
+    assert_eq!(temp_c, algo.inputs[0].param_instance.pidx);
+    assert_eq!(temp_k, algo.outputs[0].pidx);
+    assert!(mdb.search_algorithm("/Algorithms/kelvin").is_none());
+}
+
+#[test]
+fn invalid_math_operator() {
+    init_logging();
+    let xml = r#"<SpaceSystem name="Bad" xmlns="http://www.omg.org/spec/XTCE/20180204">
+        <TelemetryMetaData>
+            <ParameterTypeSet>
+                <FloatParameterType name="float"><FloatDataEncoding /></FloatParameterType>
+            </ParameterTypeSet>
+            <ParameterSet><Parameter name="p" parameterTypeRef="float" /></ParameterSet>
+            <AlgorithmSet>
+                <MathAlgorithm name="a">
+                    <MathOperation outputParameterRef="p">
+                        <ValueOperand>1</ValueOperand>
+                        <Operator>sqrt</Operator>
+                    </MathOperation>
+                </MathAlgorithm>
+            </AlgorithmSet>
+        </TelemetryMetaData>
+    </SpaceSystem>"#;
+
+    let mut mdb = MissionDatabase::new();
+    match parser::parse_str(&mut mdb, xml) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("sqrt"), "{}", e.msg),
+        r => panic!("expected parse error, got {:?}", r),
+    }
+}
+
+#[test]
+fn undefined_algorithm_input() {
+    init_logging();
+    let xml = r#"<SpaceSystem name="Yamcs" xmlns="http://www.omg.org/spec/XTCE/20180204">
+        <TelemetryMetaData>
+            <ParameterTypeSet>
+                <FloatParameterType name="float"><FloatDataEncoding /></FloatParameterType>
+            </ParameterTypeSet>
+            <ParameterSet><Parameter name="p" parameterTypeRef="float" /></ParameterSet>
+            <AlgorithmSet>
+                <CustomAlgorithm name="a">
+                    <AlgorithmText language="JavaScript">out.value = count.value;</AlgorithmText>
+                    <InputSet>
+                        <InputParameterInstanceRef parameterRef="/yamcs/count" inputName="count" />
+                    </InputSet>
+                    <OutputSet><OutputParameterRef parameterRef="p" outputName="out" /></OutputSet>
+                </CustomAlgorithm>
+            </AlgorithmSet>
+        </TelemetryMetaData>
+    </SpaceSystem>"#;
+
+    // the algorithm is skipped, the rest of the space system is loaded
+    let mut mdb = MissionDatabase::new();
+    let warnings =
+        parser::parse_str_with_options(&mut mdb, xml, None, &ParseOptions::default()).unwrap();
+    assert_eq!(1, warnings.len());
+    assert!(warnings[0].msg.contains("/yamcs/count"), "{}", warnings[0].msg);
+    assert!(mdb.search_algorithm("/Yamcs/a").is_none());
+    assert!(mdb.search_parameter("/Yamcs/p").is_some());
+
+    let options = ParseOptions { strictness: Strictness::Strict, ..Default::default() };
+    let mut mdb = MissionDatabase::new();
+    assert!(parser::parse_str_with_options(&mut mdb, xml, None, &options).is_err());
+}
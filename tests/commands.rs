@@ -0,0 +1,461 @@
+use std::{path::Path, time::Duration};
+
+use xtce_rs::{
+    mdb::{
+        CheckWindowStart, ConsequenceLevel, MatchCriteria, MdbError, MissionDatabase, NamedItem, VerifierCheck,
+        VerifierStage,
+    },
+    parser::{self, ParseOptions, XtceError},
+    proc::{commands::encode_command, ProcError, ProcessorData},
+    value::Value,
+};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+pub fn init_logging() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+fn init_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/commands.xml");
+    parser::parse(&mut mdb, path).unwrap();
+    mdb
+}
+
+#[test]
+fn fixed_position_arguments() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let cmdidx = mdb.search_meta_command("/Commands/fixed_position").unwrap();
+    assert_eq!("/Commands/fixed_position", mdb.meta_command_qn(cmdidx));
+
+    let args = [("opcode", Value::Uint64(0x12)), ("arg1", Value::Int64(-2)), ("arg2", Value::Uint64(0x34))];
+    let cmd = encode_command(&mdb, &pdata, cmdidx, &args).unwrap();
+
+    // the base container (idle pattern 0) has the 12 bits header followed by the opcode at bit 16,
+    // arg1 is at bit 40 and arg2 4 bits after it, the gaps are filled with the idle pattern 0xFF
+    assert_eq!(vec![0xA5, 0xF0, 0x12, 0xFF, 0xFF, 0xFF, 0xEF, 0x34], cmd);
+}
+
+#[test]
+fn calibrated_argument() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let cmdidx = mdb.search_meta_command("/Commands/calibrated").unwrap();
+
+    let cmd = encode_command(&mdb, &pdata, cmdidx, &[("v", Value::Double(3.0))]).unwrap();
+    assert_eq!(6.0f32.to_be_bytes().to_vec(), cmd);
+}
+
+#[test]
+fn invalid_arguments() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let cmdidx = mdb.search_meta_command("/Commands/fixed_position").unwrap();
+
+    let args = [("opcode", Value::Uint64(0x12)), ("arg1", Value::Int64(-2))];
+    let r = encode_command(&mdb, &pdata, cmdidx, &args);
+    assert!(matches!(r, Err(ProcError::MissingValue(msg)) if msg.contains("arg2")));
+
+    let r = encode_command(&mdb, &pdata, cmdidx, &[("arg3", Value::Uint64(1))]);
+    assert!(matches!(r, Err(ProcError::InvalidValue(msg)) if msg.contains("arg3")));
+
+    let args = [("opcode", Value::Uint64(0x12)), ("arg1", Value::Int64(2048)), ("arg2", Value::Uint64(0))];
+    let r = encode_command(&mdb, &pdata, cmdidx, &args);
+    assert!(matches!(r, Err(ProcError::OutOfRange(_))));
+
+    let base = mdb.search_meta_command("/Commands/base").unwrap();
+    let r = encode_command(&mdb, &pdata, base, &[("opcode", Value::Uint64(0x12))]);
+    assert!(matches!(r, Err(ProcError::InvalidValue(msg)) if msg.contains("abstract")));
+}
+
+#[test]
+fn argument_assignments() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let cmdidx = mdb.search_meta_command("/Commands/assigned").unwrap();
+
+    let names: Vec<_> = mdb.effective_arguments(cmdidx).iter().map(|a| mdb.name2str(a.name())).collect();
+    assert_eq!(vec!["arg2"], names);
+    let names: Vec<_> = mdb
+        .effective_arguments(mdb.search_meta_command("/Commands/fixed_position").unwrap())
+        .iter()
+        .map(|a| mdb.name2str(a.name()))
+        .collect();
+    assert_eq!(vec!["opcode", "arg1", "arg2"], names);
+
+    let assignments = mdb.argument_assignments(cmdidx);
+    assert_eq!(2, assignments.len());
+    assert_eq!("opcode", mdb.name2str(assignments[0].name));
+    assert!(matches!(assignments[0].value, Value::Uint64(0x21)));
+    assert!(matches!(assignments[1].value, Value::Int64(-2)));
+
+    let cmd = encode_command(&mdb, &pdata, cmdidx, &[("arg2", Value::Uint64(0x34))]).unwrap();
+    assert_eq!(vec![0xA5, 0xF0, 0x21, 0xFF, 0xFF, 0xFF, 0xEF, 0x34], cmd);
+
+    let args = [("opcode", Value::Uint64(0x12)), ("arg2", Value::Uint64(0x34))];
+    let r = encode_command(&mdb, &pdata, cmdidx, &args);
+    assert!(matches!(r, Err(ProcError::InvalidValue(msg)) if msg.contains("opcode")));
+}
+
+#[test]
+fn invalid_argument_assignment() {
+    init_logging();
+
+    let cmd = |assignment: &str| {
+        format!(
+            r#"<SpaceSystem name="Root"><CommandMetaData>
+            <ArgumentTypeSet>
+                <IntegerArgumentType name="uint8" signed="false" sizeInBits="8" />
+                <AbsoluteTimeArgumentType name="time" />
+            </ArgumentTypeSet>
+            <MetaCommandSet>
+                <MetaCommand name="base" abstract="true">
+                    <ArgumentList>
+                        <Argument name="opcode" argumentTypeRef="uint8" />
+                        <Argument name="exec_time" argumentTypeRef="time" />
+                    </ArgumentList>
+                </MetaCommand>
+                <MetaCommand name="derived"><BaseMetaCommand metaCommandRef="base">
+                    <ArgumentAssignmentList>{}</ArgumentAssignmentList>
+                </BaseMetaCommand></MetaCommand>
+            </MetaCommandSet></CommandMetaData></SpaceSystem>"#,
+            assignment
+        )
+    };
+
+    let mut mdb = MissionDatabase::new();
+    let r = parser::parse_str(&mut mdb, &cmd(r#"<ArgumentAssignment argumentName="op" argumentValue="1" />"#));
+    let err = r.unwrap_err().to_string();
+    assert!(err.contains("/Root/derived") && err.contains("'op'"), "{}", err);
+
+    let mut mdb = MissionDatabase::new();
+    let r = parser::parse_str(&mut mdb, &cmd(r#"<ArgumentAssignment argumentName="opcode" argumentValue="256" />"#));
+    let err = r.unwrap_err().to_string();
+    assert!(err.contains("/Root/derived") && err.contains("out of range"), "{}", err);
+
+    let mut mdb = MissionDatabase::new();
+    let assignment = r#"<ArgumentAssignment argumentName="exec_time" argumentValue="2024-01-01T00:00:00Z" />"#;
+    let err = parser::parse_str(&mut mdb, &cmd(assignment)).unwrap_err().to_string();
+    assert!(err.contains("/Root/derived") && err.contains("'exec_time'"), "{}", err);
+
+    // the unknown elements of the list are ignored with a warning
+    let mut mdb = MissionDatabase::new();
+    let assignment = r#"<ArgumentAssignment argumentName="opcode" argumentValue="1" /><Comment />"#;
+    let warnings =
+        parser::parse_str_with_options(&mut mdb, &cmd(assignment), None, &ParseOptions::default()).unwrap();
+    assert_eq!(1, warnings.len());
+    assert!(warnings[0].msg.contains("'Comment'"), "{}", warnings[0].msg);
+}
+
+#[test]
+fn argument_initial_values() {
+    let mdb = init_mdb();
+    let cmdidx = mdb.search_meta_command("/Commands/configure").unwrap();
+    let arg = |name: &str| mdb.get_argument(cmdidx, mdb.name_db_ref().get(name).unwrap()).unwrap();
+
+    let Some(Value::Enumerated(ev)) = &arg("mode").initial_value else {
+        panic!("expected an enumerated initial value");
+    };
+    assert_eq!((1, "ON"), (ev.key, ev.value.as_str()));
+    assert_eq!(Some(Value::Double(12.5)), arg("level").initial_value);
+    assert_eq!(None, arg("offset").initial_value);
+
+    let ranges = mdb.get_data_type(arg("level").atype).valid_ranges();
+    assert_eq!(2, ranges.len());
+    let vr = &ranges[0];
+    assert_eq!((Some(0.0), Some(28.0), false), (vr.min, vr.max, vr.max_inclusive));
+    assert!(ranges.iter().all(|vr| vr.applies_to_calibrated));
+}
+
+#[test]
+fn validate_argument() {
+    let mdb = init_mdb();
+    let cmdidx = mdb.search_meta_command("/Commands/configure").unwrap();
+    let arg = |name: &str| mdb.get_argument(cmdidx, mdb.name_db_ref().get(name).unwrap()).unwrap();
+
+    mdb.validate_argument(arg("level"), &Value::Double(27.5)).unwrap();
+    mdb.validate_argument(arg("level"), &Value::Uint64(0)).unwrap();
+    // the value has to be in any of the ranges of the set
+    mdb.validate_argument(arg("level"), &Value::Double(30.0)).unwrap();
+    let r = mdb.validate_argument(arg("level"), &Value::Double(28.0));
+    assert!(
+        matches!(r, Err(MdbError::OutOfRange(msg)) if msg.contains("level") && msg.contains("[0, 28) or [30, 32]"))
+    );
+
+    mdb.validate_argument(arg("mode"), &Value::StringValue(Box::new("OFF".to_owned()))).unwrap();
+    mdb.validate_argument(arg("mode"), arg("mode").initial_value.as_ref().unwrap()).unwrap();
+    let r = mdb.validate_argument(arg("mode"), &Value::StringValue(Box::new("STANDBY".to_owned())));
+    assert!(matches!(r, Err(MdbError::InvalidValue(msg)) if msg.contains("STANDBY") && msg.contains("mode")));
+    let r = mdb.validate_argument(arg("mode"), &Value::Uint64(1));
+    assert!(matches!(r, Err(MdbError::InvalidValue(_))));
+
+    // no range
+    mdb.validate_argument(arg("offset"), &Value::Int64(-2000)).unwrap();
+}
+
+#[test]
+fn encode_initial_values() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let cmdidx = mdb.search_meta_command("/Commands/configure").unwrap();
+
+    // mode and level take their initial values ON and 12.5 (raw 25.0)
+    let cmd = encode_command(&mdb, &pdata, cmdidx, &[("offset", Value::Int64(-2))]).unwrap();
+    assert_eq!(vec![0x01, 0x41, 0xC8, 0x00, 0x00, 0xFF, 0xE0], cmd);
+
+    let args = [("mode", Value::StringValue(Box::new("OFF".to_owned()))), ("offset", Value::Int64(-2))];
+    let cmd = encode_command(&mdb, &pdata, cmdidx, &args).unwrap();
+    assert_eq!(0x00, cmd[0]);
+
+    // offset has no initial value
+    let r = encode_command(&mdb, &pdata, cmdidx, &[("level", Value::Double(1.0))]);
+    assert!(matches!(r, Err(ProcError::MissingValue(msg)) if msg.contains("offset")));
+}
+
+#[test]
+fn encode_invalid_argument_values() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let cmdidx = mdb.search_meta_command("/Commands/configure").unwrap();
+
+    let args = [("level", Value::Double(28.0)), ("offset", Value::Int64(0))];
+    let r = encode_command(&mdb, &pdata, cmdidx, &args);
+    assert!(matches!(r, Err(ProcError::OutOfRange(msg)) if msg.contains("level")));
+
+    let args = [("mode", Value::StringValue(Box::new("STANDBY".to_owned()))), ("offset", Value::Int64(0))];
+    let r = encode_command(&mdb, &pdata, cmdidx, &args);
+    assert!(matches!(r, Err(ProcError::InvalidValue(msg)) if msg.contains("STANDBY")));
+}
+
+#[test]
+fn invalid_argument_initial_value() {
+    init_logging();
+
+    let text = r#"<SpaceSystem name="Root"><CommandMetaData>
+        <ArgumentTypeSet><IntegerArgumentType name="percent">
+            <IntegerDataEncoding sizeInBits="8" />
+            <ValidRangeSet><ValidRange minInclusive="0" maxInclusive="100" /></ValidRangeSet>
+        </IntegerArgumentType></ArgumentTypeSet>
+        <MetaCommandSet><MetaCommand name="cmd"><ArgumentList>
+            <Argument name="p" argumentTypeRef="percent" initialValue="101" />
+        </ArgumentList></MetaCommand></MetaCommandSet>
+    </CommandMetaData></SpaceSystem>"#;
+    let mut mdb = MissionDatabase::new();
+    match parser::parse_str(&mut mdb, text) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("Invalid initial value '101'"), "{}", e.msg),
+        r => panic!("expected parse error, got {:?}", r),
+    }
+}
+
+#[test]
+fn command_verifiers() {
+    let mdb = init_mdb();
+    let cmd = mdb.get_meta_command(mdb.search_meta_command("/Commands/calibrated").unwrap());
+    assert_eq!(2, cmd.verifiers.len());
+
+    let v = &cmd.verifiers[0];
+    assert_eq!(VerifierStage::Received, v.stage);
+    assert_eq!(VerifierCheck::Container(mdb.search_container("/Commands/ack_packet").unwrap()), v.check);
+    assert_eq!(None, v.check_window.time_to_start_checking);
+    assert_eq!(Duration::from_secs(5), v.check_window.time_to_stop_checking);
+    assert_eq!(CheckWindowStart::CommandRelease, v.check_window.relative_to);
+
+    let v = &cmd.verifiers[1];
+    assert_eq!(VerifierStage::Complete, v.stage);
+    let VerifierCheck::Criteria(mcidx) = v.check else {
+        panic!("expected a criteria check");
+    };
+    let MatchCriteria::Comparison(comparison) = mdb.get_match_criteria(mcidx) else {
+        panic!("expected a comparison");
+    };
+    assert_eq!("1", comparison.value);
+    assert_eq!(Some(Duration::from_millis(500)), v.check_window.time_to_start_checking);
+    assert_eq!(Duration::from_secs(60), v.check_window.time_to_stop_checking);
+    assert_eq!(CheckWindowStart::TimeLastVerifierPassed, v.check_window.relative_to);
+
+    let cmd = mdb.get_meta_command(mdb.search_meta_command("/Commands/fixed_position").unwrap());
+    assert!(cmd.verifiers.is_empty());
+}
+
+#[test]
+fn invalid_verifier_window() {
+    init_logging();
+
+    let text = |window: &str| {
+        format!(
+            r#"<SpaceSystem name="Verifiers"><TelemetryMetaData><ContainerSet>
+            <SequenceContainer name="ack"><EntryList/></SequenceContainer>
+            </ContainerSet></TelemetryMetaData><CommandMetaData><MetaCommandSet>
+            <MetaCommand name="cmd"><VerifierSet><AcceptedVerifier>
+                <ContainerRef containerRef="ack" />
+                {}
+            </AcceptedVerifier></VerifierSet></MetaCommand>
+            </MetaCommandSet></CommandMetaData></SpaceSystem>"#,
+            window
+        )
+    };
+    let cases = [
+        (r#"<CheckWindow timeToStopChecking="10s" />"#, "timeToStopChecking"),
+        (r#"<CheckWindow timeToStartChecking="P1M" timeToStopChecking="PT1S" />"#, "years and months"),
+        (r#"<CheckWindow timeToStartChecking="PT2S" timeToStopChecking="PT1S" />"#, "after timeToStopChecking"),
+        (r#"<CheckWindow />"#, "timeToStopChecking"),
+        ("", "CheckWindow"),
+    ];
+    for (window, error) in cases {
+        let mut mdb = MissionDatabase::new();
+        match parser::parse_str(&mut mdb, &text(window)) {
+            Err(XtceError::Parse(e)) => {
+                assert!(e.msg.contains(error), "{}", e.msg);
+                assert!(e.pos.row > 1);
+            }
+            r => panic!("expected parse error, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse_str(&mut mdb, &text(r#"<CheckWindow timeToStopChecking="PT1S" />"#)).unwrap();
+}
+
+#[test]
+fn unsupported_verifier_criteria() {
+    init_logging();
+
+    let text = |condition: &str| {
+        format!(
+            r#"<SpaceSystem name="Verifiers"><TelemetryMetaData><ParameterTypeSet>
+            <IntegerParameterType name="uint8"><IntegerDataEncoding sizeInBits="8" /></IntegerParameterType>
+            </ParameterTypeSet><ParameterSet><Parameter name="status" parameterTypeRef="uint8" /></ParameterSet>
+            </TelemetryMetaData><CommandMetaData><MetaCommandSet>
+            <MetaCommand name="cmd"><VerifierSet><CompleteVerifier>
+                <BooleanExpression><Condition>{}</Condition></BooleanExpression>
+                <CheckWindow timeToStopChecking="PT1S" />
+            </CompleteVerifier></VerifierSet></MetaCommand>
+            </MetaCommandSet></CommandMetaData></SpaceSystem>"#,
+            condition
+        )
+    };
+
+    // the comparisons with an argument or with another parameter are ignored with a warning
+    let cases = [
+        (r#"<ArgumentInstanceRef argumentRef="arg1" />"#, "'ArgumentInstanceRef'"),
+        (r#"<ParameterInstanceRef parameterRef="status" />"#, "'Condition'"),
+    ];
+    for (operand, warning) in cases {
+        let condition = format!(
+            r#"{}<ComparisonOperator>==</ComparisonOperator><ParameterInstanceRef parameterRef="status" />"#,
+            operand
+        );
+        let mut mdb = MissionDatabase::new();
+        let warnings =
+            parser::parse_str_with_options(&mut mdb, &text(&condition), None, &ParseOptions::default()).unwrap();
+        assert_eq!(1, warnings.len());
+        assert!(warnings[0].msg.contains(warning), "{}", warnings[0].msg);
+        let cmd = mdb.get_meta_command(mdb.search_meta_command("/Verifiers/cmd").unwrap());
+        assert!(cmd.verifiers.is_empty());
+    }
+
+    // the invalid criteria are errors
+    let condition = r#"<ParameterInstanceRef parameterRef="status" />
+        <ComparisonOperator>=~</ComparisonOperator><Value>1</Value>"#;
+    let mut mdb = MissionDatabase::new();
+    match parser::parse_str(&mut mdb, &text(condition)) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("'=~'"), "{}", e.msg),
+        r => panic!("expected parse error, got {:?}", r.map(|_| ())),
+    }
+}
+
+#[test]
+fn command_significance() {
+    let mdb = init_mdb();
+
+    let base = mdb.search_meta_command("/Commands/base").unwrap();
+    let significance = mdb.command_significance(base).unwrap();
+    assert_eq!(ConsequenceLevel::Watch, significance.consequence_level);
+    assert_eq!(Some("changes the on-board configuration"), significance.reason_for_warning.as_deref());
+
+    // inherited from the base command
+    let cmdidx = mdb.search_meta_command("/Commands/fixed_position").unwrap();
+    assert!(mdb.get_meta_command(cmdidx).default_significance.is_none());
+    assert_eq!(Some(significance), mdb.command_significance(cmdidx));
+
+    let cmdidx = mdb.search_meta_command("/Commands/calibrated").unwrap();
+    let significance = mdb.command_significance(cmdidx).unwrap();
+    assert_eq!(ConsequenceLevel::Warning, significance.consequence_level);
+    assert_eq!(None, significance.reason_for_warning);
+}
+
+#[test]
+fn invalid_significance() {
+    init_logging();
+
+    let text = r#"<SpaceSystem name="Significance"><CommandMetaData><MetaCommandSet>
+        <MetaCommand name="cmd"><DefaultSignificance consequenceLevel="dangerous" /></MetaCommand>
+        </MetaCommandSet></CommandMetaData></SpaceSystem>"#;
+    let mut mdb = MissionDatabase::new();
+    match parser::parse_str(&mut mdb, text) {
+        Err(XtceError::Parse(e)) => {
+            assert!(e.msg.contains("dangerous"), "{}", e.msg);
+            assert_eq!(2, e.pos.row);
+        }
+        r => panic!("expected parse error, got {:?}", r.map(|_| ())),
+    }
+}
+
+#[test]
+fn transmission_constraints() {
+    let mdb = init_mdb();
+    let cmd_status = mdb.search_parameter("/Commands/cmd_status").unwrap();
+
+    let cmd = mdb.get_meta_command(mdb.search_meta_command("/Commands/fixed_position").unwrap());
+    assert_eq!(2, cmd.transmission_constraints.len());
+    let tc = &cmd.transmission_constraints[0];
+    assert_eq!(Some(Duration::from_secs(2)), tc.timeout);
+    let MatchCriteria::Comparison(comparison) = mdb.get_match_criteria(tc.criteria) else {
+        panic!("expected a comparison");
+    };
+    assert_eq!(cmd_status, comparison.param_instance.pidx);
+    assert_eq!("0", comparison.value);
+
+    let tc = &cmd.transmission_constraints[1];
+    assert_eq!(None, tc.timeout);
+    assert!(matches!(mdb.get_match_criteria(tc.criteria), MatchCriteria::ComparisonList(l) if l.len() == 1));
+
+    let cmd = mdb.get_meta_command(mdb.search_meta_command("/Commands/calibrated").unwrap());
+    assert!(cmd.transmission_constraints.is_empty());
+}
+
+#[test]
+fn transmission_constraint_other_space_system() {
+    init_logging();
+
+    // the command is parsed before the parameter it refers to
+    let text = r#"<SpaceSystem name="Root">
+        <SpaceSystem name="Cmd"><CommandMetaData><MetaCommandSet><MetaCommand name="cmd">
+            <TransmissionConstraintList><TransmissionConstraint timeOut="PT0.5S">
+                <Comparison parameterRef="/Root/Tm/mode" value="1" />
+            </TransmissionConstraint></TransmissionConstraintList>
+        </MetaCommand></MetaCommandSet></CommandMetaData></SpaceSystem>
+        <SpaceSystem name="Tm"><TelemetryMetaData>
+            <ParameterTypeSet><IntegerParameterType name="uint8" /></ParameterTypeSet>
+            <ParameterSet><Parameter name="mode" parameterTypeRef="uint8" /></ParameterSet>
+        </TelemetryMetaData></SpaceSystem></SpaceSystem>"#;
+    let mut mdb = MissionDatabase::new();
+    parser::parse_str(&mut mdb, text).unwrap();
+
+    let cmd = mdb.get_meta_command(mdb.search_meta_command("/Root/Cmd/cmd").unwrap());
+    assert_eq!(1, cmd.transmission_constraints.len());
+    let tc = &cmd.transmission_constraints[0];
+    assert_eq!(Some(Duration::from_millis(500)), tc.timeout);
+    let MatchCriteria::Comparison(comparison) = mdb.get_match_criteria(tc.criteria) else {
+        panic!("expected a comparison");
+    };
+    assert_eq!(mdb.search_parameter("/Root/Tm/mode").unwrap(), comparison.param_instance.pidx);
+    // the criteria of the first attempt to parse the command is not kept
+    assert_eq!(1, mdb.match_criteria.len());
+}
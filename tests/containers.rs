@@ -0,0 +1,628 @@
+use std::path::Path;
+
+use xtce_rs::{
+    mdb::{
+        types::TypeData, ContainerEntry, ContainerEntryData, ContainerIdx, DataTypeIdx, MdbError, MissionDatabase,
+        ParameterIdx,
+    },
+    parser::{self, XtceError},
+    value::{ErrorCheck, Value},
+    proc::{
+        annotated_hexdump,
+        containers::{process, process_with},
+        ProcError, ProcessorData,
+    },
+};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+pub fn init_logging() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+fn bogus_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/BogusSAT-2.xml");
+    parser::parse(&mut mdb, path).unwrap();
+    mdb
+}
+
+fn container_parameter_names(mdb: &MissionDatabase, cname: &str) -> Vec<String> {
+    let cidx = mdb.search_container(cname).unwrap();
+    mdb.container_parameters(cidx).into_iter().map(|pidx| mdb.parameter_qn(pidx)).collect()
+}
+
+#[test]
+fn inherited_and_composed_parameters() {
+    let mdb = bogus_mdb();
+
+    let names = container_parameter_names(&mdb, "/BogusSAT/SC001/CCSDS_SpacePacket4");
+    assert_eq!(
+        vec![
+            "/BogusSAT/CCSDS_Packet_ID",
+            "/BogusSAT/CCSDS_Packet_Sequence",
+            "/BogusSAT/CCSDS_Packet_Length",
+            "/BogusSAT/SC001/Payload1/Payload_1_State",
+            "/BogusSAT/SC001/Payload1/Basic_uint32",
+            "/BogusSAT/SC001/Payload1/Basic_int32_signmag",
+            "/BogusSAT/SC001/Payload1/Basic_int32_twoscomp",
+            "/BogusSAT/SC001/Payload1/Basic_int32_onescomp",
+            "/BogusSAT/SC001/Payload1/Basic_Float32",
+            "/BogusSAT/SC001/Payload1/Basic_Float64",
+            "/BogusSAT/SC001/Payload1/enum_int16_signmag",
+            "/BogusSAT/SC001/Payload1/enum_int16_twoscomp",
+            "/BogusSAT/SC001/Payload1/enum_int16_onescomp",
+        ],
+        names
+    );
+}
+
+#[test]
+fn include_conditions_ignored() {
+    let mdb = bogus_mdb();
+
+    // the parameters with include conditions are listed as if the conditions were true
+    let names = container_parameter_names(&mdb, "/BogusSAT/SC001/CCSDS_SpacePacket2");
+    assert_eq!(
+        vec![
+            "/BogusSAT/CCSDS_Packet_ID",
+            "/BogusSAT/CCSDS_Packet_Sequence",
+            "/BogusSAT/CCSDS_Packet_Length",
+            "/BogusSAT/SC001/BusElectronics/Solar_Array_Voltage_1_State",
+            "/BogusSAT/SC001/BusElectronics/Solar_Array_Voltage_1",
+            "/BogusSAT/SC001/BusElectronics/Solar_Array_Voltage_2_State",
+            "/BogusSAT/SC001/BusElectronics/Solar_Array_Voltage_2",
+            "/BogusSAT/SC001/BusElectronics/Battery_Voltage",
+            "/BogusSAT/SC001/BusElectronics/Battery_Current",
+        ],
+        names
+    );
+}
+
+#[test]
+fn cyclic_inheritance() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/types.xml")).unwrap();
+    assert!(ProcessorData::new(&mdb).is_ok());
+
+    // the parser cannot create a cycle, make base_packet inherit from its own child
+    let base_idx = mdb.search_container("/Types/base_packet").unwrap();
+    let child_idx = mdb.search_container("/Types/hex_packet").unwrap();
+    mdb.containers[base_idx.index()].base_container = Some((child_idx, None));
+
+    match mdb.validate() {
+        Err(MdbError::InvalidMdb(msg)) => {
+            assert!(msg.contains("/Types/base_packet"), "{}", msg);
+            assert!(msg.contains("/Types/hex_packet"), "{}", msg);
+        }
+        _ => panic!("expected an InvalidMdb error"),
+    }
+    assert!(matches!(ProcessorData::new(&mdb), Err(ProcError::Mdb(MdbError::InvalidMdb(_)))));
+    assert!(process(&mdb, &[0x23, 0, 0], base_idx).is_err());
+}
+
+#[test]
+fn cyclic_container_reference() {
+    let mut mdb = bogus_mdb();
+
+    // the parser cannot create a cycle, make the first entry of IncludedContainer2 reference the container itself
+    let cidx = mdb.search_container("/BogusSAT/SC001/Payload1/IncludedContainer2").unwrap();
+    let entry = ContainerEntry {
+        location_in_container: None,
+        include_condition: None,
+        repeat: None,
+        data: ContainerEntryData::ContainerRef(cidx),
+    };
+    mdb.containers[cidx.index()].entries.insert(0, entry);
+
+    match process(&mdb, &[0; 64], cidx) {
+        Err(ProcError::InvalidMdb(msg)) => assert!(
+            msg.ends_with("/BogusSAT/SC001/Payload1/IncludedContainer2 -> /BogusSAT/SC001/Payload1/IncludedContainer2"),
+            "{}",
+            msg
+        ),
+        r => panic!("expected an InvalidMdb error, got {:?}", r.map(|pvl| pvl.len())),
+    }
+}
+
+// returns the indices of the uint8_array3 and sample types of arrays.xml
+fn array3_and_sample_types(mdb: &MissionDatabase) -> (DataTypeIdx, DataTypeIdx) {
+    let ptype = |pname: &str| {
+        let pidx = mdb.search_parameter(&format!("/Arrays/{}", pname)).unwrap();
+        mdb.get_parameter(pidx).ptype.unwrap()
+    };
+    let TypeData::Array(atype) = &mdb.get_data_type(ptype("samples")).type_data else {
+        panic!("expected an array type");
+    };
+    (ptype("data"), atype.dtype)
+}
+
+#[test]
+fn cyclic_types() {
+    // the parser cannot create a cycle, make the member of sample reference sample
+    let mut mdb = arrays_mdb();
+    let (_, sample_idx) = array3_and_sample_types(&mdb);
+    let TypeData::Aggregate(atype) = &mut mdb.parameter_types[sample_idx.index()].type_data else {
+        panic!("expected an aggregate type");
+    };
+    atype.members[0].dtype = sample_idx;
+    match mdb.validate() {
+        Err(MdbError::InvalidMdb(msg)) => assert!(msg.ends_with("/Arrays/sample -> /Arrays/sample"), "{}", msg),
+        _ => panic!("expected an InvalidMdb error"),
+    }
+    let cidx = mdb.search_container("/Arrays/samples_packet").unwrap();
+    assert!(process(&mdb, &[1, 2, 3, 4, 5, 6], cidx).is_err());
+
+    // sample contains an uint8_array3 whose elements are samples
+    let mut mdb = arrays_mdb();
+    let (array3_idx, sample_idx) = array3_and_sample_types(&mdb);
+    let TypeData::Array(atype) = &mut mdb.parameter_types[array3_idx.index()].type_data else {
+        panic!("expected an array type");
+    };
+    atype.dtype = sample_idx;
+    // the chain starts with the first type of the cycle found
+    let msg = mdb.check_type_references().unwrap_err().to_string();
+    assert!(
+        msg.ends_with("/Arrays/sample -> /Arrays/uint8_array3 -> /Arrays/sample")
+            || msg.ends_with("/Arrays/uint8_array3 -> /Arrays/sample -> /Arrays/uint8_array3"),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn self_referencing_aggregate() {
+    init_logging();
+
+    // the member type is never added before the aggregate, the reference stays unresolved
+    let text = r#"<SpaceSystem name="Cycle"><TelemetryMetaData><ParameterTypeSet>
+        <AggregateParameterType name="node"><MemberList>
+            <Member name="next" typeRef="node" />
+        </MemberList></AggregateParameterType>
+        </ParameterTypeSet></TelemetryMetaData></SpaceSystem>"#;
+    let mut mdb = MissionDatabase::new();
+    let r = parser::parse_str(&mut mdb, text);
+    assert!(matches!(r, Err(XtceError::UnresolvedReferences(_))), "{:?}", r);
+}
+
+fn layout_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/container-layout.xml")).unwrap();
+    mdb
+}
+
+fn layout_error(mdb: &MissionDatabase, cname: &str) -> String {
+    let cidx = mdb.search_container(cname).unwrap();
+    match mdb.check_container_layout(cidx) {
+        Err(MdbError::InvalidMdb(msg)) => msg,
+        _ => panic!("expected an InvalidMdb error for {}", cname),
+    }
+}
+
+#[test]
+fn container_layout_ok() {
+    let mdb = layout_mdb();
+    let cidx = mdb.search_container("/Layout/header").unwrap();
+    assert!(mdb.check_container_layout(cidx).is_ok());
+
+    let mdb = bogus_mdb();
+    let cidx = mdb.search_container("/BogusSAT/SC001/CCSDS_SpacePacket4").unwrap();
+    assert!(mdb.check_container_layout(cidx).is_ok());
+}
+
+#[test]
+fn container_layout_overlap() {
+    let mdb = layout_mdb();
+    let msg = layout_error(&mdb, "/Layout/overlapping");
+    assert!(msg.contains("entry /Layout/c (bits 24..40) overlaps entry /Layout/b (bits 16..32)"), "{}", msg);
+}
+
+#[test]
+fn container_layout_past_size() {
+    let mdb = layout_mdb();
+    let msg = layout_error(&mdb, "/Layout/too_long");
+    assert!(msg.contains("entry /Layout/c (bits 32..48) extends past the container size 40"), "{}", msg);
+}
+
+#[test]
+fn container_layout_dynamic_size() {
+    let mdb = layout_mdb();
+
+    // b follows the terminated string and is skipped, c is located again from the container start
+    let msg = layout_error(&mdb, "/Layout/dynamic");
+    assert!(!msg.contains("/Layout/b"), "{}", msg);
+    assert!(msg.contains("entry /Layout/c (bits 8..24) overlaps entry /Layout/a (bits 0..16)"), "{}", msg);
+}
+
+fn segments_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/segments.xml")).unwrap();
+    mdb
+}
+
+#[test]
+fn parameter_and_container_segments() {
+    let mdb = segments_mdb();
+    let cidx = mdb.search_container("/Segments/segmented").unwrap();
+    assert!(mdb.check_container_layout(cidx).is_ok());
+
+    let r = process(&mdb, &[0x12, 0xAA, 0x34, 0x01, 0x02], cidx).unwrap();
+    let values: Vec<String> =
+        r.into_iter().map(|pv| format!("{}={}", mdb.parameter_qn(pv.pidx), pv.eng_value)).collect();
+    assert_eq!(
+        vec!["/Segments/mid=170", "/Segments/counter=4660", "/Segments/x=1", "/Segments/y=2"],
+        values
+    );
+}
+
+#[test]
+fn segments_after_little_endian() {
+    let mdb = segments_mdb();
+    let cidx = mdb.search_container("/Segments/after_little_endian").unwrap();
+
+    let r = process(&mdb, &[0x01, 0x02, 0x12, 0x34, 0xAA, 0x56, 0x78], cidx).unwrap();
+    let values: Vec<String> =
+        r.into_iter().map(|pv| format!("{}={}", mdb.parameter_qn(pv.pidx), pv.eng_value)).collect();
+    assert_eq!(vec!["/Segments/le=513", "/Segments/mid=170", "/Segments/wide=305419896"], values);
+}
+
+#[test]
+fn crc_segments() {
+    let mdb = segments_mdb();
+    let cidx = mdb.search_container("/Segments/crc_segmented").unwrap();
+
+    let r = process(&mdb, &[0xAA, 0xF5, 0x01, 0x50], cidx).unwrap();
+    let pv = r.into_iter().last().unwrap();
+    assert_eq!("/Segments/crc", mdb.parameter_qn(pv.pidx));
+    assert_eq!("f550", pv.eng_value.to_string());
+    assert_eq!(Some(ErrorCheck::Passed), pv.error_check);
+
+    // the value between the segments is not covered by the CRC
+    let r = process(&mdb, &[0xAA, 0xF5, 0x02, 0x50], cidx).unwrap();
+    assert_eq!(Some(ErrorCheck::Passed), r.into_iter().last().unwrap().error_check);
+
+    let r = process(&mdb, &[0xAB, 0xF5, 0x01, 0x50], cidx).unwrap();
+    assert!(matches!(r.into_iter().last().unwrap().error_check, Some(ErrorCheck::Failed { .. })));
+}
+
+#[test]
+fn segments_out_of_order() {
+    let mdb = segments_mdb();
+    let cidx = mdb.search_container("/Segments/out_of_order").unwrap();
+
+    // the stream cannot start with the second segment
+    match process(&mdb, &[0x12, 0x34], cidx) {
+        Err(ProcError::DecodingError(msg)) => {
+            assert!(msg.contains("Segment 2 of parameter /Segments/counter"), "{}", msg);
+            assert!(msg.ends_with("expected segment 1"), "{}", msg);
+        }
+        _ => panic!("expected a DecodingError"),
+    }
+}
+
+#[test]
+fn segments_incomplete() {
+    let mdb = segments_mdb();
+    let cidx = mdb.search_container("/Segments/incomplete").unwrap();
+
+    match process(&mdb, &[0x12, 0x34], cidx) {
+        Err(ProcError::DecodingError(msg)) => {
+            assert!(msg.contains("Missing segments of parameter /Segments/counter"), "{}", msg)
+        }
+        _ => panic!("expected a DecodingError"),
+    }
+}
+
+#[test]
+fn undef_restriction() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/types.xml")).unwrap();
+    let cidx = mdb.search_container("/Types/base_packet").unwrap();
+    let packet = [0x24, 0x00, 0x03];
+
+    // the restriction of undef_packet refers to a parameter not present in the packet
+    let mut pdata = ProcessorData::new(&mdb).unwrap();
+    let r = process_with(&mdb, &pdata, &packet, cidx).unwrap();
+    assert_eq!(1, r.len());
+
+    pdata.set_extract_undef_subcontainers(true);
+    let r = process_with(&mdb, &pdata, &packet, cidx).unwrap();
+    assert_eq!(2, r.len());
+    assert_eq!("/Types/packet_data", mdb.parameter_qn(r[1].pidx));
+}
+
+#[test]
+fn invalid_member_path() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    match parser::parse(&mut mdb, Path::new("test-xtce-files/invalid-member-path.xml")) {
+        Err(XtceError::Mdb(MdbError::InvalidValue(msg))) => {
+            assert!(msg.contains("'x' is not a positive integer"), "{}", msg)
+        }
+        r => panic!("expected a member path error, got {:?}", r),
+    }
+}
+
+#[test]
+fn aggregate_entry() {
+    let mdb = bogus_mdb();
+    let cidx = mdb.search_container("/BogusSAT/CCSDSPacket").unwrap();
+
+    // CCSDS header followed by the PUS data field header
+    let packet = [0x08, 0x23, 0xC0, 0x56, 0x00, 0x05, 0x35, 0x10, 0x20, 0x03, 0x05, 0x00, 0x00];
+    let r = process(&mdb, &packet, cidx).unwrap();
+    assert_eq!("/BogusSAT/CCSDS_Packet_ID", mdb.parameter_qn(r[0].pidx));
+
+    let Value::Aggregate(aggrv) = &r[0].raw_value else {
+        panic!("expected an aggregate value, got {:?}", r[0].raw_value);
+    };
+    let mut members: Vec<String> =
+        aggrv.0.iter().map(|(name, v)| format!("{}={}", mdb.name2str(*name), v)).collect();
+    members.sort();
+    assert_eq!(vec!["APID=35", "SecHdrFlag=1", "Type=0", "Version=0"], members);
+
+    let Value::Aggregate(aggrv) = &r[1].eng_value else {
+        panic!("expected an aggregate value, got {:?}", r[1].eng_value);
+    };
+    let mut members: Vec<String> =
+        aggrv.0.iter().map(|(name, v)| format!("{}={}", mdb.name2str(*name), v)).collect();
+    members.sort();
+    assert_eq!(vec!["Count=86", "GroupFlags=Standalone"], members);
+}
+
+fn repeat_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/repeat.xml")).unwrap();
+    mdb
+}
+
+#[test]
+fn repeated_entry() {
+    let mdb = repeat_mdb();
+    let cidx = mdb.search_container("/Repeat/repeated").unwrap();
+
+    let r = process(&mdb, &[3, 10, 0xFF, 20, 0xFF, 30], cidx).unwrap();
+    let values: Vec<String> =
+        r.into_iter().map(|pv| format!("{}={}", mdb.parameter_qn(pv.pidx), pv.eng_value)).collect();
+    assert_eq!(vec!["/Repeat/n=3", "/Repeat/x=10", "/Repeat/x=20", "/Repeat/x=30"], values);
+
+    // the offset is only skipped between the repetitions
+    assert!(matches!(process(&mdb, &[4, 10, 0xFF, 20, 0xFF, 30], cidx), Err(ProcError::OutOfBounds(_))));
+}
+
+#[test]
+fn repeated_entry_hexdump() {
+    let mdb = repeat_mdb();
+    let cidx = mdb.search_container("/Repeat/repeated").unwrap();
+
+    let dump = annotated_hexdump(&mdb, &[3, 10, 0xFF, 20, 0xFF, 30, 0xAB, 0xCD], cidx).unwrap();
+    let expected = "\
+0000  03                                               /Repeat/n = 3
+0001  0a                                               /Repeat/x = 10
+0002  ff                                               <unknown>
+0003  14                                               /Repeat/x = 20
+0004  ff                                               <unknown>
+0005  1e                                               /Repeat/x = 30
+0006  ab cd                                            <unknown>
+";
+    assert_eq!(expected, dump);
+}
+
+#[test]
+fn repeated_entry_zero_advance() {
+    let mdb = repeat_mdb();
+    let cidx = mdb.search_container("/Repeat/zero_advance").unwrap();
+
+    match process(&mdb, &[1], cidx) {
+        Err(ProcError::Mdb(MdbError::InvalidMdb(msg))) => {
+            assert!(msg.contains("/Repeat/zero_advance"), "{}", msg)
+        }
+        r => panic!("expected an InvalidMdb error, got {:?}", r.map(|r| r.len())),
+    }
+}
+
+fn restrictions_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/restrictions.xml")).unwrap();
+    mdb
+}
+
+fn eng_values(mdb: &MissionDatabase, packet: &[u8], cidx: ContainerIdx) -> Vec<String> {
+    let r = process(mdb, packet, cidx).unwrap();
+    let mut values: Vec<String> =
+        r.into_iter().map(|pv| format!("{}={}", mdb.parameter_qn(pv.pidx), pv.eng_value)).collect();
+    values.sort();
+    values
+}
+
+#[test]
+fn restriction_on_base_parameters() {
+    let mdb = restrictions_mdb();
+    let cidx = mdb.search_container("/Restrictions/header").unwrap();
+
+    assert_eq!(
+        vec!["/Restrictions/a=10", "/Restrictions/apid=1", "/Restrictions/type=1"],
+        eng_values(&mdb, &[1, 1, 10, 20], cidx)
+    );
+    // the restriction of apid1_type2 refers to a parameter of the header
+    assert_eq!(
+        vec!["/Restrictions/a=10", "/Restrictions/apid=1", "/Restrictions/b=20", "/Restrictions/type=2"],
+        eng_values(&mdb, &[1, 2, 10, 20], cidx)
+    );
+    assert_eq!(
+        vec!["/Restrictions/apid=3", "/Restrictions/type=2"],
+        eng_values(&mdb, &[3, 2, 10, 20], cidx)
+    );
+}
+
+#[test]
+fn restriction_multiple_matches() {
+    let mdb = restrictions_mdb();
+    let cidx = mdb.search_container("/Restrictions/header").unwrap();
+
+    // both apid2 and type3 match, they are both extracted after the header
+    assert_eq!(
+        vec!["/Restrictions/apid=2", "/Restrictions/c=10", "/Restrictions/d=10", "/Restrictions/type=3"],
+        eng_values(&mdb, &[2, 3, 10], cidx)
+    );
+}
+
+#[test]
+fn default_namespace_and_foreign_elements() {
+    let mdb = restrictions_mdb();
+    let mut mdb_default_ns = MissionDatabase::new();
+    parser::parse(&mut mdb_default_ns, Path::new("test-xtce-files/restrictions-default-ns.xml"))
+        .unwrap();
+
+    let item_names = |mdb: &MissionDatabase| {
+        let mut names: Vec<String> = (0..mdb.parameters.len())
+            .map(|i| mdb.parameter_qn(ParameterIdx::new(i)))
+            .chain((0..mdb.containers.len()).map(|i| mdb.container_qn(ContainerIdx::new(i))))
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(item_names(&mdb), item_names(&mdb_default_ns));
+
+    let cidx = mdb.search_container("/Restrictions/header").unwrap();
+    let cidx_default_ns = mdb_default_ns.search_container("/Restrictions/header").unwrap();
+    for packet in [&[1u8, 1, 10, 20][..], &[1, 2, 10, 20], &[2, 3, 10], &[3, 3, 10]] {
+        assert_eq!(
+            eng_values(&mdb, packet, cidx),
+            eng_values(&mdb_default_ns, packet, cidx_default_ns)
+        );
+    }
+}
+
+fn arrays_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/arrays.xml")).unwrap();
+    mdb
+}
+
+#[test]
+fn array_entries() {
+    let mdb = arrays_mdb();
+    let cidx = mdb.search_container("/Arrays/header").unwrap();
+
+    let r = process(&mdb, &[1, 2, 3, 4, 5, 6, 7], cidx).unwrap();
+    let Value::Array(data) = &r[0].eng_value else { panic!("expected an array, got {:?}", r[0].eng_value) };
+    assert_eq!(vec![Value::Uint64(1), Value::Uint64(2), Value::Uint64(3)], **data);
+
+    // the elements are calibrated, the first index is the most significant one
+    let Value::Array(matrix) = &r[1].eng_value else { panic!("expected an array, got {:?}", r[1].eng_value) };
+    let rows: Vec<Vec<Value>> = matrix
+        .iter()
+        .map(|row| match row {
+            Value::Array(v) => v.to_vec(),
+            _ => panic!("expected an array, got {:?}", row),
+        })
+        .collect();
+    assert_eq!(
+        vec![vec![Value::Double(2.0), Value::Double(2.5)], vec![Value::Double(3.0), Value::Double(3.5)]],
+        rows
+    );
+    // the size of the arrays with fixed dimensions is known
+    let matrix_type = mdb.get_data_type(mdb.get_parameter(r[1].pidx).ptype.unwrap());
+    assert_eq!(Some(32), matrix_type.size_in_bits(&mdb));
+}
+
+#[test]
+fn array_of_aggregates() {
+    let mdb = arrays_mdb();
+    let cidx = mdb.search_container("/Arrays/samples_packet").unwrap();
+
+    let r = process(&mdb, &[1, 2, 3, 4, 5, 6], cidx).unwrap();
+    assert_eq!(
+        "samples eng_value: {[{values: [1, 2, 3]}, {values: [4, 5, 6]}]}, \
+         raw_value: {[{values: [1, 2, 3]}, {values: [4, 5, 6]}]}",
+        format!("{:?}", r[0].dbg(&mdb))
+    );
+}
+
+#[test]
+fn restriction_on_array_element() {
+    let mdb = arrays_mdb();
+    let cidx = mdb.search_container("/Arrays/header").unwrap();
+
+    // data[0] == 5
+    let r = process(&mdb, &[5, 2, 3, 0, 0, 0, 0, 10], cidx).unwrap();
+    assert_eq!(3, r.len());
+    assert_eq!("/Arrays/x", mdb.parameter_qn(r[2].pidx));
+    assert_eq!(Value::Uint64(10), r[2].eng_value);
+
+    // matrix[1][0] == 2 (raw value 4)
+    let r = process(&mdb, &[1, 2, 3, 0, 0, 4, 0, 11], cidx).unwrap();
+    assert_eq!(3, r.len());
+    assert_eq!("/Arrays/y", mdb.parameter_qn(r[2].pidx));
+
+    let r = process(&mdb, &[1, 5, 3, 4, 0, 0, 0, 11], cidx).unwrap();
+    assert_eq!(2, r.len());
+}
+
+#[test]
+fn restriction_on_array_element_starting_index() {
+    let mdb = arrays_mdb();
+    let cidx = mdb.search_container("/Arrays/data1_packet").unwrap();
+
+    // data1[1] is the first element
+    let r = process(&mdb, &[7, 2, 10], cidx).unwrap();
+    assert_eq!(2, r.len());
+    assert_eq!("/Arrays/x", mdb.parameter_qn(r[1].pidx));
+
+    let r = process(&mdb, &[2, 7, 10], cidx).unwrap();
+    assert_eq!(1, r.len());
+}
+
+#[test]
+fn restriction_on_array_length() {
+    let mdb = arrays_mdb();
+    let cidx = mdb.search_container("/Arrays/data_n_packet").unwrap();
+
+    // data_n >= 4
+    let r = process(&mdb, &[3, 1, 2, 3, 4, 10], cidx).unwrap();
+    assert_eq!(3, r.len());
+    assert_eq!("/Arrays/y", mdb.parameter_qn(r[2].pidx));
+
+    // data_n == 2
+    let r = process(&mdb, &[1, 1, 2, 10], cidx).unwrap();
+    assert_eq!(3, r.len());
+    assert_eq!("/Arrays/x", mdb.parameter_qn(r[2].pidx));
+
+    let r = process(&mdb, &[0, 1], cidx).unwrap();
+    assert_eq!(2, r.len());
+}
+
+#[test]
+fn array_dimension_exceeding_packet() {
+    let mdb = arrays_mdb();
+    let cidx = mdb.search_container("/Arrays/data_n_packet").unwrap();
+
+    let r = process(&mdb, &[2, 1, 2, 3], cidx).unwrap();
+    assert_eq!(Value::Array(Box::new(vec![Value::Uint64(1), Value::Uint64(2), Value::Uint64(3)])), r[1].eng_value);
+
+    // the 256 elements cannot fit in the remaining 24 bits
+    match process(&mdb, &[255, 1, 2, 3], cidx) {
+        Err(ProcError::DecodingError(msg)) => assert!(msg.contains("[256]"), "{}", msg),
+        _ => panic!("expected a DecodingError"),
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use xtce_rs::{
+    mdb::{MatchCriteriaIdx, MissionDatabase},
+    parser,
+    mdb::types::AlgorithmText,
+    proc::{
+        containers::{process, process_with},
+        criteria_evaluator::MatchResult,
+        CustomAlgorithmExecutor, ProcError, ProcessorData,
+    },
+    pvlist::ParameterValueList,
+    value::{ParameterValue, Value},
+};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+pub fn init_logging() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+fn init_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/types.xml");
+    parser::parse(&mut mdb, path).unwrap();
+    mdb
+}
+
+// returns the restriction criteria of the hex_packet container: packet_type == 0x23
+fn restriction_criteria(mdb: &MissionDatabase) -> MatchCriteriaIdx {
+    let cidx = mdb.search_container("/Types/hex_packet").unwrap();
+    mdb.get_container(cidx).base_container.unwrap().1.unwrap()
+}
+
+fn value_list(mdb: &MissionDatabase, pname: &str, v: u64) -> ParameterValueList {
+    let pidx = mdb.search_parameter(pname).unwrap();
+    let mut pvlist = ParameterValueList::new();
+    pvlist.push(ParameterValue::new(pidx, Value::Uint64(v), Value::Uint64(v)));
+    pvlist
+}
+
+#[test]
+fn evaluate_comparison() {
+    let mdb = init_mdb();
+    let mcidx = restriction_criteria(&mdb);
+    let pdata = ProcessorData::new(&mdb).unwrap();
+
+    let values = value_list(&mdb, "/Types/packet_type", 0x23);
+    assert_eq!(MatchResult::OK, pdata.evaluate(&mdb, mcidx, &values));
+
+    let values = value_list(&mdb, "/Types/packet_type", 0x24);
+    assert_eq!(MatchResult::NOK, pdata.evaluate(&mdb, mcidx, &values));
+
+    // the parameter used in the comparison is not in the list
+    let values = value_list(&mdb, "/Types/uint8_range", 0x23);
+    assert_eq!(MatchResult::UNDEF, pdata.evaluate(&mdb, mcidx, &values));
+    assert_eq!(MatchResult::UNDEF, pdata.evaluate(&mdb, mcidx, &ParameterValueList::new()));
+}
+
+#[test]
+fn boolean_expression_include_condition() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/conditional_packet").unwrap();
+
+    // packet_data is included when packet_type == 2 or 10 <= packet_type < 12
+    for (packet_type, included) in [(2, true), (10, true), (11, true), (1, false), (12, false)] {
+        let r = process(&mdb, &[packet_type, 0x00, 0x03], container).unwrap();
+        assert_eq!(if included { 2 } else { 1 }, r.len(), "packet_type {}", packet_type);
+    }
+
+    let r = process(&mdb, &[2, 0x00, 0x03], container).unwrap();
+    assert_eq!(Value::Int64(3), r[1].eng_value);
+}
+
+// executes the algorithm "high_type" as if it was JavaScript: packet_type > 0x40
+struct MockExecutor;
+
+impl CustomAlgorithmExecutor for MockExecutor {
+    fn execute(
+        &self,
+        name: &str,
+        algorithm_text: Option<&AlgorithmText>,
+        inputs: &[Value],
+    ) -> Result<Vec<Value>, ProcError> {
+        assert_eq!("high_type", name);
+        assert_eq!("JavaScript", algorithm_text.unwrap().language);
+        match inputs {
+            [Value::Uint64(x)] => Ok(vec![Value::Boolean(*x > 0x40)]),
+            _ => Err(ProcError::InvalidValue(format!("unexpected inputs {:?}", inputs))),
+        }
+    }
+}
+
+#[test]
+fn custom_algorithm_restriction_criteria() {
+    let mdb = init_mdb();
+    let base = mdb.search_container("/Types/base_packet").unwrap();
+
+    // without executor the criteria evaluates to ERROR and the inheriting container is skipped
+    let r = process(&mdb, &[0x50, 0x00, 0x03], base).unwrap();
+    assert_eq!(1, r.len());
+
+    let mut pdata = ProcessorData::new(&mdb).unwrap();
+    pdata.set_algorithm_executor(Box::new(MockExecutor));
+
+    let r = process_with(&mdb, &pdata, &[0x50, 0x00, 0x03], base).unwrap();
+    assert_eq!(2, r.len());
+    assert_eq!(Value::Int64(3), r[1].eng_value);
+
+    let r = process_with(&mdb, &pdata, &[0x10, 0x00, 0x03], base).unwrap();
+    assert_eq!(1, r.len());
+}
+
+// an executor which is not thread safe, as an embedded interpreter usually is
+#[cfg(not(feature = "parallel"))]
+struct CountingExecutor(std::rc::Rc<std::cell::Cell<u32>>);
+
+#[cfg(not(feature = "parallel"))]
+impl CustomAlgorithmExecutor for CountingExecutor {
+    fn execute(&self, name: &str, text: Option<&AlgorithmText>, inputs: &[Value]) -> Result<Vec<Value>, ProcError> {
+        self.0.set(self.0.get() + 1);
+        MockExecutor.execute(name, text, inputs)
+    }
+}
+
+#[test]
+#[cfg(not(feature = "parallel"))]
+fn executor_not_thread_safe() {
+    let mdb = init_mdb();
+    let base = mdb.search_container("/Types/base_packet").unwrap();
+
+    let count = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut pdata = ProcessorData::new(&mdb).unwrap();
+    pdata.set_algorithm_executor(Box::new(CountingExecutor(count.clone())));
+
+    let r = process_with(&mdb, &pdata, &[0x50, 0x00, 0x03], base).unwrap();
+    assert_eq!(2, r.len());
+    assert!(count.get() > 0);
+}
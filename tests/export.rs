@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use xtce_rs::{
+    mdb::MissionDatabase,
+    parser,
+    proc::containers::process,
+    value::Value,
+};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+pub fn init_logging() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+fn parse_file(path: &str) -> MissionDatabase {
+    init_logging();
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new(path)).unwrap();
+    mdb
+}
+
+fn reparse(mdb: &MissionDatabase) -> MissionDatabase {
+    let mut mdb1 = MissionDatabase::new();
+    parser::parse_str(&mut mdb1, &mdb.to_xtce_string()).unwrap();
+    mdb1
+}
+
+// the aggregate members are identified by name, the name indices differ between databases
+fn value_str(mdb: &MissionDatabase, v: &Value) -> String {
+    match v {
+        Value::Aggregate(av) => {
+            let mut members: Vec<_> =
+                av.0.iter().map(|(name, mv)| format!("{}: {}", mdb.name2str(*name), value_str(mdb, mv))).collect();
+            members.sort();
+            format!("{{{}}}", members.join(", "))
+        }
+        _ => v.to_string(),
+    }
+}
+
+// processes the packet and returns the qualified name, raw and engineering value of each parameter
+fn process_packet(mdb: &MissionDatabase, root: &str, packet: &[u8]) -> Vec<(String, String, String)> {
+    let cidx = mdb.search_container(root).unwrap();
+    process(mdb, packet, cidx)
+        .unwrap()
+        .into_iter()
+        .map(|pv| (mdb.parameter_qn(pv.pidx), value_str(mdb, &pv.raw_value), value_str(mdb, &pv.eng_value)))
+        .collect()
+}
+
+#[test]
+fn bogus_round_trip() {
+    let mdb = parse_file("test-xtce-files/BogusSAT-2.xml");
+    let mdb1 = reparse(&mdb);
+
+    let packet: Vec<u8> = vec![0x08, 0x23, 0xC0, 0x56, 0, 5, 0x35, 0x10, 0x20, 0x03, 0x05, 0, 0];
+    let r = process_packet(&mdb, "/BogusSAT/CCSDSPacket", &packet);
+    assert_eq!(4, r.len());
+    assert_eq!(r, process_packet(&mdb1, "/BogusSAT/CCSDSPacket", &packet));
+
+    assert_eq!(mdb.version_info(), mdb1.version_info());
+    assert_eq!(mdb.parameters.len(), mdb1.parameters.len());
+    assert_eq!(mdb.containers.len(), mdb1.containers.len());
+}
+
+#[test]
+fn calibrated_round_trip() {
+    let mdb = parse_file("test-xtce-files/types.xml");
+    let mdb1 = reparse(&mdb);
+
+    // exporting again the parsed document gives the same document
+    let xtce = mdb.to_xtce_string();
+    assert_eq!(xtce, mdb1.to_xtce_string());
+    assert!(xtce.contains("<xtce:EnumerationAlarm alarmLevel=\"critical\" enumerationLabel=\"ERROR\"/>"), "{}", xtce);
+
+    // the context calibrator applies for packet_type 1
+    for packet in [[0x00, 10], [0x01, 10]] {
+        assert_eq!(
+            process_packet(&mdb, "/Types/calibrated_packet", &packet),
+            process_packet(&mdb1, "/Types/calibrated_packet", &packet)
+        );
+    }
+    assert_eq!(
+        process_packet(&mdb, "/Types/crc_frame", &[0x0A, 0x00, 0x01, 0x1B, 0x7C]),
+        process_packet(&mdb1, "/Types/crc_frame", &[0x0A, 0x00, 0x01, 0x1B, 0x7C])
+    );
+}
+
+#[test]
+fn stable_export() {
+    let mdb = parse_file("test-xtce-files/BogusSAT-2.xml");
+    let xtce = mdb.to_xtce_string();
+
+    let mut mdb1 = MissionDatabase::new();
+    parser::parse_str(&mut mdb1, &xtce).unwrap();
+    assert_eq!(xtce, mdb1.to_xtce_string());
+}
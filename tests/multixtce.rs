@@ -1,6 +1,10 @@
 use std::path::Path;
 
-use xtce_rs::{mdb::MissionDatabase, parser, proc::containers::process};
+use xtce_rs::{
+    mdb::{AliasTarget, MdbError, MissionDatabase, QualifiedName, VersionInfo},
+    parser,
+    proc::containers::process,
+};
 
 static INIT: std::sync::Once = std::sync::Once::new();
 
@@ -35,3 +39,428 @@ fn type_defined_in_different_file() {
     assert_eq!(1, r.len());
     assert_eq!("-17", r[0].eng_value.to_string());
 }
+
+#[test]
+fn incremental_loading() {
+    init_logging();
+
+    // the file containing the reference is added before the file defining the type
+    let mut loader = parser::MdbLoader::new();
+    loader.add_file(Path::new("test-xtce-files/multi-pkt.xml")).unwrap();
+    loader.add_file(Path::new("test-xtce-files/multi-dt.xml")).unwrap();
+    let mdb = loader.finish().expect("multixtce files should be valid");
+
+    let packet: Vec<u8> = vec![0xff, 0xef];
+
+    let root_container = mdb.search_container("/multi-pkt/packet-signedint").unwrap();
+    let r = process(&mdb, &packet, root_container).unwrap();
+
+    assert_eq!(1, r.len());
+    assert_eq!("-17", r[0].eng_value.to_string());
+}
+
+#[test]
+fn incremental_loading_duplicate_file() {
+    init_logging();
+
+    let mut loader = parser::MdbLoader::new();
+    loader.add_file(Path::new("test-xtce-files/multi-dt.xml")).unwrap();
+    assert!(matches!(
+        loader.add_file(Path::new("test-xtce-files/multi-dt.xml")),
+        Err(parser::XtceError::DuplicateName(..))
+    ));
+}
+
+#[test]
+fn unresolved_references_position() {
+    init_logging();
+
+    let mut loader = parser::MdbLoader::new();
+    loader.add_file(Path::new("test-xtce-files/multi-dt.xml")).unwrap();
+    loader.add_file(Path::new("test-xtce-files/unresolved-refs.xml")).unwrap();
+
+    let err = loader.finish().expect_err("the references cannot be resolved");
+    let parser::XtceError::UnresolvedReferences(refs) = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert_eq!(2, refs.len());
+    for r in refs {
+        assert_eq!(Path::new("test-xtce-files/unresolved-refs.xml"), r.file);
+    }
+    let mut rows: Vec<u32> = refs.iter().map(|r| r.pos.row).collect();
+    rows.sort();
+    assert_eq!(vec![6, 10], rows);
+
+    let msg = err.to_string();
+    assert!(msg.contains("test-xtce-files/unresolved-refs.xml:6:4 container2 (SequenceContainer)"), "{}", msg);
+    assert!(msg.contains("test-xtce-files/unresolved-refs.xml:10:4 container1 (SequenceContainer)"), "{}", msg);
+}
+
+#[test]
+fn references_to_other_space_system() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/multi-ss.xml")).unwrap();
+
+    let packet: Vec<u8> = vec![0x07, 0x00, 0x01, 0x00, 0x02, 0x03, 0x04];
+    let root_container = mdb.search_container("/Root/A/packet").unwrap();
+    let r = process(&mdb, &packet, root_container).unwrap();
+
+    // param4 is extracted by the sub_packet container, its restriction criteria being on param1
+    let values: Vec<(String, String)> = r
+        .into_iter()
+        .map(|pv| (mdb.parameter_qn(pv.pidx), pv.eng_value.to_string()))
+        .collect();
+    let expected = [
+        ("/Root/A/counter", "7"),
+        ("/Root/B/param1", "1"),
+        ("/Root/B/param2", "2"),
+        ("/Root/B/param3", "3"),
+        ("/Root/B/param4", "4"),
+    ];
+    assert_eq!(expected.map(|(n, v)| (n.to_owned(), v.to_owned())).to_vec(), values);
+}
+
+#[test]
+fn space_system_version_info() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/multi-ss.xml")).unwrap();
+
+    let info = mdb.version_info();
+    assert_eq!(3, info.len());
+    let VersionInfo { space_system, operational_status, version, date } = &info[0];
+    assert_eq!(("/Root", Some("unittest"), None, None), (space_system.as_str(), *operational_status, *version, *date));
+    // only the version is given in the header
+    assert_eq!("/Root/A", info[1].space_system);
+    assert_eq!((None, Some("2.3"), None), (info[1].operational_status, info[1].version, info[1].date));
+    assert_eq!("/Root/B", info[2].space_system);
+    assert_eq!((None, None, None), (info[2].operational_status, info[2].version, info[2].date));
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/BogusSAT-2.xml")).unwrap();
+    let info = mdb.version_info();
+    assert_eq!("/BogusSAT", info[0].space_system);
+    assert_eq!((Some("unittest"), Some("1.0"), Some("27-10-2012")), (info[0].operational_status, info[0].version, info[0].date));
+}
+
+#[test]
+fn documents_from_strings() {
+    init_logging();
+
+    let texts = [
+        include_str!("../test-xtce-files/multi-pkt.xml"),
+        include_str!("../test-xtce-files/multi-dt.xml"),
+    ];
+    let mdb = parser::parse_documents(&texts).expect("multixtce documents should be valid");
+
+    let root_container = mdb.search_container("/multi-pkt/packet-signedint").unwrap();
+    let r = process(&mdb, &[0xff, 0xef], root_container).unwrap();
+    assert_eq!("-17", r[0].eng_value.to_string());
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse_str(&mut mdb, include_str!("../test-xtce-files/multi-ss.xml")).unwrap();
+    assert!(mdb.search_container("/Root/A/packet").is_some());
+}
+
+#[test]
+fn algorithm_in_other_document() {
+    init_logging();
+
+    // the algorithm document comes first, the algorithm is added once its parameters have been
+    let texts = [
+        r#"<SpaceSystem name="Derived"><TelemetryMetaData><AlgorithmSet>
+            <MathAlgorithm name="fahrenheit"><MathOperation outputParameterRef="/Raw/temp_f">
+                <ParameterInstanceRefOperand parameterRef="/Raw/temp_c" />
+                <ValueOperand>1.8</ValueOperand><Operator>*</Operator>
+                <ValueOperand>32</ValueOperand><Operator>+</Operator>
+            </MathOperation></MathAlgorithm>
+        </AlgorithmSet></TelemetryMetaData></SpaceSystem>"#,
+        r#"<SpaceSystem name="Raw"><TelemetryMetaData>
+            <ParameterTypeSet><FloatParameterType name="float" /></ParameterTypeSet>
+            <ParameterSet>
+                <Parameter name="temp_c" parameterTypeRef="float" />
+                <Parameter name="temp_f" parameterTypeRef="float" />
+            </ParameterSet>
+        </TelemetryMetaData></SpaceSystem>"#,
+    ];
+    let mdb = parser::parse_documents(&texts).unwrap();
+
+    let aidx = mdb.search_algorithm("/Derived/fahrenheit").unwrap();
+    assert_eq!("/Derived/fahrenheit", mdb.algorithm_qn(aidx));
+    assert_eq!(1, mdb.algorithms.len());
+    let algo = mdb.get_algorithm(aidx);
+    assert_eq!(mdb.search_parameter("/Raw/temp_c").unwrap(), algo.inputs[0].param_instance.pidx);
+    assert_eq!(mdb.search_parameter("/Raw/temp_f").unwrap(), algo.outputs[0].pidx);
+}
+
+#[test]
+fn documents_from_strings_source_names() {
+    init_logging();
+
+    let mut loader = parser::MdbLoader::new();
+    loader.add_str(include_str!("../test-xtce-files/multi-dt.xml"), None).unwrap();
+    let text = include_str!("../test-xtce-files/unresolved-refs.xml");
+    loader.add_str(text, Some("embedded/unresolved-refs.xml")).unwrap();
+
+    let err = loader.finish().expect_err("the references cannot be resolved");
+    let msg = err.to_string();
+    let expected = "embedded/unresolved-refs.xml:6:4 container2 (SequenceContainer)";
+    assert!(msg.contains(expected), "{}", msg);
+
+    // the unnamed documents are reported by their index
+    let mut loader = parser::MdbLoader::new();
+    loader.add_str(include_str!("../test-xtce-files/multi-dt.xml"), None).unwrap();
+    loader.add_str(text, None).unwrap();
+    let msg = loader.finish().expect_err("the references cannot be resolved").to_string();
+    assert!(msg.contains("<document 1>:10:4 container1 (SequenceContainer)"), "{}", msg);
+
+    // the XML errors are reported with their source and position
+    let mut mdb = MissionDatabase::new();
+    let text = "<SpaceSystem name=\"a\">\n<x></SpaceSystem>";
+    let err = parser::parse_str(&mut mdb, text).unwrap_err();
+    let parser::XtceError::Parse(e) = err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert_eq!(Some("<document 0>"), e.source.as_deref());
+    assert_eq!(2, e.pos.row);
+    assert!(e.msg.starts_with("invalid XML"), "{}", e.msg);
+}
+
+#[test]
+fn parse_error_source() {
+    init_logging();
+
+    let paths =
+        ["test-xtce-files/multi-dt.xml", "test-xtce-files/invalid-bcd-size.xml"].map(Path::new);
+    let err = parser::parse_files(&paths).expect_err("the BCD size is invalid");
+    let parser::XtceError::Parse(e) = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert_eq!(Some("test-xtce-files/invalid-bcd-size.xml"), e.source.as_deref());
+    assert_eq!(9, e.pos.row);
+    let prefix = format!("test-xtce-files/invalid-bcd-size.xml:{}:{}: ", e.pos.row, e.pos.col);
+    assert!(e.to_string().starts_with(&prefix), "{}", e);
+
+    // the documents given as strings are reported with their source name
+    let mut loader = parser::MdbLoader::new();
+    let text = include_str!("../test-xtce-files/invalid-bcd-size.xml");
+    loader.add_str(text, Some("embedded.xml")).unwrap();
+    let err = loader.finish().expect_err("the BCD size is invalid");
+    assert!(err.to_string().starts_with("parse error: embedded.xml:"), "{}", err);
+
+    // the same for the XML errors of the files
+    let mut loader = parser::MdbLoader::new();
+    let err = loader.add_file(Path::new("test-xtce-files/malformed.xml")).expect_err("the XML is malformed");
+    assert!(err.to_string().starts_with("parse error: test-xtce-files/malformed.xml:"), "{}", err);
+    let err = parser::parse_files(&[Path::new("test-xtce-files/malformed.xml")]).expect_err("the XML is malformed");
+    assert!(err.to_string().starts_with("parse error: test-xtce-files/malformed.xml:"), "{}", err);
+}
+
+#[test]
+fn included_space_systems() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/include-top.xml")).unwrap();
+    let mut flat_mdb = MissionDatabase::new();
+    parser::parse(&mut flat_mdb, Path::new("test-xtce-files/include-flat.xml")).unwrap();
+
+    let packet = [0x01, 0x02, 0x03, 0x04];
+    let mut results = vec![];
+    for mdb in [&mdb, &flat_mdb] {
+        for qn in ["/Top/counter", "/Top/Sensors/temperature", "/Top/Sensors/Power/voltage"] {
+            assert!(mdb.search_parameter(qn).is_some(), "{} not found", qn);
+        }
+        let container = mdb.search_container("/Top/packet").unwrap();
+        let r = process(mdb, &packet, container).unwrap();
+        let values: Vec<_> = (0..r.len())
+            .map(|i| (mdb.parameter_qn(r[i].pidx), r[i].eng_value.to_string()))
+            .collect();
+        results.push(values);
+    }
+    assert_eq!(results[0], results[1]);
+    assert_eq!("/Top/Sensors/Power/voltage", results[0][2].0);
+    assert_eq!("772", results[0][2].1);
+
+    // the included files are loaded also when the top level file is added to a loader
+    let mut loader = parser::MdbLoader::new();
+    loader.add_file(Path::new("test-xtce-files/include-top.xml")).unwrap();
+    let mdb = loader.finish().unwrap();
+    assert!(mdb.search_parameter("/Top/Sensors/Power/voltage").is_some());
+}
+
+#[test]
+fn include_cycle() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let err = parser::parse(&mut mdb, Path::new("test-xtce-files/include-cycle.xml"))
+        .expect_err("the files include each other");
+    let parser::XtceError::Parse(e) = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert!(e.msg.contains("include cycle"), "{}", e.msg);
+    assert_eq!(Some("test-xtce-files/include/cycle.xml"), e.source.as_deref());
+}
+
+#[test]
+fn include_depth_limit() {
+    init_logging();
+
+    let path = Path::new("test-xtce-files/include-top.xml");
+    let options = parser::ParseOptions { max_include_depth: 1, ..Default::default() };
+    let mut mdb = MissionDatabase::new();
+    let err = parser::parse_with_options(&mut mdb, path, &options)
+        .expect_err("power.xml is included at depth 2");
+    let parser::XtceError::Parse(e) = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert!(e.msg.contains("maximum include depth 1"), "{}", e.msg);
+    assert_eq!(Some("test-xtce-files/include/sensors.xml"), e.source.as_deref());
+
+    let options = parser::ParseOptions { max_include_depth: 2, ..Default::default() };
+    let mut mdb = MissionDatabase::new();
+    parser::parse_with_options(&mut mdb, path, &options).unwrap();
+}
+
+#[test]
+fn deterministic_indices() {
+    init_logging();
+
+    let paths = ["test-xtce-files/multi-dt.xml", "test-xtce-files/multi-pkt.xml"].map(Path::new);
+    let names = |mdb: &MissionDatabase| {
+        let params: Vec<_> = mdb.parameters.iter().map(|p| mdb.name2str(p.ndescr.name).to_owned()).collect();
+        let containers: Vec<_> = mdb.containers.iter().map(|c| mdb.name2str(c.ndescr.name).to_owned()).collect();
+        (params, containers)
+    };
+
+    let expected = names(&parser::parse_files(&paths).unwrap());
+    for _ in 0..5 {
+        assert_eq!(expected, names(&parser::parse_files(&paths).unwrap()));
+
+        let mut loader = parser::MdbLoader::new();
+        for path in paths {
+            loader.add_file(path).unwrap();
+        }
+        assert_eq!(expected, names(&loader.finish().unwrap()));
+    }
+}
+
+#[test]
+fn space_system_from_new_names() {
+    let mut mdb = MissionDatabase::new();
+
+    // the names are not known yet, the lookup fails
+    assert_eq!(None, QualifiedName::from_str(mdb.name_db_ref(), "/Sat/Payload"));
+
+    let qn = QualifiedName::from_str_interning(mdb.name_db_ref(), "/Sat/Payload");
+    assert_eq!(Some(&qn), QualifiedName::from_str(mdb.name_db_ref(), "/Sat/Payload").as_ref());
+    assert_eq!(qn, QualifiedName::from_str_interning(mdb.name_db_ref(), "Sat//Payload/"));
+    assert_eq!("/Sat/Payload", mdb.qn_to_string(&qn));
+    assert!(QualifiedName::from_str_interning(mdb.name_db_ref(), "/").is_root());
+
+    mdb.new_space_system(qn.clone().parent()).unwrap();
+    mdb.new_space_system(qn.clone()).unwrap();
+    assert!(mdb.get_space_system(&qn).is_some());
+}
+
+#[test]
+fn reload_space_system() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/parameters.xml")).unwrap();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/aliases.xml")).unwrap();
+    let old_voltage = mdb.search_parameter("/Aliases/voltage").unwrap();
+    let initial_values = mdb.initial_values().len();
+
+    let qn = QualifiedName::from_str(mdb.name_db_ref(), "/Aliases").unwrap();
+    mdb.remove_space_system(&qn).unwrap();
+    assert!(mdb.search_parameter("/Aliases/voltage").is_none());
+    assert!(mdb.search_container("/Aliases/power").is_none());
+    assert_eq!(None, mdb.find_by_alias("FSW", "V_BAT"));
+
+    // the new version renames the voltage parameter
+    let text = std::fs::read_to_string("test-xtce-files/aliases.xml")
+        .unwrap()
+        .replace("\"voltage\"", "\"battery_voltage\"");
+    parser::parse_str(&mut mdb, &text).unwrap();
+
+    assert!(mdb.search_parameter("/Aliases/voltage").is_none());
+    let voltage = mdb.search_parameter("/Aliases/battery_voltage").unwrap();
+    assert_ne!(old_voltage, voltage);
+    assert_eq!("/Aliases/battery_voltage", mdb.parameter_qn(voltage));
+    assert_eq!(Some(AliasTarget::Parameter(voltage)), mdb.find_by_alias("FSW", "V_BAT"));
+
+    let power = mdb.search_container("/Aliases/power").unwrap();
+    let r = process(&mdb, &[0x01, 0x02], power).unwrap();
+    assert_eq!(2, r.len());
+    assert_eq!(voltage, r[0].pidx);
+
+    // the other space system is not affected
+    assert!(mdb.search_parameter("/Parameters/param1").is_some());
+    assert_eq!(initial_values, mdb.initial_values().len());
+    let systems: Vec<_> = mdb.version_info().into_iter().map(|v| v.space_system).collect();
+    assert_eq!(vec!["/Aliases", "/Parameters"], systems);
+}
+
+#[test]
+fn remove_sub_systems_and_clear() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    parser::parse(&mut mdb, Path::new("test-xtce-files/multi-ss.xml")).unwrap();
+    let qn = |mdb: &MissionDatabase, s| QualifiedName::from_str(mdb.name_db_ref(), s).unwrap();
+
+    // the base container of sub_packet is removed with it
+    let packet = mdb.search_container("/Root/A/packet").unwrap();
+    assert!(mdb.child_containers.contains_key(&packet));
+    mdb.remove_space_system(&qn(&mdb, "/Root/A")).unwrap();
+    assert!(mdb.child_containers.is_empty());
+    assert!(mdb.search_container("/Root/A/packet").is_none());
+    assert!(mdb.search_parameter("/Root/B/param1").is_some());
+
+    assert!(matches!(mdb.remove_space_system(&qn(&mdb, "/Root/A")), Err(MdbError::InvalidName(_))));
+    assert!(matches!(mdb.remove_space_system(&QualifiedName::empty()), Err(MdbError::InvalidName(_))));
+
+    // the sub-systems are removed with their parent
+    mdb.remove_space_system(&qn(&mdb, "/Root")).unwrap();
+    assert!(mdb.version_info().is_empty());
+    assert!(mdb.search_parameter("/Root/B/param1").is_none());
+
+    parser::parse(&mut mdb, Path::new("test-xtce-files/multi-ss.xml")).unwrap();
+    assert_eq!(3, mdb.version_info().len());
+    let name = mdb.name_db_ref().get("param1");
+
+    mdb.clear();
+    assert!(mdb.version_info().is_empty());
+    assert!(mdb.parameters.is_empty());
+    assert!(mdb.get_space_system(&QualifiedName::empty()).is_some());
+    // the names stay interned
+    assert_eq!(name, mdb.name_db_ref().get("param1"));
+    parser::parse(&mut mdb, Path::new("test-xtce-files/multi-ss.xml")).unwrap();
+    assert!(mdb.search_container("/Root/A/sub_packet").is_some());
+}
+
+#[test]
+fn add_files_malformed_file() {
+    init_logging();
+
+    // the files before the malformed one are added, as when adding them one by one
+    let paths = ["test-xtce-files/multi-dt.xml", "test-xtce-files/multi-pkt.xml", "test-xtce-files/malformed.xml"]
+        .map(Path::new);
+    let mut loader = parser::MdbLoader::new();
+    let err = loader.add_files(&paths).expect_err("the XML is malformed");
+    let parser::XtceError::Parse(e) = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert_eq!(Some("test-xtce-files/malformed.xml"), e.source.as_deref());
+    let mdb = loader.finish().expect("the files added before the malformed one should be valid");
+
+    let root_container = mdb.search_container("/multi-pkt/packet-signedint").unwrap();
+    let r = process(&mdb, &[0xff, 0xef], root_container).unwrap();
+    assert_eq!("-17", r[0].eng_value.to_string());
+}
@@ -0,0 +1,271 @@
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use xtce_rs::{
+    mdb::{AliasTarget, DataSource, MissionDatabase, NamedItem, RateBasis, RateInStream},
+    parser::{self, ParseOptions, Strictness, XtceError},
+    proc::{ProcError, ProcessorData},
+    value::{ParameterValue, Value},
+};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+pub fn init_logging() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+fn init_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/parameters.xml");
+    parser::parse(&mut mdb, path).unwrap();
+    mdb
+}
+
+#[test]
+fn parameter_properties() {
+    let mdb = init_mdb();
+
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/param1").unwrap());
+    assert!(p.properties.is_none());
+    assert!(!p.read_only());
+    assert!(p.persistence());
+    assert_eq!(None, p.system_name());
+
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/local_para1").unwrap());
+    assert!(matches!(p.data_source, DataSource::Local));
+    assert!(p.read_only());
+    let props = p.properties.as_ref().unwrap();
+    assert!(!props.persistence);
+    assert_eq!(Some("OBC"), props.system_name.as_deref());
+    assert!(!p.persistence());
+    assert_eq!(Some("OBC"), p.system_name());
+
+    // the properties without persistence attribute are persisted
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/local_para2").unwrap());
+    assert!(p.properties.is_some());
+    assert!(p.persistence());
+    assert!(!p.read_only());
+}
+
+#[test]
+fn rate_in_stream() {
+    let mdb = init_mdb();
+
+    // only the first rate of the set is kept
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/rate_para").unwrap());
+    let rate = RateInStream { basis: RateBasis::PerSecond, minimum_value: Some(0.1), maximum_value: Some(2.0) };
+    assert_eq!(Some(&rate), p.rate_in_stream());
+    assert_eq!(Some(Duration::from_secs(10)), ParameterValue::expiry(&rate));
+
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/local_para1").unwrap());
+    assert_eq!(None, p.rate_in_stream());
+
+    let c = mdb.get_container(mdb.search_container("/Parameters/hk_packet").unwrap());
+    let rate = c.default_rate_in_stream.as_ref().unwrap();
+    assert_eq!(RateBasis::PerContainerUpdate, rate.basis);
+    assert_eq!((Some(1.0), Some(1.0)), (rate.minimum_value, rate.maximum_value));
+    assert_eq!(None, ParameterValue::expiry(rate));
+
+    let c = mdb.get_container(mdb.search_container("/Parameters/event_packet").unwrap());
+    assert!(c.default_rate_in_stream.is_none());
+
+    // the rates are preserved by the export
+    let mut mdb1 = MissionDatabase::new();
+    parser::parse_str(&mut mdb1, &mdb.to_xtce_string()).unwrap();
+    let p = mdb1.get_parameter(mdb1.search_parameter("/Parameters/rate_para").unwrap());
+    assert_eq!(mdb.get_parameter(mdb.search_parameter("/Parameters/rate_para").unwrap()).rate_in_stream(), p.rate_in_stream());
+    let c = mdb1.get_container(mdb1.search_container("/Parameters/hk_packet").unwrap());
+    assert_eq!(Some(RateBasis::PerContainerUpdate), c.default_rate_in_stream.as_ref().map(|r| r.basis));
+}
+
+#[test]
+fn parameter_initial_value() {
+    let mdb = init_mdb();
+
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/param1").unwrap());
+    assert!(p.initial_value.is_none());
+
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/param2").unwrap());
+    assert_eq!(Some(Value::Double(2.5)), p.initial_value);
+
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/local_para2").unwrap());
+    assert_eq!(Some(Value::Uint64(42)), p.initial_value);
+}
+
+#[test]
+fn initial_values() {
+    let mdb = init_mdb();
+
+    let mut values: Vec<String> = mdb
+        .initial_values()
+        .into_iter()
+        .map(|pv| format!("{}={}", mdb.parameter_qn(pv.pidx), pv.eng_value))
+        .collect();
+    values.sort();
+    assert_eq!(
+        vec![
+            "/Parameters/flag1=true",
+            "/Parameters/flag2=false",
+            "/Parameters/local_para2=42",
+            "/Parameters/mode1=OFF",
+            "/Parameters/mode2=ON",
+            "/Parameters/param2=2.5",
+        ],
+        values
+    );
+
+    let mode = mdb.search_parameter("/Parameters/mode2").unwrap();
+    let Some(Value::Enumerated(ev)) = &mdb.get_parameter(mode).initial_value else {
+        panic!("expected an enumerated initial value");
+    };
+    assert_eq!(1, ev.key);
+}
+
+#[test]
+fn recalibrate_initial_values() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+
+    // the booleans and enumerated values are not raw values
+    let mut values = mdb.initial_values();
+    assert!(matches!(pdata.recalibrate(&mdb, &mut values), Err(ProcError::InvalidValue(_))));
+}
+
+#[test]
+fn invalid_initial_value() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/invalid-initial-value.xml");
+    match parser::parse(&mut mdb, path) {
+        Err(XtceError::Parse(e)) => {
+            assert_eq!(13, e.pos.row);
+            assert!(e.msg.contains("300"));
+        }
+        _ => panic!("expected parse error"),
+    }
+}
+
+#[test]
+fn unsupported_initial_value() {
+    init_logging();
+
+    let xml = r#"<SpaceSystem name="Root"><TelemetryMetaData>
+        <ParameterTypeSet><AbsoluteTimeParameterType name="time" /></ParameterTypeSet>
+        <ParameterSet><Parameter name="t" parameterTypeRef="time" initialValue="2024-01-01T00:00:00Z" /></ParameterSet>
+    </TelemetryMetaData></SpaceSystem>"#;
+
+    // the initial value is ignored with a warning
+    let mut mdb = MissionDatabase::new();
+    let warnings = parser::parse_str_with_options(&mut mdb, xml, None, &ParseOptions::default()).unwrap();
+    assert_eq!(1, warnings.len());
+    assert!(warnings[0].msg.contains("initial value of parameter t"), "{}", warnings[0].msg);
+    assert!(mdb.get_parameter(mdb.search_parameter("/Root/t").unwrap()).initial_value.is_none());
+
+    let options = ParseOptions { strictness: Strictness::Strict, ..Default::default() };
+    let mut mdb = MissionDatabase::new();
+    assert!(parser::parse_str_with_options(&mut mdb, xml, None, &options).is_err());
+}
+
+#[test]
+fn qualified_names() {
+    let mdb = init_mdb();
+
+    let pidx = mdb.search_parameter("/Parameters/local_para1").unwrap();
+    assert_eq!("/Parameters/local_para1", mdb.parameter_qn(pidx));
+
+    let ptype = mdb.get_parameter(pidx).ptype.unwrap();
+    assert_eq!("/Parameters/uint16", mdb.data_type_qn(ptype));
+}
+
+#[test]
+fn descriptions_and_aliases() {
+    let mdb = init_mdb();
+
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/temp1").unwrap());
+    let nd = p.name_descr();
+    assert_eq!(Some("Battery temperature"), nd.short_description.as_deref());
+    assert_eq!(Some("Temperature measured on the battery casing"), nd.long_description.as_deref());
+    assert_eq!(Some("bat_temp"), nd.alias("FSW"));
+    assert_eq!(Some("BAT-TEMP-1"), nd.alias("MCS"));
+    assert_eq!(None, nd.alias("OBC"));
+
+    let nd = mdb.get_data_type(p.ptype.unwrap()).name_descr();
+    assert_eq!(Some("Temperature in degrees"), nd.short_description.as_deref());
+    assert_eq!(
+        Some("Signed temperature with a resolution of one degree"),
+        nd.long_description.as_deref()
+    );
+    assert_eq!(HashMap::from([("FSW".to_owned(), "temp_t".to_owned())]), nd.aliases);
+
+    let p = mdb.get_parameter(mdb.search_parameter("/Parameters/param1").unwrap());
+    assert!(p.name_descr().long_description.is_none());
+    assert!(p.name_descr().aliases.is_empty());
+}
+
+#[test]
+fn find_by_alias() {
+    init_logging();
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/aliases.xml");
+    let warnings = parser::parse_with_options(&mut mdb, path, &ParseOptions::default()).unwrap();
+
+    // the duplicate alias is reported as a warning on the second parameter using it
+    assert_eq!(1, warnings.len());
+    assert_eq!(
+        "Duplicate alias 'V_BAT' in namespace 'FSW': used by /Aliases/voltage and /Aliases/voltage2; \
+         keeping /Aliases/voltage",
+        warnings[0].msg
+    );
+    assert_eq!(27, warnings[0].pos.row);
+
+    let voltage = mdb.search_parameter("/Aliases/voltage").unwrap();
+    let current = mdb.search_parameter("/Aliases/current").unwrap();
+    let power = mdb.search_container("/Aliases/power").unwrap();
+
+    assert_eq!(Some(AliasTarget::Parameter(voltage)), mdb.find_by_alias("FSW", "V_BAT"));
+    assert_eq!(Some(AliasTarget::Parameter(voltage)), mdb.find_by_alias("MCS", "PWR-001"));
+    assert_eq!(Some(AliasTarget::Parameter(current)), mdb.find_by_alias("FSW", "I_BAT"));
+    assert_eq!(Some(AliasTarget::Container(power)), mdb.find_by_alias("FSW", "PWR_HK"));
+    assert_eq!(None, mdb.find_by_alias("MCS", "V_BAT"));
+    assert_eq!(None, mdb.find_by_alias("OBC", "V_BAT"));
+
+    // the duplicate alias is kept on the item but the lookup returns the item with the smallest
+    // qualified name
+    let voltage2 = mdb.get_parameter(mdb.search_parameter("/Aliases/voltage2").unwrap());
+    assert_eq!(Some("V_BAT"), voltage2.name_descr().alias("FSW"));
+}
+
+#[test]
+fn case_sensitive_values() {
+    let mdb = init_mdb();
+
+    let flag1 = mdb.get_parameter(mdb.search_parameter("/Parameters/flag1").unwrap());
+    let dtype = mdb.get_data_type(flag1.ptype.unwrap());
+    assert_eq!(Value::Boolean(true), dtype.from_str("Enabled", true).unwrap());
+    assert_eq!(Value::Boolean(false), dtype.from_str("false", true).unwrap());
+    assert!(dtype.from_str("enabled", true).is_err());
+    assert!(dtype.from_str("TRUE", true).is_err());
+
+    // the capitalized data sources are accepted as well
+    for (ds, ok) in [("local", true), ("Local", true), ("LOCAL", false)] {
+        let text = format!(
+            r#"<SpaceSystem name="DS"><TelemetryMetaData>
+                <ParameterTypeSet><FloatParameterType name="float" /></ParameterTypeSet>
+                <ParameterSet><Parameter name="p" parameterTypeRef="float"><ParameterProperties dataSource="{}" />
+                </Parameter></ParameterSet>
+            </TelemetryMetaData></SpaceSystem>"#,
+            ds
+        );
+        let mut mdb = MissionDatabase::new();
+        let r = parser::parse_str(&mut mdb, &text);
+        assert_eq!(ok, r.is_ok(), "{}", ds);
+        if ok {
+            let p = mdb.get_parameter(mdb.search_parameter("/DS/p").unwrap());
+            assert!(matches!(p.data_source, DataSource::Local));
+        }
+    }
+}
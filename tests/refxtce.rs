@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use xtce_rs::{mdb::MissionDatabase, parser, proc::containers::process};
+use xtce_rs::{mdb::MissionDatabase, parser, proc::containers::process, value::Value};
 
 static INIT: std::sync::Once = std::sync::Once::new();
 
@@ -42,6 +42,11 @@ fn fixed_sized_array() {
 
     let root_container = mdb.search_container("/RefXtce/packet3").unwrap();
     let r = process(&mdb, &packet, root_container).unwrap();
+
+    assert_eq!(1, r.len());
+    let expected: Vec<Value> = [0x0102, 0x0304, 0x0506, 0x0708].map(Value::Uint64).to_vec();
+    assert_eq!(Value::Array(Box::new(expected)), r[0].eng_value);
+    assert_eq!("[258, 772, 1286, 1800]", r[0].eng_value.to_string());
 }
 
 #[test]
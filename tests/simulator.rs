@@ -1,6 +1,14 @@
 use std::path::Path;
 
-use xtce_rs::{mdb::MissionDatabase, parser, proc::containers::process};
+use xtce_rs::{
+    mdb::{ConsequenceLevel, MdbError, MissionDatabase},
+    parser,
+    proc::{
+        containers::{process, process_from_name, process_packets, process_with},
+        ProcError, ProcessorData,
+    },
+    pvlist::ParameterValueList,
+};
 
 static INIT: std::sync::Once = std::sync::Once::new();
 
@@ -22,12 +30,110 @@ fn init_mdb() -> MissionDatabase {
 #[test]
 fn dhs() {
     let mdb = init_mdb();
+    process_dhs(&mdb);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn dhs_serde_roundtrip() {
+    let mdb = init_mdb();
+    let expected = process_dhs(&mdb);
 
+    let json = serde_json::to_string(&mdb).unwrap();
+    let mdb1: MissionDatabase = serde_json::from_str(&json).unwrap();
+    assert_eq!(expected, process_dhs(&mdb1));
+
+    let bin = bincode::serialize(&mdb).unwrap();
+    let mdb2: MissionDatabase = bincode::deserialize(&bin).unwrap();
+    assert_eq!(expected, process_dhs(&mdb2));
+}
+
+fn process_dhs(mdb: &MissionDatabase) -> Vec<(String, String)> {
     let packet: Vec<u8> =
         hex_to_bytes("0801fff50015517e58c1b065000000020401050105010402000074b6").unwrap();
 
     let root_container = mdb.search_container("/YSS/SIMULATOR/DHS").unwrap();
-    let r = process(&mdb, &packet, root_container).unwrap();
+    assert_eq!("/YSS/SIMULATOR/DHS", mdb.container_qn(root_container));
+    let r = process(mdb, &packet, root_container).unwrap();
+    assert!(!r.is_empty());
+
+    eng_values(mdb, r)
+}
+
+// returns the qualified name and engineering value of each extracted parameter
+fn eng_values(mdb: &MissionDatabase, r: ParameterValueList) -> Vec<(String, String)> {
+    r.into_iter()
+        .map(|pv| (mdb.parameter_qn(pv.pidx).to_string(), format!("{:?}", pv.eng_value)))
+        .collect()
+}
+
+#[test]
+fn dhs_stream() {
+    let mdb = init_mdb();
+    let packet: Vec<u8> =
+        hex_to_bytes("0801fff50015517e58c1b065000000020401050105010402000074b6").unwrap();
+    let root_container = mdb.search_container("/YSS/SIMULATOR/DHS").unwrap();
+    let expected = process_dhs(&mdb);
+
+    // the processor data is created once for all the packets
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    for _ in 0..20_000 {
+        let r = process_with(&mdb, &pdata, &packet, root_container).unwrap();
+        assert_eq!(expected, eng_values(&mdb, r));
+    }
+}
+
+#[test]
+fn dhs_from_name() {
+    let mdb = init_mdb();
+    let packet: Vec<u8> =
+        hex_to_bytes("0801fff50015517e58c1b065000000020401050105010402000074b6").unwrap();
+
+    let r = process_from_name(&mdb, &packet, "/YSS/SIMULATOR/DHS").unwrap();
+    assert_eq!(process_dhs(&mdb), eng_values(&mdb, r));
+
+    match process_from_name(&mdb, &packet, "/YSS/SIMULATOR/NOT_THERE") {
+        Err(ProcError::Mdb(MdbError::InvalidName(msg))) => {
+            assert!(msg.contains("/YSS/SIMULATOR/NOT_THERE"), "{}", msg)
+        }
+        r => panic!("expected an InvalidName error, got {:?}", r.map(|r| r.len())),
+    }
+}
+
+#[test]
+fn dhs_entry_names() {
+    let mdb = init_mdb();
+    let cidx = mdb.search_container("/YSS/SIMULATOR/DHS").unwrap();
+
+    assert_eq!(9, mdb.get_container(cidx).entry_count());
+    assert_eq!(
+        vec![
+            "PrimBusVoltage1",
+            "PrimBusCurrent1",
+            "PrimBusVoltage2",
+            "PrimBusCurrent2",
+            "SecBusVoltage2",
+            "SecBusCurrent2",
+            "SecBusVoltage3",
+            "SecBusCurrent3",
+            "MemoryRate",
+        ],
+        mdb.container_entry_names(cidx)
+    );
+}
+
+#[test]
+fn dhs_truncated() {
+    let mdb = init_mdb();
+
+    // the DHS packet above without the last 6 bytes
+    let packet: Vec<u8> = hex_to_bytes("0801fff50015517e58c1b06500000002040105010501").unwrap();
+
+    let root_container = mdb.search_container("/YSS/SIMULATOR/DHS").unwrap();
+    match process(&mdb, &packet, root_container) {
+        Err(ProcError::OutOfBounds(_)) => {}
+        r => panic!("expected an OutOfBounds error, got {:?}", r.map(|pvl| pvl.len())),
+    }
 }
 
 #[test]
@@ -45,6 +151,40 @@ fn flightdata() {
     }
 }
 
+#[test]
+fn flightdata_stream() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let root_container = mdb.search_container("/YSS/SIMULATOR/FlightData").unwrap();
+
+    let packet: Vec<u8> =
+        hex_to_bytes("0801fb7e0047517e74b4b36500000021435dc000c27265604254e148458ccd9a41ddb43940314c983e00c49c42ec8a3d42ec8a3d3ebbbecb3f7ec02f4238333340af2a30c1ad70a441ddb4390520").unwrap();
+    // every 100th packet is truncated and cannot be processed
+    let packets: Vec<Vec<u8>> = (0..1000)
+        .map(|i| {
+            let mut p = packet.clone();
+            p[20] = i as u8;
+            if i % 100 == 99 {
+                p.truncate(40);
+            }
+            p
+        })
+        .collect();
+
+    let values = |r: &Result<ParameterValueList, ProcError>| match r {
+        Ok(pvl) => {
+            Ok(pvl.into_iter().map(|pv| (pv.pidx, pv.raw_value.clone(), pv.eng_value.clone())).collect::<Vec<_>>())
+        }
+        Err(e) => Err(format!("{:?}", e)),
+    };
+    let r = process_packets(&mdb, &pdata, &packets, root_container);
+    assert_eq!(packets.len(), r.len());
+    for (packet, pr) in packets.iter().zip(&r) {
+        assert_eq!(values(&process_with(&mdb, &pdata, packet, root_container)), values(pr));
+    }
+    assert_eq!(10, r.iter().filter(|pr| pr.is_err()).count());
+}
+
 fn hex_to_bytes(s: &str) -> Option<Vec<u8>> {
     if s.len() % 2 == 0 {
         (0..s.len())
@@ -55,3 +195,28 @@ fn hex_to_bytes(s: &str) -> Option<Vec<u8>> {
         None
     }
 }
+
+#[test]
+fn dhs_positions() {
+    let mdb = init_mdb();
+    let packet: Vec<u8> =
+        hex_to_bytes("0801fff50015517e58c1b065000000020401050105010402000074b6").unwrap();
+    let r = process_from_name(&mdb, &packet, "/YSS/SIMULATOR/DHS").unwrap();
+
+    assert_eq!("/YSS/SIMULATOR/PrimBusCurrent1", mdb.parameter_qn(r[1].pidx));
+    let cpos = r[1].position.as_ref().unwrap();
+    assert_eq!(0, cpos.start_offset);
+    assert_eq!(136, cpos.bit_offset);
+    assert_eq!(8, cpos.bit_size);
+    assert!((&r).into_iter().all(|pv| pv.position.is_some()));
+}
+
+#[test]
+fn xtce11_significance() {
+    let mdb = init_mdb();
+
+    // consequenceLevel="user2" from XTCE 1.1
+    let cmdidx = mdb.search_meta_command("/YSS/SIMULATOR/CRITICAL_TC2").unwrap();
+    let significance = mdb.command_significance(cmdidx).unwrap();
+    assert_eq!(ConsequenceLevel::User2, significance.consequence_level);
+}
@@ -1,6 +1,13 @@
 use std::{path::Path};
 
-use xtce_rs::{mdb::MissionDatabase, parser, proc::containers::process};
+use xtce_rs::{
+    mdb::{MdbError, MissionDatabase},
+    parser,
+    proc::{
+        containers::{process, process_with},
+        ContainerBuf, CustomStringDecoder, ProcError, ProcessorData,
+    },
+};
 static INIT: std::sync::Once = std::sync::Once::new();
 
 pub fn init_logging() {
@@ -158,3 +165,223 @@ fn fixed_size5_too_long() {
     let r = process(&mdb, &packet, root_container);
     assert!(r.is_err());
 }
+
+#[test]
+fn leading_size_little_endian() {
+    let mdb = str_mdb();
+
+    let packet: Vec<u8> = vec![2, 0, b'a', b'b', 0x01, 0x02];
+
+    let root_container = mdb.search_container("/StringsTm/packet7").unwrap();
+    let r = process(&mdb, &packet, root_container).unwrap();
+
+    assert_eq!("ab", r[0].eng_value.to_string());
+    // the byte order of the size tag does not affect the following big endian parameter
+    assert_eq!(0x0102u64, r.eng(1).try_into().unwrap());
+}
+
+#[test]
+fn leading_size_4_bytes() {
+    let mdb = str_mdb();
+
+    let packet: Vec<u8> = vec![3, 0, 0, 0, b'a', b'b', b'c', 0x01, 0x02];
+
+    let root_container = mdb.search_container("/StringsTm/packet8").unwrap();
+    let r = process(&mdb, &packet, root_container).unwrap();
+
+    assert_eq!("abc", r[0].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(1).try_into().unwrap());
+
+    // the size exceeds the box
+    let packet: Vec<u8> = vec![0, 0, 0, 1, b'a', b'b', b'c', 0x01, 0x02];
+    assert!(process(&mdb, &packet, root_container).is_err());
+}
+
+#[test]
+fn leading_size_4_bits() {
+    let mdb = str_mdb();
+
+    // the 4 bits size tag follows a 4 bits parameter such that the string starts at byte boundary
+    let packet: Vec<u8> = vec![0x72, b'a', b'b', 0x01, 0x02];
+
+    let root_container = mdb.search_container("/StringsTm/packet14").unwrap();
+    let r = process(&mdb, &packet, root_container).unwrap();
+
+    assert_eq!(7u64, r.eng(0).try_into().unwrap());
+    assert_eq!("ab", r[1].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(2).try_into().unwrap());
+
+    // the size exceeds the maximum box size of 4 characters
+    let packet: Vec<u8> = vec![0x05, b'a', b'b', b'c', b'd', b'e', 0x01, 0x02];
+    assert!(process(&mdb, &packet, root_container).is_err());
+
+    // the 4 characters and the size tag fill the 36 bits of the maximum box size
+    let packet: Vec<u8> = vec![0x74, b'a', b'b', b'c', b'd', 0x01, 0x02];
+    let r = process(&mdb, &packet, root_container).unwrap();
+    assert_eq!("abcd", r[1].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(2).try_into().unwrap());
+
+    // the size tag starting at byte boundary, the string would not
+    let root_container = mdb.search_container("/StringsTm/packet15").unwrap();
+    match process(&mdb, &[0x20, b'a', b'b'], root_container) {
+        Err(ProcError::DecodingError(msg)) => assert!(msg.contains("byte boundary"), "{}", msg),
+        r => panic!("expected decoding error, got {:?}", r.map(|pvl| pvl.len())),
+    }
+}
+
+#[test]
+fn crlf_terminated() {
+    let mdb = str_mdb();
+
+    // a lone CR or LF does not terminate the string
+    let packet: Vec<u8> = vec![b'a', 0x0D, b'b', 0x0A, 0x0D, 0x0A, 0x01, 0x02];
+
+    let root_container = mdb.search_container("/StringsTm/packet9").unwrap();
+    let r = process(&mdb, &packet, root_container).unwrap();
+
+    assert_eq!("a\rb\n", r[0].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(1).try_into().unwrap());
+
+    // the terminator has to be found in the undefined buffer
+    let packet: Vec<u8> = vec![b'a', b'b', 0x0D, 0x01, 0x02];
+    assert!(process(&mdb, &packet, root_container).is_err());
+}
+
+#[test]
+fn utf16_byte_order() {
+    let mdb = str_mdb();
+
+    // "é€😀", the last character is encoded with a surrogate pair
+    let units: [u16; 4] = [0x00E9, 0x20AC, 0xD83D, 0xDE00];
+    let le: Vec<u8> = units.iter().flat_map(|u| u.to_le_bytes()).chain([0x01, 0x02]).collect();
+    let be: Vec<u8> = units.iter().flat_map(|u| u.to_be_bytes()).chain([0x01, 0x02]).collect();
+
+    let root_container = mdb.search_container("/StringsTm/packet11").unwrap();
+    let r = process(&mdb, &le, root_container).unwrap();
+    assert_eq!("é€😀", r[0].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(1).try_into().unwrap());
+
+    let root_container = mdb.search_container("/StringsTm/packet12").unwrap();
+    let r = process(&mdb, &be, root_container).unwrap();
+    assert_eq!("é€😀", r[0].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(1).try_into().unwrap());
+
+    // the terminator is searched only at the code unit boundaries: 0x2000 0x0041 contains 00 00
+    // at an odd offset
+    let packet: Vec<u8> = vec![0x20, 0x00, 0x00, 0x41, 0x00, 0x00, 0, 0, 0x01, 0x02];
+    let r = process(&mdb, &packet, root_container).unwrap();
+    assert_eq!("\u{2000}A", r[0].eng_value.to_string());
+}
+
+#[test]
+fn utf32_null_terminated() {
+    let mdb = str_mdb();
+    let root_container = mdb.search_container("/StringsTm/packet13").unwrap();
+
+    // "\u{100}A😀" followed by the terminator; the 00 00 00 00 spanning the code units of 'A'
+    // and '😀' is not at a code unit boundary
+    let packet: Vec<u8> =
+        vec![0x00, 0x01, 0, 0, 0x41, 0, 0, 0, 0x00, 0xF6, 0x01, 0, 0, 0, 0, 0, 0x01, 0x02];
+    let r = process(&mdb, &packet, root_container).unwrap();
+    assert_eq!("\u{100}A😀", r[0].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(1).try_into().unwrap());
+
+    // the terminator has to be found within the 16 bytes of the variable buffer
+    let packet: Vec<u8> = vec![0x41, 0, 0, 0].repeat(4).into_iter().chain([0, 0, 0, 0]).collect();
+    assert!(process(&mdb, &packet, root_container).is_err());
+}
+
+#[test]
+fn invalid_utf16_terminator() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/invalid-utf16-terminator.xml");
+    match parser::parse(&mut mdb, path) {
+        Err(parser::XtceError::Parse(e)) => {
+            assert!(e.msg.contains("not a multiple of the 2 bytes code unit"), "{}", e.msg)
+        }
+        r => panic!("expected parse error, got {:?}", r),
+    }
+}
+
+#[test]
+fn double_null_terminated() {
+    let mdb = str_mdb();
+
+    let packet: Vec<u8> = vec![b'a', 0, b'b', 0, 0, 0, 0x01, 0x02];
+
+    let root_container = mdb.search_container("/StringsTm/packet10").unwrap();
+    let r = process(&mdb, &packet, root_container).unwrap();
+
+    assert_eq!("a\0b", r[0].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(1).try_into().unwrap());
+
+    // no terminator, the string fills the buffer
+    let packet: Vec<u8> = vec![b'a', b'b', b'c', b'd', b'e', 0, 0x01, 0x02];
+    let r = process(&mdb, &packet, root_container).unwrap();
+    assert_eq!("abcde\0", r[0].eng_value.to_string());
+}
+
+// the first byte is the size of the string
+struct ByteSizePrefixedString;
+
+impl CustomStringDecoder for ByteSizePrefixedString {
+    fn decode(&self, buf: &mut ContainerBuf) -> Result<(String, usize), ProcError> {
+        let n = buf.get_byte()? as usize;
+        let s = String::from_utf8_lossy(buf.get_bytes_ref(n)?).into_owned();
+        Ok((s, 8 * (n + 1)))
+    }
+}
+
+#[test]
+fn custom_decoder() {
+    let mdb = str_mdb();
+    let mut pdata = ProcessorData::new(&mdb).unwrap();
+    pdata.register_string_decoder("ByteSizePrefixedString", Box::new(ByteSizePrefixedString));
+
+    let packet: Vec<u8> = vec![3, b'a', b'b', b'c', 0x01, 0x02];
+
+    let root_container = mdb.search_container("/StringsTm/packet6").unwrap();
+    let r = process_with(&mdb, &pdata, &packet, root_container).unwrap();
+
+    assert_eq!("abc", r[0].eng_value.to_string());
+    assert_eq!(0x0102u64, r.eng(1).try_into().unwrap());
+}
+
+#[test]
+fn custom_decoder_not_registered() {
+    let mdb = str_mdb();
+
+    let packet: Vec<u8> = vec![3, b'a', b'b', b'c', 0x01, 0x02];
+
+    let root_container = mdb.search_container("/StringsTm/packet6").unwrap();
+    match process(&mdb, &packet, root_container) {
+        Err(ProcError::Mdb(MdbError::InvalidMdb(msg))) => assert!(msg.contains("ByteSizePrefixedString"), "{}", msg),
+        _ => panic!("expected an InvalidMdb error"),
+    }
+}
+
+#[test]
+fn short_packets() {
+    let mdb = str_mdb();
+
+    // (container, packet too short for the string, name of the string parameter)
+    let cases: &[(&str, &[u8], &str)] = &[
+        ("packet1", &[b'a', b'b'], "string1"),
+        ("packet7", &[0x01], "string7"),
+        ("packet7", &[0x05, 0x00, b'a'], "string7"),
+        ("packet9", &[b'a', b'b'], "string9"),
+        ("packet13", &[b'a', 0, 0], "string13"),
+    ];
+
+    for (cname, packet, pname) in cases {
+        let container = mdb.search_container(&format!("/StringsTm/{}", cname)).unwrap();
+        match process(&mdb, packet, container) {
+            Err(ProcError::OutOfBounds(msg) | ProcError::DecodingError(msg)) => {
+                assert!(msg.contains(pname), "{}: {}", cname, msg)
+            }
+            r => panic!("{}: expected an error, got {:?}", cname, r.map(|pvl| pvl.len())),
+        }
+    }
+}
@@ -0,0 +1,925 @@
+use std::path::Path;
+
+use xtce_rs::{
+    mdb::{
+        debug::MdbItemDebug,
+        types::{
+            AlarmLevel, BinarySize, Calibrator, DataEncoding, DataType, FloatEncodingType, PolynomialCalibrator,
+            TypeData,
+        },
+        MissionDatabase,
+    },
+    parser::{self, ParseOptions, Strictness, XtceError},
+    proc::{
+        containers::{process, process_at, process_with},
+        types,
+        ProcError, ProcessorData, UndefinedEnumeration,
+    },
+    pvlist::ParameterValueList,
+    value::{ErrorCheck, ParameterValue, Value},
+};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+pub fn init_logging() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+fn init_mdb() -> MissionDatabase {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/types.xml");
+    parser::parse(&mut mdb, path).unwrap();
+    mdb
+}
+
+// returns the type of the parameter with the given name
+fn param_type<'a>(mdb: &'a MissionDatabase, pname: &str) -> &'a DataType {
+    let pidx = mdb.search_parameter(&format!("/Types/{}", pname)).unwrap();
+    mdb.get_data_type(mdb.get_parameter(pidx).ptype.unwrap())
+}
+
+#[test]
+fn inclusive_valid_range() {
+    let mdb = init_mdb();
+    let dtype = param_type(&mdb, "uint8_range");
+
+    let vr = &dtype.valid_ranges()[0];
+    assert_eq!(Some(10.0), vr.min);
+    assert!(vr.min_inclusive);
+
+    assert!(dtype.check_valid_range(&Value::Uint64(10), true).is_ok());
+    assert!(dtype.check_valid_range(&Value::Uint64(20), true).is_ok());
+    assert!(dtype.check_valid_range(&Value::Uint64(9), true).is_err());
+    assert!(dtype.check_valid_range(&Value::Uint64(21), true).is_err());
+    // the range applies to the calibrated value
+    assert!(dtype.check_valid_range(&Value::Uint64(21), false).is_ok());
+}
+
+#[test]
+fn exclusive_valid_range() {
+    let mdb = init_mdb();
+
+    let dtype = param_type(&mdb, "int16_exclusive_range");
+    assert!(dtype.check_valid_range(&Value::Int64(-4), true).is_ok());
+    assert!(dtype.check_valid_range(&Value::Int64(4), true).is_ok());
+    assert!(dtype.check_valid_range(&Value::Int64(-5), true).is_err());
+    assert!(dtype.check_valid_range(&Value::Int64(5), true).is_err());
+
+    let dtype = param_type(&mdb, "float_exclusive_range");
+    assert!(dtype.check_valid_range(&Value::Double(0.0), true).is_err());
+    assert!(dtype.check_valid_range(&Value::Double(0.001), true).is_ok());
+    assert!(dtype.check_valid_range(&Value::Double(100.0), true).is_ok());
+    assert!(dtype.check_valid_range(&Value::Double(100.001), true).is_err());
+}
+
+#[test]
+fn member_descriptions() {
+    let mdb = init_mdb();
+    let dtype = param_type(&mdb, "described_aggregate");
+    let TypeData::Aggregate(adt) = &dtype.type_data else {
+        panic!("expected aggregate type");
+    };
+
+    let name = |n: &str| mdb.name_db_ref().get(n).unwrap();
+    let m = adt.member(name("voltage")).unwrap();
+    assert_eq!(Some("battery voltage"), m.ndescr.short_description.as_deref());
+    assert_eq!(
+        Some("The voltage measured at the battery terminals"),
+        m.ndescr.long_description.as_deref()
+    );
+    assert_eq!(Some("V_BAT"), m.ndescr.alias("FSW"));
+
+    let m = adt.member(name("current")).unwrap();
+    assert!(m.ndescr.short_description.is_none());
+    assert!(m.ndescr.long_description.is_none());
+    // the name of the aggregate type itself is not a member
+    assert!(adt.member(name("described_aggregate")).is_none());
+}
+
+#[test]
+fn enumeration_range() {
+    let mdb = init_mdb();
+
+    let dtype = param_type(&mdb, "range_enum");
+    let TypeData::Enumerated(edt) = &dtype.type_data else {
+        panic!("expected enumerated type");
+    };
+    assert_eq!(4, edt.enumeration.len());
+    assert_eq!("[0-15]: NOMINAL", format!("{:?}", edt.enumeration[0]));
+    // maxValue equal to value is formatted as a single value
+    assert_eq!("16: WARNING", format!("{:?}", edt.enumeration[1]));
+    assert_eq!("17: ERROR", format!("{:?}", edt.enumeration[2]));
+    assert_eq!("[32-47]: HEX_RANGE", format!("{:?}", edt.enumeration[3]));
+
+    let container = mdb.search_container("/Types/enum_packet").unwrap();
+    for (raw, label) in [(0u8, "NOMINAL"), (7, "NOMINAL"), (15, "NOMINAL"), (16, "WARNING"), (17, "ERROR"), (18, "UNDEF"), (0x25, "HEX_RANGE")] {
+        let r = process(&mdb, &[raw], container).unwrap();
+        assert_eq!(label, r[0].eng_value.to_string());
+    }
+}
+
+#[test]
+fn enumeration_alarm() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+
+    let TypeData::Enumerated(edt) = &param_type(&mdb, "range_enum").type_data else {
+        panic!("expected enumerated type");
+    };
+    let alarm = edt.default_alarm.as_ref().unwrap();
+    assert_eq!(AlarmLevel::Normal, alarm.default_level);
+    assert_eq!(2, alarm.levels.len());
+    assert_eq!(1, edt.context_alarm.len());
+
+    let packet_type = mdb.search_parameter("/Types/packet_type").unwrap();
+    let mode = |x: u64| {
+        let mut values = ParameterValueList::new();
+        values.push(ParameterValue::new(packet_type, Value::Uint64(x), Value::Uint64(x)));
+        values
+    };
+
+    let container = mdb.search_container("/Types/enum_packet").unwrap();
+    for (raw, level, level1) in [
+        (7u8, AlarmLevel::Normal, AlarmLevel::Distress),
+        (16, AlarmLevel::Warning, AlarmLevel::Distress),
+        (17, AlarmLevel::Critical, AlarmLevel::Watch),
+        (18, AlarmLevel::Normal, AlarmLevel::Distress),
+    ] {
+        let r = process(&mdb, &[raw], container).unwrap();
+        let pv = &r[0];
+        assert_eq!(Some(level), pdata.enumeration_alarm_level(&mdb, pv, &ParameterValueList::new()));
+        assert_eq!(Some(level), pdata.enumeration_alarm_level(&mdb, pv, &mode(0)));
+        // the context alarm applies for packet_type 1
+        assert_eq!(Some(level1), pdata.enumeration_alarm_level(&mdb, pv, &mode(1)));
+    }
+
+    // the parameters without alarm
+    let pv = &mode(1)[0];
+    assert_eq!(None, pdata.enumeration_alarm_level(&mdb, pv, &ParameterValueList::new()));
+}
+
+#[test]
+fn unknown_enumeration_alarm_label() {
+    init_logging();
+
+    let text = include_str!("../test-xtce-files/types.xml").replace("enumerationValue=\"ERROR\"", "enumerationValue=\"BOGUS\"");
+    let mut mdb = MissionDatabase::new();
+    match parser::parse_str(&mut mdb, &text) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("unknown label 'BOGUS'"), "{}", e.msg),
+        r => panic!("expected parse error, got {:?}", r.err()),
+    }
+}
+
+#[test]
+fn undefined_enumeration() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/enum_packet").unwrap();
+    let mut pdata = ProcessorData::new(&mdb).unwrap();
+
+    pdata.set_undefined_enumeration(UndefinedEnumeration::Label("UNKNOWN".to_owned()));
+    let r = process_with(&mdb, &pdata, &[18], container).unwrap();
+    assert_eq!("UNKNOWN", r[0].eng_value.to_string());
+    let r = process_with(&mdb, &pdata, &[16], container).unwrap();
+    assert_eq!("WARNING", r[0].eng_value.to_string());
+
+    pdata.set_undefined_enumeration(UndefinedEnumeration::Error);
+    match process_with(&mdb, &pdata, &[18], container) {
+        Err(ProcError::DecodingError(msg)) => {
+            assert!(msg.contains("range_enum") && msg.contains("raw value 18"), "{}", msg)
+        }
+        r => panic!("expected a decoding error, got {:?}", r.map(|pvl| pvl.len())),
+    }
+    assert!(process_with(&mdb, &pdata, &[0x25], container).is_ok());
+}
+
+#[test]
+fn invalid_enumeration_range() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/invalid-enum-range.xml");
+    match parser::parse(&mut mdb, path) {
+        Err(XtceError::Parse(e)) => {
+            assert_eq!(8, e.pos.row);
+            assert!(e.msg.contains("maxValue"));
+        }
+        _ => panic!("expected parse error"),
+    }
+}
+
+#[test]
+fn hex_comparison() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/base_packet").unwrap();
+
+    let r = process(&mdb, &[0x23, 0x00, 0x03], container).unwrap();
+    assert_eq!(2, r.len());
+    assert_eq!("3", r[1].eng_value.to_string());
+
+    let r = process(&mdb, &[0x24, 0x00, 0x03], container).unwrap();
+    assert_eq!(1, r.len());
+}
+
+#[test]
+fn unit_set() {
+    let mdb = init_mdb();
+    let dtype = param_type(&mdb, "velocity");
+
+    assert_eq!(2, dtype.units.len());
+    assert_eq!("m", dtype.units[0].unit);
+    assert_eq!(1.0, dtype.units[0].power);
+    assert_eq!(Some("meter".to_owned()), dtype.units[0].description);
+    assert_eq!("s", dtype.units[1].unit);
+    assert_eq!(-1.0, dtype.units[1].power);
+    assert_eq!("1", dtype.units[1].factor);
+    assert_eq!(Some("per second".to_owned()), dtype.units[1].description);
+
+    let dbg = format!("{:?}", MdbItemDebug { item: dtype, mdb: &mdb });
+    assert!(dbg.ends_with(", units: m s^-1)"), "{}", dbg);
+}
+
+#[test]
+fn process_after_header() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/located_packet").unwrap();
+
+    // 6 bytes header followed by the container; the parameter location is relative to the container start
+    let packet = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xAA, 0x00, 0x04];
+    let r = process_at(&mdb, &packet, container, 48).unwrap();
+    assert_eq!(1, r.len());
+    assert_eq!("4", r[0].eng_value.to_string());
+    // the position is relative to the container start in the packet
+    let pos = r[0].position.as_ref().unwrap();
+    assert_eq!((6, 8, 16), (pos.start_offset, pos.bit_offset, pos.bit_size));
+    let start = (pos.start_offset + pos.bit_offset / 8) as usize;
+    assert_eq!([0x00, 0x04], packet[start..start + 2]);
+
+    assert!(matches!(process_at(&mdb, &packet, container, 44), Err(ProcError::InvalidValue(_))));
+    assert!(matches!(process_at(&mdb, &packet, container, 80), Err(ProcError::OutOfBounds(_))));
+}
+
+#[test]
+fn binary_transform_algorithm() {
+    let mdb = init_mdb();
+    let dtype = param_type(&mdb, "binary_transformed");
+
+    let DataEncoding::Binary(bde) = &dtype.encoding else {
+        panic!("expected binary encoding");
+    };
+    assert!(matches!(bde.size_in_bits, BinarySize::Fixed(16)));
+    let algo = bde.from_binary_transform_algorithm.as_ref().unwrap();
+    assert_eq!("NestedPacketDecoder", algo.name);
+    let text = algo.algorithm_text.as_ref().unwrap();
+    assert_eq!("java", text.language);
+    assert_eq!("org.example.NestedPacketDecoder()", text.text);
+
+    // without algorithm executor the algorithm is not executed, the binary data is returned
+    let container = mdb.search_container("/Types/binary_packet").unwrap();
+    let r = process(&mdb, &[0xCA, 0xFE, 0x01], container).unwrap();
+    assert_eq!(1, r.len());
+    assert_eq!("cafe", r[0].eng_value.to_string());
+}
+
+#[test]
+fn binary_size_not_multiple_of_8() {
+    init_logging();
+
+    let text = r#"<SpaceSystem name="Bin"><TelemetryMetaData>
+        <ParameterTypeSet><BinaryParameterType name="bin12"><BinaryDataEncoding>
+            <SizeInBits><FixedValue>12</FixedValue></SizeInBits>
+        </BinaryDataEncoding></BinaryParameterType></ParameterTypeSet>
+        <ParameterSet><Parameter name="data" parameterTypeRef="bin12" /></ParameterSet>
+        <ContainerSet><SequenceContainer name="packet"><EntryList>
+            <ParameterRefEntry parameterRef="data" />
+        </EntryList></SequenceContainer></ContainerSet>
+    </TelemetryMetaData></SpaceSystem>"#;
+    let options = ParseOptions { strictness: Strictness::Strict, ..Default::default() };
+    match parser::parse_str_with_options(&mut MissionDatabase::new(), text, None, &options) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("not a multiple of 8"), "{}", e.msg),
+        _ => panic!("expected parse error"),
+    }
+
+    // lenient: the type is accepted with a warning but the data cannot be extracted
+    let mut mdb = MissionDatabase::new();
+    let warnings = parser::parse_str_with_options(&mut mdb, text, None, &ParseOptions::default()).unwrap();
+    assert_eq!(1, warnings.len());
+    assert_eq!("BinaryDataEncoding", warnings[0].tag);
+    let container = mdb.search_container("/Bin/packet").unwrap();
+    match process(&mdb, &[0xCA, 0xFE], container) {
+        Err(ProcError::DecodingError(msg)) => assert!(msg.contains("not a multiple of 8"), "{}", msg),
+        r => panic!("expected decoding error, got {:?}", r.map(|pvl| pvl.len())),
+    }
+}
+
+#[test]
+fn crc_verification() {
+    let mdb = init_mdb();
+
+    let dtype = param_type(&mdb, "frame_crc");
+    let DataEncoding::Binary(bde) = &dtype.encoding else {
+        panic!("expected binary encoding");
+    };
+    let crc = bde.crc.as_ref().unwrap();
+    assert_eq!((16, 0x1021, 0xFFFF, 0), (crc.width, crc.polynomial, crc.init_remainder, crc.final_xor));
+
+    let container = mdb.search_container("/Types/crc_frame").unwrap();
+    let r = process(&mdb, &[0x0A, 0x00, 0x01, 0x1B, 0x7C], container).unwrap();
+    let pv = r.into_iter().last().unwrap();
+    assert_eq!("1b7c", pv.eng_value.to_string());
+    assert_eq!(Some(ErrorCheck::Passed), pv.error_check);
+
+    // corrupted data, the CRC does not match anymore
+    let r = process(&mdb, &[0x0A, 0x00, 0x02, 0x1B, 0x7C], container).unwrap();
+    let pv = r.into_iter().last().unwrap();
+    assert!(matches!(pv.error_check, Some(ErrorCheck::Failed { .. })));
+}
+
+#[test]
+fn change_threshold() {
+    let mdb = init_mdb();
+
+    assert_eq!(Some(2.0), param_type(&mdb, "uint8_range").encoding.change_threshold());
+    assert_eq!(Some(0.5), param_type(&mdb, "float_exclusive_range").encoding.change_threshold());
+    assert_eq!(None, param_type(&mdb, "int16_exclusive_range").encoding.change_threshold());
+
+    let pidx = mdb.search_parameter("/Types/uint8_range").unwrap();
+    let pv = |x: u64| ParameterValue::new(pidx, Value::Uint64(x), Value::Uint64(x));
+    assert!(!pv(12).significant_change(&pv(10), Some(2.0)));
+    assert!(pv(13).significant_change(&pv(10), Some(2.0)));
+    assert!(pv(7).significant_change(&pv(10), Some(2.0)));
+
+    // without threshold any change is significant
+    assert!(!pv(10).significant_change(&pv(10), None));
+    assert!(pv(11).significant_change(&pv(10), None));
+
+    let spv = |s: &str| ParameterValue::new(pidx, Value::StringValue(Box::new(s.to_owned())), Value::Uint64(0));
+    assert!(spv("a").significant_change(&spv("b"), Some(2.0)));
+    assert!(!spv("a").significant_change(&spv("a"), Some(2.0)));
+}
+
+#[test]
+fn context_calibrator() {
+    let mdb = init_mdb();
+
+    let dtype = param_type(&mdb, "scaled_value");
+    assert!(dtype.calibrator.is_some());
+    assert_eq!(1, dtype.context_calibrators.len());
+
+    let container = mdb.search_container("/Types/calibrated_packet").unwrap();
+
+    // packet_type 0: the default calibrator 1 + 2x applies
+    let r = process(&mdb, &[0x00, 10], container).unwrap();
+    assert_eq!(Value::Double(21.0), r.into_iter().last().unwrap().eng_value);
+
+    // packet_type 1: the context calibrator x/2 applies
+    let r = process(&mdb, &[0x01, 10], container).unwrap();
+    assert_eq!(Value::Double(5.0), r.into_iter().last().unwrap().eng_value);
+}
+
+#[test]
+fn effective_calibrator() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let packet_type = mdb.search_parameter("/Types/packet_type").unwrap();
+    let scaled_value = mdb.search_parameter("/Types/scaled_value").unwrap();
+    let mode = |x: u64| {
+        let mut values = ParameterValueList::new();
+        values.push(ParameterValue::new(packet_type, Value::Uint64(x), Value::Uint64(x)));
+        values
+    };
+
+    // without the packet_type the context criteria does not match
+    let cal = pdata.effective_calibrator(&mdb, scaled_value, &ParameterValueList::new());
+    assert!(matches!(cal, Some(Calibrator::Polynomial(_))));
+
+    let cal = pdata.effective_calibrator(&mdb, scaled_value, &mode(0));
+    assert!(matches!(cal, Some(Calibrator::Polynomial(_))));
+
+    let cal = pdata.effective_calibrator(&mdb, scaled_value, &mode(1));
+    assert!(matches!(cal, Some(Calibrator::Spline(_))));
+
+    // the packet_type itself is not calibrated
+    assert!(pdata.effective_calibrator(&mdb, packet_type, &mode(1)).is_none());
+}
+
+#[test]
+fn integer_encoding_calibrator() {
+    let mdb = init_mdb();
+
+    let dtype = param_type(&mdb, "scaled_int_value");
+    let Some(Calibrator::Polynomial(pc)) = &dtype.calibrator else {
+        panic!("expected a polynomial calibrator");
+    };
+    assert_eq!(vec![-40.0, 0.5], pc.coefficients);
+
+    // -40 + x/2 applied to the signed raw values, the result is an integer engineering value
+    let container = mdb.search_container("/Types/scaled_int_packet").unwrap();
+    let r = process(&mdb, &[0x00, 100], container).unwrap();
+    assert_eq!(Value::Int64(100), r[0].raw_value);
+    assert_eq!(Value::Int64(10), r[0].eng_value);
+    let r = process(&mdb, &[0xFF, 0xEC], container).unwrap();
+    assert_eq!(Value::Int64(-50), r[0].eng_value);
+}
+
+#[test]
+fn math_operation_calibrator() {
+    let mdb = init_mdb();
+
+    let dtype = param_type(&mdb, "sqrt_value");
+    let Some(Calibrator::MathOperation(ops)) = &dtype.calibrator else {
+        panic!("expected a math operation calibrator");
+    };
+    assert_eq!(2, ops.len());
+
+    let container = mdb.search_container("/Types/sqrt_packet").unwrap();
+    // the engineering type has 32 bits
+    for (raw, eng) in [(0, 0.0), (1, 1.0), (16, 4.0), (200, 200f64.sqrt() as f32 as f64)] {
+        let r = process(&mdb, &[raw], container).unwrap();
+        assert_eq!(Value::Double(eng), r[0].eng_value, "raw value {}", raw);
+    }
+}
+
+#[test]
+fn recalibrate() {
+    let mut mdb = init_mdb();
+    let container = mdb.search_container("/Types/calibrated_packet").unwrap();
+    let mut default_cal = process(&mdb, &[0x00, 10], container).unwrap();
+    let mut context_cal = process(&mdb, &[0x01, 10], container).unwrap();
+
+    // replace the default calibrator 1 + 2x by 3x
+    let pidx = mdb.search_parameter("/Types/scaled_value").unwrap();
+    let dtidx = mdb.get_parameter(pidx).ptype.unwrap();
+    mdb.parameter_types[dtidx.index()].calibrator =
+        Some(Calibrator::Polynomial(PolynomialCalibrator { coefficients: vec![0.0, 3.0] }));
+
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    pdata.recalibrate(&mdb, &mut default_cal).unwrap();
+    assert_eq!(&Value::Double(30.0), default_cal.eng(default_cal.len() - 1));
+    assert_eq!(&Value::Uint64(10), default_cal.raw(default_cal.len() - 1));
+
+    // the context calibrator x/2 is still selected by the packet_type preceding the value
+    pdata.recalibrate(&mdb, &mut context_cal).unwrap();
+    assert_eq!(&Value::Double(5.0), context_cal.eng(context_cal.len() - 1));
+}
+
+#[test]
+fn decalibrate() {
+    let mdb = init_mdb();
+    let pdata = ProcessorData::new(&mdb).unwrap();
+    let no_values = ParameterValueList::new();
+    let decal = |pname: &str, v: Value, values: &ParameterValueList| {
+        pdata.decalibrate(&mdb, param_type(&mdb, pname), &v, values)
+    };
+    let label = |s: &str| Value::StringValue(Box::new(s.to_owned()));
+
+    // the label is mapped to the start of its range
+    assert_eq!(Value::Uint64(16), decal("range_enum", label("WARNING"), &no_values).unwrap());
+    assert_eq!(Value::Uint64(0x20), decal("range_enum", label("HEX_RANGE"), &no_values).unwrap());
+    assert!(matches!(decal("range_enum", label("BOGUS"), &no_values), Err(ProcError::InvalidValue(_))));
+
+    // no calibrator, the value only has to fit in the encoding
+    assert_eq!(Value::Uint64(200), decal("uint8_range", Value::Uint64(200), &no_values).unwrap());
+    assert!(matches!(decal("uint8_range", Value::Uint64(256), &no_values), Err(ProcError::OutOfRange(_))));
+    assert_eq!(Value::Int64(-3), decal("int16_exclusive_range", Value::Int64(-3), &no_values).unwrap());
+    assert_eq!(Value::Double(1.5), decal("float_exclusive_range", Value::Double(1.5), &no_values).unwrap());
+
+    // the inverse of the default calibrator 1 + 2x
+    assert_eq!(Value::Uint64(10), decal("scaled_value", Value::Double(21.0), &no_values).unwrap());
+
+    // packet_type 1 selects the spline context calibrator which cannot be inverted
+    let pidx = mdb.search_parameter("/Types/packet_type").unwrap();
+    let mut values = ParameterValueList::new();
+    values.push(ParameterValue::new(pidx, Value::Uint64(1), Value::Uint64(1)));
+    assert!(matches!(decal("scaled_value", Value::Double(5.0), &values), Err(ProcError::InvalidMdb(_))));
+}
+
+#[test]
+fn discrete_lookup_size() {
+    let mdb = init_mdb();
+
+    let DataEncoding::Binary(bde) = &param_type(&mdb, "lookup_blob").encoding else {
+        panic!("expected binary encoding");
+    };
+    assert!(matches!(bde.size_in_bits, BinarySize::Dynamic(_)));
+
+    let container = mdb.search_container("/Types/lookup_packet").unwrap();
+
+    let r = process(&mdb, &[0x01, 0xAB, 0xCD], container).unwrap();
+    assert_eq!("abcd", r.into_iter().last().unwrap().eng_value.to_string());
+
+    let r = process(&mdb, &[0x03, 0xAB, 0xCD], container).unwrap();
+    assert_eq!("ab", r.into_iter().last().unwrap().eng_value.to_string());
+
+    // no lookup matches
+    let r = process(&mdb, &[0x05, 0xAB, 0xCD], container);
+    assert!(matches!(r, Err(ProcError::DecodingError(msg)) if msg.contains("16: NOK, 8: NOK")));
+}
+
+#[test]
+fn discrete_lookup_parsed_again() {
+    init_logging();
+
+    // the first lookup refers to a parameter of the parent space system, created before the type; the second one
+    // to a parameter defined after the type such that the type is parsed again
+    let text = r#"<SpaceSystem name="Lookup">
+        <TelemetryMetaData>
+            <ParameterTypeSet>
+                <IntegerParameterType name="uint8"><IntegerDataEncoding sizeInBits="8" /></IntegerParameterType>
+            </ParameterTypeSet>
+            <ParameterSet><Parameter name="mode" parameterTypeRef="uint8" /></ParameterSet>
+        </TelemetryMetaData>
+        <SpaceSystem name="Sub"><TelemetryMetaData>
+            <ParameterTypeSet>
+                <BinaryParameterType name="blob"><BinaryDataEncoding><SizeInBits><DiscreteLookupList>
+                    <DiscreteLookup value="8"><Comparison parameterRef="/Lookup/mode" value="1" /></DiscreteLookup>
+                    <DiscreteLookup value="16"><Comparison parameterRef="mode2" value="1" /></DiscreteLookup>
+                </DiscreteLookupList></SizeInBits></BinaryDataEncoding></BinaryParameterType>
+            </ParameterTypeSet>
+            <ParameterSet><Parameter name="mode2" parameterTypeRef="/Lookup/uint8" /></ParameterSet>
+        </TelemetryMetaData></SpaceSystem>
+    </SpaceSystem>"#;
+    let mut mdb = MissionDatabase::new();
+    parser::parse_str(&mut mdb, text).unwrap();
+
+    // only the criteria of the lookups finally added are kept
+    assert_eq!(2, mdb.match_criteria.len());
+}
+
+#[test]
+fn engineering_range() {
+    let mdb = init_mdb();
+
+    // 8 bits unsigned raw value calibrated with 1 + 2x
+    assert_eq!(Some((1.0, 511.0)), types::engineering_range(param_type(&mdb, "scaled_value")));
+
+    // the valid range applies to the engineering value
+    assert_eq!(Some((10.0, 20.0)), types::engineering_range(param_type(&mdb, "uint8_range")));
+
+    // no calibrator and no valid range, the range of the encoding
+    assert_eq!(Some((f32::MIN as f64, f32::MAX as f64)), types::engineering_range(param_type(&mdb, "velocity")));
+
+    assert_eq!(None, types::engineering_range(param_type(&mdb, "range_enum")));
+
+    // the same through the data type
+    assert_eq!(Some((1.0, 511.0)), param_type(&mdb, "scaled_value").engineering_range());
+    assert_eq!(None, param_type(&mdb, "range_enum").engineering_range());
+}
+
+#[test]
+fn named_map() {
+    let mdb = init_mdb();
+
+    let container = mdb.search_container("/Types/repeated_packet").unwrap();
+    let r = process(&mdb, &[0x01, 0x00, 0x03, 0x02], container).unwrap();
+    assert_eq!(3, r.len());
+
+    let map = r.into_named_map(&mdb);
+    assert_eq!(2, map.len());
+    assert_eq!(Value::Int64(3), map["/Types/packet_data"].eng_value);
+    // the last instance is kept
+    assert_eq!(Value::Uint64(2), map["/Types/packet_type"].eng_value);
+}
+
+#[test]
+fn float_size() {
+    let mdb = init_mdb();
+
+    let TypeData::Float(fdt) = &param_type(&mdb, "narrowed_value").type_data else { panic!("expected a float type") };
+    assert_eq!(32, fdt.size_in_bits);
+    // the size defaults to 32 bits
+    let TypeData::Float(fdt) = &param_type(&mdb, "scaled_value").type_data else { panic!("expected a float type") };
+    assert_eq!(32, fdt.size_in_bits);
+
+    let container = mdb.search_container("/Types/float_packet").unwrap();
+    let packet: Vec<u8> = [0.1f64.to_be_bytes(), 0.1f64.to_be_bytes()].concat();
+    let r = process(&mdb, &packet, container).unwrap();
+    assert_eq!(Value::Double(0.1), r[0].raw_value);
+    assert_eq!(Value::Double(0.1f32 as f64), r[0].eng_value);
+    assert_eq!(Value::Double(0.1), r[1].eng_value);
+
+    // out of the single precision range
+    let packet: Vec<u8> = [(-1e300f64).to_be_bytes(), 1e300f64.to_be_bytes()].concat();
+    let r = process(&mdb, &packet, container).unwrap();
+    assert_eq!(Value::Double(f32::MIN as f64), r[0].eng_value);
+    assert_eq!(Value::Double(1e300), r[1].eng_value);
+}
+
+#[test]
+fn size_from_encoding() {
+    let mdb = init_mdb();
+
+    let TypeData::Float(fdt) = &param_type(&mdb, "implicit_double_value").type_data else {
+        panic!("expected a float type")
+    };
+    assert_eq!(64, fdt.size_in_bits);
+    let TypeData::Integer(idt) = &param_type(&mdb, "uint48_value").type_data else {
+        panic!("expected an integer type")
+    };
+    assert_eq!(64, idt.size_in_bits);
+
+    let container = mdb.search_container("/Types/implicit_size_packet").unwrap();
+    let packet: Vec<u8> = [&0.1f64.to_be_bytes()[..], &[0xFF; 6]].concat();
+    let r = process(&mdb, &packet, container).unwrap();
+    assert_eq!(Value::Double(0.1), r[0].eng_value);
+    assert_eq!(Value::Uint64(0xFFFF_FFFF_FFFF), r[1].eng_value);
+}
+
+#[test]
+fn invalid_float_size() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/invalid-float-size.xml");
+    match parser::parse(&mut mdb, path) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("Invalid float size in bits 16"), "{}", e.msg),
+        _ => panic!("expected parse error"),
+    }
+}
+
+#[test]
+fn little_endian_enum_and_boolean() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/le_packet").unwrap();
+
+    let r = process(&mdb, &[0x02, 0x01, 0x00, 0x01, 0x00, 0x03], container).unwrap();
+    assert_eq!(Value::Uint64(0x0102), r[0].raw_value);
+    assert_eq!("LE_0x0102", r[0].eng_value.to_string());
+    // the bytes 00 01 read as little endian
+    assert_eq!(Value::Uint64(0x0100), r[1].raw_value);
+    assert_eq!(Value::Boolean(true), r[1].eng_value);
+    // the following big endian parameter is not affected
+    assert_eq!(Value::Int64(3), r[2].eng_value);
+
+    let r = process(&mdb, &[0x01, 0x00, 0x00, 0x00, 0x00, 0x03], container).unwrap();
+    assert_eq!("ONE", r[0].eng_value.to_string());
+    assert_eq!(Value::Boolean(false), r[1].eng_value);
+}
+
+#[test]
+fn little_endian_bit_fields() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/le_bitfield_packet").unwrap();
+
+    // a = 5, b = 0xABC and c = 0x1ABCD packed in a little endian 32 bits word
+    // followed by a 4 bits field = 7 and a 12 bits field = 0x9A5 in a 16 bits word
+    let packet = [0xE5, 0xD5, 0xE6, 0xD5, 0x57, 0x9A, 0x00, 0x03];
+    let r = process(&mdb, &packet, container).unwrap();
+    assert_eq!(Value::Uint64(5), r[0].raw_value);
+    assert_eq!(Value::Uint64(0xABC), r[1].raw_value);
+    assert_eq!(Value::Uint64(0x1ABCD), r[2].raw_value);
+    assert_eq!(Value::Uint64(7), r[3].raw_value);
+    assert_eq!(Value::Uint64(0x9A5), r[4].raw_value);
+    // the following big endian parameter starts at the byte boundary
+    assert_eq!(Value::Int64(3), r[5].eng_value);
+}
+
+#[test]
+fn half_floats() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/half_packet").unwrap();
+
+    // 0x3C00 = 1.0 big endian and 0xC000 = -2.0 little endian
+    let r = process(&mdb, &[0x3C, 0x00, 0x00, 0xC0], container).unwrap();
+    assert_eq!(Value::Double(1.0), r[0].raw_value);
+    assert_eq!(Value::Double(-2.0), r[1].raw_value);
+
+    // 0x7BFF = 65504 (largest normal) and 0x0001 = 2^-24 (smallest subnormal)
+    let r = process(&mdb, &[0x7B, 0xFF, 0x01, 0x00], container).unwrap();
+    assert_eq!(Value::Double(65504.0), r[0].raw_value);
+    assert_eq!(Value::Double(2f64.powi(-24)), r[1].raw_value);
+
+    // 0x7C00 = infinity and 0x7E00 = NaN
+    let r = process(&mdb, &[0x7C, 0x00, 0x00, 0x7E], container).unwrap();
+    assert_eq!(Value::Double(f64::INFINITY), r[0].eng_value);
+    assert!(matches!(r[1].eng_value, Value::Double(x) if x.is_nan()));
+}
+
+#[test]
+fn custom_float_layout() {
+    let mdb = init_mdb();
+    let DataEncoding::Float(fde) = &param_type(&mdb, "float24_value").encoding else {
+        panic!("expected a float encoding");
+    };
+    let FloatEncodingType::Custom { exponent_bits, mantissa_bits } = fde.encoding else {
+        panic!("expected a custom float encoding");
+    };
+    assert_eq!((7, 16), (exponent_bits, mantissa_bits));
+
+    // 0x3F0000 = 1.0 and 0xC04000 = -1.25 * 2
+    let container = mdb.search_container("/Types/float24_packet").unwrap();
+    let r = process(&mdb, &[0x3F, 0x00, 0x00, 0xC0, 0x40, 0x00], container).unwrap();
+    assert_eq!(Value::Double(1.0), r[0].eng_value);
+    assert_eq!(Value::Double(-2.5), r[1].eng_value);
+}
+
+#[test]
+fn invalid_custom_float_layout() {
+    init_logging();
+
+    let text = |exponent: &str, mantissa: &str, size: u32| {
+        format!(
+            r#"<SpaceSystem name="Custom"><TelemetryMetaData><ParameterTypeSet>
+            <FloatParameterType name="float24"><AncillaryDataSet>
+                <AncillaryData name="FloatExponentBits">{}</AncillaryData>
+                <AncillaryData name="FloatMantissaBits">{}</AncillaryData>
+            </AncillaryDataSet><FloatDataEncoding sizeInBits="{}" /></FloatParameterType>
+            </ParameterTypeSet></TelemetryMetaData></SpaceSystem>"#,
+            exponent, mantissa, size
+        )
+    };
+    // (exponent bits, mantissa bits, size in bits, error, row of the element reported)
+    let cases = [
+        ("7", "16", 32, "does not match", 5),
+        ("12", "16", 29, "Invalid custom float", 2),
+        // 1 + 11 + 62 bits do not fit in 64 bits
+        ("11", "62", 74, "Invalid custom float", 2),
+    ];
+    for (exponent, mantissa, size, error, row) in cases {
+        let mut mdb = MissionDatabase::new();
+        match parser::parse_str(&mut mdb, &text(exponent, mantissa, size)) {
+            Err(XtceError::Parse(e)) => {
+                assert!(e.msg.contains(error), "{}", e.msg);
+                assert_eq!(row, e.pos.row);
+            }
+            r => panic!("expected parse error, got {:?}", r),
+        }
+    }
+}
+
+#[test]
+fn milstd1750a_floats() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/milstd_packet").unwrap();
+
+    // 0x50000004 little endian followed by 0x400000FF0000
+    let r = process(&mdb, &[0x04, 0x00, 0x00, 0x50, 0x40, 0x00, 0x00, 0xFF, 0x00, 0x00], container).unwrap();
+    assert_eq!(Value::Double(10.0), r[0].raw_value);
+    assert_eq!(Value::Double(0.25), r[1].raw_value);
+
+    let r = process(&mdb, &[0x00, 0x00, 0x00, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00, 0x01], container).unwrap();
+    assert_eq!(Value::Double(-1.0), r[0].raw_value);
+    assert_eq!(Value::Double(-1.0 + 2f64.powi(-39)), r[1].raw_value);
+}
+
+#[test]
+fn invalid_milstd1750a_size() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/invalid-milstd-size.xml");
+    match parser::parse(&mut mdb, path) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("Invalid size in bits 64"), "{}", e.msg),
+        _ => panic!("expected parse error"),
+    }
+
+    // the half precision is supported only for the IEEE 754 encoding
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/invalid-milstd-half.xml");
+    match parser::parse(&mut mdb, path) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("Invalid size in bits 16"), "{}", e.msg),
+        _ => panic!("expected parse error"),
+    }
+}
+
+#[test]
+fn float_size_per_encoding() {
+    init_logging();
+
+    let text = |encoding: &str| {
+        format!(
+            r#"<SpaceSystem name="Floats"><TelemetryMetaData><ParameterTypeSet>
+            <FloatParameterType name="float48"><FloatDataEncoding encoding="{}" sizeInBits="48" /></FloatParameterType>
+            </ParameterTypeSet></TelemetryMetaData></SpaceSystem>"#,
+            encoding
+        )
+    };
+
+    // 48 bits is the MIL-STD-1750A extended precision
+    let mut mdb = MissionDatabase::new();
+    parser::parse_str(&mut mdb, &text("MILSTD_1750A")).unwrap();
+
+    let mut mdb = MissionDatabase::new();
+    match parser::parse_str(&mut mdb, &text("IEEE754_1985")) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("Invalid size in bits 48"), "{}", e.msg),
+        r => panic!("expected parse error, got {:?}", r),
+    }
+}
+
+#[test]
+fn bcd_encodings() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/bcd_packet").unwrap();
+
+    let r = process(&mdb, &[0x12, 0x34, 0x05, 0x09], container).unwrap();
+    assert_eq!(Value::Uint64(1234), r[0].raw_value);
+    assert_eq!(Value::Uint64(59), r[1].raw_value);
+
+    match process(&mdb, &[0x1A, 0x34, 0x05, 0x09], container) {
+        Err(ProcError::DecodingError(msg)) => {
+            assert!(msg.contains("packed_bcd_value") && msg.contains("bit offset 4"), "{}", msg)
+        }
+        _ => panic!("expected decoding error"),
+    }
+    // a full byte digit
+    match process(&mdb, &[0x12, 0x34, 0x05, 0x10], container) {
+        Err(ProcError::DecodingError(msg)) => {
+            assert!(msg.contains("bcd_value") && msg.contains("bit offset 24"), "{}", msg)
+        }
+        _ => panic!("expected decoding error"),
+    }
+}
+
+#[test]
+fn unsigned_encoding_signed_type() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/int64_packet").unwrap();
+
+    let r = process(&mdb, &[0, 0, 0, 0, 0, 0, 0, 0x2A], container).unwrap();
+    assert_eq!(Value::Uint64(42), r[0].raw_value);
+    assert_eq!(Value::Int64(42), r[0].eng_value);
+
+    // above i64::MAX the value is clamped, still as a signed value
+    let r = process(&mdb, &[0xFF; 8], container).unwrap();
+    assert_eq!(Value::Uint64(u64::MAX), r[0].raw_value);
+    assert_eq!(Value::Int64(i64::MAX), r[0].eng_value);
+}
+
+#[test]
+fn boolean_string_values() {
+    let mdb = init_mdb();
+    let container = mdb.search_container("/Types/switch_packet").unwrap();
+    let switch_type = param_type(&mdb, "switch_value");
+    let le_bool_type = param_type(&mdb, "le_bool_value");
+
+    let r = process(&mdb, &[0x01, 0x00, 0x00], container).unwrap();
+    assert_eq!(Value::Boolean(true), r[0].eng_value);
+    assert_eq!("ENABLED", switch_type.to_str(&r[0].eng_value));
+    // the default string values
+    assert_eq!("False", le_bool_type.to_str(&r[1].eng_value));
+
+    let r = process(&mdb, &[0x00, 0x01, 0x00], container).unwrap();
+    assert_eq!("DISABLED", switch_type.to_str(&r[0].eng_value));
+    assert_eq!("True", le_bool_type.to_str(&r[1].eng_value));
+
+    // the string values are parsed back to the boolean
+    assert_eq!(Value::Boolean(false), switch_type.from_str("DISABLED", true).unwrap());
+    // the values of other types are displayed as they are
+    assert_eq!("17", param_type(&mdb, "packet_type").to_str(&Value::Uint64(17)));
+}
+
+#[test]
+fn invalid_bcd_size() {
+    init_logging();
+
+    let mut mdb = MissionDatabase::new();
+    let path = Path::new("test-xtce-files/invalid-bcd-size.xml");
+    match parser::parse(&mut mdb, path) {
+        Err(XtceError::Parse(e)) => assert!(e.msg.contains("Invalid size in bits 10"), "{}", e.msg),
+        _ => panic!("expected parse error"),
+    }
+
+    // the raw values are at most 64 bits long, a signed encoding needs at least one bit
+    let text = include_str!("../test-xtce-files/invalid-bcd-size.xml");
+    for encoding in ["encoding=\"packedBCD\" sizeInBits=\"68\"", "encoding=\"twosComplement\" sizeInBits=\"0\""] {
+        let text = text.replace("encoding=\"packedBCD\" sizeInBits=\"10\"", encoding);
+        let mut mdb = MissionDatabase::new();
+        match parser::parse_str(&mut mdb, &text) {
+            Err(XtceError::Parse(e)) => assert!(e.msg.contains("should be between 1 and 64"), "{}", e.msg),
+            r => panic!("expected parse error, got {:?}", r.err()),
+        }
+    }
+}
+
+#[test]
+fn short_packets() {
+    let mdb = init_mdb();
+
+    // (container, packet too short for the parameter, name of the parameter)
+    // the error names the parameter instead of panicking
+    let cases: &[(&str, &[u8], &str)] = &[
+        ("base_packet", &[], "packet_type"),
+        ("hex_packet", &[0x01], "packet_data"),
+        ("enum_packet", &[], "range_enum"),
+        ("le_packet", &[0x01, 0x00, 0x01], "le_bool_value"),
+        ("le_bitfield_packet", &[0x01, 0x02], "le_c"),
+        ("float_packet", &[0x00, 0x00, 0x00], "narrowed_value"),
+        ("half_packet", &[0x00], "half_value"),
+        ("milstd_packet", &[0x00, 0x00], "milstd_le_value"),
+        ("bcd_packet", &[0x12], "packed_bcd_value"),
+        ("binary_packet", &[0xCA], "binary_transformed"),
+    ];
+
+    for (cname, packet, pname) in cases {
+        let container = mdb.search_container(&format!("/Types/{}", cname)).unwrap();
+        match process(&mdb, packet, container) {
+            Err(ProcError::OutOfBounds(msg) | ProcError::DecodingError(msg)) => {
+                assert!(msg.contains(pname), "{}: {}", cname, msg)
+            }
+            r => panic!("{}: expected an error, got {:?}", cname, r.map(|pvl| pvl.len())),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use xtce_rs::{
+    mdb::{types::DataEncoding, MissionDatabase},
+    parser::{self, ParseOptions, Strictness, XtceError},
+};
+
+static INIT: std::sync::Once = std::sync::Once::new();
+
+pub fn init_logging() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+#[test]
+fn lenient_collects_warnings() {
+    init_logging();
+    let path = Path::new("test-xtce-files/ignored-elements.xml");
+    let mut mdb = MissionDatabase::new();
+    let mut warnings = parser::parse_with_options(&mut mdb, path, &ParseOptions::default()).unwrap();
+    warnings.sort_by_key(|w| w.pos.row);
+
+    assert_eq!(2, warnings.len());
+    assert_eq!("ToString", warnings[0].tag);
+    assert_eq!(12, warnings[0].pos.row);
+    assert_eq!(5, warnings[0].pos.col);
+    assert_eq!("RateInStreamSet", warnings[1].tag);
+    assert_eq!(22, warnings[1].pos.row);
+    assert_eq!(path, warnings[1].file);
+    assert!(warnings[1].to_string().starts_with("test-xtce-files/ignored-elements.xml:22:5 "));
+
+    // the items are created without the ignored elements
+    assert!(mdb.search_parameter("/Ignored/para1").is_some());
+    assert!(mdb.search_container("/Ignored/packet1").is_some());
+}
+
+#[test]
+fn strict_fails_on_ignored_element() {
+    init_logging();
+    let options = ParseOptions { strictness: Strictness::Strict, ..Default::default() };
+    let path = Path::new("test-xtce-files/ignored-elements.xml");
+    let r = parser::parse_files_with_options(&[path], &options);
+    assert!(matches!(r, Err(XtceError::Parse(_))));
+
+    let (_, warnings) =
+        parser::parse_files_with_options(&[Path::new("test-xtce-files/segments.xml")], &options)
+            .unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn misspelled_element() {
+    init_logging();
+    let path = Path::new("test-xtce-files/misspelled-element.xml");
+
+    // lenient: the type is created without encoding
+    let mut mdb = MissionDatabase::new();
+    let warnings = parser::parse_with_options(&mut mdb, path, &ParseOptions::default()).unwrap();
+    assert_eq!(1, warnings.len());
+    assert_eq!("IntegerDataEncodig", warnings[0].tag);
+    assert_eq!(11, warnings[0].pos.row);
+    let pidx = mdb.search_parameter("/Misspelled/para1").unwrap();
+    let dtype = mdb.get_data_type(mdb.get_parameter(pidx).ptype.unwrap());
+    assert!(matches!(dtype.encoding, DataEncoding::None));
+
+    // strict: the parsing fails at the misspelled element
+    let options = ParseOptions { strictness: Strictness::Strict, ..Default::default() };
+    let mut mdb = MissionDatabase::new();
+    match parser::parse_with_options(&mut mdb, path, &options) {
+        Err(XtceError::Parse(e)) => {
+            assert!(e.msg.contains("'IntegerDataEncodig'"), "{}", e.msg);
+            assert_eq!(11, e.pos.row);
+            assert_eq!(Some("test-xtce-files/misspelled-element.xml"), e.source.as_deref());
+        }
+        r => panic!("expected parse error, got {:?}", r),
+    }
+}